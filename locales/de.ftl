@@ -0,0 +1,249 @@
+# Deutsche Meldungen.
+
+error = Fehler
+warning = Warnung
+
+## Help
+help-file = DOIs oder arXiv-IDs aus einer Datei lesen (eine pro Zeile).
+help-input = Zu suchende DOI(s) oder arXiv-ID(s), durch Leerzeichen getrennt.
+help-config = Zu verwendende Konfigurationsdatei. Standard ist $D2B_CONFIG oder d2b/config.toml im Konfigurationsverzeichnis.
+help-escape = Maskiert das Zeichen "&" (kaufmännisches Und).
+help-annotate = Stellt jedem Eintrag einen Kommentar mit Eingabe, Resolver und Abrufdatum voran.
+help-strict = Bricht bei Einträgen mit BibTeX-Problemen ab, statt sie zu reparieren und zu warnen.
+help-strict-ids = Weist Eingaben zurück, die nicht genau ein Identifikator sind, statt den Identifikator aus ihnen herauszusuchen.
+help-no-backup = Behält keine Kopie der Dateien, die ein Befehl überschreibt, für `d2b undo`.
+help-no-sanitize = Gibt das BibTeX der Verlage unverändert weiter, ohne Sonderzeichen zu maskieren oder Anführungszeichen und Ligaturen zu vereinheitlichen.
+help-strip-math = Entfernt $...$-Mathematik-Markup aus arXiv-Titeln, statt es zu erhalten.
+help-dblp = Schlägt die Kürzel der Konferenzen von ACM- und IEEE-Konferenzbeiträgen bei DBLP nach.
+help-error-format = Wie Fehler auf stderr ausgegeben werden. json schreibt ein Objekt pro Zeile.
+help-color = Hebt auf die Standardausgabe geschriebenes BibTeX farbig hervor. auto tut das, wenn sie ein Terminal ist.
+help-format = Ausgabedialekt. biblatex ermöglicht Eintragstypen wie @dataset und @software; csv und tsv schreiben eine Tabelle mit den --columns; alfred und dmenu schreiben Auswahllisten für Launcher.
+help-screening = Schreibt die Einträge mit ihren Abstracts zum Import in ein Screening-Werkzeug für systematische Reviews: RIS für covidence, CSV für rayyan.
+help-columns = Die durch Kommas getrennten Spalten von --format csv oder tsv: key, type, authors, year, title, venue, doi, url, citations oder ein beliebiges anderes Feld.
+help-prefer = Ob für arXiv-Artikel mit DOI der veröffentlichte oder der Eprint-Eintrag ausgegeben wird.
+help-romanize = Transkribiert kyrillische und CJK-Autorennamen in lateinische Schrift und behält die Originale in origauthor (biblatex) oder note.
+help-with-funding = Ergänzt Förderer und Fördernummern von CrossRef, in funding (biblatex) oder note.
+help-with-license = Ergänzt die Lizenz des Werks, von CrossRef, DataCite oder arXiv, in einem Feld license.
+help-archive = Speichert für Webseiten ohne DOI einen Schnappschuss in der Wayback Machine und zitiert ihn mit dem Abrufdatum.
+help-hash = Ergänzt ein Feld sourcehash mit einem SHA-256 der Metadaten, aus denen jeder Eintrag erzeugt wurde, für d2b verify --check-hashes.
+help-follow-published = Gibt für Preprint-DOIs (bioRxiv, SSRN, ...) stattdessen die veröffentlichte Version aus, auf die CrossRef verweist.
+help-emit-cite = Gibt zusätzlich den Zitierbefehl jedes Eintrags auf der Standardfehlerausgabe aus, zum Einfügen bereit.
+help-with-papers = Gibt für Tagungsbände zusätzlich Einträge für alle enthaltenen Beiträge aus.
+help-both = Gibt für arXiv-Artikel mit DOI verknüpfte Einträge für die veröffentlichte Version und den Eprint aus.
+help-append = Hängt das BibTeX an den Inhalt der Zwischenablage an, statt ihn zu ersetzen.
+help-clipwatch-interval = Abfrageintervall der Zwischenablage in Millisekunden.
+help-sort = Reihenfolge der Einträge in der Ausgabe.
+help-group-by = Ordnet die Einträge in Abschnitte nach Jahr oder Eintragstyp, jeweils mit einem Kommentar als Überschrift.
+help-ndjson = Liest Identifikatoren als JSON-Zeilen von stdin und schreibt jedes Ergebnis als JSON-Zeile, sobald es aufgelöst ist.
+help-jobs = Wie viele Eingaben mit --ndjson gleichzeitig aufgelöst werden.
+help-checkpoint = Hält jede Eingabe in FILE fest, sobald sie aufgelöst ist, damit ein abgebrochener Lauf fortgesetzt werden kann und nur das Nichtaufgelöste wiederholt.
+help-summary = Gibt auf stderr aus, wie viele Eingaben aufgelöst wurden und wie lange jeder Host für seine Antworten brauchte.
+help-metrics-file = Schreibt dieselben Statistiken im Textformat von Prometheus in FILE.
+help-insert-key-only = Hängt die Einträge an die Datei von --bib oder die konfigurierte an, sofern sie sie noch nicht enthält, und gibt nur ihre Schlüssel aus, für Editor-Tastenbelegungen, die ein Zitat einfügen.
+help-insert-bib = Die .bib-Datei, an die --insert-key-only anhängt, statt der konfigurierten.
+help-report = Schreibt eine HTML-Seite nach FILE, die jede Eingabe mit ihrem Ergebnis, ihrem Zitat und Links auflistet, um die Ergebnisse zu teilen.
+help-fetch-pdf = Lädt den Volltext (von arXiv oder frei zugänglich über Unpaywall) nach DIR herunter, benannt nach dem Schlüssel.
+help-obsidian = Schreibt zusätzlich für jeden Eintrag eine Obsidian-Literaturnotiz nach DIR, mit den Werten von --tag als Schlagwörtern. Vorhandene Notizen bleiben erhalten.
+help-hugo = Schreibt zusätzlich für jeden Eintrag eine Publikationsseite (KEY/index.md und KEY/cite.bib) nach DIR, wie sie akademische Hugo-Themes in content/publication erwarten. Vorhandene Dateien bleiben erhalten.
+help-http1 = Verwendet nur HTTP/1.1, für Proxys, die HTTP/2 nicht vertragen.
+help-pool-idle-timeout = Wie lange ungenutzte Verbindungen zur Wiederverwendung offen bleiben. 0 öffnet für jede Anfrage eine neue Verbindung.
+help-tcp-keepalive = Intervall der TCP-Keepalive-Proben auf offenen Verbindungen.
+help-doi-resolver = Löst jede DOI über dieses Backend auf, statt in der konfigurierten Reihenfolge oder über das für den Verlag der DOI gewählte.
+help-race = Fragt für jede DOI CrossRef, DataCite und doi.org gleichzeitig ab und nimmt den ersten vollständigen Eintrag, statt sie nacheinander zu versuchen.
+help-best-of = Fragt alle DOI-Backends ab und behält den vollständigsten Eintrag (Seiten, Band, alle Autoren, Erscheinungsort).
+help-merge-sources = Wie --best-of, ergänzt aber auch die Felder, die dem vollständigsten Eintrag fehlen, aus den Einträgen der anderen Backends.
+help-journal-strings = Schreibt Zeitschriftennamen als @string-Makros: die von AASTeX (aas), IEEEtran (ieee) oder die @strings einer .bib-Datei.
+help-template = Schreibt jeden Eintrag über die Handlebars-Vorlage FILE statt als BibTeX aus.
+help-only-category = Gibt nur Einträge in dieser arXiv-Kategorie (cs.LG) oder diesem Archiv (cs) aus. Kann mehrfach angegeben werden.
+help-style = Schreibt jeden Eintrag als formatierte Literaturangabe in diesem Stil (ISO 690 oder GOST R 7.0.5) statt als BibTeX aus.
+help-post-hook = Leitet jeden Eintrag als JSON durch den Shell-Befehl CMD und verwendet stattdessen den Eintrag, den dieser ausgibt.
+help-record = Speichert jede Antwort in DIR, für --replay.
+help-replay = Beantwortet Anfragen aus den von --record in DIR gespeicherten Antworten statt über das Netzwerk.
+help-http-debug = Gibt Status, Protokollversion, Gegenstellenadresse und Dauer jeder Antwort auf stderr aus.
+help-rename-template = Vorlage für Dateinamen, mit den Platzhaltern {key}, {year}, {author}, {title}, {shorttitle}, {journal} und {category}.
+help-rename-apply = Benennt die Dateien um, statt nur die neuen Namen auszugeben.
+help-undo-dir = Das Verzeichnis, dessen Dateien wiederhergestellt werden. Standard ist das aktuelle.
+help-undo-list = Listet stattdessen die Läufe auf, die rückgängig gemacht werden können, die neuesten zuerst.
+help-rekey-files = Die .bib-Dateien, deren Schlüssel neu erzeugt werden, und die LaTeX-Quellen, die sie zitieren.
+help-key-format = Vorlage für Schlüssel, mit den Platzhaltern von `d2b rename --template`.
+help-rekey-apply = Schreibt die Dateien um, statt nur ein Diff auszugeben.
+help-manifest = Datei mit einem Identifikator pro Zeile. Leere Zeilen und Zeilen, die mit # beginnen, werden ignoriert.
+help-update-bib = Die zu aktualisierende .bib-Datei.
+help-prune = Entfernt zusätzlich Einträge, auf die kein Identifikator der Liste verweist.
+help-import-export = Eine aus Zotero (RDF oder JSON) oder Mendeley (JSON) exportierte Bibliothek.
+help-output = Schreibt in diese Datei statt auf die Standardausgabe.
+help-mailbox = Eine mbox-Datei oder ein Maildir-Verzeichnis mit Benachrichtigungs-E-Mails.
+help-append-bib = Die .bib-Datei, an die angehängt wird.
+help-check-bib = Die zu prüfende .bib-Datei.
+help-require-doi = Meldet Einträge ohne DOI.
+help-require-resolvable = Meldet Einträge, deren DOI, arXiv-ID oder URL sich nicht mehr auflösen lässt.
+help-check-hashes = Ruft Einträge mit einem Feld sourcehash erneut ab und meldet die, deren Metadaten sich seither geändert haben.
+help-verify-json = Gibt den Bericht als JSON aus.
+help-overwrite = Ersetzt auch bereits vorhandene Felder und den Eintragstyp durch die abgerufenen.
+help-convert-to = Das Zielformat.
+help-list-journal = Listet die Werke der Zeitschrift mit dieser ISSN auf.
+help-list-funder = Listet die Werke auf, die der Förderer mit dieser ID der Open Funder Registry gefördert hat, z. B. 100000001.
+help-list-from = Listet nur Werke auf, die an oder nach diesem Datum erschienen sind, als JJJJ, JJJJ-MM oder JJJJ-MM-TT.
+help-list-until = Listet nur Werke auf, die an oder vor diesem Datum erschienen sind, als JJJJ, JJJJ-MM oder JJJJ-MM-TT.
+help-limit = Hört nach so vielen Werken auf.
+help-ids-input = DOI, arXiv-ID, ADS-Bibcode, PMID, PMCID, Semantic-Scholar- oder OpenAlex-ID des Werks.
+help-ids-json = Gibt die Kennungen als JSON-Objekt statt als Tabelle aus.
+help-paper = DOI oder arXiv-ID des Artikels.
+help-related-limit = Wie viele Artikel empfohlen werden, höchstens 500.
+help-watch-ids = Datei mit einer DOI oder arXiv-ID pro Zeile. Leere Zeilen und Zeilen, die mit # beginnen, werden ignoriert.
+help-watch-ids-interval = Prüft fortlaufend in diesem Abstand, z. B. 12h oder 1d, statt nur einmal.
+help-watch-ids-state = Datei, in der festgehalten wird, was zuletzt gesehen wurde. Standard ist der Name der Liste mit .watch.json.
+help-auth-token = Wird von der Standardeingabe gelesen, wenn es fehlt, was es aus der Shell-Historie heraushält.
+help-feed-limit = Wie viele der neuesten Einträge aufgenommen werden. Standard ist 20.
+help-feed-title = Der Titel des Feeds.
+help-feed-link = Die URL, unter der der Feed veröffentlicht wird, als seine ID.
+help-library-only = Bietet nur Einträge aus der lokalen Bibliothek an, ohne CrossRef zu durchsuchen.
+help-diff = Zeigt die Änderungen an der Datei als Unified Diff und fragt vor dem Schreiben nach.
+help-yes = Schreibt die mit --diff angezeigten Änderungen ohne Nachfrage.
+help-db = Pfad der Bibliotheksdatenbank. Standard ist $D2B_LIBRARY oder library.sqlite im Datenverzeichnis.
+help-images = Fotos oder Bildschirmfotos mit QR-Codes, in jedem Format, das ZBar liest.
+help-ocr = Liest die erste Seite mit Tesseract, wenn der Text eines PDFs keinen Identifikator enthält, wie bei eingescannten Artikeln.
+help-on-conflict = Was mit einem neuen Eintrag geschieht, dessen Schlüssel in der Datei schon ein anderes Werk hat.
+help-notify-url = Sendet jeden neuen oder geänderten Eintrag an diesen Slack-, Discord- oder Matrix-Webhook.
+help-tag = Speichert die aufgelösten Einträge zusätzlich unter diesem Schlagwort in der Bibliothek. Kann mehrfach angegeben werden.
+help-lib-add-tag = Versieht die hinzugefügten Einträge mit Schlagwörtern. Kann mehrfach angegeben werden.
+help-lib-ls-tag = Listet nur Einträge mit einem dieser Schlagwörter auf.
+help-lib-export-tag = Exportiert nur Einträge mit einem dieser Schlagwörter.
+help-lib-feed-tag = Nimmt nur Einträge mit einem dieser Schlagwörter auf.
+about-clipwatch = Überwacht die Zwischenablage und ersetzt kopierte DOIs oder arXiv-IDs durch ihr BibTeX.
+about-rename = Benennt PDFs nach den enthaltenen Artikeln um, anhand ihrer DOI oder arXiv-ID. Ohne --apply wird nur angezeigt, was passieren würde.
+about-sync = Gleicht eine .bib-Datei mit einer Liste von Identifikatoren ab, ergänzt fehlende Einträge und lässt alle anderen unverändert.
+about-import = Erzeugt eine .bib-Datei aus einer aus Zotero oder Mendeley exportierten Bibliothek neu, indem ihre DOIs und arXiv-IDs aufgelöst werden.
+about-mail = Hängt die Artikel aus Benachrichtigungs-E-Mails (mbox-Datei oder Maildir) an eine .bib-Datei an.
+about-verify = Prüft eine .bib-Datei auf Probleme und endet mit Status 1, falls welche gefunden werden, oder mit 3, falls nur Server nicht erreichbar waren.
+about-auth = Verwaltet API-Tokens für Dienste, die welche benötigen.
+about-auth-set = Speichert das Token eines Dienstes in der Zugangsdatei.
+about-auth-rm = Entfernt das Token eines Dienstes aus der Zugangsdatei.
+about-auth-ls = Listet die Dienste mit ihren maskierten Tokens und deren Herkunft auf.
+about-lib = Verwaltet eine lokale Bibliothek aufgelöster Einträge.
+about-lib-add = Löst Identifikatoren auf und fügt ihre Einträge der Bibliothek hinzu.
+about-lib-ls = Listet die Einträge der Bibliothek auf.
+about-lib-rm = Entfernt Einträge anhand ihres Schlüssels aus der Bibliothek.
+about-lib-tag = Versieht einen Eintrag mit Schlagwörtern.
+about-lib-untag = Entfernt Schlagwörter von einem Eintrag.
+about-lib-tags = Listet die Schlagwörter der Bibliothek auf, mit der Anzahl ihrer Einträge.
+about-lib-export = Gibt die Bibliothek oder die Einträge mit bestimmten Schlagwörtern als .bib-Datei aus.
+about-lib-feed = Gibt einen Atom-Feed der zuletzt zur Bibliothek hinzugefügten Einträge aus.
+about-list = Gibt BibTeX für die Werke aus, die eine Zeitschrift oder ein Förderer zwischen zwei Daten veröffentlicht hat, laut CrossRef.
+about-ids = Gibt alle Kennungen eines Werks aus: DOI, arXiv-ID, ADS-Bibcode, PubMed-IDs sowie Semantic-Scholar- und OpenAlex-IDs.
+about-mcp = Stellt die Resolver von d2b LLM-Assistenten als Model-Context-Protocol-Werkzeuge bereit, über stdin und stdout.
+about-complete = Vervollständigt angefangene Titel und Autorennamen mit Einträgen aus der Bibliothek und von CrossRef, für Editor-Plugins, die beim Tippen zitieren.
+about-qr = Löst die DOIs und arXiv-IDs in den QR-Codes von Bildern auf, etwa von Fotos von Postern.
+about-refs = Gibt BibTeX für alle Werke im Literaturverzeichnis eines Artikels aus, laut Semantic Scholar oder OpenAlex.
+about-cited-by = Gibt BibTeX für die Werke aus, die einen Artikel zitieren, laut Semantic Scholar oder OpenAlex.
+about-related = Gibt BibTeX für die Artikel aus, die einem Artikel laut den Empfehlungen von Semantic Scholar am ähnlichsten sind, mit ihrer Ähnlichkeit als Kommentar.
+about-enrich = Ergänzt fehlende Felder der Einträge einer .bib-Datei anhand der DOI oder arXiv-ID in jedem Eintrag; vorhandene Felder bleiben ohne --overwrite unverändert.
+about-merge = Führt .bib-Dateien zusammen, vereint Einträge für dasselbe Werk und benennt Einträge für verschiedene Werke mit gleichem Schlüssel um.
+about-convert = Konvertiert .bib-Dateien ohne Netzwerkzugriff in CSL-JSON, RIS oder Hayagriva-YAML.
+about-rekey = Erzeugt die Schlüssel von .bib-Dateien neu aus einer Vorlage und passt die \cite-Befehle in LaTeX-Quellen an. Ohne --apply wird nur angezeigt, was passieren würde.
+about-undo = Stellt die Dateien wieder her, die der letzte Befehl in einem Verzeichnis überschrieben hat, aus den Sicherungen in .d2b/undo.
+about-watch-ids = Prüft eine Liste von Identifikatoren erneut und meldet neue arXiv-Versionen, Zeitschriften-DOIs für Preprints und Rückzüge seit der letzten Prüfung.
+
+## Errors
+config-unreadable = Konfigurationsdatei konnte nicht gelesen werden: { $error }
+invalid-seconds = Keine gültige Anzahl Sekunden: { $arg }
+invalid-interval = Ungültiges Abfrageintervall!
+invalid-jobs = --jobs muss eine positive Zahl sein!
+missing-arguments = Fehlende Argumente!
+file-unreadable = { $file } konnte nicht gelesen werden: { $error }
+file-unwritable = { $file } konnte nicht geschrieben werden: { $error }
+library-error = Fehler in der Bibliothek: { $error }
+invalid-input = Keine DOI, arXiv-ID, ISBN oder URL!
+ndjson-bad-line = Weder ein JSON-String noch ein Objekt mit "id"
+malformed-identifier = Fehlerhafter Identifikator!
+inexact-identifier = Nicht genau ein Identifikator (--strict-ids)!
+unconfirmed-arxiv = Weder eine arXiv-ID noch ein DataCite bekanntes DOI-Suffix!
+guessed-doi = { $input } ist keine arXiv-ID; wird als DOI { $doi } aufgelöst
+unreachable = { $resolver } ist nicht erreichbar: { $error }
+no-record = { $resolver } kennt diesen Identifikator nicht
+service-error = { $resolver } antwortete mit { $status }
+bad-bibtex = Das BibTeX von doi.org konnte nicht gelesen werden: { $error }
+bad-feed = Die Antwort von arXiv konnte nicht gelesen werden: { $error }
+no-book = Kein Buch zu dieser ISBN gefunden!
+no-metadata = Keine Zitationsdaten unter { $url } gefunden!
+no-snapshot = Die Wayback Machine hat keinen Snapshot von { $url }
+no-arxiv = Diese arXiv-ID gibt es nicht!
+invalid-entry = Der Eintrag hat die BibTeX-Prüfung nicht bestanden!
+invalid-date = Kein Datum der Form JJJJ, JJJJ-MM oder JJJJ-MM-TT: { $arg }
+invalid-limit = --limit muss eine positive Zahl sein!
+invalid-duration = Keine Dauer wie 30m, 12h oder 1d: { $arg }
+rekey-no-bib = Keine .bib-Datei angegeben!
+no-bib-configured = Keine .bib-Datei mit --bib angegeben oder mit `bib` in config.toml konfiguriert!
+graph-invalid-input = Keine DOI oder arXiv-ID!
+
+## Status
+auth-prompt = Token für { $service }:
+auth-empty = Das Token ist leer!
+auth-missing = { $resolver } braucht einen API-Schlüssel: mit `d2b auth set { $service }` setzen
+no-doi = { $resolver } kennt keine DOI dafür
+pdf-saved = { $key }: { $path } gespeichert
+note-saved = { $key }: { $path } geschrieben
+note-exists = { $path } existiert bereits und bleibt unverändert
+no-entry-with-key = kein Eintrag mit dem Schlüssel { $key }
+library-added = { $key } hinzugefügt
+library-feed-title = Neu in der Bibliographie
+sync-summary = { $file }: { $added } hinzugefügt, { $removed } entfernt
+conflict-prompt = { $key } ist schon „{ $existing }“. Für „{ $new }“: Schlüssel mit [s]uffix versehen, überspringen [k] oder Eintrag e[r]setzen?
+conflict-suffixed = { $key } gehört schon zu einem anderen Werk; als { $renamed } angefügt
+conflict-skipped = { $key } gehört schon zu einem anderen Werk; { $input } übersprungen
+conflict-replaced = { $key } gehört schon zu einem anderen Werk; durch { $input } ersetzt
+waiting-for-lock = warte darauf, dass ein anderer d2b-Prozess { $file } freigibt
+undo-restored = { $file } auf den Stand vor { $time } zurückgesetzt
+undo-nothing = in { $dir } gibt es nichts rückgängig zu machen
+undo-failed = Rückgängigmachen fehlgeschlagen: { $error }
+diff-confirm = Diese Änderungen in { $file } schreiben? [j/N]
+diff-declined = { $file } unverändert gelassen
+ids-unknown-input = { $input } ist keine Kennung eines Werks
+qr-no-zbar = zum Lesen von QR-Codes wird zbarimg aus ZBar benötigt (https://github.com/mchehab/zbar)
+qr-unreadable = QR-Codes in { $file } konnten nicht gelesen werden: { $error }
+qr-none = kein QR-Code mit Kennung oder Link in { $file }
+import-invalid = { $file } ist kein gültiger JSON-Export: { $error }
+import-no-id = keine DOI oder arXiv-ID für „{ $title }“, übersprungen
+import-summary = { $entries } Einträge aus { $items } Elementen
+mail-found = { $ids } Identifikatoren in { $messages } Nachrichten gefunden
+enrich-filled = { $key }: { $fields } ergänzt
+enrich-summary = { $file }: { $enriched } von { $checked } Einträgen mit Identifikator ergänzt
+merge-duplicate = { $dropped } ist dasselbe Werk wie { $kept }; in { $kept } zusammengeführt
+merge-key-conflict = { $key } bezeichnet verschiedene Werke; umbenannt in { $keys }
+merge-summary = { $entries } Einträge aus { $files } Dateien, { $duplicates } Duplikate zusammengeführt
+verify-summary = { $file }: { $entries } Einträge, { $problems } Probleme
+rename-no-identifier = { $file }: keine DOI oder arXiv-ID gefunden
+ocr-missing-tool = zum Lesen gescannter PDFs wird { $tool } benötigt; installieren Sie Poppler und Tesseract (https://github.com/tesseract-ocr/tesseract)
+ocr-failed = { $file } konnte nicht per OCR gelesen werden: { $error }
+rename-exists = { $file } existiert bereits
+rename-dry-run = (Probelauf; mit --apply wird umbenannt)
+rekey-summary = { $keys } Schlüssel in { $files } Dateien geändert
+rekey-dry-run = (Probelauf; mit --apply werden die Dateien geändert)
+clipboard-unavailable = Kein Zugriff auf die Zwischenablage: { $error }
+clipboard-unwritable = Die Zwischenablage konnte nicht beschrieben werden: { $error }
+clipboard-watching = Die Zwischenablage wird auf DOIs und arXiv-IDs überwacht. Beenden mit Strg-C.
+clipboard-unresolved = { $input } konnte nicht aufgelöst werden
+clipboard-changed = { $input } wurde aufgelöst, aber die Zwischenablage hat sich inzwischen geändert
+clipboard-copied = BibTeX für { $input } kopiert
+summary = { $resolved } aufgelöst, { $cached } aus dem Checkpoint, { $failed } fehlgeschlagen in { $seconds } s
+report-title = d2b-Auflösungsbericht
+report-summary = { $inputs } Eingaben: { $resolved } aufgelöst, { $merged } zusammengeführt, { $failed } fehlgeschlagen, am { $date }
+report-input = Eingabe
+report-status = Status
+report-citation = Zitation
+report-links = Links
+report-resolved = aufgelöst
+report-merged = dasselbe Werk wie { $input }
+report-failed = fehlgeschlagen
+report-skipped = übersprungen
+fixture-missing = Keine aufgezeichnete Antwort für { $url } (erwartet: { $name }.json)
+hook-failed = --post-hook { $command } ist fehlgeschlagen: { $error }
+hook-bad-output = es hat keinen Eintrag als JSON ausgegeben
+graph-skipped = { $count } Werke ohne DOI oder arXiv-ID übersprungen
+watch-new-version = { $input }: neue Version { $version }
+watch-published = { $input }: veröffentlicht als { $doi }
+watch-retracted = { $input }: zurückgezogen
+watch-unchecked = { $input }: konnte nicht geprüft werden; nächstes Mal erneut
+watch-unsupported = { $input }: nur DOIs und arXiv-IDs können beobachtet werden
+webhook-filed = Neuer Artikel abgelegt: { $input }
+webhook-failed = Benachrichtigung an { $url } fehlgeschlagen: { $error }
@@ -0,0 +1,252 @@
+# English messages, which every other locale falls back to.
+#
+# The syntax is a subset of Fluent (https://projectfluent.org): one `id = message` per line, with
+# `{ $name }` for arguments. Comments start with `#`.
+
+error = error
+warning = warning
+
+## Help
+help-file = Read DOIs or arXiv identifiers from a file (one per line).
+help-input = DOI(s) or arXiv identifier(s) to search for, separated by spaces.
+help-config = Configuration file to use. Defaults to $D2B_CONFIG, or d2b/config.toml in the config directory.
+help-escape = Escapes the "&" (ampersand) character.
+help-annotate = Prefixes each entry with a comment recording its input, resolver and retrieval date.
+help-strict = Fails on entries with BibTeX problems instead of repairing them and warning.
+help-strict-ids = Rejects inputs that aren't exactly an identifier, instead of picking the identifier out of them.
+help-no-backup = Doesn't keep a copy of the files a command rewrites for `d2b undo`.
+help-no-sanitize = Passes publisher BibTeX through without escaping special characters or normalizing quotes and ligatures.
+help-strip-math = Strips $...$ math markup from arXiv titles instead of preserving it.
+help-dblp = Looks up venue acronyms for ACM and IEEE conference papers on DBLP.
+help-error-format = How errors are written to stderr. json writes one object per line.
+help-color = Highlights BibTeX written to standard output. auto does so when it's a terminal.
+help-format = Output dialect. biblatex enables entry types such as @dataset and @software; csv and tsv write a table with the --columns; alfred and dmenu write choices for launchers.
+help-screening = Writes the entries, with their abstracts, for import into a systematic-review screening tool: RIS for covidence, CSV for rayyan.
+help-columns = The comma-separated columns of --format csv or tsv: key, type, authors, year, title, venue, doi, url, citations or any other field.
+help-prefer = For arXiv papers with a DOI, whether to emit the published or the eprint entry.
+help-romanize = Romanizes Cyrillic and CJK author names, keeping the originals in origauthor (biblatex) or note.
+help-with-funding = Adds funders and grant numbers from CrossRef, in funding (biblatex) or note.
+help-with-license = Adds the license of the work, from CrossRef, DataCite or arXiv, in a license field.
+help-archive = For web pages without a DOI, saves a Wayback Machine snapshot and cites it with the access date.
+help-hash = Adds a sourcehash field with a SHA-256 of the metadata each entry was built from, for d2b verify --check-hashes.
+help-follow-published = For preprint DOIs (bioRxiv, SSRN, ...), emits the published version CrossRef links to instead.
+help-emit-cite = Also prints the citation command for each entry to standard error, ready to paste.
+help-with-papers = For proceedings volumes, also emits entries for every paper they contain.
+help-both = For arXiv papers with a DOI, emits linked entries for both the published version and the eprint.
+help-append = Appends the BibTeX to the clipboard instead of replacing its contents.
+help-clipwatch-interval = Clipboard polling interval in milliseconds.
+help-sort = Order of the entries in the output.
+help-group-by = Sorts the entries into sections by year or entry type, each headed by a comment.
+help-ndjson = Reads identifiers as JSON lines on stdin and writes each result as a JSON line as soon as it resolves.
+help-jobs = How many inputs are resolved at once with --ndjson.
+help-checkpoint = Records each input in FILE as it resolves, so that an interrupted run can be resumed, only retrying what hadn't resolved.
+help-summary = Prints how many inputs resolved and how long each host took to answer to stderr.
+help-metrics-file = Writes the same statistics to FILE in the Prometheus text format.
+help-insert-key-only = Appends the entries to the --bib file, or the one configured, unless it has them already, and prints only their keys, for editor mappings that insert a citation.
+help-insert-bib = The .bib file --insert-key-only appends to, instead of the configured one.
+help-report = Writes an HTML page to FILE listing each input with whether it resolved, its citation and links, for sharing the results.
+help-fetch-pdf = Downloads the full text (from arXiv, or open access via Unpaywall) into DIR, named after the key.
+help-obsidian = Also writes an Obsidian literature note for each entry into DIR, with the --tag values as tags. Existing notes are kept.
+help-hugo = Also writes a publication page (KEY/index.md and KEY/cite.bib) for each entry into DIR, as Hugo academic themes expect in content/publication. Existing files are kept.
+help-http1 = Only uses HTTP/1.1, for proxies that break HTTP/2.
+help-pool-idle-timeout = How long idle connections are kept for reuse. 0 opens a new connection for every request.
+help-tcp-keepalive = Interval of TCP keepalive probes on open connections.
+help-doi-resolver = Resolves every DOI through this backend, instead of the configured order or the one picked for the DOI's publisher.
+help-race = Queries CrossRef, DataCite and doi.org for each DOI at once and takes the first complete entry, instead of trying them in order.
+help-best-of = Queries every DOI backend and keeps the most complete entry (pages, volume, all authors, venue).
+help-merge-sources = Like --best-of, but also fills in the fields the most complete entry lacks from the other backends' entries.
+help-journal-strings = Writes journal names as @string macros: those of AASTeX (aas), IEEEtran (ieee), or the @strings of a .bib file.
+help-template = Writes each entry out through the Handlebars template FILE instead of as BibTeX.
+help-only-category = Only prints entries in this arXiv category (cs.LG) or archive (cs). Can be given more than once.
+help-style = Writes each entry out as a formatted reference in this style (ISO 690 or GOST R 7.0.5) instead of as BibTeX.
+help-post-hook = Pipes every entry through the shell command CMD as JSON, and uses the entry it prints instead.
+help-record = Stores every response in DIR, for --replay.
+help-replay = Answers requests from the responses stored in DIR by --record instead of the network.
+help-http-debug = Prints the status, protocol version, peer address and time of every response to stderr.
+help-rename-template = File name template, with {key}, {year}, {author}, {title}, {shorttitle}, {journal} and {category} placeholders.
+help-rename-apply = Renames the files instead of only printing the new names.
+help-undo-dir = The directory whose files to restore. Defaults to the current one.
+help-undo-list = Lists the runs that can be undone, newest first, instead.
+help-rekey-files = The .bib files to rekey and the LaTeX sources citing them.
+help-key-format = Key template, with the placeholders of `d2b rename --template`.
+help-rekey-apply = Rewrites the files instead of only printing a diff.
+help-manifest = File with one identifier per line. Blank lines and lines starting with # are ignored.
+help-update-bib = The .bib file to update.
+help-prune = Also removes entries that no identifier in the manifest refers to.
+help-import-export = A library exported from Zotero (RDF or JSON) or Mendeley (JSON).
+help-output = Writes to this file instead of standard output.
+help-mailbox = An mbox file or a Maildir directory of alert emails.
+help-append-bib = The .bib file to append to.
+help-check-bib = The .bib file to check.
+help-require-doi = Reports entries without a DOI.
+help-require-resolvable = Reports entries whose DOI, arXiv ID or URL no longer resolves.
+help-check-hashes = Re-fetches entries with a sourcehash field and reports those whose metadata changed since.
+help-verify-json = Prints the report as JSON.
+help-overwrite = Also replaces fields that are already there, and the entry type, with the fetched ones.
+help-convert-to = The format to convert to.
+help-list-journal = Lists the works of the journal with this ISSN.
+help-list-funder = Lists the works funded by the funder with this Open Funder Registry ID, e.g. 100000001.
+help-list-from = Only lists works published on or after this date, as YYYY, YYYY-MM or YYYY-MM-DD.
+help-list-until = Only lists works published on or before this date, as YYYY, YYYY-MM or YYYY-MM-DD.
+help-limit = Stops after this many works.
+help-ids-input = DOI, arXiv ID, ADS bibcode, PMID, PMCID, Semantic Scholar or OpenAlex ID of the work.
+help-ids-json = Prints the identifiers as a JSON object instead of a table.
+help-paper = DOI or arXiv ID of the paper.
+help-related-limit = How many papers to recommend, at most 500.
+help-watch-ids = File with one DOI or arXiv ID per line. Blank lines and lines starting with # are ignored.
+help-watch-ids-interval = Keeps checking this often, e.g. 12h or 1d, instead of checking once.
+help-watch-ids-state = File to keep what was last seen in. Defaults to the list's name with .watch.json.
+help-auth-token = Read from standard input if omitted, which keeps it out of the shell history.
+help-feed-limit = How many of the latest entries to include. Defaults to 20.
+help-feed-title = The title of the feed.
+help-feed-link = The URL the feed will be published at, used as its ID.
+help-library-only = Only offers entries from the local library, without searching CrossRef.
+help-diff = Shows the changes to the file as a unified diff and asks before writing them.
+help-yes = Writes the changes shown with --diff without asking.
+help-db = Path of the library database. Defaults to $D2B_LIBRARY, or library.sqlite in the data directory.
+help-images = Photos or screenshots with QR codes in them, in any format ZBar reads.
+help-ocr = Reads the first page with Tesseract when a PDF has no identifier in its text, as scanned papers don't.
+help-on-conflict = What to do with a new entry whose key is taken by a different work in the file.
+help-notify-url = Posts each new or changed entry to this Slack, Discord or Matrix webhook.
+help-tag = Also stores the resolved entries in the library under this tag. May be given more than once.
+help-lib-add-tag = Tags the added entries. May be given more than once.
+help-lib-ls-tag = Only lists entries with any of these tags.
+help-lib-export-tag = Only exports entries with any of these tags.
+help-lib-feed-tag = Only includes entries with any of these tags.
+about-clipwatch = Watches the clipboard and replaces copied DOIs or arXiv IDs with their BibTeX.
+about-rename = Renames PDFs after the papers in them, found through their DOI or arXiv ID. Dry run unless --apply is given.
+about-sync = Updates a .bib file to match a manifest of identifiers, adding missing entries and leaving the rest untouched.
+about-import = Regenerates a .bib file from a library exported from Zotero or Mendeley, by resolving its DOIs and arXiv IDs.
+about-mail = Appends the papers in alert emails (an mbox file or Maildir) to a .bib file.
+about-verify = Checks a .bib file for problems, exiting with status 1 if there are any, or 3 if the only ones are servers that could not be reached.
+about-auth = Manages API tokens for services that need them.
+about-auth-set = Stores the token for a service in the credentials file.
+about-auth-rm = Removes the token for a service from the credentials file.
+about-auth-ls = Lists services, their masked tokens and where each was found.
+about-lib = Manages a local library of resolved entries.
+about-lib-add = Resolves identifiers and adds their entries to the library.
+about-lib-ls = Lists the entries in the library.
+about-lib-rm = Removes entries from the library by key.
+about-lib-tag = Adds tags to an entry.
+about-lib-untag = Removes tags from an entry.
+about-lib-tags = Lists the tags in the library, with how many entries have each.
+about-lib-export = Prints the library, or the entries with some tags, as a .bib file.
+about-lib-feed = Prints an Atom feed of the entries most recently added to the library.
+about-list = Prints BibTeX for the works a journal or funder published between two dates, from CrossRef.
+about-ids = Prints every identifier of a work: DOI, arXiv ID, ADS bibcode, PubMed IDs, Semantic Scholar and OpenAlex IDs.
+about-mcp = Serves d2b's resolvers to LLM assistants as Model Context Protocol tools, over stdin and stdout.
+about-complete = Completes partly typed titles and author names with entries from the library and CrossRef, for cite-as-you-type editor plugins.
+about-qr = Resolves the DOIs and arXiv IDs in the QR codes of images, such as photos of posters.
+about-refs = Prints BibTeX for every work in a paper's reference list, from Semantic Scholar or OpenAlex.
+about-cited-by = Prints BibTeX for the works citing a paper, from Semantic Scholar or OpenAlex.
+about-related = Prints BibTeX for the papers most related to one, from Semantic Scholar's recommendations, with their similarity in comments.
+about-enrich = Fills in missing fields of the entries in a .bib file from the DOI or arXiv ID found in each, leaving existing fields alone unless --overwrite is given.
+about-merge = Merges .bib files, combining entries for the same work and renaming entries for different works that share a key.
+about-convert = Converts .bib files to CSL-JSON, RIS or Hayagriva YAML, without going online.
+about-rekey = Regenerates the keys of .bib files from a template and updates the \cite commands in LaTeX sources to match. Dry run unless --apply is given.
+about-undo = Restores the files the last command rewrote in a directory, from the backups kept in .d2b/undo.
+about-watch-ids = Re-checks a list of identifiers and reports new arXiv versions, journal DOIs for preprints and retractions since the last check.
+
+## Errors
+config-unreadable = Could not read configuration file { $error }
+invalid-seconds = Not a number of seconds: { $arg }
+invalid-interval = Invalid polling interval!
+invalid-jobs = --jobs must be a positive number!
+missing-arguments = Missing arguments!
+file-unreadable = Could not read { $file }: { $error }
+file-unwritable = Could not write { $file }: { $error }
+library-error = Library error: { $error }
+invalid-input = Not a DOI, arXiv ID, ISBN or URL!
+ndjson-bad-line = Not a JSON string or an object with an "id"
+malformed-identifier = Malformed identifier!
+inexact-identifier = Not exactly an identifier (--strict-ids)!
+unconfirmed-arxiv = Neither an arXiv ID nor a DOI suffix DataCite knows!
+guessed-doi = { $input } isn't an arXiv ID; resolving it as the DOI { $doi }
+unreachable = Could not reach { $resolver }: { $error }
+no-record = { $resolver } has no record of it
+service-error = { $resolver } returned { $status }
+bad-bibtex = Could not parse BibTeX from doi.org: { $error }
+bad-feed = Could not parse the arXiv response: { $error }
+no-book = No book found for ISBN!
+no-metadata = No citation metadata found at { $url }!
+no-snapshot = The Wayback Machine has no snapshot of { $url }
+no-arxiv = No such arXiv ID!
+invalid-entry = Entry failed BibTeX validation!
+invalid-date = Not a date in the form YYYY, YYYY-MM or YYYY-MM-DD: { $arg }
+invalid-limit = --limit must be a positive number!
+invalid-duration = Not a duration such as 30m, 12h or 1d: { $arg }
+rekey-no-bib = No .bib file given!
+no-bib-configured = No .bib file given with --bib or configured with `bib` in config.toml!
+graph-invalid-input = Not a DOI or arXiv ID!
+
+## Status
+auth-prompt = Token for { $service }:
+auth-empty = The token is empty!
+auth-missing = { $resolver } needs an API key: set one with `d2b auth set { $service }`
+no-doi = { $resolver } has no DOI for it
+pdf-saved = { $key }: saved { $path }
+note-saved = { $key }: wrote { $path }
+note-exists = { $path } already exists; left as is
+no-entry-with-key = no entry with key { $key }
+library-added = added { $key }
+library-feed-title = Latest additions to the bibliography
+sync-summary = { $file }: { $added } added, { $removed } removed
+conflict-prompt = { $key } is already “{ $existing }”. For “{ $new }”: [s]uffix the key, s[k]ip it or [r]eplace the entry?
+conflict-suffixed = { $key } is taken by a different work; appended as { $renamed }
+conflict-skipped = { $key } is taken by a different work; skipped { $input }
+conflict-replaced = { $key } is taken by a different work; replaced it with { $input }
+waiting-for-lock = waiting for another d2b process to finish with { $file }
+undo-restored = restored { $file } as it was before { $time }
+undo-nothing = nothing to undo in { $dir }
+undo-failed = could not undo: { $error }
+diff-confirm = Write these changes to { $file }? [y/N]
+diff-declined = { $file } left unchanged
+ids-unknown-input = { $input } is not an identifier of a work
+qr-no-zbar = reading QR codes needs zbarimg, from ZBar (https://github.com/mchehab/zbar)
+qr-unreadable = could not read QR codes in { $file }: { $error }
+qr-none = no QR code with an identifier or link in { $file }
+import-invalid = { $file } is not a valid JSON export: { $error }
+import-no-id = no DOI or arXiv ID for “{ $title }”, skipped
+import-summary = { $entries } entries from { $items } items
+mail-found = found { $ids } identifiers in { $messages } messages
+enrich-filled = { $key }: filled { $fields }
+enrich-summary = { $file }: { $enriched } of { $checked } entries with an identifier enriched
+merge-duplicate = { $dropped } is the same work as { $kept }; merged into { $kept }
+merge-key-conflict = { $key } names different works; renamed them { $keys }
+merge-summary = { $entries } entries from { $files } files, { $duplicates } duplicates merged
+verify-summary = { $file }: { $entries } entries, { $problems } problems
+rename-no-identifier = { $file }: no DOI or arXiv ID found
+ocr-missing-tool = reading scanned PDFs needs { $tool }; install Poppler and Tesseract (https://github.com/tesseract-ocr/tesseract)
+ocr-failed = could not read { $file } with OCR: { $error }
+rename-exists = { $file } already exists
+rename-dry-run = (dry run; pass --apply to rename)
+rekey-summary = { $keys } keys changed in { $files } files
+rekey-dry-run = (dry run; pass --apply to rewrite the files)
+clipboard-unavailable = Could not access the clipboard: { $error }
+clipboard-unwritable = Could not write to the clipboard: { $error }
+clipboard-watching = Watching the clipboard for DOIs and arXiv IDs. Press Ctrl-C to stop.
+clipboard-unresolved = Could not resolve { $input }
+clipboard-changed = Resolved { $input }, but the clipboard changed
+clipboard-copied = Copied BibTeX for { $input }
+summary = { $resolved } resolved, { $cached } from the checkpoint, { $failed } failed in { $seconds } s
+report-title = d2b resolution report
+report-summary = { $inputs } inputs: { $resolved } resolved, { $merged } merged, { $failed } failed, on { $date }
+report-input = Input
+report-status = Status
+report-citation = Citation
+report-links = Links
+report-resolved = resolved
+report-merged = same work as { $input }
+report-failed = failed
+report-skipped = skipped
+fixture-missing = No recorded response for { $url } (expected { $name }.json)
+hook-failed = --post-hook { $command } failed: { $error }
+hook-bad-output = it did not print an entry as JSON
+graph-skipped = skipped { $count } works without a DOI or arXiv ID
+watch-new-version = { $input }: new version { $version }
+watch-published = { $input }: published as { $doi }
+watch-retracted = { $input }: retracted
+watch-unchecked = { $input }: could not be checked; will try again next time
+watch-unsupported = { $input }: only DOIs and arXiv IDs can be watched
+webhook-filed = New paper filed: { $input }
+webhook-failed = Could not notify { $url }: { $error }
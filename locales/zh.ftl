@@ -0,0 +1,249 @@
+# 简体中文消息。
+
+error = 错误
+warning = 警告
+
+## Help
+help-file = 从文件中读取 DOI 或 arXiv 标识符（每行一个）。
+help-input = 要查找的 DOI 或 arXiv 标识符，用空格分隔。
+help-config = 要使用的配置文件。默认为 $D2B_CONFIG，或配置目录中的 d2b/config.toml。
+help-escape = 转义 "&"（和号）字符。
+help-annotate = 在每个条目前加一条注释，记录其输入、解析器和获取日期。
+help-strict = 遇到有 BibTeX 问题的条目时报错，而不是修复并给出警告。
+help-strict-ids = 拒绝不完全是标识符的输入，而不是从中提取标识符。
+help-no-backup = 不为 `d2b undo` 保留命令所改写文件的副本。
+help-no-sanitize = 原样输出出版商的 BibTeX，不转义特殊字符，也不规范引号和连字。
+help-strip-math = 去掉 arXiv 标题中的 $...$ 数学标记，而不是保留它。
+help-dblp = 在 DBLP 上查找 ACM 和 IEEE 会议论文的会议缩写。
+help-error-format = 错误写入 stderr 的方式。json 每行写一个对象。
+help-color = 高亮写到标准输出的 BibTeX。auto 在标准输出是终端时高亮。
+help-format = 输出格式。biblatex 启用 @dataset、@software 等条目类型；csv 和 tsv 按 --columns 输出表格；alfred 和 dmenu 输出供启动器使用的选项。
+help-screening = 输出带摘要的条目，供导入系统综述筛选工具：covidence 用 RIS，rayyan 用 CSV。
+help-columns = --format csv 或 tsv 的列，用逗号分隔：key、type、authors、year、title、venue、doi、url、citations 或任何其他字段。
+help-prefer = 对于有 DOI 的 arXiv 论文，输出正式发表版本还是预印本条目。
+help-romanize = 将西里尔文和中日韩作者姓名转写为拉丁字母，原名保留在 origauthor（biblatex）或 note 中。
+help-with-funding = 添加来自 CrossRef 的资助机构和资助编号，写入 funding（biblatex）或 note。
+help-with-license = 在 license 字段中添加作品的许可证，来自 CrossRef、DataCite 或 arXiv。
+help-archive = 对于没有 DOI 的网页，在 Wayback Machine 中保存快照，并连同访问日期一起引用。
+help-hash = 添加 sourcehash 字段，记录生成每个条目所用元数据的 SHA-256，供 d2b verify --check-hashes 使用。
+help-follow-published = 对于预印本 DOI（bioRxiv、SSRN 等），改为输出 CrossRef 链接到的正式发表版本。
+help-emit-cite = 同时把每个条目的引用命令输出到标准错误，便于直接粘贴。
+help-with-papers = 对于会议论文集，同时输出其中每篇论文的条目。
+help-both = 对于有 DOI 的 arXiv 论文，同时输出相互关联的正式发表版本和预印本条目。
+help-append = 把 BibTeX 追加到剪贴板内容之后，而不是替换它。
+help-clipwatch-interval = 剪贴板轮询间隔，单位为毫秒。
+help-sort = 输出中条目的顺序。
+help-group-by = 按年份或条目类型把条目分成若干节，每节以一条注释作为标题。
+help-ndjson = 从 stdin 按 JSON 行读取标识符，每个结果一解析完成就写出一行 JSON。
+help-jobs = 使用 --ndjson 时同时解析的输入数量。
+help-checkpoint = 每解析完一个输入就记录到 FILE 中，使中断的运行可以继续，只重试尚未解析的输入。
+help-summary = 在 stderr 上输出解析成功的输入数量以及每个主机的响应耗时。
+help-metrics-file = 以 Prometheus 文本格式把同样的统计数据写入 FILE。
+help-insert-key-only = 把条目追加到 --bib 指定的或配置的文件中（文件中尚无这些条目时），只输出它们的键，供插入引用的编辑器快捷键使用。
+help-insert-bib = --insert-key-only 追加到的 .bib 文件，代替配置的文件。
+help-report = 向 FILE 写入一个 HTML 页面，列出每个输入是否解析成功、其引用和链接，便于分享结果。
+help-fetch-pdf = 把全文（来自 arXiv，或通过 Unpaywall 获取的开放获取版本）下载到 DIR，以键命名。
+help-obsidian = 同时为每个条目在 DIR 中写一篇 Obsidian 文献笔记，以 --tag 的值作为标签。已有笔记保持不变。
+help-hugo = 同时为每个条目在 DIR 中写一个出版物页面（KEY/index.md 和 KEY/cite.bib），符合 Hugo 学术主题在 content/publication 中的要求。已有文件保持不变。
+help-http1 = 只使用 HTTP/1.1，用于不支持 HTTP/2 的代理。
+help-pool-idle-timeout = 空闲连接保留以供复用的时长。0 表示每个请求都新建连接。
+help-tcp-keepalive = 打开的连接上 TCP keepalive 探测的间隔。
+help-doi-resolver = 通过此后端解析所有 DOI，而不是按配置的顺序或按 DOI 出版商选定的后端。
+help-race = 对每个 DOI 同时查询 CrossRef、DataCite 和 doi.org，取第一个完整的条目，而不是依次尝试。
+help-best-of = 查询所有 DOI 后端，保留最完整的条目（页码、卷、全部作者、出处）。
+help-merge-sources = 与 --best-of 相同，但还会用其他后端的条目补全最完整条目中缺少的字段。
+help-journal-strings = 把期刊名写成 @string 宏：AASTeX（aas）、IEEEtran（ieee）的宏，或某个 .bib 文件中的 @string。
+help-template = 用 Handlebars 模板 FILE 输出每个条目，而不是输出 BibTeX。
+help-only-category = 只输出此 arXiv 分类（cs.LG）或档案（cs）中的条目。可以多次指定。
+help-style = 按此格式（ISO 690 或 GOST R 7.0.5）把每个条目输出为格式化的参考文献，而不是 BibTeX。
+help-post-hook = 把每个条目以 JSON 形式传给 shell 命令 CMD，并改用该命令输出的条目。
+help-record = 把每个响应保存到 DIR 中，供 --replay 使用。
+help-replay = 用 --record 保存在 DIR 中的响应来应答请求，而不访问网络。
+help-http-debug = 在 stderr 上输出每个响应的状态、协议版本、对端地址和耗时。
+help-rename-template = 文件名模板，可用占位符 {key}、{year}、{author}、{title}、{shorttitle}、{journal} 和 {category}。
+help-rename-apply = 实际重命名文件，而不只是输出新文件名。
+help-undo-dir = 要恢复其中文件的目录。默认为当前目录。
+help-undo-list = 改为列出可以撤销的运行，最新的在前。
+help-rekey-files = 要重新生成键的 .bib 文件，以及引用它们的 LaTeX 源文件。
+help-key-format = 键模板，占位符与 `d2b rename --template` 相同。
+help-rekey-apply = 实际改写文件，而不只是输出差异。
+help-manifest = 每行一个标识符的文件。空行和以 # 开头的行会被忽略。
+help-update-bib = 要更新的 .bib 文件。
+help-prune = 同时删除清单中没有任何标识符指向的条目。
+help-import-export = 从 Zotero（RDF 或 JSON）或 Mendeley（JSON）导出的文献库。
+help-output = 写入此文件，而不是标准输出。
+help-mailbox = 包含提醒邮件的 mbox 文件或 Maildir 目录。
+help-append-bib = 要追加到的 .bib 文件。
+help-check-bib = 要检查的 .bib 文件。
+help-require-doi = 报告没有 DOI 的条目。
+help-require-resolvable = 报告 DOI、arXiv 标识符或 URL 已无法解析的条目。
+help-check-hashes = 重新获取带 sourcehash 字段的条目，报告此后元数据发生变化的条目。
+help-verify-json = 以 JSON 格式输出报告。
+help-overwrite = 同时用获取到的字段和条目类型替换已有的字段和条目类型。
+help-convert-to = 要转换成的格式。
+help-list-journal = 列出具有此 ISSN 的期刊的作品。
+help-list-funder = 列出 Open Funder Registry ID 为此值的资助机构所资助的作品，例如 100000001。
+help-list-from = 只列出在此日期当天或之后发表的作品，格式为 YYYY、YYYY-MM 或 YYYY-MM-DD。
+help-list-until = 只列出在此日期当天或之前发表的作品，格式为 YYYY、YYYY-MM 或 YYYY-MM-DD。
+help-limit = 达到此数量的作品后停止。
+help-ids-input = 作品的 DOI、arXiv 标识符、ADS bibcode、PMID、PMCID、Semantic Scholar 或 OpenAlex ID。
+help-ids-json = 以 JSON 对象而不是表格输出标识符。
+help-paper = 论文的 DOI 或 arXiv 标识符。
+help-related-limit = 推荐的论文数量，最多 500 篇。
+help-watch-ids = 每行一个 DOI 或 arXiv 标识符的文件。空行和以 # 开头的行会被忽略。
+help-watch-ids-interval = 按此间隔（例如 12h 或 1d）持续检查，而不是只检查一次。
+help-watch-ids-state = 记录上次检查结果的文件。默认为清单文件名加 .watch.json。
+help-auth-token = 省略时从标准输入读取，这样令牌不会留在 shell 历史中。
+help-feed-limit = 包含的最新条目数量。默认为 20。
+help-feed-title = Feed 的标题。
+help-feed-link = Feed 将发布的 URL，用作其 ID。
+help-library-only = 只提供本地文献库中的条目，不搜索 CrossRef。
+help-diff = 以统一差异格式显示对文件的更改，并在写入前询问。
+help-yes = 不经询问写入 --diff 显示的更改。
+help-db = 文献库数据库的路径。默认为 $D2B_LIBRARY，或数据目录中的 library.sqlite。
+help-images = 含有二维码的照片或截图，可以是 ZBar 能读取的任何格式。
+help-ocr = 当 PDF 的文本中没有标识符时（扫描论文通常如此），用 Tesseract 识别第一页。
+help-on-conflict = 当新条目的键已被文件中另一作品占用时的处理方式。
+help-notify-url = 把每个新增或更改的条目发送到此 Slack、Discord 或 Matrix webhook。
+help-tag = 同时把解析得到的条目以此标签存入文献库。可以多次指定。
+help-lib-add-tag = 为添加的条目加上标签。可以多次指定。
+help-lib-ls-tag = 只列出带有其中任一标签的条目。
+help-lib-export-tag = 只导出带有其中任一标签的条目。
+help-lib-feed-tag = 只包含带有其中任一标签的条目。
+about-clipwatch = 监视剪贴板，把复制的 DOI 或 arXiv 标识符替换为对应的 BibTeX。
+about-rename = 根据 PDF 中的 DOI 或 arXiv 标识符，用论文信息重命名 PDF。不加 --apply 时只显示将要进行的操作。
+about-sync = 按标识符清单更新 .bib 文件：添加缺少的条目，其余条目保持不变。
+about-import = 解析从 Zotero 或 Mendeley 导出的文献库中的 DOI 和 arXiv ID，重新生成 .bib 文件。
+about-mail = 把提醒邮件（mbox 文件或 Maildir）中的论文追加到 .bib 文件。
+about-verify = 检查 .bib 文件中的问题，发现问题时以状态码 1 退出；若问题仅为服务器无法访问，则以状态码 3 退出。
+about-auth = 管理需要 API 令牌的服务的令牌。
+about-auth-set = 把某个服务的令牌保存到凭据文件中。
+about-auth-rm = 从凭据文件中删除某个服务的令牌。
+about-auth-ls = 列出各服务、隐去大部分内容的令牌及其来源。
+about-lib = 管理本地的文献库。
+about-lib-add = 解析标识符并把条目加入文献库。
+about-lib-ls = 列出文献库中的条目。
+about-lib-rm = 按引用键从文献库中删除条目。
+about-lib-tag = 给条目添加标签。
+about-lib-untag = 删除条目的标签。
+about-lib-tags = 列出文献库中的标签及每个标签下的条目数。
+about-lib-export = 把文献库或带有指定标签的条目输出为 .bib 文件。
+about-lib-feed = 输出文献库中最近添加条目的 Atom 订阅源。
+about-list = 从 CrossRef 输出某期刊或资助机构在两个日期之间发表的所有作品的 BibTeX。
+about-ids = 列出作品的所有标识符：DOI、arXiv ID、ADS bibcode、PubMed ID、Semantic Scholar 和 OpenAlex ID。
+about-mcp = 通过标准输入输出，以 Model Context Protocol 工具的形式向 LLM 助手提供 d2b 的解析器。
+about-complete = 用文献库和 CrossRef 中的条目补全输入了一部分的标题和作者名，供边输入边引用的编辑器插件使用。
+about-qr = 解析图像（如海报照片）中二维码所含的 DOI 和 arXiv ID。
+about-refs = 根据 Semantic Scholar 或 OpenAlex 输出某篇论文参考文献中所有作品的 BibTeX。
+about-cited-by = 根据 Semantic Scholar 或 OpenAlex 输出引用某篇论文的作品的 BibTeX。
+about-related = 根据 Semantic Scholar 的推荐输出与某篇论文最相关的论文的 BibTeX，并在注释中给出相似度。
+about-enrich = 根据 .bib 文件中每个条目里的 DOI 或 arXiv 标识符补全缺失字段；除非指定 --overwrite，否则不改动已有字段。
+about-merge = 合并多个 .bib 文件：同一作品的条目合而为一，键相同的不同作品会被重命名。
+about-convert = 将 .bib 文件离线转换为 CSL-JSON、RIS 或 Hayagriva YAML。
+about-rekey = 按模板重新生成 .bib 文件的键，并相应更新 LaTeX 源文件中的 \cite 命令。除非指定 --apply，否则只做演练。
+about-undo = 从 .d2b/undo 中的备份恢复上一条命令在某目录中改写的文件。
+about-watch-ids = 重新检查一组标识符，报告自上次检查以来的 arXiv 新版本、预印本获得的期刊 DOI 以及撤稿。
+
+## Errors
+config-unreadable = 无法读取配置文件 { $error }
+invalid-seconds = 不是有效的秒数：{ $arg }
+invalid-interval = 轮询间隔无效！
+invalid-jobs = --jobs 必须是正整数！
+missing-arguments = 缺少参数！
+file-unreadable = 无法读取 { $file }：{ $error }
+file-unwritable = 无法写入 { $file }：{ $error }
+library-error = 文献库错误：{ $error }
+invalid-input = 不是 DOI、arXiv 标识符、ISBN 或网址！
+ndjson-bad-line = 既不是 JSON 字符串，也不是带有 "id" 的对象
+malformed-identifier = 标识符格式错误！
+inexact-identifier = 输入不完全是一个标识符（--strict-ids）！
+unconfirmed-arxiv = 既不是 arXiv 标识符，也不是 DataCite 已知的 DOI 后缀！
+guessed-doi = { $input } 不是 arXiv 标识符；按 DOI { $doi } 解析
+unreachable = 无法连接 { $resolver }：{ $error }
+no-record = { $resolver } 没有该标识符的记录
+service-error = { $resolver } 返回 { $status }
+bad-bibtex = 无法解析 doi.org 返回的 BibTeX：{ $error }
+bad-feed = 无法解析 arXiv 的响应：{ $error }
+no-book = 找不到该 ISBN 对应的图书！
+no-metadata = { $url } 没有引用元数据！
+no-snapshot = Wayback Machine 没有 { $url } 的快照
+no-arxiv = 不存在该 arXiv 标识符！
+invalid-entry = 条目未通过 BibTeX 校验！
+invalid-date = 不是 YYYY、YYYY-MM 或 YYYY-MM-DD 格式的日期：{ $arg }
+invalid-limit = --limit 必须是正数！
+invalid-duration = 不是 30m、12h 或 1d 这样的时长：{ $arg }
+rekey-no-bib = 没有指定 .bib 文件！
+no-bib-configured = 没有用 --bib 指定 .bib 文件，也没有在 config.toml 中用 `bib` 配置！
+graph-invalid-input = 不是 DOI 或 arXiv 标识符！
+
+## Status
+auth-prompt = { $service } 的令牌：
+auth-empty = 令牌为空！
+auth-missing = { $resolver } 需要 API 密钥：请用 `d2b auth set { $service }` 设置
+no-doi = { $resolver } 没有它的 DOI
+pdf-saved = { $key }：已保存 { $path }
+note-saved = { $key }：已写入 { $path }
+note-exists = { $path } 已存在，保持不变
+no-entry-with-key = 没有引用键为 { $key } 的条目
+library-added = 已添加 { $key }
+library-feed-title = 文献库最新条目
+sync-summary = { $file }：添加 { $added } 条，删除 { $removed } 条
+conflict-prompt = { $key } 已是“{ $existing }”。对于“{ $new }”：添加后缀 [s]、跳过 [k] 还是替换 [r]？
+conflict-suffixed = { $key } 已被另一作品使用；以 { $renamed } 追加
+conflict-skipped = { $key } 已被另一作品使用；已跳过 { $input }
+conflict-replaced = { $key } 已被另一作品使用；已替换为 { $input }
+waiting-for-lock = 正在等待另一个 d2b 进程释放 { $file }
+undo-restored = 已将 { $file } 恢复到 { $time } 之前的状态
+undo-nothing = { $dir } 中没有可撤销的操作
+undo-failed = 无法撤销：{ $error }
+diff-confirm = 将这些更改写入 { $file }？[y/N]
+diff-declined = 未修改 { $file }
+ids-unknown-input = { $input } 不是作品的标识符
+qr-no-zbar = 读取二维码需要 ZBar 中的 zbarimg（https://github.com/mchehab/zbar）
+qr-unreadable = 无法读取 { $file } 中的二维码：{ $error }
+qr-none = { $file } 中没有包含标识符或链接的二维码
+import-invalid = { $file } 不是有效的 JSON 导出文件：{ $error }
+import-no-id = “{ $title }”没有 DOI 或 arXiv ID，已跳过
+import-summary = 从 { $items } 个条目中生成 { $entries } 条记录
+mail-found = 在 { $messages } 封邮件中找到 { $ids } 个标识符
+enrich-filled = { $key }：补全了 { $fields }
+enrich-summary = { $file }：{ $checked } 个带标识符的条目中补全了 { $enriched } 个
+merge-duplicate = { $dropped } 与 { $kept } 是同一作品，已合并到 { $kept }
+merge-key-conflict = { $key } 指向不同的作品，已重命名为 { $keys }
+merge-summary = 来自 { $files } 个文件的 { $entries } 个条目，合并了 { $duplicates } 个重复项
+verify-summary = { $file }：共 { $entries } 条，发现 { $problems } 个问题
+rename-no-identifier = { $file }：未找到 DOI 或 arXiv 标识符
+ocr-missing-tool = 读取扫描版 PDF 需要 { $tool }；请安装 Poppler 和 Tesseract（https://github.com/tesseract-ocr/tesseract）
+ocr-failed = 无法用 OCR 读取 { $file }：{ $error }
+rename-exists = { $file } 已存在
+rename-dry-run = （试运行；加 --apply 才会重命名）
+rekey-summary = 在 { $files } 个文件中更改了 { $keys } 个键
+rekey-dry-run = （演练；加上 --apply 才会改写文件）
+clipboard-unavailable = 无法访问剪贴板：{ $error }
+clipboard-unwritable = 无法写入剪贴板：{ $error }
+clipboard-watching = 正在监视剪贴板中的 DOI 和 arXiv 标识符。按 Ctrl-C 停止。
+clipboard-unresolved = 无法解析 { $input }
+clipboard-changed = 已解析 { $input }，但剪贴板内容已经改变
+clipboard-copied = 已复制 { $input } 的 BibTeX
+summary = 已解析 { $resolved } 个，来自检查点 { $cached } 个，失败 { $failed } 个，用时 { $seconds } 秒
+report-title = d2b 解析报告
+report-summary = { $inputs } 个输入：{ $resolved } 个已解析，{ $merged } 个已合并，{ $failed } 个失败，{ $date }
+report-input = 输入
+report-status = 状态
+report-citation = 引文
+report-links = 链接
+report-resolved = 已解析
+report-merged = 与 { $input } 为同一作品
+report-failed = 失败
+report-skipped = 已跳过
+fixture-missing = 没有 { $url } 的录制响应（应为 { $name }.json）
+hook-failed = --post-hook { $command } 失败：{ $error }
+hook-bad-output = 它没有输出 JSON 格式的条目
+graph-skipped = 跳过了 { $count } 个没有 DOI 或 arXiv 标识符的作品
+watch-new-version = { $input }：新版本 { $version }
+watch-published = { $input }：已发表，DOI 为 { $doi }
+watch-retracted = { $input }：已撤稿
+watch-unchecked = { $input }：无法检查，下次再试
+watch-unsupported = { $input }：只能关注 DOI 和 arXiv 标识符
+webhook-filed = 已收录新论文：{ $input }
+webhook-failed = 无法通知 { $url }：{ $error }
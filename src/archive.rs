@@ -0,0 +1,175 @@
+//! `--archive`: for web pages without a DOI, asks the Wayback Machine for a snapshot and cites the
+//! page with the date it was accessed and the address of the snapshot, as biblatex recommends for
+//! online sources that may change or disappear. Pages with no citation metadata at all become an
+//! `@online` from their title.
+
+use crate::bibtex::{self, Entry};
+use crate::http::SendWithRetry;
+use crate::{html, text, Format, CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref TITLE_RE: Regex = Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap();
+    static ref SNAPSHOT_RE: Regex = Regex::new(r"^https?://web\.archive\.org/web/\d{14}/").unwrap();
+    static ref YEAR_RE: Regex = Regex::new(r"\b(1[89]|20)\d{2}\b").unwrap();
+}
+
+/// The address of a new Wayback Machine snapshot of `url`, or of the latest one if a new one
+/// couldn't be made.
+pub async fn snapshot(url: &str) -> Option<String> {
+    if let Ok(res) = CLIENT
+        .get(format!("https://web.archive.org/save/{}", url))
+        .send_with_retry()
+        .await
+    {
+        let location = res
+            .headers()
+            .get("content-location")
+            .and_then(|l| l.to_str().ok())
+            .map(|l| format!("https://web.archive.org{}", l));
+        let saved = Some(res.url().to_string())
+            .into_iter()
+            .chain(location)
+            .find(|u| SNAPSHOT_RE.is_match(u));
+        if saved.is_some() {
+            return saved;
+        }
+    }
+    let res = CLIENT
+        .get("https://archive.org/wayback/available")
+        .query(&[("url", url)])
+        .send_with_retry()
+        .await
+        .ok()?;
+    let json: Value = serde_json::from_str(&res.text().await.ok()?).ok()?;
+    json["archived_snapshots"]["closest"]["url"]
+        .as_str()
+        .map(|u| u.replacen("http://", "https://", 1))
+}
+
+/// An `@online` (or, for BibTeX, a `@misc`) for a page from its title and its author, date and
+/// site meta tags, if it has a title.
+pub fn page_entry(page: &str, url: &str, format: Format) -> Option<Entry> {
+    let tags = html::meta_tags(page);
+    let title = html::first(&tags, "og:title")
+        .map(str::to_owned)
+        .or_else(|| {
+            TITLE_RE
+                .captures(page)
+                .map(|c| text::collapse_whitespace(&text::decode_entities(&c[1])))
+        })
+        .filter(|t| !t.is_empty())?;
+    let authors = html::all(&tags, "author")
+        .into_iter()
+        .chain(html::all(&tags, "dc.creator"))
+        .filter(|a| !a.starts_with("http"))
+        .map(bibtex::name_last_first)
+        .collect::<Vec<_>>();
+    let year = html::first(&tags, "article:published_time")
+        .or_else(|| html::first(&tags, "dc.date"))
+        .and_then(|d| YEAR_RE.find(d))
+        .map(|m| m.as_str());
+    let site = html::first(&tags, "og:site_name");
+
+    let family = authors
+        .first()
+        .map(|a| bibtex::family_name(a))
+        .or(site)
+        .unwrap_or("anonymous")
+        .replace(' ', "");
+    let key = match year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    };
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("online", &key),
+        Format::Bibtex => Entry::new("misc", &key),
+    };
+    entry.set("title", &title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(site) = site {
+        match format {
+            Format::Biblatex => entry.set("organization", site),
+            Format::Bibtex => entry.set("howpublished", site),
+        }
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    entry.set("url", url);
+    Some(entry)
+}
+
+/// Adds the access date, and the snapshot if there is one, to an entry: in `urldate` and
+/// `addendum` for biblatex, and in `note` for BibTeX, which has no fields for them.
+pub fn add_snapshot(entry: &mut Entry, snapshot: Option<&str>, accessed: &str, format: Format) {
+    match format {
+        Format::Biblatex => {
+            entry.set("urldate", accessed);
+            if let Some(snapshot) = snapshot {
+                entry.set("addendum", &format!("Archived at \\url{{{}}}", snapshot));
+            }
+        }
+        Format::Bibtex => {
+            let mut note = format!("Accessed {}", accessed);
+            if let Some(snapshot) = snapshot {
+                note.push_str(&format!(". Archived at \\url{{{}}}", snapshot));
+            }
+            entry.append_note(&note);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_entry() {
+        let page = r#"<html><head><title> Rust &amp; WebAssembly
+            </title><meta name="author" content="Jane Doe">
+            <meta property="og:site_name" content="Rust Blog"></head></html>"#;
+        let mut entry = page_entry(page, "https://blog.rust-lang.org/x", Format::Biblatex).unwrap();
+        add_snapshot(
+            &mut entry,
+            Some("https://web.archive.org/web/20240101000000/https://blog.rust-lang.org/x"),
+            "2024-01-01",
+            Format::Biblatex,
+        );
+        assert_eq!(
+            entry.to_string(),
+            "@online{Doe,\n  title={Rust & WebAssembly},\n  author={Doe, Jane},\n  \
+             organization={Rust Blog},\n  url={https://blog.rust-lang.org/x},\n  \
+             urldate={2024-01-01},\n  \
+             addendum={Archived at \\url{https://web.archive.org/web/20240101000000/https://blog.rust-lang.org/x}}\n}"
+        );
+
+        let mut entry =
+            page_entry("<title>Home</title>", "https://example.com", Format::Bibtex).unwrap();
+        assert_eq!(entry.key, "anonymous");
+        add_snapshot(&mut entry, None, "2024-01-01", Format::Bibtex);
+        assert_eq!(entry.get("note"), Some("Accessed 2024-01-01"));
+        assert!(page_entry("<p>no title</p>", "https://example.com", Format::Bibtex).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_survives_sanitize() {
+        let snapshot = "https://web.archive.org/web/2024/https://example.com/a_b?q=100%25&x=1#top";
+        let mut entry = Entry::new("misc", "a");
+        entry.set("title", "50% off_sale");
+        add_snapshot(&mut entry, Some(snapshot), "2024-01-01", Format::Bibtex);
+        bibtex::sanitize(&mut entry);
+        assert_eq!(entry.get("title"), Some("50\\% off\\_sale"));
+        assert_eq!(
+            entry.get("note"),
+            Some(&*format!(
+                "Accessed 2024-01-01. Archived at \\url{{{}}}",
+                snapshot
+            ))
+        );
+    }
+}
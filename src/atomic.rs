@@ -0,0 +1,113 @@
+//! Writes to `.bib` files that several d2b processes may update at once, such as the editor
+//! plugins that each append what the user cites. A file is only ever replaced whole, by renaming a
+//! finished copy over it, so that readers never see it half-written; and the commands that read a
+//! file to rewrite it hold an advisory lock on it meanwhile, so that one's changes aren't lost to
+//! another's.
+//!
+//! The lock is taken on a `.<name>.lock` file next to the file rather than on the file itself,
+//! which the rename replaces. The lock file is left behind when the lock is released: deleting it
+//! would let a process still waiting on the deleted file and one that creates a new file both
+//! take "the" lock at once. It is empty, and safe to delete or ignore when no d2b is running.
+
+use crate::{i18n, undo};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The lock on a file, released when dropped.
+#[derive(Debug)]
+pub struct Lock {
+    _file: File,
+}
+
+/// The file a path names, through any symlinks, so that the link is kept and its target updated.
+fn target(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
+
+/// A hidden file next to `path`, named after it.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}{}", name, suffix))
+}
+
+/// Takes the lock on `path`, waiting for any other d2b process that holds it.
+pub fn lock(path: impl AsRef<Path>) -> io::Result<Lock> {
+    let path = target(path.as_ref());
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(sibling(&path, ".lock"))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            eprintln!(
+                "{}",
+                i18n::message("waiting-for-lock", &[("file", &path.display())])
+            );
+            file.lock()?;
+        }
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+    Ok(Lock { _file: file })
+}
+
+/// Replaces the contents of `path` in one step, keeping its permissions, after backing up what
+/// it had for `d2b undo`.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = target(path.as_ref());
+    undo::backup(&path)?;
+    replace(&path, contents)
+}
+
+/// Replaces the contents of `path` in one step, keeping its permissions, without a backup.
+pub fn replace(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = target(path.as_ref());
+    let tmp = sibling(&path, &format!(".{}.tmp", std::process::id()));
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents.as_ref())?;
+        if let Ok(metadata) = std::fs::metadata(&path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        std::fs::rename(&tmp, &path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let dir = std::env::temp_dir().join(format!("d2b-atomic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("refs.bib");
+        std::fs::write(&path, "old").unwrap();
+
+        let lock = lock(&path).unwrap();
+        assert!(dir.join(".refs.bib.lock").exists());
+        let other = File::open(dir.join(".refs.bib.lock")).unwrap();
+        assert!(matches!(other.try_lock(), Err(TryLockError::WouldBlock)));
+        write(&path, "new").unwrap();
+        drop(lock);
+        assert!(other.try_lock().is_ok());
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+        let mut left = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        left.sort();
+        // The lock file outlives the lock, see the module docs.
+        assert_eq!(left, [".d2b", ".refs.bib.lock", "refs.bib"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+//! API tokens for services that need them, from the environment, the credentials file that
+//! `d2b auth set` writes, or the `[tokens]` table of the configuration file, in that order.
+
+use crate::config::{self, Config};
+use crate::error::{Error, ErrorFormat, Kind};
+use crate::i18n;
+use clap::ArgMatches;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+
+/// The services d2b knows about, with the environment variable their own tools conventionally
+/// read the token from.
+pub const SERVICES: &[(&str, &str)] = &[
+    ("ads", "ADS_API_TOKEN"),
+    ("semanticscholar", "S2_API_KEY"),
+    ("zotero", "ZOTERO_API_KEY"),
+    ("ieee", "IEEE_API_KEY"),
+    ("scopus", "SCOPUS_API_KEY"),
+    ("wos", "WOS_API_KEY"),
+    ("worldcat", "WORLDCAT_TOKEN"),
+];
+
+/// A secret, which is never shown in full, including in debug output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Token(String);
+
+impl Token {
+    /// The token, for sending to its service.
+    pub fn secret(&self) -> &str {
+        &self.0
+    }
+
+    /// The token with all but its last four characters hidden.
+    pub fn masked(&self) -> String {
+        let chars = self.0.chars().collect::<Vec<_>>();
+        let shown = if chars.len() > 8 { 4 } else { 0 };
+        let hidden = "*".repeat(chars.len().min(12) - shown);
+        hidden
+            + chars[chars.len() - shown..]
+                .iter()
+                .collect::<String>()
+                .as_str()
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Token({})", self.masked())
+    }
+}
+
+/// Where a token was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Env(String),
+    Credentials,
+    Config,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Env(var) => write!(f, "${}", var),
+            Source::Credentials => write!(f, "{}", credentials_path().display()),
+            Source::Config => write!(f, "config"),
+        }
+    }
+}
+
+/// The environment variables a service's token is read from: `D2B_<SERVICE>_TOKEN`, then the
+/// conventional one if there is one.
+fn env_vars(service: &str) -> Vec<String> {
+    let mut vars = vec![format!(
+        "D2B_{}_TOKEN",
+        service.to_ascii_uppercase().replace('-', "_")
+    )];
+    if let Some((_, var)) = SERVICES.iter().find(|(s, _)| *s == service) {
+        vars.push((*var).to_owned());
+    }
+    vars
+}
+
+/// `$D2B_CREDENTIALS`, or `d2b/credentials.toml` in the configuration directory.
+pub fn credentials_path() -> PathBuf {
+    std::env::var_os("D2B_CREDENTIALS")
+        .map(PathBuf::from)
+        .or_else(|| config::dir().map(|d| d.join("credentials.toml")))
+        .unwrap_or_else(|| PathBuf::from("credentials.toml"))
+}
+
+fn read_credentials() -> BTreeMap<String, String> {
+    std::fs::read_to_string(credentials_path())
+        .ok()
+        .and_then(|src| toml::from_str(&src).ok())
+        .unwrap_or_default()
+}
+
+/// Writes the credentials file, readable only by the user.
+fn write_credentials(tokens: &BTreeMap<String, String>) -> std::io::Result<()> {
+    let path = credentials_path();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let src = toml::to_string(tokens).map_err(std::io::Error::other)?;
+    options.open(&path)?.write_all(src.as_bytes())
+}
+
+fn lookup(
+    service: &str,
+    env: impl Fn(&str) -> Option<String>,
+    credentials: &BTreeMap<String, String>,
+    config: &Config,
+) -> Option<(Token, Source)> {
+    let found = env_vars(service)
+        .into_iter()
+        .find_map(|var| Some((env(&var)?, Source::Env(var))))
+        .or_else(|| Some((credentials.get(service)?.clone(), Source::Credentials)))
+        .or_else(|| Some((config.tokens.get(service)?.clone(), Source::Config)))?;
+    Some(found)
+        .filter(|(t, _)| !t.trim().is_empty())
+        .map(|(t, s)| (Token(t.trim().to_owned()), s))
+}
+
+/// The token for `service`, if one is set anywhere.
+pub fn token(service: &str, config: &Config) -> Option<Token> {
+    lookup(
+        service,
+        |v| std::env::var(v).ok(),
+        &read_credentials(),
+        config,
+    )
+    .map(|(t, _)| t)
+}
+
+/// Runs `d2b auth ...`.
+pub fn run(matches: &ArgMatches, config: &Config, format: ErrorFormat) {
+    let fail = |e: std::io::Error| -> ! {
+        Error::new(
+            Kind::Io,
+            i18n::message(
+                "file-unwritable",
+                &[("file", &credentials_path().display()), ("error", &e)],
+            ),
+        )
+        .exit(format)
+    };
+    match matches.subcommand() {
+        ("set", Some(sub)) => {
+            let service = sub.value_of("service").unwrap();
+            // Reading the token from stdin keeps it out of the shell history.
+            let token = match sub.value_of("token") {
+                Some(token) => token.to_owned(),
+                None => {
+                    eprint!(
+                        "{} ",
+                        i18n::message("auth-prompt", &[("service", &service)])
+                    );
+                    let mut line = String::new();
+                    std::io::stdin()
+                        .lock()
+                        .read_line(&mut line)
+                        .unwrap_or_else(|e| fail(e));
+                    line.trim().to_owned()
+                }
+            };
+            if token.is_empty() {
+                Error::new(Kind::Usage, i18n::text("auth-empty")).exit(format);
+            }
+            let mut tokens = read_credentials();
+            tokens.insert(service.to_owned(), token);
+            write_credentials(&tokens).unwrap_or_else(|e| fail(e));
+        }
+        ("rm", Some(sub)) => {
+            let mut tokens = read_credentials();
+            if tokens.remove(sub.value_of("service").unwrap()).is_some() {
+                write_credentials(&tokens).unwrap_or_else(|e| fail(e));
+            }
+        }
+        ("ls", Some(_)) => {
+            let credentials = read_credentials();
+            let mut services = SERVICES.iter().map(|(s, _)| *s).collect::<Vec<_>>();
+            for s in credentials.keys().chain(config.tokens.keys()) {
+                if !services.contains(&s.as_str()) {
+                    services.push(s);
+                }
+            }
+            for service in services {
+                match lookup(service, |v| std::env::var(v).ok(), &credentials, config) {
+                    Some((token, source)) => {
+                        println!("{}\t{}\t{}", service, token.masked(), source)
+                    }
+                    None => println!("{}\t-", service),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let config = Config::parse("[tokens]\nads = \"from-config\"\nieee = \"\"").unwrap();
+        let mut credentials = BTreeMap::new();
+        credentials.insert("ads".to_owned(), "from-credentials".to_owned());
+        let env = |var: &str| (var == "S2_API_KEY").then(|| "from-env-1234567".to_owned());
+
+        let (token, source) = lookup("ads", env, &credentials, &config).unwrap();
+        assert_eq!(token.secret(), "from-credentials");
+        assert_eq!(source, Source::Credentials);
+        assert_eq!(
+            lookup("ads", env, &BTreeMap::new(), &config).unwrap().1,
+            Source::Config
+        );
+        let (token, source) = lookup("semanticscholar", env, &credentials, &config).unwrap();
+        assert_eq!(source, Source::Env("S2_API_KEY".to_owned()));
+        assert_eq!(format!("{:?}", token), "Token(********4567)");
+        assert!(lookup("ieee", env, &credentials, &config).is_none());
+        assert_eq!(Token("short".to_owned()).masked(), "*****");
+    }
+}
@@ -0,0 +1,233 @@
+//! A whole `.bib` file as a list of items (entries, `@string` macros, `@preamble`s and the comments
+//! between them) that can be edited and written back. Items that weren't changed are written
+//! exactly as they were read, so that editing one entry doesn't reformat the rest of the file.
+
+use crate::bibtex::{self, Entry, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The macros every BibTeX style defines.
+const MONTHS: &[(&str, &str)] = &[
+    ("jan", "January"),
+    ("feb", "February"),
+    ("mar", "March"),
+    ("apr", "April"),
+    ("may", "May"),
+    ("jun", "June"),
+    ("jul", "July"),
+    ("aug", "August"),
+    ("sep", "September"),
+    ("oct", "October"),
+    ("nov", "November"),
+    ("dec", "December"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Entry(Entry),
+    /// An `@string{name = value}` macro definition.
+    String(String, Value),
+    Preamble(Value),
+    /// Anything else, kept verbatim: comments, whitespace, `@comment`s and entries too broken to
+    /// parse.
+    Comment(String),
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |v: &Value| match v {
+            Value::Text(s) => format!("{{{}}}", s),
+            Value::Raw(s) => s.clone(),
+        };
+        match self {
+            Item::Entry(entry) => write!(f, "{}", entry),
+            Item::String(name, v) => write!(f, "@string{{{} = {}}}", name, value(v)),
+            Item::Preamble(v) => write!(f, "@preamble{{{}}}", value(v)),
+            Item::Comment(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Block {
+    item: Item,
+    /// The text the item was read from, and what it was read as.
+    source: Option<(String, Item)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bibliography {
+    blocks: Vec<Block>,
+}
+
+impl Bibliography {
+    /// Parses a `.bib` file. Like [`bibtex::split_file`], this never fails: what can't be parsed
+    /// is kept as a comment.
+    pub fn parse(src: &str) -> Self {
+        let blocks = bibtex::split_file(src)
+            .into_iter()
+            .map(|chunk| {
+                let item = match chunk.entry {
+                    Some(entry) => Item::Entry(entry),
+                    None => bibtex::parse_string(chunk.text)
+                        .map(|(name, value)| Item::String(name, value))
+                        .or_else(|_| bibtex::parse_preamble(chunk.text).map(Item::Preamble))
+                        .unwrap_or_else(|_| Item::Comment(chunk.text.to_owned())),
+                };
+                Block {
+                    source: Some((chunk.text.to_owned(), item.clone())),
+                    item,
+                }
+            })
+            .collect();
+        Bibliography { blocks }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.blocks.iter().map(|b| &b.item)
+    }
+
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut Item> {
+        self.blocks.iter_mut().map(|b| &mut b.item)
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.items().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> {
+        self.items_mut().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// Appends an item on a paragraph of its own.
+    pub fn push(&mut self, item: Item) {
+        let separator = match self.blocks.last().map(|b| b.item.to_string()) {
+            None => "",
+            Some(last) if last.ends_with("\n\n") || last.is_empty() => "",
+            Some(last) if last.ends_with('\n') => "\n",
+            Some(_) => "\n\n",
+        };
+        if !separator.is_empty() {
+            self.blocks.push(Block {
+                item: Item::Comment(separator.to_owned()),
+                source: None,
+            });
+        }
+        for item in [item, Item::Comment("\n".to_owned())] {
+            self.blocks.push(Block { item, source: None });
+        }
+    }
+
+    /// The `@string` macros, by lowercase name, with the month names every style defines, each
+    /// expanded with the macros defined before it.
+    pub fn strings(&self) -> HashMap<String, String> {
+        let mut strings = MONTHS
+            .iter()
+            .map(|(m, name)| (m.to_string(), name.to_string()))
+            .collect::<HashMap<_, _>>();
+        for item in self.items() {
+            if let Item::String(name, value) = item {
+                if let Some(expanded) = expand_value(value, &strings) {
+                    strings.insert(name.to_lowercase(), expanded);
+                }
+            }
+        }
+        strings
+    }
+
+    /// An entry with the macros and concatenations in its fields replaced by the text they stand
+    /// for. Fields using a macro that isn't defined are left as they are.
+    pub fn expand(&self, entry: &Entry) -> Entry {
+        let strings = self.strings();
+        let mut expanded = entry.clone();
+        for (_, value) in &mut expanded.fields {
+            let raw = match value {
+                Value::Raw(raw) => raw,
+                Value::Text(_) => continue,
+            };
+            if raw.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            if let Some(text) = expand_value(value, &strings) {
+                *value = Value::Text(text);
+            }
+        }
+        expanded
+    }
+}
+
+/// The text a value stands for, or `None` if it uses an undefined macro. A bare number is its own
+/// text.
+fn expand_value(value: &Value, strings: &HashMap<String, String>) -> Option<String> {
+    bibtex::value_parts(value)
+        .iter()
+        .map(|part| match part {
+            Value::Text(s) => Some(s.clone()),
+            Value::Raw(s) if s.chars().all(|c| c.is_ascii_digit()) => Some(s.clone()),
+            Value::Raw(s) => strings.get(&s.to_lowercase()).cloned(),
+        })
+        .collect()
+}
+
+impl fmt::Display for Bibliography {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for block in &self.blocks {
+            match &block.source {
+                Some((text, read)) if *read == block.item => write!(f, "{}", text)?,
+                _ => write!(f, "{}", block.item)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let src = "% My references\n@STRING( jss = \"Journal of \" # {Statistical Software} )\n\
+                   @preamble{ \"\\newcommand{\\noop}[1]{}\" }\n\n\
+                   @article{a,\n    title = {A},\n    journal = jss,\n    month = jan # {~1},\n    year = 2020\n}\n\
+                   @comment{not an entry}\n\
+                   @misc{b, title={B}, journal = undefined}\n";
+        let mut bib = Bibliography::parse(src);
+        assert_eq!(bib.to_string(), src);
+        assert_eq!(bib.entries().count(), 2);
+        assert!(matches!(bib.items().nth(1), Some(Item::String(name, _)) if name == "jss"));
+        assert!(matches!(bib.items().nth(3), Some(Item::Preamble(_))));
+
+        let strings = bib.strings();
+        assert_eq!(strings["jss"], "Journal of Statistical Software");
+        let a = bib.expand(bib.entries().next().unwrap());
+        assert_eq!(
+            a.fields[1],
+            (
+                "journal".to_owned(),
+                Value::Text("Journal of Statistical Software".to_owned())
+            )
+        );
+        assert_eq!(a.get("month"), Some("January~1"));
+        assert_eq!(a.fields[3].1, Value::Raw("2020".to_owned()));
+        let b = bib.expand(bib.entries().nth(1).unwrap());
+        assert_eq!(b.fields[1].1, Value::Raw("undefined".to_owned()));
+
+        // Only what changed is written anew.
+        bib.entries_mut().nth(1).unwrap().set("title", "New");
+        let mut c = Entry::new("book", "c");
+        c.set("title", "C");
+        bib.push(Item::Entry(c));
+        let out = bib.to_string();
+        assert!(out.starts_with(&src[..src.find("@misc").unwrap()]));
+        assert!(out.ends_with(
+            "@misc{b,\n  title={New},\n  journal=undefined\n}\n\n@book{c,\n  title={C}\n}\n"
+        ));
+    }
+}
@@ -0,0 +1,901 @@
+//! A small BibTeX entry model, with a lenient parser for publisher-supplied entries and a
+//! validation pass that repairs (or reports) the problems that tend to break LaTeX builds.
+
+use std::fmt;
+
+/// The value of a single field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    /// A braced or quoted string, stored without its outer delimiters.
+    Text(String),
+    /// A bare number, `@string` macro name or `#` concatenation, written out verbatim.
+    Raw(String),
+}
+
+impl Value {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Value::Text(s) | Value::Raw(s) => s,
+        }
+    }
+}
+
+/// A single `@type{key, field={value}, ...}` entry. Fields keep their original order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub kind: String,
+    pub key: String,
+    pub fields: Vec<(String, Value)>,
+}
+
+impl Entry {
+    pub fn new(kind: &str, key: &str) -> Self {
+        Entry {
+            kind: kind.to_owned(),
+            key: key.to_owned(),
+            fields: Vec::new(),
+        }
+    }
+
+    /// Looks up a field by name, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets a text field, replacing any existing field with the same name.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self
+            .fields
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some((_, v)) => *v = Value::Text(value.to_owned()),
+            None => self
+                .fields
+                .push((name.to_owned(), Value::Text(value.to_owned()))),
+        }
+    }
+
+    /// Appends a sentence to `note`, creating it if necessary.
+    pub fn append_note(&mut self, text: &str) {
+        let note = match self.get("note") {
+            Some(note) if !note.is_empty() => format!("{}. {}", note.trim_end_matches('.'), text),
+            _ => text.to_owned(),
+        };
+        self.set("note", &note);
+    }
+
+    /// Renames a field, keeping its position and value.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some((n, _)) = self
+            .fields
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(from))
+        {
+            *n = to.to_owned();
+        }
+    }
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "@{}{{{}", self.kind, self.key)?;
+        for (name, value) in &self.fields {
+            match value {
+                Value::Text(s) => write!(f, ",\n  {}={{{}}}", name, s)?,
+                Value::Raw(s) => write!(f, ",\n  {}={}", name, s)?,
+            }
+        }
+        write!(f, "\n}}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+        &self.src[start..self.pos]
+    }
+
+    fn err<T>(&self, msg: &str) -> Result<T, ParseError> {
+        Err(ParseError(format!("{} at offset {}", msg, self.pos)))
+    }
+
+    /// Whether `s` (the text right after a closing brace) starts a new field, a concatenation or
+    /// the end of the entry, i.e. whether that closing brace can legitimately end a value.
+    fn is_value_end(s: &str, close: char) -> bool {
+        let s = s.trim_start();
+        s.starts_with(',') || s.starts_with('#') || s.starts_with(close)
+    }
+
+    /// Whether `s` (the text right after a closing brace) is a new field or the end of the entry.
+    /// Used to recover from values with a missing closing brace.
+    fn is_field_boundary(s: &str, close: char) -> bool {
+        let is_entry_end = |s: &str| s.strip_prefix(close).is_some_and(|r| r.trim().is_empty());
+        let s = s.trim_start();
+        match s.strip_prefix(',') {
+            Some(s) => {
+                let s = s.trim_start();
+                let name_len = s
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(s.len());
+                name_len > 0 && s[name_len..].trim_start().starts_with('=') || is_entry_end(s)
+            }
+            None => is_entry_end(s),
+        }
+    }
+
+    /// Reads a braced value, returning its contents. Braces inside may be unbalanced; stray braces
+    /// are kept in the value for [`validate`] to deal with.
+    fn braced(&mut self, close: char) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut depth = 1usize;
+        let mut escaped = false;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    escaped = !escaped;
+                    continue;
+                }
+                '{' if !escaped => depth += 1,
+                '}' if !escaped => {
+                    depth -= 1;
+                    if depth == 0 {
+                        if Self::is_value_end(self.rest(), close) {
+                            return Ok(self.src[start..self.pos - 1].to_owned());
+                        }
+                        // A stray closing brace in the middle of the value.
+                        depth = 1;
+                    }
+                }
+                _ => {}
+            }
+            escaped = false;
+        }
+
+        // Ran off the end, so some opening brace was never closed. Fall back to ending the value
+        // at the first closing brace that is followed by another field or the end of the entry.
+        let body = &self.src[start..];
+        for (i, _) in body.match_indices('}') {
+            if Self::is_field_boundary(&body[i + 1..], close) {
+                self.pos = start + i + 1;
+                return Ok(body[..i].to_owned());
+            }
+        }
+        self.pos = start;
+        self.err("unterminated field value")
+    }
+
+    fn quoted(&mut self) -> Result<String, ParseError> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.bump() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                '"' if depth == 0 => return Ok(self.src[start..self.pos - 1].to_owned()),
+                _ => {}
+            }
+        }
+        self.err("unterminated quoted value")
+    }
+
+    /// Reads the parts of a value, which `#` concatenates.
+    fn parts(&mut self, close: char) -> Result<Vec<Value>, ParseError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_ws();
+            let part = if self.eat('{') {
+                Value::Text(self.braced(close)?)
+            } else if self.eat('"') {
+                Value::Text(self.quoted()?)
+            } else {
+                let word =
+                    self.take_while(|c| !(c == ',' || c == '#' || c == close || c.is_whitespace()));
+                if word.is_empty() {
+                    return self.err("expected a field value");
+                }
+                Value::Raw(word.to_owned())
+            };
+            parts.push(part);
+            self.skip_ws();
+            if !self.eat('#') {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self, close: char) -> Result<Value, ParseError> {
+        let mut parts = self.parts(close)?;
+        if parts.len() == 1 {
+            Ok(parts.pop().unwrap())
+        } else {
+            Ok(Value::Raw(
+                parts
+                    .iter()
+                    .map(|p| match p {
+                        Value::Text(s) => format!("{{{}}}", s),
+                        Value::Raw(s) => s.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" # "),
+            ))
+        }
+    }
+
+    /// Reads the start of a `@kind{` or `@kind(` block, returning the closing delimiter.
+    fn open(&mut self, kind: &str) -> Result<char, ParseError> {
+        self.skip_ws();
+        if !self.eat('@') {
+            return self.err("expected '@'");
+        }
+        if !self
+            .take_while(|c| c.is_alphanumeric())
+            .eq_ignore_ascii_case(kind)
+        {
+            return self.err(&format!("expected @{}", kind));
+        }
+        self.skip_ws();
+        if self.eat('{') {
+            Ok('}')
+        } else if self.eat('(') {
+            Ok(')')
+        } else {
+            self.err("expected '{' or '('")
+        }
+    }
+
+    fn close(&mut self, close: char) -> Result<(), ParseError> {
+        self.skip_ws();
+        if self.eat(close) {
+            Ok(())
+        } else {
+            self.err(&format!("expected '{}'", close))
+        }
+    }
+
+    fn string(&mut self) -> Result<(String, Value), ParseError> {
+        let close = self.open("string")?;
+        self.skip_ws();
+        let name = self
+            .take_while(|c| !(c == '=' || c == close || c.is_whitespace()))
+            .to_owned();
+        self.skip_ws();
+        if name.is_empty() || !self.eat('=') {
+            return self.err("expected a macro definition");
+        }
+        let value = self.value(close)?;
+        self.close(close)?;
+        Ok((name, value))
+    }
+
+    fn preamble(&mut self) -> Result<Value, ParseError> {
+        let close = self.open("preamble")?;
+        let value = self.value(close)?;
+        self.close(close)?;
+        Ok(value)
+    }
+
+    fn entry(&mut self) -> Result<Entry, ParseError> {
+        self.skip_ws();
+        if !self.eat('@') {
+            return self.err("expected '@'");
+        }
+        let kind = self.take_while(|c| c.is_alphanumeric()).to_owned();
+        if kind.is_empty() {
+            return self.err("expected an entry type");
+        }
+        self.skip_ws();
+        let close = if self.eat('{') {
+            '}'
+        } else if self.eat('(') {
+            ')'
+        } else {
+            return self.err("expected '{' or '('");
+        };
+        self.skip_ws();
+        let key = self
+            .take_while(|c| c != ',' && c != close)
+            .trim()
+            .to_owned();
+
+        let mut entry = Entry {
+            kind,
+            key,
+            fields: Vec::new(),
+        };
+        loop {
+            self.skip_ws();
+            if self.eat(close) {
+                return Ok(entry);
+            }
+            if self.eat(',') {
+                continue;
+            }
+            if self.peek().is_none() {
+                return self.err("unterminated entry");
+            }
+            let name = self
+                .take_while(|c| !(c == '=' || c == ',' || c == close || c.is_whitespace()))
+                .to_owned();
+            self.skip_ws();
+            if name.is_empty() || !self.eat('=') {
+                return self.err("expected a field");
+            }
+            let value = self.value(close)?;
+            entry.fields.push((name, value));
+        }
+    }
+}
+
+/// Parses a single entry, tolerating stray or missing braces inside field values.
+pub fn parse_entry(src: &str) -> Result<Entry, ParseError> {
+    Parser { src, pos: 0 }.entry()
+}
+
+/// Parses an `@string{name = value}` macro definition.
+pub fn parse_string(src: &str) -> Result<(String, Value), ParseError> {
+    Parser { src, pos: 0 }.string()
+}
+
+/// Parses an `@preamble{...}`.
+pub fn parse_preamble(src: &str) -> Result<Value, ParseError> {
+    Parser { src, pos: 0 }.preamble()
+}
+
+/// The parts of a field value that `#` concatenates, e.g. `jan # { 1}` or a bare macro name.
+pub fn value_parts(value: &Value) -> Vec<Value> {
+    match value {
+        Value::Text(_) => vec![value.clone()],
+        Value::Raw(raw) => {
+            // Parse it as if it were followed by the end of an entry, which braced parts look for.
+            let src = format!("{}}}", raw);
+            let mut parser = Parser { src: &src, pos: 0 };
+            match parser.parts('}') {
+                Ok(parts) if parser.rest() == "}" => parts,
+                _ => vec![value.clone()],
+            }
+        }
+    }
+}
+
+/// A piece of a `.bib` file: either an entry, or the text (comments, whitespace, `@string`s, ...)
+/// between entries. Concatenating the chunks' text gives back the file exactly.
+#[derive(Debug)]
+pub struct Chunk<'a> {
+    pub text: &'a str,
+    pub entry: Option<Entry>,
+}
+
+/// The length of the entry at the start of `s`, up to its closing delimiter.
+fn entry_len(s: &str) -> Option<usize> {
+    let open = s.find(['{', '('])?;
+    let close = if s[open..].starts_with('{') { '}' } else { ')' };
+    let mut depth = 0usize;
+    let mut escaped = false;
+    for (i, c) in s[open + 1..].char_indices() {
+        match c {
+            '\\' => {
+                escaped = !escaped;
+                continue;
+            }
+            '{' if !escaped => depth += 1,
+            '}' if !escaped && depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => return Some(open + 1 + i + 1),
+            _ => {}
+        }
+        escaped = false;
+    }
+    None
+}
+
+/// Splits a `.bib` file into entries and the text between them. An entry with unbalanced braces
+/// is cut off where the next one starts.
+pub fn split_file(src: &str) -> Vec<Chunk<'_>> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in src.split_inclusive('\n') {
+        let indent = line.len() - line.trim_start().len();
+        if line[indent..].starts_with('@') {
+            starts.push(offset + indent);
+        }
+        offset += line.len();
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = 0;
+    for (i, &start) in starts.iter().enumerate() {
+        if start < pos {
+            continue;
+        }
+        if start > pos {
+            chunks.push(Chunk {
+                text: &src[pos..start],
+                entry: None,
+            });
+        }
+        let next = starts.get(i + 1).copied().unwrap_or(src.len());
+        let end = entry_len(&src[start..])
+            .map(|len| start + len)
+            .filter(|&end| end <= next)
+            .unwrap_or(next);
+        let text = &src[start..end];
+        let entry = parse_entry(text).ok().filter(|e| {
+            !matches!(
+                e.kind.to_ascii_lowercase().as_str(),
+                "string" | "comment" | "preamble"
+            )
+        });
+        chunks.push(Chunk { text, entry });
+        pos = end;
+    }
+    if pos < src.len() {
+        chunks.push(Chunk {
+            text: &src[pos..],
+            entry: None,
+        });
+    }
+    chunks
+}
+
+/// Converts a "Given Family" name to BibTeX's "Family, Given" form. Names that already contain a
+/// comma are assumed to be in that form and returned unchanged.
+pub fn name_last_first(name: &str) -> String {
+    let name = name.trim();
+    if name.contains(',') {
+        return name.to_owned();
+    }
+    let parts = name.split_whitespace().collect::<Vec<_>>();
+    match parts.split_last() {
+        Some((family, [])) => (*family).to_owned(),
+        Some((family, given)) => format!("{}, {}", family, given.join(" ")),
+        None => String::new(),
+    }
+}
+
+/// The family name of a name in either "Family, Given" or "Given Family" form.
+pub fn family_name(name: &str) -> &str {
+    match name.split_once(',') {
+        Some((family, _)) => family.trim(),
+        None => name.split_whitespace().last().unwrap_or(""),
+    }
+}
+
+/// Gives entries that share a key the suffixes `a`, `b`, `c` and so on, in order, as the
+/// author-year styles do for same-year works, going on with `aa`, `ab`, ... after `z`. Suffixed
+/// keys that another entry already has are skipped.
+pub fn disambiguate_keys(entries: &mut [Entry]) {
+    let mut counts = std::collections::HashMap::new();
+    for entry in entries.iter() {
+        *counts.entry(entry.key.clone()).or_insert(0) += 1;
+    }
+    let mut taken = entries
+        .iter()
+        .map(|e| e.key.to_lowercase())
+        .collect::<std::collections::HashSet<_>>();
+    let mut next = std::collections::HashMap::new();
+    for entry in entries.iter_mut() {
+        if counts[&entry.key] > 1 {
+            let n = next.entry(entry.key.clone()).or_insert(0usize);
+            entry.key = loop {
+                let key = format!("{}{}", entry.key, key_suffix(*n));
+                *n += 1;
+                if taken.insert(key.to_lowercase()) {
+                    break key;
+                }
+            };
+        }
+    }
+}
+
+/// The `n`th key suffix, counting from zero: `a` to `z`, then `aa`, `ab`, ...
+fn key_suffix(n: usize) -> String {
+    let mut n = n + 1;
+    let mut suffix = Vec::new();
+    while n > 0 {
+        n -= 1;
+        suffix.push(b'a' + (n % 26) as u8);
+        n /= 26;
+    }
+    suffix.reverse();
+    String::from_utf8(suffix).unwrap()
+}
+
+/// Characters that cannot appear in a citation key without confusing BibTeX or LaTeX.
+pub const ILLEGAL_KEY_CHARS: &[char] =
+    &[',', '{', '}', '(', ')', '"', '#', '%', '\'', '=', '\\', '~'];
+
+/// Required fields per entry type, following the standard BibTeX styles. A `|` separates
+/// alternatives, any one of which satisfies the requirement.
+fn required_fields(kind: &str) -> &'static [&'static str] {
+    match kind.to_ascii_lowercase().as_str() {
+        // An arXiv preprint is an `@article` with its eprint standing in for the journal.
+        "article" => &["author", "title", "journal|eprint", "year"],
+        "book" => &["author|editor", "title", "publisher", "year"],
+        "booklet" => &["title"],
+        "inbook" => &[
+            "author|editor",
+            "title",
+            "chapter|pages",
+            "publisher",
+            "year",
+        ],
+        "incollection" => &["author", "title", "booktitle", "publisher", "year"],
+        "inproceedings" | "conference" => &["author", "title", "booktitle", "year"],
+        "manual" => &["title"],
+        "mastersthesis" | "phdthesis" => &["author", "title", "school", "year"],
+        "proceedings" => &["title", "year"],
+        "techreport" => &["author", "title", "institution", "year"],
+        "unpublished" => &["author", "title", "note"],
+        _ => &[],
+    }
+}
+
+/// Balances the braces in `s`, dropping unmatched closing braces and appending missing ones.
+/// Returns `None` if `s` was already balanced.
+fn balance_braces(s: &str) -> Option<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut depth = 0usize;
+    let mut escaped = false;
+    let mut changed = false;
+    for c in s.chars() {
+        match c {
+            '{' if !escaped => depth += 1,
+            '}' if !escaped => {
+                if depth == 0 {
+                    changed = true;
+                    continue;
+                }
+                depth -= 1;
+            }
+            _ => {}
+        }
+        escaped = c == '\\' && !escaped;
+        out.push(c);
+    }
+    if depth > 0 {
+        changed = true;
+        out.extend(std::iter::repeat_n('}', depth));
+    }
+    if changed {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+/// Checks `entry` for illegal key characters, duplicate fields, unbalanced braces and missing
+/// required fields. Everything except missing fields is repaired in place when `repair` is set.
+/// Returns a description of each problem found.
+pub fn validate(entry: &mut Entry, repair: bool) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if entry.key.is_empty() {
+        problems.push("entry has no citation key".to_owned());
+    } else if entry
+        .key
+        .contains(|c: char| c.is_whitespace() || ILLEGAL_KEY_CHARS.contains(&c))
+    {
+        problems.push(format!("illegal characters in key \"{}\"", entry.key));
+        if repair {
+            entry.key = entry
+                .key
+                .chars()
+                .filter(|c| !ILLEGAL_KEY_CHARS.contains(c))
+                .map(|c| if c.is_whitespace() { '_' } else { c })
+                .collect();
+        }
+    }
+
+    let mut seen: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < entry.fields.len() {
+        let name = entry.fields[i].0.to_ascii_lowercase();
+        if seen.contains(&name) {
+            problems.push(format!("duplicate field \"{}\"", name));
+            if repair {
+                entry.fields.remove(i);
+                continue;
+            }
+        } else {
+            seen.push(name.clone());
+        }
+        if let Value::Text(s) = &entry.fields[i].1 {
+            if let Some(balanced) = balance_braces(s) {
+                problems.push(format!("unbalanced braces in field \"{}\"", name));
+                if repair {
+                    entry.fields[i].1 = Value::Text(balanced);
+                }
+            }
+        }
+        i += 1;
+    }
+
+    for req in required_fields(&entry.kind) {
+        if !req.split('|').any(|f| entry.get(f).is_some()) {
+            problems.push(format!(
+                "missing required field \"{}\" for @{}",
+                req.replace('|', "\" or \""),
+                entry.kind
+            ));
+        }
+    }
+
+    problems
+}
+
+/// Fields whose values are verbatim (URLs, identifiers, paths) and must never be escaped.
+const VERBATIM_FIELDS: &[&str] = &[
+    "url",
+    "doi",
+    "eprint",
+    "file",
+    "pdf",
+    "isbn",
+    "issn",
+    "repository",
+    "license",
+    "swhid",
+];
+
+/// Escapes every occurrence of the characters in `special` that isn't already escaped, inside
+/// `$...$` math or inside a `\url{...}`.
+pub fn escape_bare(s: &str, special: &[char]) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut escaped = false;
+    let mut math = false;
+    let mut i = 0;
+    while let Some(c) = s[i..].chars().next() {
+        if let Some(len) = url_len(&s[i..]).filter(|_| !escaped) {
+            out.push_str(&s[i..i + len]);
+            i += len;
+            continue;
+        }
+        if !escaped && !math && special.contains(&c) {
+            out.push('\\');
+        }
+        if c == '$' && !escaped {
+            math = !math;
+        }
+        escaped = c == '\\' && !escaped;
+        out.push(c);
+        i += c.len_utf8();
+    }
+    out
+}
+
+/// The length of the `\url{...}` that `s` starts with, if it starts with a closed one.
+fn url_len(s: &str) -> Option<usize> {
+    let inner = s.strip_prefix("\\url{")?;
+    let mut depth = 1;
+    for (i, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(s.len() - inner.len() + i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replaces typographic characters that pdfLaTeX chokes on with their TeX equivalents.
+fn normalize_typography(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\u{2018}' => out.push('`'),
+            '\u{2019}' => out.push('\''),
+            '\u{201C}' => out.push_str("``"),
+            '\u{201D}' => out.push_str("''"),
+            '\u{FB00}' => out.push_str("ff"),
+            '\u{FB01}' => out.push_str("fi"),
+            '\u{FB02}' => out.push_str("fl"),
+            '\u{FB03}' => out.push_str("ffi"),
+            '\u{FB04}' => out.push_str("ffl"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn map_text_fields(entry: &mut Entry, f: impl Fn(&str) -> String) {
+    for (name, value) in entry.fields.iter_mut() {
+        if VERBATIM_FIELDS.contains(&name.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        if let Value::Text(s) = value {
+            *s = f(s);
+        }
+    }
+}
+
+/// Escapes the characters in `special` in every text field, leaving URLs and identifiers alone.
+pub fn escape_fields(entry: &mut Entry, special: &[char]) {
+    map_text_fields(entry, |s| escape_bare(s, special));
+}
+
+/// Escapes bare `%`, `&`, `#` and `_` and normalizes smart quotes and ligatures in every text
+/// field, leaving URLs and identifiers alone.
+pub fn sanitize(entry: &mut Entry) {
+    map_text_fields(entry, |s| {
+        escape_bare(&normalize_typography(s), &['%', '&', '#', '_'])
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_publisher_bibtex() {
+        let src = " @article{Knuth_1978, title={The {TeX} Book}, volume={21}, \
+                   author=\"Knuth, Donald\", year=1978, month=apr }";
+        let entry = parse_entry(src).unwrap();
+        assert_eq!(entry.kind, "article");
+        assert_eq!(entry.key, "Knuth_1978");
+        assert_eq!(entry.get("title"), Some("The {TeX} Book"));
+        assert_eq!(entry.get("AUTHOR"), Some("Knuth, Donald"));
+        assert_eq!(entry.fields[3].1, Value::Raw("1978".to_owned()));
+        assert_eq!(entry.fields[4].1, Value::Raw("apr".to_owned()));
+        assert_eq!(
+            entry.to_string(),
+            "@article{Knuth_1978,\n  title={The {TeX} Book},\n  volume={21},\n  \
+             author={Knuth, Donald},\n  year=1978,\n  month=apr\n}"
+        );
+    }
+
+    #[test]
+    fn test_repair_stray_and_missing_braces() {
+        let mut entry = parse_entry(
+            "@article{a, title={Stray } brace}, journal={Missing {brace}, year={2020}}",
+        )
+        .unwrap();
+        assert_eq!(entry.get("title"), Some("Stray } brace"));
+        assert_eq!(entry.get("journal"), Some("Missing {brace"));
+        assert_eq!(entry.get("year"), Some("2020"));
+
+        let problems = validate(&mut entry, true);
+        assert_eq!(problems.len(), 3);
+        assert_eq!(entry.get("title"), Some("Stray  brace"));
+        assert_eq!(entry.get("journal"), Some("Missing {brace}"));
+        assert!(problems[2].contains("author"));
+    }
+
+    #[test]
+    fn test_repair_key_and_duplicates() {
+        let mut entry = parse_entry("@misc{Smith (2019), title={A}, Title={B}}").unwrap();
+        let problems = validate(&mut entry, true);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(entry.key, "Smith_2019");
+        assert_eq!(entry.fields.len(), 1);
+        assert_eq!(entry.get("title"), Some("A"));
+    }
+
+    #[test]
+    fn test_validate_arxiv() {
+        let mut entry = parse_entry(
+            "@article{Silver_2017, title={Mastering Chess}, author={Silver, David}, year={2017}, \
+             eprint={1712.01815}, archivePrefix={arXiv}, primaryClass={cs.AI}}",
+        )
+        .unwrap();
+        assert!(validate(&mut entry, false).is_empty());
+
+        entry.fields.retain(|(name, _)| name != "eprint");
+        let problems = validate(&mut entry, false);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("\"journal\" or \"eprint\""));
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let mut entry = parse_entry(
+            "@article{a, journal={Science & Engineering}, title={\u{201C}E\u{FB03}cient\u{201D} 100% of $x_1$ \\& #1}, \
+             url={https://example.com/a_b?c=1&d=2}}",
+        )
+        .unwrap();
+        sanitize(&mut entry);
+        assert_eq!(entry.get("journal"), Some("Science \\& Engineering"));
+        assert_eq!(
+            entry.get("title"),
+            Some("``Efficient'' 100\\% of $x_1$ \\& \\#1")
+        );
+        assert_eq!(entry.get("url"), Some("https://example.com/a_b?c=1&d=2"));
+    }
+
+    #[test]
+    fn test_split_file() {
+        let src = "% My references\n@string{jss = {Journal of Statistical Software}}\n\n\
+                   @article{a, title={Braces {inside}}, journal=jss}\n\
+                   @book(b, title = \"B\")\n\n\
+                   @misc{broken, title={Never closed}\n\
+                   @misc{c, title={C}}\ntrailing";
+        let chunks = split_file(src);
+        assert_eq!(chunks.iter().map(|c| c.text).collect::<String>(), src);
+        let keys = chunks
+            .iter()
+            .filter_map(|c| c.entry.as_ref().map(|e| e.key.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["a", "b", "c"]);
+        assert!(chunks[1].text.starts_with("@string") && chunks[1].entry.is_none());
+        assert_eq!(chunks.last().unwrap().text, "\ntrailing");
+    }
+
+    #[test]
+    fn test_disambiguate_keys() {
+        let mut entries = ["Shen_2021", "Doe_2020", "Shen_2021", "Shen_2021"]
+            .iter()
+            .map(|k| Entry::new("article", k))
+            .collect::<Vec<_>>();
+        disambiguate_keys(&mut entries);
+        let keys = entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["Shen_2021a", "Doe_2020", "Shen_2021b", "Shen_2021c"]);
+
+        let mut entries = ["Doe_2020a", "Doe_2020", "Doe_2020"]
+            .iter()
+            .map(|k| Entry::new("article", k))
+            .collect::<Vec<_>>();
+        disambiguate_keys(&mut entries);
+        let keys = entries.iter().map(|e| e.key.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["Doe_2020a", "Doe_2020b", "Doe_2020c"]);
+
+        let mut entries = vec![Entry::new("article", "Wang_2024"); 28];
+        disambiguate_keys(&mut entries);
+        assert_eq!(entries[25].key, "Wang_2024z");
+        assert_eq!(entries[26].key, "Wang_2024aa");
+        assert_eq!(entries[27].key, "Wang_2024ab");
+        assert_eq!(key_suffix(26 + 26 * 26), "aaa");
+    }
+}
@@ -0,0 +1,305 @@
+//! `@book` entries from book catalogues, for books that have no DOI: Google Books volumes, and the
+//! Library of Congress and WorldCat records of older books, by LCCN and OCLC number.
+
+use crate::bibtex::{self, Entry};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    /// The life dates catalogues put after a name, as in "Vise, David A., 1960-".
+    static ref LIFE_DATES_RE: Regex = Regex::new(r",?\s*(?:b\.\s*|ca\.\s*)?\d{4}-(?:\d{4})?\.?$").unwrap();
+    /// The imprint of a catalogue record, "Place : Publisher, Year".
+    static ref IMPRINT_RE: Regex =
+        Regex::new(r"^\s*(?:\[?([^:\]]+?)\]?\s*:\s*)?(.+?),?\s*\[?[cp©]?(\d{4})\]?\.?\s*$").unwrap();
+}
+
+fn str_field<'a>(v: &'a Value, field: &str) -> Option<&'a str> {
+    v[field].as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// A citation key from a book's names ("Family, Given") and year.
+fn book_key(authors: &[String], year: Option<&str>) -> String {
+    let family = authors
+        .first()
+        .map_or("anonymous", |a| bibtex::family_name(a))
+        .replace(' ', "");
+    match year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    }
+}
+
+/// An LCCN in its normalized form: without spaces or a revision suffix, and with the serial
+/// padded to six digits if it was written after a hyphen (`85-2` is `85000002`).
+pub fn normalize_lccn(lccn: &str) -> String {
+    let lccn = lccn
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    match lccn.split_once('-') {
+        Some((prefix, serial)) => format!("{}{:0>6}", prefix, serial),
+        None => lccn,
+    }
+}
+
+/// A catalogue heading as "Family, Given", without life dates or the full stop that ends it (but
+/// with the one of a final initial).
+fn heading_name(name: &str) -> String {
+    let name = LIFE_DATES_RE.replace(name.trim(), "");
+    let name = name.trim().trim_end_matches(',');
+    let last_word = name.rsplit([' ', ',']).next().unwrap_or_default();
+    match name.strip_suffix('.') {
+        Some(stripped) if last_word.chars().count() > 2 => stripped.to_owned(),
+        _ => name.to_owned(),
+    }
+}
+
+/// Sets the publisher, address and year of an entry from an imprint statement.
+fn set_imprint(entry: &mut Entry, imprint: &str) {
+    let c = match IMPRINT_RE.captures(imprint) {
+        Some(c) => c,
+        None => return,
+    };
+    entry.set("publisher", c[2].trim_end_matches([',', ';']).trim());
+    if let Some(place) = c.get(1) {
+        entry.set("address", place.as_str().trim());
+    }
+    entry.set("year", &c[3]);
+}
+
+/// Builds a `@book` from the JSON of a loc.gov item.
+pub fn loc_entry(json: &Value) -> Option<Entry> {
+    let item = &json["item"];
+    let title = str_field(item, "title")?
+        .trim_end_matches(['/', '.', ' '])
+        .to_owned();
+    let authors = ["contributor_names", "contributors"]
+        .iter()
+        .find_map(|field| item[*field].as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(heading_name)
+        .collect::<Vec<_>>();
+    let imprint = item["created_published"][0]
+        .as_str()
+        .or_else(|| item["created_published"].as_str());
+    let year = item["date"]
+        .as_str()
+        .filter(|d| d.len() >= 4)
+        .map(|d| d[..4].to_owned());
+
+    let mut entry = Entry::new("book", &book_key(&authors, year.as_deref()));
+    entry.set("title", &title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(imprint) = imprint {
+        set_imprint(&mut entry, imprint);
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    let isbn = item["number_isbn"][0]
+        .as_str()
+        .or_else(|| item["isbn"][0].as_str());
+    if let Some(isbn) = isbn {
+        entry.set("isbn", isbn.split_whitespace().next().unwrap_or(isbn));
+    }
+    if let Some(lccn) = item["number_lccn"][0].as_str() {
+        let lccn = normalize_lccn(lccn);
+        entry.set("lccn", &lccn);
+        entry.set("url", &format!("https://lccn.loc.gov/{}", lccn));
+    }
+    Some(entry)
+}
+
+/// A WorldCat value, which is either a string or an object with the string in `text`.
+fn text(v: &Value) -> Option<&str> {
+    v["text"].as_str().or_else(|| v.as_str()).map(str::trim)
+}
+
+/// Builds a `@book` from a WorldCat Search API bibliographic record.
+pub fn worldcat_entry(json: &Value) -> Option<Entry> {
+    let title = text(&json["title"]["mainTitles"][0])?;
+    // Main titles carry the statement of responsibility: "Title / by Author".
+    let title = title
+        .split(" / ")
+        .next()
+        .unwrap_or(title)
+        .trim_end_matches(['.', ' '])
+        .to_owned();
+    let authors = json["contributor"]["creators"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| {
+            let family = text(&c["secondName"]).filter(|s| !s.is_empty());
+            let given = text(&c["firstName"]).filter(|s| !s.is_empty());
+            match (family, given) {
+                (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+                (Some(name), None) | (None, Some(name)) => Some(heading_name(name)),
+                (None, None) => None,
+            }
+        })
+        .collect::<Vec<_>>();
+    let year = json["date"]["publicationDate"]
+        .as_str()
+        .map(|d| d.chars().filter(char::is_ascii_digit).collect::<String>())
+        .filter(|d| d.len() >= 4)
+        .map(|d| d[..4].to_owned());
+
+    let mut entry = Entry::new("book", &book_key(&authors, year.as_deref()));
+    entry.set("title", &title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    let publisher = &json["publishers"][0];
+    if let Some(name) = text(&publisher["publisherName"]) {
+        entry.set("publisher", name.trim_end_matches([',', ';', ' ']));
+    }
+    if let Some(place) = text(&publisher["publicationPlace"]) {
+        entry.set("address", place.trim_end_matches([':', ' ']));
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    if let Some(isbn) = json["identifier"]["isbns"][0].as_str() {
+        entry.set("isbn", isbn);
+    }
+    let oclc = json["identifier"]["oclcNumber"].as_str()?;
+    entry.set("oclc", oclc);
+    entry.set(
+        "url",
+        &format!("https://search.worldcat.org/title/{}", oclc),
+    );
+    Some(entry)
+}
+
+/// Builds a `@book` from a Google Books volume, with its ISBN-13 if it has one and its ISBN-10
+/// otherwise.
+pub fn google_books_entry(volume: &Value) -> Option<Entry> {
+    let info = &volume["volumeInfo"];
+    let title = match (str_field(info, "title"), str_field(info, "subtitle")) {
+        (Some(title), Some(subtitle)) => format!("{}: {}", title, subtitle),
+        (title, _) => title?.to_owned(),
+    };
+    let authors = info["authors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(bibtex::name_last_first)
+        .collect::<Vec<_>>();
+    let year = str_field(info, "publishedDate").map(|d| &d[..4.min(d.len())]);
+    let isbns = info["industryIdentifiers"].as_array();
+    let isbn = ["ISBN_13", "ISBN_10"].iter().find_map(|kind| {
+        isbns?
+            .iter()
+            .find(|i| i["type"] == *kind)
+            .and_then(|i| str_field(i, "identifier"))
+    });
+
+    let mut entry = Entry::new("book", &book_key(&authors, year));
+    entry.set("title", &title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(publisher) = str_field(info, "publisher") {
+        entry.set("publisher", publisher);
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    if let Some(isbn) = isbn {
+        entry.set("isbn", isbn);
+    }
+    let url = str_field(info, "canonicalVolumeLink")
+        .map(str::to_owned)
+        .or_else(|| {
+            str_field(volume, "id").map(|id| format!("https://books.google.com/books?id={}", id))
+        });
+    if let Some(url) = url {
+        entry.set("url", &url);
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loc_entry() {
+        assert_eq!(normalize_lccn("85-2"), "85000002");
+        assert_eq!(normalize_lccn(" n78-89035 "), "n78089035");
+        assert_eq!(normalize_lccn("2001000002/r75"), "2001000002");
+
+        let json = serde_json::json!({"item": {
+            "title": "The Google story /",
+            "contributor_names": ["Vise, David A., 1960-", "Malseed, Mark."],
+            "created_published": ["New York : Delacorte Press, c2005."],
+            "date": "2005",
+            "number_isbn": ["055380457X (hardcover)"],
+            "number_lccn": ["2005047376"]
+        }});
+        assert_eq!(
+            loc_entry(&json).unwrap().to_string(),
+            "@book{Vise_2005,\n  title={The Google story},\n  \
+             author={Vise, David A. and Malseed, Mark},\n  publisher={Delacorte Press},\n  \
+             address={New York},\n  year={2005},\n  isbn={055380457X},\n  lccn={2005047376},\n  \
+             url={https://lccn.loc.gov/2005047376}\n}"
+        );
+    }
+
+    #[test]
+    fn test_worldcat_entry() {
+        let json = serde_json::json!({
+            "identifier": {"oclcNumber": "60589465", "isbns": ["9780553804577"]},
+            "title": {"mainTitles": [{"text": "The Google story / David A. Vise with Mark Malseed."}]},
+            "contributor": {"creators": [
+                {"firstName": {"text": "David A."}, "secondName": {"text": "Vise"}},
+                {"nonPersonName": {"text": "Ignored"}}
+            ]},
+            "publishers": [{"publisherName": {"text": "Delacorte Press"}, "publicationPlace": "New York :"}],
+            "date": {"publicationDate": "©2005"}
+        });
+        assert_eq!(
+            worldcat_entry(&json).unwrap().to_string(),
+            "@book{Vise_2005,\n  title={The Google story},\n  author={Vise, David A.},\n  \
+             publisher={Delacorte Press},\n  address={New York},\n  year={2005},\n  \
+             isbn={9780553804577},\n  oclc={60589465},\n  \
+             url={https://search.worldcat.org/title/60589465}\n}"
+        );
+    }
+
+    #[test]
+    fn test_google_books_entry() {
+        let volume = serde_json::json!({
+            "id": "zyTCAlFPjgYC",
+            "volumeInfo": {
+                "title": "The Google Story",
+                "subtitle": "Inside the Hottest Business",
+                "authors": ["David A. Vise", "Mark Malseed"],
+                "publisher": "Random House Digital, Inc.",
+                "publishedDate": "2005-11-15",
+                "industryIdentifiers": [
+                    {"type": "ISBN_10", "identifier": "055380457X"},
+                    {"type": "ISBN_13", "identifier": "9780553804577"}
+                ]
+            }
+        });
+        assert_eq!(
+            google_books_entry(&volume).unwrap().to_string(),
+            "@book{Vise_2005,\n  title={The Google Story: Inside the Hottest Business},\n  \
+             author={Vise, David A. and Malseed, Mark},\n  \
+             publisher={Random House Digital, Inc.},\n  year={2005},\n  isbn={9780553804577},\n  \
+             url={https://books.google.com/books?id=zyTCAlFPjgYC}\n}"
+        );
+    }
+}
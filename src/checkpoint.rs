@@ -0,0 +1,140 @@
+//! `--checkpoint FILE`: each input that resolves is appended to FILE as soon as it does, so that
+//! an interrupted batch can be run again and only the inputs that hadn't resolved are retried.
+//!
+//! The file has one JSON line per input, with the finished entries as BibTeX. Entries are reused
+//! as they were written, even if the options have changed since.
+
+use crate::bibtex;
+use crate::error::{Error, Kind};
+use crate::provenance::Provenance;
+use crate::{classify, i18n, IdType, Resolved};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+pub struct Checkpoint {
+    path: String,
+    file: File,
+    /// What earlier runs resolved, by input.
+    done: HashMap<String, Vec<Resolved>>,
+}
+
+fn line(input: &str, resolved: &[Resolved]) -> String {
+    let entries = resolved
+        .iter()
+        .map(|r| {
+            json!({
+                "source": r.source,
+                "bibtex": r.entry.to_string(),
+                "provenance": r.provenance.to_json(&r.entry, &r.source),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({ "input": input, "entries": entries }).to_string()
+}
+
+/// The inputs recorded in a checkpoint file. A line cut short by an interruption is skipped.
+fn parse(src: &str) -> HashMap<String, Vec<Resolved>> {
+    src.lines()
+        .filter_map(|line| {
+            let record: Value = serde_json::from_str(line).ok()?;
+            let input = record["input"].as_str()?;
+            let resolver = classify(input).unwrap_or(IdType::Url);
+            let resolved = record["entries"]
+                .as_array()?
+                .iter()
+                .map(|e| {
+                    Some(Resolved {
+                        input: input.to_owned(),
+                        resolver,
+                        source: e["source"].as_str()?.to_owned(),
+                        entry: bibtex::parse_entry(e["bibtex"].as_str()?).ok()?,
+                        merged: Vec::new(),
+                        provenance: Provenance::from_json(&e["provenance"]),
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((input.to_owned(), resolved))
+        })
+        .collect()
+}
+
+impl Checkpoint {
+    /// Opens a checkpoint file, creating it if it doesn't exist yet.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let fail = |key: &'static str, e: std::io::Error| {
+            Error::new(
+                Kind::Io,
+                i18n::message(key, &[("file", &path), ("error", &e)]),
+            )
+        };
+        let src = match std::fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(fail("file-unreadable", e)),
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| fail("file-unwritable", e))?;
+        // Start on a fresh line if the last run was interrupted mid-write.
+        if !src.is_empty() && !src.ends_with('\n') {
+            writeln!(file).map_err(|e| fail("file-unwritable", e))?;
+        }
+        Ok(Checkpoint {
+            path: path.to_owned(),
+            file,
+            done: parse(&src),
+        })
+    }
+
+    /// Takes what an earlier run resolved for `input`, if anything.
+    pub fn take(&mut self, input: &str) -> Option<Vec<Resolved>> {
+        self.done.remove(input)
+    }
+
+    /// Records that an input resolved. Failing to is only a warning, since the run itself is fine.
+    pub fn record(&mut self, resolved: &[Resolved]) {
+        let input = match resolved.first() {
+            Some(r) => &r.input,
+            None => return,
+        };
+        if let Err(e) = writeln!(self.file, "{}", line(input, resolved)) {
+            eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message("file-unwritable", &[("file", &self.path), ("error", &e)])
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex::Entry;
+
+    #[test]
+    fn test_parse() {
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("title", "A {B} title");
+        let resolved = Resolved {
+            input: "10.1000/xyz".to_owned(),
+            resolver: IdType::Doi,
+            source: "doi.org".to_owned(),
+            entry,
+            merged: Vec::new(),
+            provenance: Provenance::default(),
+        };
+        let first = line("10.1000/xyz", &[resolved]);
+        let src = format!("{}\n{}", first, &first[..20]);
+        let done = parse(&src);
+        assert_eq!(done.len(), 1);
+        let r = &done["10.1000/xyz"][0];
+        assert_eq!(r.source, "doi.org");
+        assert_eq!(r.entry.get("title"), Some("A {B} title"));
+        assert!(matches!(r.resolver, IdType::Doi));
+    }
+}
@@ -0,0 +1,228 @@
+//! Clipboard access and the `clipwatch` daemon.
+//!
+//! Rather than linking against each platform's clipboard API, this shells out to the standard
+//! command-line tools (`pbpaste`/`pbcopy` on macOS, PowerShell on Windows, `wl-paste`/`wl-copy`
+//! or `xclip` on Linux), which are present on virtually every desktop.
+
+use crate::error::{Error, Kind};
+use crate::{
+    classify, get_bibtex, i18n, webhook, IdType, Options, ARXIV_IDENT_RE, ARXIV_RE, DOI_IDENT_RE,
+    DOI_RE,
+};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Longest clipboard content that is still considered a possible identifier.
+const MAX_CANDIDATE_LEN: usize = 256;
+
+fn paste_command() -> Command {
+    if cfg!(target_os = "macos") {
+        Command::new("pbpaste")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-Command", "Get-Clipboard -Raw"]);
+        cmd
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut cmd = Command::new("wl-paste");
+        cmd.arg("--no-newline");
+        cmd
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-o"]);
+        cmd
+    }
+}
+
+fn copy_command() -> Command {
+    if cfg!(target_os = "macos") {
+        Command::new("pbcopy")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-Command", "$input | Set-Clipboard"]);
+        cmd
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        Command::new("wl-copy")
+    } else {
+        let mut cmd = Command::new("xclip");
+        cmd.args(["-selection", "clipboard", "-i"]);
+        cmd
+    }
+}
+
+/// Reads the current text contents of the clipboard.
+pub fn read() -> io::Result<String> {
+    let out = paste_command().stderr(Stdio::null()).output()?;
+    if !out.status.success() {
+        return Err(io::Error::other("clipboard command failed"));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Replaces the clipboard contents with `contents`.
+pub fn write(contents: &str) -> io::Result<()> {
+    let mut child = copy_command()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child.stdin.take().unwrap().write_all(contents.as_bytes())?;
+    child.wait()?;
+    Ok(())
+}
+
+/// Shows a desktop notification. Failures are ignored since notifications are purely cosmetic.
+///
+/// The summary and body can come from the clipboard, so they reach the scripting languages of
+/// macOS and Windows as environment variables read by a fixed script, never as part of it.
+pub fn notify(summary: &str, body: &str) {
+    let mut cmd = if cfg!(target_os = "macos") {
+        let mut cmd = Command::new("osascript");
+        cmd.arg("-e").arg(
+            "display notification (system attribute \"D2B_BODY\") \
+             with title (system attribute \"D2B_SUMMARY\")",
+        );
+        cmd
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args(["-NoProfile", "-Command"]).arg(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $n = New-Object System.Windows.Forms.NotifyIcon; \
+             $n.Icon = [System.Drawing.SystemIcons]::Information; $n.Visible = $true; \
+             $n.ShowBalloonTip(5000, $env:D2B_SUMMARY, $env:D2B_BODY, 'Info'); \
+             Start-Sleep -Seconds 5; $n.Dispose()",
+        );
+        cmd
+    } else {
+        let mut cmd = Command::new("notify-send");
+        cmd.arg("--").arg(summary).arg(body);
+        cmd
+    };
+    let _ = cmd
+        .env("D2B_SUMMARY", summary)
+        .env("D2B_BODY", body)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+}
+
+fn is_full_match<const N: usize>(res: &arrayvec::ArrayVec<regex::Regex, N>, s: &str) -> bool {
+    res.iter()
+        .any(|re| re.find(s).is_some_and(|m| m.as_str() == s))
+}
+
+/// Whether the clipboard contents look like a single copied identifier or identifier URL. This is
+/// deliberately stricter than the command-line input handling, since arbitrary copied text often
+/// contains things that look like arXiv IDs.
+fn is_candidate(contents: &str) -> bool {
+    if contents.is_empty()
+        || contents.len() > MAX_CANDIDATE_LEN
+        || contents.contains(char::is_whitespace)
+    {
+        return false;
+    }
+    let prefixed = DOI_IDENT_RE.is_match(contents) || ARXIV_IDENT_RE.is_match(contents);
+    match classify(contents) {
+        Some(IdType::Doi) => prefixed || is_full_match(&DOI_RE, contents),
+        Some(IdType::Arxiv) => prefixed || is_full_match(&ARXIV_RE, contents),
+        Some(IdType::Isbn) | Some(IdType::Url) | None => false,
+    }
+}
+
+/// Polls the clipboard every `interval`, resolving any identifier that gets copied and putting the
+/// resulting BibTeX back on the clipboard (after the original contents if `append` is set). Each
+/// entry is also posted to `notify_url`, if given. Only returns if the clipboard can't be read to
+/// begin with.
+pub async fn watch(
+    opts: Options,
+    append: bool,
+    interval: Duration,
+    notify_url: Option<&str>,
+) -> Result<(), Error> {
+    if let Err(e) = read() {
+        return Err(Error::new(
+            Kind::Io,
+            i18n::message("clipboard-unavailable", &[("error", &e)]),
+        ));
+    }
+    eprintln!("{}", i18n::text("clipboard-watching"));
+
+    let mut last = read().unwrap_or_default();
+    loop {
+        tokio::time::sleep(interval).await;
+        let current = match read() {
+            Ok(current) => current,
+            Err(_) => continue,
+        };
+        if current == last {
+            continue;
+        }
+        last = current.clone();
+
+        let candidate = current.trim();
+        if !is_candidate(candidate) {
+            continue;
+        }
+
+        let bibtex = match get_bibtex(candidate.to_owned(), opts.clone()).await {
+            Ok(bibtex) => bibtex,
+            Err(e) => {
+                e.report(opts.error_format);
+                notify(
+                    "d2b",
+                    &i18n::message("clipboard-unresolved", &[("input", &candidate)]),
+                );
+                continue;
+            }
+        };
+
+        // Don't clobber something the user copied while we were resolving.
+        if read().map_or(true, |now| now != current) {
+            notify(
+                "d2b",
+                &i18n::message("clipboard-changed", &[("input", &candidate)]),
+            );
+            continue;
+        }
+
+        let replacement = if append {
+            format!("{}\n\n{}", current.trim_end(), bibtex)
+        } else {
+            bibtex.clone()
+        };
+        if let Err(e) = write(&replacement) {
+            eprintln!(
+                "{}",
+                i18n::message("clipboard-unwritable", &[("error", &e)])
+            );
+            continue;
+        }
+        last = read().unwrap_or(replacement);
+        notify(
+            "d2b",
+            &i18n::message("clipboard-copied", &[("input", &candidate)]),
+        );
+        if let Some(url) = notify_url {
+            let text = i18n::message("webhook-filed", &[("input", &candidate)]);
+            webhook::post(url, &text, Some(&bibtex)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_candidate() {
+        assert!(is_candidate("https://doi.org/10.1145/359327.359336"));
+        assert!(is_candidate("10.1145/359327.359336"));
+        assert!(is_candidate("https://arxiv.org/abs/2105.11572"));
+        assert!(is_candidate("arXiv:2105.11572"));
+        assert!(is_candidate("2105.11572v2"));
+        assert!(!is_candidate("version 2105.11572 of the draft"));
+        assert!(!is_candidate("v2105.11572"));
+        assert!(!is_candidate("@article{Smith_2019,"));
+        assert!(!is_candidate(""));
+    }
+}
@@ -0,0 +1,174 @@
+//! `--color`: BibTeX written to a terminal with the entry types, keys, field names and values in
+//! different colours, so that a fetched entry can be checked at a glance. Output that goes to a
+//! file or a pipe stays plain unless `--color always` is given, and `NO_COLOR` is honoured.
+
+use std::io::IsTerminal;
+
+const KIND: &str = "\x1b[1;35m";
+const KEY: &str = "\x1b[1;33m";
+const NAME: &str = "\x1b[36m";
+const VALUE: &str = "\x1b[32m";
+const COMMENT: &str = "\x1b[2m";
+const ADDED: &str = "\x1b[32m";
+const REMOVED: &str = "\x1b[31m";
+const HUNK: &str = "\x1b[36m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    Auto,
+    Always,
+    Never,
+}
+
+impl When {
+    pub const NAMES: &'static [&'static str] = &["auto", "always", "never"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(When::Auto),
+            "always" => Some(When::Always),
+            "never" => Some(When::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether standard output gets colours.
+    pub fn enabled(self) -> bool {
+        match self {
+            When::Always => true,
+            When::Never => false,
+            When::Auto => {
+                std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// Where in an entry the scanner is.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Outside,
+    Comment,
+    Kind,
+    Key,
+    Name,
+    /// A value, with the depth of braces and whether it's inside quotes.
+    Value(usize, bool),
+}
+
+/// Text with colour codes, which only switches colours when it has to and resets them at the end
+/// of each line, so that pagers showing part of it don't bleed colours.
+#[derive(Default)]
+struct Painter {
+    out: String,
+    color: Option<&'static str>,
+}
+
+impl Painter {
+    fn push(&mut self, color: Option<&'static str>, c: char) {
+        let color = color.filter(|_| c != '\n');
+        if color != self.color {
+            if self.color.is_some() {
+                self.out.push_str(RESET);
+            }
+            self.out.extend(color);
+            self.color = color;
+        }
+        self.out.push(c);
+    }
+
+    fn finish(mut self) -> String {
+        if self.color.is_some() {
+            self.out.push_str(RESET);
+        }
+        self.out
+    }
+}
+
+/// BibTeX text with its parts in colour. Anything that isn't an entry, like comments, is dimmed.
+pub fn highlight(src: &str) -> String {
+    let mut painter = Painter::default();
+    let mut state = State::Outside;
+    let mut line_start = true;
+    for c in src.chars() {
+        let (color, next) = match state {
+            State::Outside if c == '@' => (Some(KIND), State::Kind),
+            State::Outside if line_start && c == '%' => (Some(COMMENT), State::Comment),
+            State::Outside => (None, State::Outside),
+            State::Comment if c == '\n' => (None, State::Outside),
+            State::Comment => (Some(COMMENT), State::Comment),
+            State::Kind if c == '{' || c == '(' => (None, State::Key),
+            State::Kind => (Some(KIND), State::Kind),
+            // `@string{name = value}` has no key, only a field.
+            State::Key if c == '=' => (None, State::Value(0, false)),
+            State::Key if c == ',' => (None, State::Name),
+            State::Key if c == '}' || c == ')' => (None, State::Outside),
+            State::Key if c.is_whitespace() => (None, State::Key),
+            State::Key => (Some(KEY), State::Key),
+            State::Name if c == '=' => (None, State::Value(0, false)),
+            State::Name if c == '}' || c == ')' => (None, State::Outside),
+            State::Name if c.is_whitespace() || c == ',' => (None, State::Name),
+            State::Name => (Some(NAME), State::Name),
+            State::Value(0, false) if c == ',' => (None, State::Name),
+            State::Value(0, false) if c == '}' || c == ')' => (None, State::Outside),
+            State::Value(0, false) if c.is_whitespace() || c == '#' => (None, state),
+            State::Value(0, quoted) if c == '"' => (Some(VALUE), State::Value(0, !quoted)),
+            State::Value(depth, quoted) if c == '{' => {
+                (Some(VALUE), State::Value(depth + 1, quoted))
+            }
+            State::Value(depth, quoted) if c == '}' => {
+                (Some(VALUE), State::Value(depth.saturating_sub(1), quoted))
+            }
+            State::Value(..) => (Some(VALUE), state),
+        };
+        painter.push(color, c);
+        state = next;
+        line_start = c == '\n';
+    }
+    painter.finish()
+}
+
+/// A unified diff with added lines in green, removed ones in red and hunk headers in cyan.
+pub fn diff(src: &str) -> String {
+    src.split_inclusive('\n')
+        .map(|line| {
+            let color = match line.as_bytes().first() {
+                _ if line.starts_with("+++") || line.starts_with("---") => KIND,
+                Some(b'+') => ADDED,
+                Some(b'-') => REMOVED,
+                Some(b'@') => HUNK,
+                _ => return line.to_owned(),
+            };
+            let (text, newline) = match line.strip_suffix('\n') {
+                Some(text) => (text, "\n"),
+                None => (line, ""),
+            };
+            format!("{}{}{}{}", color, text, RESET, newline)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight() {
+        let src = "% fetched\n@article{Doe_2020,\n  title={A {B}, C},\n  year=2020 # \"x\"\n}";
+        let plain = highlight(src)
+            .replace(KIND, "<k>")
+            .replace(KEY, "<y>")
+            .replace(NAME, "<n>")
+            .replace(VALUE, "<v>")
+            .replace(COMMENT, "<c>")
+            .replace(RESET, "</>");
+        assert_eq!(
+            plain,
+            "<c>% fetched</>\n<k>@article</>{<y>Doe_2020</>,\n  <n>title</>=<v>{A {B}, C}</>,\n  \
+             <n>year</>=<v>2020</> # <v>\"x\"</>\n}"
+        );
+        assert_eq!(When::from_name("never").map(When::enabled), Some(false));
+    }
+}
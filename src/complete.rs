@@ -0,0 +1,229 @@
+//! `d2b complete`: a completion backend for cite-as-you-type plugins in LaTeX and Typst editors.
+//! The plugin writes what is typed under the cursor, part of a title or an author's name, as a
+//! JSON line on stdin, and gets back a JSON line of candidates with their keys and full entries:
+//! first those in the local library (`d2b lib`) that match every word, then works CrossRef finds.
+//!
+//! A request is a JSON string or an object with a `query`, an optional `limit` and an `id` that
+//! is echoed back. Requests are answered as their searches finish rather than in order, so that a
+//! plugin can take the answer to the latest one and ignore the rest.
+
+use crate::bibtex::Entry;
+use crate::library::{self, Library};
+use crate::{crossref, i18n, launcher, notes};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::io::Write;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 50;
+
+/// Queries shorter than this are answered from the library alone, as nearly anything matches
+/// them.
+const MIN_REMOTE_CHARS: usize = 4;
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("complete")
+        .about(crate::i18n::text("about-complete"))
+        .arg(library::db_arg())
+        .arg(
+            Arg::with_name("library-only")
+                .long("library-only")
+                .help(i18n::text("help-library-only")),
+        )
+}
+
+/// A completion request.
+#[derive(Debug, PartialEq)]
+struct Request {
+    id: Value,
+    query: String,
+    limit: usize,
+}
+
+fn parse_request(line: &str) -> Result<Request, String> {
+    let bad = || "expected a JSON string or an object with a `query`".to_owned();
+    match serde_json::from_str(line).map_err(|_| bad())? {
+        Value::String(query) => Ok(Request {
+            id: Value::Null,
+            query,
+            limit: DEFAULT_LIMIT,
+        }),
+        Value::Object(request) => Ok(Request {
+            query: request
+                .get("query")
+                .and_then(Value::as_str)
+                .ok_or_else(bad)?
+                .to_owned(),
+            limit: request
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map_or(DEFAULT_LIMIT, |l| (l as usize).clamp(1, MAX_LIMIT)),
+            id: request.get("id").cloned().unwrap_or(Value::Null),
+        }),
+        _ => Err(bad()),
+    }
+}
+
+/// The words of a query or of what it's matched against, without markup or case.
+fn words(s: &str) -> Vec<String> {
+    notes::plain(s)
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Whether an entry's key, title or authors have every word of the query, the last of which may
+/// still be being typed and so only has to start a word.
+fn matches(entry: &Entry, query: &[String]) -> bool {
+    let mut haystack = words(&entry.key);
+    haystack.extend(words(entry.get("title").unwrap_or("")));
+    haystack.extend(words(entry.get("author").unwrap_or("")));
+    query.iter().enumerate().all(|(n, word)| {
+        haystack.iter().any(|w| match n + 1 == query.len() {
+            true => w.starts_with(word.as_str()),
+            false => w == word,
+        })
+    })
+}
+
+fn candidate(entry: &Entry, source: &str) -> Value {
+    json!({
+        "key": entry.key,
+        "title": entry.get("title").map(notes::plain).unwrap_or_default(),
+        "detail": launcher::subtitle(entry),
+        "source": source,
+        "bibtex": entry.to_string(),
+    })
+}
+
+/// The library's entries matching a query, latest first.
+fn from_library(lib: &Library, query: &[String], limit: usize) -> Vec<Entry> {
+    let mut records = lib.list(&[]).unwrap_or_default();
+    records.reverse();
+    records
+        .into_iter()
+        .map(|r| r.entry)
+        .filter(|e| matches(e, query))
+        .take(limit)
+        .collect()
+}
+
+/// The answer to a request: the library's candidates, then CrossRef's for works not among them.
+async fn answer(request: Request, local: Vec<Entry>, remote: bool) -> Value {
+    let mut candidates = local
+        .iter()
+        .map(|e| candidate(e, "library"))
+        .collect::<Vec<_>>();
+    let wanted = request.limit - local.len();
+    if remote && wanted > 0 && request.query.trim().chars().count() >= MIN_REMOTE_CHARS {
+        let known = local
+            .iter()
+            .filter_map(|e| e.get("doi"))
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>();
+        let works = crossref::search(&request.query, wanted as u64).await;
+        candidates.extend(
+            works
+                .unwrap_or_default()
+                .iter()
+                .filter_map(crossref::entry)
+                .filter(|e| {
+                    e.get("doi")
+                        .is_none_or(|d| !known.contains(&d.to_lowercase()))
+                })
+                .map(|e| candidate(&e, "api.crossref.org")),
+        );
+    }
+    json!({ "id": request.id, "candidates": candidates })
+}
+
+/// Runs `d2b complete` until stdin is closed.
+pub async fn run(matches: &ArgMatches<'_>, opts: crate::Options) {
+    let lib = library::open(matches, opts.error_format);
+    let remote = !matches.is_present("library-only");
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut pending = FuturesUnordered::new();
+    let mut eof = false;
+    let stdout = std::io::stdout();
+    loop {
+        let reply = tokio::select! {
+            line = lines.next_line(), if !eof => match line {
+                Ok(Some(line)) if line.trim().is_empty() => continue,
+                Ok(Some(line)) => match parse_request(&line) {
+                    Ok(request) => {
+                        // The library is searched straight away; only CrossRef is waited for.
+                        let query = words(&request.query);
+                        let local = match query.is_empty() {
+                            true => Vec::new(),
+                            false => from_library(&lib, &query, request.limit),
+                        };
+                        pending.push(answer(request, local, remote));
+                        continue;
+                    }
+                    Err(e) => json!({ "id": Value::Null, "error": e }),
+                },
+                Ok(None) | Err(_) => {
+                    eof = true;
+                    continue;
+                }
+            },
+            Some(reply) = pending.next(), if !pending.is_empty() => reply,
+            else => break,
+        };
+        let mut out = stdout.lock();
+        if writeln!(out, "{}", reply)
+            .and_then(|_| out.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request(r#"{"id": 7, "query": "attention is", "limit": 500}"#).unwrap(),
+            Request {
+                id: json!(7),
+                query: "attention is".to_owned(),
+                limit: MAX_LIMIT
+            }
+        );
+        assert_eq!(parse_request(r#""hoare""#).unwrap().limit, DEFAULT_LIMIT);
+        assert!(parse_request(r#"{"id": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_from_library() {
+        let lib = Library::open_in_memory().unwrap();
+        let mut csp = Entry::new("article", "Hoare_1978");
+        csp.set("author", "Hoare, C. A. R.");
+        csp.set("title", "Communicating {Sequential} Processes");
+        lib.add("10.1145/359576.359585", &csp, &[]).unwrap();
+        let mut quicksort = Entry::new("article", "Hoare_1962");
+        quicksort.set("author", "Hoare, C. A. R.");
+        quicksort.set("title", "Quicksort");
+        lib.add("10.1093/comjnl/5.1.10", &quicksort, &[]).unwrap();
+
+        let keys = |query: &str| {
+            from_library(&lib, &words(query), DEFAULT_LIMIT)
+                .into_iter()
+                .map(|e| e.key)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys("hoare"), ["Hoare_1962", "Hoare_1978"]);
+        assert_eq!(keys("communicating seq"), ["Hoare_1978"]);
+        assert!(keys("communicat sequential").is_empty());
+        assert!(keys("dijkstra").is_empty());
+    }
+}
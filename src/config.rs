@@ -0,0 +1,230 @@
+//! The user configuration file, `config.toml`.
+//!
+//! ```toml
+//! # The `.bib` file `--insert-key-only` appends to. Relative paths are relative to this file.
+//! bib = "refs.bib"
+//!
+//! # Added to every entry that doesn't already have them.
+//! [fields]
+//! read = "false"
+//! added = "{date}"
+//! file = "papers/{key}.pdf"
+//!
+//! # API tokens, which can also be set with `d2b auth set` or environment variables.
+//! [tokens]
+//! ads = "..."
+//!
+//! # Which resolvers are tried, in order, for each kind of identifier.
+//! [resolvers]
+//! doi = ["crossref", "doi.org"]
+//!
+//! # Resolvers for inputs d2b doesn't know about; see `resolvers`.
+//! [[resolver]]
+//! name = "hal"
+//! pattern = '^(?:hal:)?(hal-\d+)$'
+//! url = "https://api.archives-ouvertes.fr/search/?q=halId_s:{1}&wt=bibtex"
+//! format = "bibtex"
+//!
+//! # Scripts that format entries, by output format; see `script`. Relative paths are relative to
+//! # this file.
+//! [formatters]
+//! bibtex = "lab.rhai"
+//! ```
+
+use crate::bibtex::Entry;
+use crate::resolvers::{Custom, Priorities};
+use crate::Format;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{([A-Za-z_-]+)\}").unwrap();
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Extra fields for every entry, as name/template pairs in the order they were configured.
+    fields: toml::Table,
+    /// API tokens by service name.
+    pub tokens: BTreeMap<String, String>,
+    resolvers: BTreeMap<String, Vec<String>>,
+    /// Resolvers defined by `[[resolver]]` tables, in the order they are tried.
+    #[serde(rename = "resolver")]
+    pub custom: Vec<Custom>,
+    formatters: BTreeMap<String, PathBuf>,
+    bib: Option<PathBuf>,
+    /// The directory the file was read from.
+    #[serde(skip)]
+    dir: Option<PathBuf>,
+}
+
+/// `d2b` in the platform's configuration directory.
+pub fn dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(dir.join("d2b"))
+}
+
+/// `$D2B_CONFIG`, or `d2b/config.toml` in the platform's configuration directory.
+pub fn default_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os("D2B_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+    Some(dir()?.join("config.toml"))
+}
+
+impl Config {
+    /// Reads the configuration from `path`, or from the default location if that's `None`. Only
+    /// an explicitly given file has to exist.
+    pub fn load(path: Option<&str>) -> Result<Self, String> {
+        let (path, required) = match path {
+            Some(path) => (PathBuf::from(path), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Config::default()),
+            },
+        };
+        let src = match std::fs::read_to_string(&path) {
+            Ok(src) => src,
+            Err(_) if !required => return Ok(Config::default()),
+            Err(e) => return Err(format!("{}: {}", path.display(), e)),
+        };
+        let mut config = Self::parse(&src).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.dir = path.parent().map(PathBuf::from);
+        Ok(config)
+    }
+
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let config: Config = toml::from_str(src).map_err(|e| e.to_string())?;
+        Priorities::parse(&config.resolvers)?;
+        if let Some(format) = config
+            .formatters
+            .keys()
+            .find(|f| !Format::NAMES.contains(&f.as_str()))
+        {
+            return Err(format!("formatters: unknown format \"{}\"", format));
+        }
+        Ok(config)
+    }
+
+    /// A path given in the file, which is relative to it.
+    fn path(&self, path: &Path) -> PathBuf {
+        match &self.dir {
+            Some(dir) => dir.join(path),
+            None => path.to_owned(),
+        }
+    }
+
+    /// The script configured to format entries in `format`, if any.
+    pub fn formatter(&self, format: &str) -> Option<PathBuf> {
+        Some(self.path(self.formatters.get(format)?))
+    }
+
+    /// The `.bib` file configured for `--insert-key-only`, if any.
+    pub fn bib(&self) -> Option<PathBuf> {
+        Some(self.path(self.bib.as_ref()?))
+    }
+
+    /// The configured resolver order, or the default for identifier types that aren't configured.
+    pub fn priorities(&self) -> Priorities {
+        Priorities::parse(&self.resolvers).unwrap_or_default()
+    }
+
+    /// The configured extra fields, with non-string values written out as TOML would.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        self.fields
+            .iter()
+            .map(|(name, value)| {
+                let value = match value {
+                    toml::Value::String(s) => s.clone(),
+                    v => v.to_string(),
+                };
+                (name.clone(), value)
+            })
+            .collect()
+    }
+}
+
+/// Fills in a field template. `{key}`, `{date}` (today, as YYYY-MM-DD) and `{input}` are
+/// replaced, as is `{name}` for any field the entry has; other braces are kept.
+pub fn expand(template: &str, entry: &Entry, input: &str) -> String {
+    PLACEHOLDER_RE
+        .replace_all(template, |c: &regex::Captures| match &c[1] {
+            "key" => entry.key.clone(),
+            "date" => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            "input" => input.trim().to_owned(),
+            name => entry
+                .get(name)
+                .map(str::to_owned)
+                .unwrap_or_else(|| c[0].to_owned()),
+        })
+        .into_owned()
+}
+
+/// Adds the configured extra fields that an entry doesn't already have.
+pub fn add_fields(entry: &mut Entry, fields: &[(String, String)], input: &str) {
+    for (name, template) in fields {
+        if entry.get(name).is_none() {
+            let value = expand(template, entry, input);
+            entry.set(name, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_fields() {
+        let config = Config::parse(
+            r#"
+            [fields]
+            read = false
+            file = "papers/{key}.pdf"
+            source = "{input} ({doi}, {nope})"
+            note = "not used"
+            "#,
+        )
+        .unwrap();
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("doi", "10.1000/xyz");
+        entry.set("note", "Keep me");
+        add_fields(&mut entry, &config.fields(), " doi:10.1000/xyz ");
+
+        assert_eq!(entry.get("read"), Some("false"));
+        assert_eq!(entry.get("file"), Some("papers/Shen_2021.pdf"));
+        assert_eq!(
+            entry.get("source"),
+            Some("doi:10.1000/xyz (10.1000/xyz, {nope})")
+        );
+        assert_eq!(entry.get("note"), Some("Keep me"));
+        assert_eq!(
+            entry
+                .fields
+                .iter()
+                .map(|(n, _)| n.as_str())
+                .collect::<Vec<_>>(),
+            ["doi", "note", "read", "file", "source"]
+        );
+        assert!(Config::parse("fields = 3").is_err());
+    }
+
+    #[test]
+    fn test_bib() {
+        let mut config = Config::parse("bib = \"papers/refs.bib\"").unwrap();
+        assert_eq!(config.bib(), Some(PathBuf::from("papers/refs.bib")));
+        config.dir = Some(PathBuf::from("/home/me/.config/d2b"));
+        assert_eq!(
+            config.bib(),
+            Some(PathBuf::from("/home/me/.config/d2b/papers/refs.bib"))
+        );
+        assert_eq!(Config::default().bib(), None);
+    }
+}
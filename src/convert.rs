@@ -0,0 +1,218 @@
+//! `d2b convert`: writes the entries of existing `.bib` files as CSL-JSON, RIS or Hayagriva YAML
+//! (Typst's bibliography format), without going online. `@string` macros are expanded first, and
+//! TeX grouping braces are dropped from the values.
+
+use crate::bibtex::Entry;
+use crate::error::{Error, Kind};
+use crate::notes::{self, plain, yaml_str};
+use crate::{atomic, csl, i18n, screening, Options};
+use clap::ArgMatches;
+use d2b::bibfile::Bibliography;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    CslJson,
+    Ris,
+    Hayagriva,
+}
+
+impl Target {
+    pub const NAMES: &'static [&'static str] = &["csl-json", "ris", "hayagriva"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csl-json" => Some(Target::CslJson),
+            "ris" => Some(Target::Ris),
+            "hayagriva" => Some(Target::Hayagriva),
+            _ => None,
+        }
+    }
+
+    pub fn convert(self, entries: &[Entry]) -> String {
+        match self {
+            Target::CslJson => {
+                let items = entries.iter().map(csl::item).collect::<Vec<_>>();
+                serde_json::to_string_pretty(&items).unwrap() + "\n"
+            }
+            Target::Ris => entries
+                .iter()
+                .map(|e| screening::ris(e, e.get("abstract").map(plain).as_deref()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Target::Hayagriva => entries.iter().map(hayagriva).collect(),
+        }
+    }
+}
+
+/// The Hayagriva type of an entry, and the type of its parent if it's published in one.
+fn hayagriva_types(entry: &Entry) -> (&'static str, Option<&'static str>) {
+    match entry.kind.to_lowercase().as_str() {
+        "article" => ("article", Some("periodical")),
+        "inproceedings" | "conference" => ("article", Some("proceedings")),
+        "incollection" | "inbook" => ("chapter", Some("book")),
+        "book" => ("book", None),
+        "proceedings" => ("proceedings", None),
+        "phdthesis" | "mastersthesis" | "thesis" => ("thesis", None),
+        "techreport" | "report" => ("report", None),
+        "online" => ("web", None),
+        "audio" => ("audio", None),
+        "video" => ("video", None),
+        _ => ("misc", None),
+    }
+}
+
+/// A YAML scalar: numbers as they are, anything else quoted.
+fn scalar(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+        value.to_owned()
+    } else {
+        yaml_str(value)
+    }
+}
+
+/// An entry as a Hayagriva YAML entry, keyed by its BibTeX key. The venue, volume and issue go in
+/// the `parent` of articles and chapters, as Hayagriva models them.
+pub fn hayagriva(entry: &Entry) -> String {
+    let field = |name: &str| entry.get(name).map(plain).filter(|v| !v.is_empty());
+    let names = |name: &str| {
+        csl::csl_names(entry.get(name))
+            .iter()
+            .map(|p| match (p["family"].as_str(), p["given"].as_str()) {
+                (Some(family), Some(given)) => yaml_str(&format!("{}, {}", family, given)),
+                _ => yaml_str(p["literal"].as_str().unwrap_or_default()),
+            })
+            .collect::<Vec<_>>()
+    };
+    let (kind, parent) = hayagriva_types(entry);
+    let mut out = String::new();
+    writeln!(out, "{}:", yaml_str(&entry.key)).unwrap();
+    writeln!(out, "  type: {}", kind).unwrap();
+    if let Some(title) = field("title") {
+        writeln!(out, "  title: {}", yaml_str(&title)).unwrap();
+    }
+    for name in ["author", "editor"] {
+        let names = names(name);
+        if !names.is_empty() {
+            writeln!(out, "  {}: [{}]", name, names.join(", ")).unwrap();
+        }
+    }
+    let year = field("year").or_else(|| field("date").and_then(|d| d.get(..4).map(str::to_owned)));
+    if let Some(year) = year {
+        match field("month").and_then(|m| notes::month(&m)) {
+            Some(month) => writeln!(out, "  date: {}-{:02}", year, month).unwrap(),
+            None => writeln!(out, "  date: {}", field("date").unwrap_or(year)).unwrap(),
+        }
+    }
+    if let Some(pages) = field("pages") {
+        writeln!(out, "  page-range: {}", yaml_str(&pages.replace("--", "-"))).unwrap();
+    }
+    if let Some(edition) = field("edition") {
+        writeln!(out, "  edition: {}", scalar(&edition)).unwrap();
+    }
+    let publisher = field("publisher")
+        .or_else(|| field("school"))
+        .or_else(|| field("institution"));
+    let (publisher_key, publisher) = match kind {
+        "thesis" | "report" => ("organization", publisher),
+        _ => ("publisher", publisher),
+    };
+    let location = field("address").or_else(|| field("location"));
+    let venue = field("journal")
+        .or_else(|| field("journaltitle"))
+        .or_else(|| field("booktitle"));
+    let indent = match (parent, venue) {
+        (Some(parent), Some(venue)) => {
+            writeln!(out, "  parent:").unwrap();
+            writeln!(out, "    type: {}", parent).unwrap();
+            writeln!(out, "    title: {}", yaml_str(&venue)).unwrap();
+            "    "
+        }
+        _ => "  ",
+    };
+    for (key, value) in [
+        ("volume", field("volume")),
+        ("issue", field("number")),
+        (publisher_key, publisher),
+        ("location", location),
+    ] {
+        if let Some(value) = value {
+            writeln!(out, "{}{}: {}", indent, key, scalar(&value)).unwrap();
+        }
+    }
+    let serials = [
+        ("doi", field("doi")),
+        ("isbn", field("isbn")),
+        ("issn", field("issn")),
+        ("arxiv", field("eprint")),
+    ];
+    if serials.iter().any(|(_, v)| v.is_some()) {
+        writeln!(out, "  serial-number:").unwrap();
+        for (key, value) in serials {
+            if let Some(value) = value {
+                writeln!(out, "    {}: {}", key, yaml_str(&value)).unwrap();
+            }
+        }
+    }
+    if let Some(url) = field("url") {
+        writeln!(out, "  url: {}", yaml_str(&url)).unwrap();
+    }
+    if let Some(note) = field("note") {
+        writeln!(out, "  note: {}", yaml_str(&note)).unwrap();
+    }
+    out
+}
+
+/// Runs `d2b convert`.
+pub fn run(matches: &ArgMatches, opts: Options) {
+    let target = Target::from_name(matches.value_of("to").unwrap()).unwrap();
+    let mut entries = Vec::new();
+    for file in matches.values_of("files").unwrap() {
+        let src = std::fs::read_to_string(file).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unreadable", &[("file", &file), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        });
+        let bib = Bibliography::parse(&src);
+        entries.extend(bib.entries().map(|e| bib.expand(e)));
+    }
+
+    let out = target.convert(&entries);
+    match matches.value_of("output") {
+        Some(path) => atomic::write(path, out).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &path), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        }),
+        None => print!("{}", out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex;
+
+    #[test]
+    fn test_hayagriva() {
+        let entry = bibtex::parse_entry(
+            "@inproceedings{Doe_2020, title={{Fast} Things: A Survey}, author={Doe, Jane and Roe, R. and others}, \
+             booktitle={Proceedings of FOO}, pages={1--10}, year={2020}, month=sep, publisher={ACM}, \
+             doi={10.1/x}}",
+        )
+        .unwrap();
+        assert_eq!(
+            hayagriva(&entry),
+            "\"Doe_2020\":\n  type: article\n  title: \"Fast Things: A Survey\"\n  \
+             author: [\"Doe, Jane\", \"Roe, R.\"]\n  date: 2020-09\n  page-range: \"1-10\"\n  \
+             parent:\n    type: proceedings\n    title: \"Proceedings of FOO\"\n    publisher: \"ACM\"\n  \
+             serial-number:\n    doi: \"10.1/x\"\n"
+        );
+        let ris = Target::Ris.convert(&[entry]);
+        assert!(ris.starts_with("TY  - CONF\nID  - Doe_2020\n"));
+    }
+}
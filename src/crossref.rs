@@ -0,0 +1,545 @@
+//! Building entries from CrossRef's JSON metadata, for the work types where the BibTeX negotiated
+//! through doi.org loses information (editors and book titles of chapters in particular).
+
+use crate::bibtex::Entry;
+use crate::error::{Error, Kind};
+use crate::graph;
+use crate::http::SendWithRetry;
+use crate::language::language_name;
+use crate::{check_response, CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+lazy_static! {
+    static ref CHAPTER_RE: Regex = Regex::new(r"(?i)^(?:chapter|ch\.)\s*(\d+)").unwrap();
+}
+
+/// Fetches the `message` object of `https://api.crossref.org/works/{doi}`.
+pub async fn fetch_work(doi: &str) -> Option<Value> {
+    let mut url = reqwest::Url::parse("https://api.crossref.org/works/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(doi.split('/'));
+    let body = CLIENT
+        .get(url)
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let mut json: Value = serde_json::from_str(&body).ok()?;
+    Some(json["message"].take())
+}
+
+/// Whether a negotiated entry of this type might be a chapter or book that CrossRef JSON can
+/// describe better.
+pub fn is_book_like(kind: &str) -> bool {
+    matches!(
+        kind.to_ascii_lowercase().as_str(),
+        "book" | "inbook" | "incollection" | "proceedings"
+    )
+}
+
+/// Lists every paper in the proceedings volume with the given DOI, paging through the works of
+/// the CrossRef member that registered it.
+pub async fn proceedings_papers(doi: &str) -> Vec<Entry> {
+    let volume = match fetch_work(doi).await {
+        Some(volume) => volume,
+        None => return Vec::new(),
+    };
+    let isbn = match first_str(&volume, "ISBN") {
+        Some(isbn) => isbn.to_owned(),
+        None => return Vec::new(),
+    };
+    let url = match volume["member"].as_str() {
+        Some(member) => format!("https://api.crossref.org/members/{}/works", member),
+        None => "https://api.crossref.org/works".to_owned(),
+    };
+
+    works(
+        &url,
+        &format!("isbn:{},type:proceedings-article", isbn),
+        None,
+    )
+    .await
+    .unwrap_or_default()
+    .iter()
+    .filter_map(paper_entry)
+    .collect()
+}
+
+/// Pages through the works listed at a CrossRef `.../works` URL that match `filter` (all of them if
+/// it's empty), stopping after `limit` of them if given. A page that fails fails the whole listing,
+/// rather than cutting it short unnoticed.
+pub async fn works(url: &str, filter: &str, limit: Option<usize>) -> Result<Vec<Value>, Error> {
+    let mut works = Vec::new();
+    let mut cursor = "*".to_owned();
+    loop {
+        let mut query = vec![("rows", "1000".to_owned()), ("cursor", cursor.clone())];
+        if !filter.is_empty() {
+            query.push(("filter", filter.to_owned()));
+        }
+        let page = CLIENT.get(url).query(&query).send_with_retry().await;
+        let body = check_response(page, "api.crossref.org")?.text().await?;
+        let mut json: Value =
+            serde_json::from_str(&body).map_err(|e| Error::new(Kind::Parse, e.to_string()))?;
+        let items = match json["message"]["items"].take() {
+            Value::Array(items) if !items.is_empty() => items,
+            _ => break,
+        };
+        works.extend(items);
+        if limit.is_some_and(|limit| works.len() >= limit) {
+            works.truncate(limit.unwrap());
+            break;
+        }
+        match json["message"]["next-cursor"].as_str() {
+            Some(next) if next != cursor => cursor = next.to_owned(),
+            _ => break,
+        }
+    }
+    Ok(works)
+}
+
+/// The works CrossRef finds for some bibliographic details, like a title and author names, best
+/// match first; or, if the search failed, the status CrossRef answered with.
+pub async fn search(query: &str, rows: u64) -> Result<Vec<Value>, Option<StatusCode>> {
+    let req = CLIENT
+        .get("https://api.crossref.org/works")
+        .query(&[("query.bibliographic", query), ("rows", &rows.to_string())]);
+    match graph::get_json(req).await?["message"]["items"].take() {
+        Value::Array(items) => Ok(items),
+        _ => Err(None),
+    }
+}
+
+/// The entry for a work of any type, as CrossRef's JSON describes it.
+pub fn entry(work: &Value) -> Option<Entry> {
+    book_entry(work, &make_key(work))
+        .or_else(|| paper_entry(work))
+        .or_else(|| article_entry(work))
+}
+
+/// A `Family_Year` key for a work, using the first author or, failing that, the first editor.
+pub fn make_key(work: &Value) -> String {
+    let person = &work["author"][0];
+    let person = if person.is_null() {
+        &work["editor"][0]
+    } else {
+        person
+    };
+    let family = person["family"]
+        .as_str()
+        .or_else(|| person["name"].as_str())
+        .or_else(|| person["literal"].as_str())
+        .unwrap_or("anonymous")
+        .replace(' ', "");
+    match year(work) {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    }
+}
+
+/// Builds an `@inproceedings` entry for a CrossRef `proceedings-article` work.
+pub fn paper_entry(work: &Value) -> Option<Entry> {
+    if work["type"].as_str()? != "proceedings-article" {
+        return None;
+    }
+    let mut entry = Entry::new("inproceedings", &make_key(work));
+    entry.set("title", first_str(work, "title")?);
+    if let Some(authors) = names(work, "author") {
+        entry.set("author", &authors);
+    }
+    if let Some(booktitle) = first_str(work, "container-title") {
+        entry.set("booktitle", booktitle);
+    }
+    if let Some(publisher) = first_str(work, "publisher") {
+        entry.set("publisher", publisher);
+    }
+    if let Some(year) = year(work) {
+        entry.set("year", &year.to_string());
+    }
+    if let Some(pages) = first_str(work, "page") {
+        entry.set("pages", &pages.replace("--", "-").replace('-', "--"));
+    }
+    if let Some(language) = first_str(work, "language") {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = first_str(work, "DOI") {
+        entry.set("doi", doi);
+        entry.set("url", &format!("https://doi.org/{}", doi));
+    }
+    Some(entry)
+}
+
+/// Builds an `@article` entry for a journal article, or a `@techreport` or `@misc` for any other
+/// work, for when DOIs are resolved through the CrossRef API rather than doi.org. Books, chapters
+/// and proceedings papers should go through [`book_entry`] and [`paper_entry`] first.
+pub fn article_entry(work: &Value) -> Option<Entry> {
+    let kind = match work["type"].as_str()? {
+        "journal-article" => "article",
+        "report" => "techreport",
+        _ => "misc",
+    };
+    let mut entry = Entry::new(kind, &make_key(work));
+    entry.set("title", first_str(work, "title")?);
+    if let Some(authors) = names(work, "author") {
+        entry.set("author", &authors);
+    }
+    match (kind, first_str(work, "container-title")) {
+        ("article", Some(journal)) => entry.set("journal", journal),
+        ("misc", Some(container)) => entry.set("howpublished", container),
+        _ => {}
+    }
+    if let Some(volume) = first_str(work, "volume") {
+        entry.set("volume", volume);
+    }
+    if let Some(issue) = first_str(work, "issue") {
+        entry.set("number", issue);
+    }
+    if let Some(pages) = first_str(work, "page") {
+        entry.set("pages", &pages.replace("--", "-").replace('-', "--"));
+    }
+    if let Some(publisher) = first_str(work, "publisher") {
+        match kind {
+            "techreport" => entry.set("institution", publisher),
+            _ => entry.set("publisher", publisher),
+        }
+    }
+    if let Some(year) = year(work) {
+        entry.set("year", &year.to_string());
+    }
+    if let Some(language) = first_str(work, "language") {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = first_str(work, "DOI") {
+        entry.set("doi", doi);
+        entry.set("url", &format!("https://doi.org/{}", doi));
+    }
+    Some(entry)
+}
+
+pub fn first_str<'a>(work: &'a Value, field: &str) -> Option<&'a str> {
+    work[field]
+        .as_str()
+        .or_else(|| work[field][0].as_str())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+}
+
+/// Formats a CrossRef contributor list (`author`, `editor`, ...) as a BibTeX name list.
+pub fn names(work: &Value, role: &str) -> Option<String> {
+    let names = work[role]
+        .as_array()?
+        .iter()
+        .filter_map(|p| match (p["family"].as_str(), p["given"].as_str()) {
+            (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+            (Some(family), None) => Some(family.to_owned()),
+            // Organizations are braced so that BibTeX doesn't try to split them into names.
+            // CSL-JSON calls their name `literal`.
+            _ => p["name"]
+                .as_str()
+                .or_else(|| p["literal"].as_str())
+                .map(|n| format!("{{{}}}", n)),
+        })
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names.join(" and "))
+    }
+}
+
+/// The DOI of the published version of a preprint, from the work's `is-preprint-of` relation or,
+/// for posted content, its `is-version-of`/`has-version` relations.
+pub fn published_version(work: &Value) -> Option<String> {
+    let mut relations = vec!["is-preprint-of"];
+    if work["type"].as_str() == Some("posted-content") {
+        relations.extend(["is-version-of", "has-version"]);
+    }
+    relations.iter().find_map(|r| {
+        work["relation"][*r]
+            .as_array()?
+            .iter()
+            .filter(|rel| rel["id-type"].as_str() == Some("doi"))
+            .find_map(|rel| rel["id"].as_str())
+            .map(str::to_owned)
+    })
+}
+
+/// Whether a work is a published version a preprint can be followed to, rather than another
+/// preprint, such as the next version of the same one.
+pub fn is_published(work: &Value) -> bool {
+    work["type"].as_str().is_some_and(|t| t != "posted-content")
+}
+
+/// Formats the funders of a work with their award numbers, e.g.
+/// `National Science Foundation (AST-1234, AST-5678); NASA`.
+pub fn funding(work: &Value) -> Option<String> {
+    let funders = work["funder"]
+        .as_array()?
+        .iter()
+        .filter_map(|f| {
+            let name = f["name"]
+                .as_str()
+                .map(str::trim)
+                .filter(|n| !n.is_empty())?;
+            let awards = f["award"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|a| a.as_str().map(str::trim).filter(|a| !a.is_empty()))
+                .collect::<Vec<_>>();
+            if awards.is_empty() {
+                Some(name.to_owned())
+            } else {
+                Some(format!("{} ({})", name, awards.join(", ")))
+            }
+        })
+        .collect::<Vec<_>>();
+    if funders.is_empty() {
+        None
+    } else {
+        Some(funders.join("; "))
+    }
+}
+
+/// The URL of the license of a work, preferring the one for the version of record.
+pub fn license(work: &Value) -> Option<String> {
+    let licenses = work["license"].as_array()?;
+    licenses
+        .iter()
+        .find(|l| l["content-version"].as_str() == Some("vor"))
+        .or_else(|| licenses.first())
+        .and_then(|l| first_str(l, "URL"))
+        .map(str::to_owned)
+}
+
+pub fn year(work: &Value) -> Option<i64> {
+    ["published-print", "published-online", "issued", "created"]
+        .iter()
+        .find_map(|k| work[*k]["date-parts"][0][0].as_i64())
+}
+
+/// Builds an `@incollection`, `@inbook`, `@book` or `@proceedings` entry for book-type works, or
+/// returns `None` for any other type, in which case the negotiated BibTeX should be used as is.
+pub fn book_entry(work: &Value, key: &str) -> Option<Entry> {
+    let kind = match work["type"].as_str()? {
+        "book-chapter" | "book-section" | "book-part" | "reference-entry" => {
+            if first_str(work, "container-title").is_some() {
+                "incollection"
+            } else {
+                "inbook"
+            }
+        }
+        "book" | "edited-book" | "monograph" | "reference-book" | "book-set" => "book",
+        "proceedings" => "proceedings",
+        _ => return None,
+    };
+
+    let mut entry = Entry::new(kind, key);
+    let title = first_str(work, "title")?;
+    let container = first_str(work, "container-title");
+
+    entry.set("title", title);
+    match (kind, container) {
+        ("incollection", Some(container)) => entry.set("booktitle", container),
+        // For books and proceedings, CrossRef's container title is the book series.
+        ("book" | "proceedings", Some(series)) => entry.set("series", series),
+        _ => {}
+    }
+    if kind == "incollection" || kind == "inbook" {
+        if let Some(c) = CHAPTER_RE.captures(title) {
+            entry.set("chapter", &c[1]);
+        }
+    }
+
+    if let Some(authors) = names(work, "author") {
+        entry.set("author", &authors);
+    }
+    if let Some(editors) = names(work, "editor") {
+        entry.set("editor", &editors);
+    }
+    if let Some(publisher) = first_str(work, "publisher") {
+        entry.set("publisher", publisher);
+    }
+    if let Some(address) = first_str(work, "publisher-location") {
+        entry.set("address", address);
+    }
+    if let Some(year) = year(work) {
+        entry.set("year", &year.to_string());
+    }
+    if let Some(volume) = first_str(work, "volume") {
+        entry.set("volume", volume);
+    }
+    if let Some(pages) = first_str(work, "page") {
+        entry.set("pages", &pages.replace("--", "-").replace('-', "--"));
+    }
+    if let Some(isbn) = first_str(work, "ISBN") {
+        entry.set("isbn", isbn);
+    }
+    if let Some(language) = first_str(work, "language") {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = first_str(work, "DOI") {
+        entry.set("doi", doi);
+        entry.set("url", &format!("https://doi.org/{}", doi));
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_chapter() {
+        let work: Value = serde_json::from_str(
+            r#"{
+                "type": "book-chapter",
+                "title": ["Chapter 3 Markov Chains"],
+                "container-title": ["Handbook of Probability"],
+                "author": [{"given": "Ada", "family": "Lovelace"}],
+                "editor": [{"given": "Alan", "family": "Turing"}, {"name": "The Editors"}],
+                "publisher": "Springer",
+                "issued": {"date-parts": [[2019, 5]]},
+                "page": "45-67",
+                "ISBN": ["9783030000000", "9783030000001"],
+                "DOI": "10.1007/978-3-030-00000-0_3"
+            }"#,
+        )
+        .unwrap();
+        let entry = book_entry(&work, "Lovelace_2019").unwrap();
+        assert_eq!(entry.kind, "incollection");
+        assert_eq!(entry.get("booktitle"), Some("Handbook of Probability"));
+        assert_eq!(entry.get("chapter"), Some("3"));
+        assert_eq!(entry.get("author"), Some("Lovelace, Ada"));
+        assert_eq!(entry.get("editor"), Some("Turing, Alan and {The Editors}"));
+        assert_eq!(entry.get("year"), Some("2019"));
+        assert_eq!(entry.get("pages"), Some("45--67"));
+        assert_eq!(entry.get("isbn"), Some("9783030000000"));
+
+        let article: Value = serde_json::from_str(r#"{"type": "journal-article"}"#).unwrap();
+        assert!(book_entry(&article, "x").is_none());
+    }
+
+    #[test]
+    fn test_article_entry() {
+        let work: Value = serde_json::from_str(
+            r#"{
+                "type": "journal-article",
+                "title": ["Time, clocks, and the ordering of events in a distributed system"],
+                "container-title": ["Communications of the ACM"],
+                "author": [{"given": "Leslie", "family": "Lamport"}],
+                "publisher": "Association for Computing Machinery (ACM)",
+                "published-print": {"date-parts": [[1978, 7]]},
+                "volume": "21", "issue": "7", "page": "558-565",
+                "DOI": "10.1145/359545.359563"
+            }"#,
+        )
+        .unwrap();
+        let entry = article_entry(&work).unwrap();
+        assert_eq!(entry.kind, "article");
+        assert_eq!(entry.key, "Lamport_1978");
+        assert_eq!(entry.get("journal"), Some("Communications of the ACM"));
+        assert_eq!(entry.get("number"), Some("7"));
+        assert_eq!(entry.get("pages"), Some("558--565"));
+        assert_eq!(entry.get("doi"), Some("10.1145/359545.359563"));
+    }
+
+    #[test]
+    fn test_proceedings_volume() {
+        let work: Value = serde_json::from_str(
+            r#"{
+                "type": "proceedings",
+                "title": ["Computer Vision – ECCV 2020"],
+                "container-title": ["Lecture Notes in Computer Science"],
+                "editor": [{"given": "Andrea", "family": "Vedaldi"}],
+                "publisher": "Springer International Publishing",
+                "volume": "12346",
+                "issued": {"date-parts": [[2020]]}
+            }"#,
+        )
+        .unwrap();
+        let entry = book_entry(&work, &make_key(&work)).unwrap();
+        assert_eq!(entry.kind, "proceedings");
+        assert_eq!(entry.key, "Vedaldi_2020");
+        assert_eq!(
+            entry.get("series"),
+            Some("Lecture Notes in Computer Science")
+        );
+        assert_eq!(entry.get("editor"), Some("Vedaldi, Andrea"));
+        assert_eq!(entry.get("volume"), Some("12346"));
+        assert!(entry.get("chapter").is_none());
+    }
+
+    #[test]
+    fn test_funding_and_license() {
+        let work: Value = serde_json::from_str(
+            r#"{"funder": [
+                {"name": "National Science Foundation", "award": ["AST-1234", " AST-5678"]},
+                {"name": "NASA", "award": []},
+                {"award": ["orphan"]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            funding(&work).as_deref(),
+            Some("National Science Foundation (AST-1234, AST-5678); NASA")
+        );
+        assert!(funding(&serde_json::json!({})).is_none());
+
+        let work = serde_json::json!({"license": [
+            {"URL": "http://www.elsevier.com/tdm/userlicense/1.0/", "content-version": "tdm"},
+            {"URL": "https://creativecommons.org/licenses/by/4.0/", "content-version": "vor"}
+        ]});
+        assert_eq!(
+            license(&work).as_deref(),
+            Some("https://creativecommons.org/licenses/by/4.0/")
+        );
+    }
+
+    #[test]
+    fn test_published_version() {
+        let preprint = serde_json::json!({
+            "type": "posted-content",
+            "relation": {
+                "has-review": [{"id-type": "doi", "id": "10.1101/review"}],
+                "is-preprint-of": [
+                    {"id-type": "uri", "id": "https://example.com"},
+                    {"id-type": "doi", "id": "10.1038/s41586-020-2649-2"}
+                ]
+            }
+        });
+        assert_eq!(
+            published_version(&preprint).as_deref(),
+            Some("10.1038/s41586-020-2649-2")
+        );
+
+        let article = serde_json::json!({
+            "type": "journal-article",
+            "relation": {"has-version": [{"id-type": "doi", "id": "10.1101/x"}]}
+        });
+        assert!(published_version(&article).is_none());
+        assert!(is_published(&article));
+    }
+
+    #[test]
+    fn test_preprint_version_cycle() {
+        let v1 = serde_json::json!({
+            "type": "posted-content",
+            "relation": {"has-version": [{"id-type": "doi", "id": "10.1101/v2"}]}
+        });
+        let v2 = serde_json::json!({
+            "type": "posted-content",
+            "relation": {"is-version-of": [{"id-type": "doi", "id": "10.1101/v1"}]}
+        });
+        assert_eq!(published_version(&v1).as_deref(), Some("10.1101/v2"));
+        assert_eq!(published_version(&v2).as_deref(), Some("10.1101/v1"));
+        assert!(!is_published(&v1));
+        assert!(!is_published(&v2));
+    }
+}
@@ -0,0 +1,204 @@
+//! Building entries from CSL-JSON, the format most repositories and reference managers export
+//! citations in, and CSL-JSON items from entries for `d2b convert`. Its names and dates are laid
+//! out as in CrossRef's JSON, whose helpers are reused.
+
+use crate::bibtex::Entry;
+use crate::crossref::{first_str, make_key, names, year};
+use crate::{notes, template};
+use serde_json::{json, Map, Value};
+
+/// Builds an entry from a CSL-JSON item, or from the first item of an array of them.
+pub fn entry(item: &Value) -> Option<Entry> {
+    if let Some(items) = item.as_array() {
+        return entry(items.first()?);
+    }
+    let kind = match item["type"].as_str().unwrap_or_default() {
+        "article-journal" | "article-magazine" | "article-newspaper" | "article" => "article",
+        "paper-conference" => "inproceedings",
+        "chapter" | "entry-encyclopedia" | "entry-dictionary" => "incollection",
+        "book" => "book",
+        "thesis" => "phdthesis",
+        "report" => "techreport",
+        _ => "misc",
+    };
+    let mut entry = Entry::new(kind, &make_key(item));
+    entry.set("title", first_str(item, "title")?);
+    if let Some(authors) = names(item, "author") {
+        entry.set("author", &authors);
+    }
+    if let Some(editors) = names(item, "editor") {
+        entry.set("editor", &editors);
+    }
+    if let Some(container) = first_str(item, "container-title") {
+        let field = match kind {
+            "article" => "journal",
+            "inproceedings" | "incollection" => "booktitle",
+            _ => "howpublished",
+        };
+        entry.set(field, container);
+    }
+    if let Some(volume) = first_str(item, "volume") {
+        entry.set("volume", volume);
+    }
+    if let Some(issue) = first_str(item, "issue") {
+        entry.set("number", issue);
+    }
+    if let Some(pages) = first_str(item, "page") {
+        entry.set("pages", &pages.replace("--", "-").replace('-', "--"));
+    }
+    if let Some(publisher) = first_str(item, "publisher") {
+        let field = match kind {
+            "phdthesis" => "school",
+            "techreport" => "institution",
+            _ => "publisher",
+        };
+        entry.set(field, publisher);
+    }
+    if let Some(year) = year(item) {
+        entry.set("year", &year.to_string());
+    }
+    if let Some(isbn) = first_str(item, "ISBN") {
+        entry.set("isbn", isbn);
+    }
+    if let Some(doi) = first_str(item, "DOI") {
+        entry.set("doi", doi);
+    }
+    match (first_str(item, "URL"), first_str(item, "DOI")) {
+        (Some(url), _) => entry.set("url", url),
+        (None, Some(doi)) => entry.set("url", &format!("https://doi.org/{}", doi)),
+        (None, None) => {}
+    }
+    Some(entry)
+}
+
+/// The names of an author or editor field as CSL names. Braced names, such as collaborations,
+/// and names without a given name are kept as literals.
+pub fn csl_names(field: Option<&str>) -> Vec<Value> {
+    field
+        .unwrap_or_default()
+        .split(" and ")
+        .map(str::trim)
+        .filter(|n| !n.is_empty() && *n != "others")
+        .map(|name| {
+            let person = &template::people(Some(name))[0];
+            let family = notes::plain(person["family"].as_str().unwrap_or_default());
+            let given = notes::plain(person["given"].as_str().unwrap_or_default());
+            if given.is_empty() || (name.starts_with('{') && name.ends_with('}')) {
+                json!({ "literal": notes::plain(name) })
+            } else {
+                json!({ "family": family, "given": given })
+            }
+        })
+        .collect()
+}
+
+/// A CSL-JSON item for an entry, the inverse of [`entry`].
+pub fn item(entry: &Entry) -> Value {
+    let field = |name: &str| entry.get(name).map(notes::plain).filter(|v| !v.is_empty());
+    let mut item = Map::new();
+    item.insert("id".to_owned(), json!(entry.key));
+    item.insert("type".to_owned(), json!(notes::publication_type(entry)));
+    let mut set = |name: &str, value: Option<String>| {
+        if let Some(value) = value {
+            item.insert(name.to_owned(), Value::String(value));
+        }
+    };
+    set("title", field("title"));
+    set(
+        "container-title",
+        field("journal")
+            .or_else(|| field("journaltitle"))
+            .or_else(|| field("booktitle")),
+    );
+    set("volume", field("volume"));
+    set("issue", field("number"));
+    set("page", field("pages").map(|p| p.replace("--", "-")));
+    set("edition", field("edition"));
+    set(
+        "publisher",
+        field("publisher")
+            .or_else(|| field("school"))
+            .or_else(|| field("institution")),
+    );
+    set(
+        "publisher-place",
+        field("address").or_else(|| field("location")),
+    );
+    set("ISBN", field("isbn"));
+    set("ISSN", field("issn"));
+    set("DOI", field("doi"));
+    set("URL", field("url"));
+    set("abstract", field("abstract"));
+    set("note", field("note"));
+    for name in ["author", "editor"] {
+        let names = csl_names(entry.get(name));
+        if !names.is_empty() {
+            item.insert(name.to_owned(), Value::Array(names));
+        }
+    }
+    let date = field("date");
+    let year = field("year")
+        .or_else(|| date.as_ref().and_then(|d| d.get(..4).map(str::to_owned)))
+        .and_then(|y| y.parse::<i64>().ok());
+    if let Some(year) = year {
+        let month = field("month")
+            .and_then(|m| notes::month(&m))
+            .or_else(|| date.as_ref()?.get(5..7)?.parse().ok());
+        let parts = match month {
+            Some(month) => json!([[year, month]]),
+            None => json!([[year]]),
+        };
+        item.insert("issued".to_owned(), json!({ "date-parts": parts }));
+    }
+    Value::Object(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex;
+
+    #[test]
+    fn test_entry() {
+        let item = json!([{
+            "type": "thesis",
+            "title": "Dark Matter in Dwarf Galaxies",
+            "author": [{"family": "Shen", "given": "Jeff"}, {"literal": "The XYZ Collaboration"}],
+            "publisher": "University of Toronto",
+            "issued": {"date-parts": [[2021, 5]]},
+            "URL": "https://hdl.handle.net/1807/12345"
+        }]);
+        let entry = entry(&item).unwrap();
+        assert_eq!(entry.kind, "phdthesis");
+        assert_eq!(entry.key, "Shen_2021");
+        assert_eq!(
+            entry.get("author"),
+            Some("Shen, Jeff and {The XYZ Collaboration}")
+        );
+        assert_eq!(entry.get("school"), Some("University of Toronto"));
+        assert_eq!(entry.get("url"), Some("https://hdl.handle.net/1807/12345"));
+        assert!(super::entry(&json!({"type": "book"})).is_none());
+    }
+
+    #[test]
+    fn test_item() {
+        let entry = bibtex::parse_entry(
+            "@article{Lamport_1978, title={Time, Clocks}, author={Lamport, Leslie and {The ACM}}, \
+             journal={CACM}, volume={21}, number={7}, pages={558--565}, year={1978}, month={jul}, \
+             doi={10.1145/359545.359563}}",
+        )
+        .unwrap();
+        let item = item(&entry);
+        assert_eq!(
+            item,
+            json!({
+                "id": "Lamport_1978", "type": "article-journal", "title": "Time, Clocks",
+                "container-title": "CACM", "volume": "21", "issue": "7", "page": "558-565",
+                "DOI": "10.1145/359545.359563",
+                "author": [{"family": "Lamport", "given": "Leslie"}, {"literal": "The ACM"}],
+                "issued": {"date-parts": [[1978, 7]]}
+            })
+        );
+        assert_eq!(super::entry(&item).unwrap().get("pages"), Some("558--565"));
+    }
+}
@@ -0,0 +1,312 @@
+//! Dataset and software entries built from DataCite metadata.
+//!
+//! DOIs minted through DataCite (Zenodo, figshare, institutional data repositories, ...) negotiate
+//! to a bare `@misc` that drops the version and repository, so these are rebuilt from the record.
+
+use crate::bibtex::Entry;
+use crate::http::SendWithRetry;
+use crate::language::language_name;
+use crate::{Format, CLIENT};
+use serde_json::Value;
+
+/// Fetches the `data.attributes` object of `https://api.datacite.org/dois/{doi}`.
+pub async fn fetch_doi(doi: &str) -> Option<Value> {
+    let mut url = reqwest::Url::parse("https://api.datacite.org/dois/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(doi.split('/'));
+    let body = CLIENT
+        .get(url)
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let mut json: Value = serde_json::from_str(&body).ok()?;
+    Some(json["data"]["attributes"].take())
+}
+
+/// The DOI ending in `/{suffix}`, if DataCite knows exactly one, for inputs that may be a DOI
+/// without its prefix.
+pub async fn find_suffix(suffix: &str) -> Option<String> {
+    let body = CLIENT
+        .get("https://api.datacite.org/dois")
+        .query(&[
+            ("query", format!("suffix:\"{}\"", suffix.replace('"', ""))),
+            ("page[size]", "2".to_owned()),
+        ])
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let json: Value = serde_json::from_str(&body).ok()?;
+    let dois = json["data"].as_array()?;
+    match dois.as_slice() {
+        [doi] => doi["id"].as_str().map(str::to_lowercase),
+        _ => None,
+    }
+}
+
+/// Whether a negotiated entry might be a DataCite dataset or software record: one of those types,
+/// or a `@misc` in the form DataCite negotiates to, keyed by its DOI URL. Other `@misc`s, which
+/// CrossRef negotiates too, aren't worth the extra requests.
+pub fn is_dataset_like(entry: &Entry) -> bool {
+    match entry.kind.to_ascii_lowercase().as_str() {
+        "dataset" | "software" => true,
+        "misc" | "online" => entry.key.starts_with("https://doi.org/"),
+        _ => false,
+    }
+}
+
+fn str_field(v: &Value) -> Option<&str> {
+    v.as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Formats DataCite creators as a BibTeX name list, also returning the first family name.
+fn creators(attrs: &Value) -> Option<(String, String)> {
+    let people = attrs["creators"].as_array()?;
+    let mut first = None;
+    let names = people
+        .iter()
+        .filter_map(|p| {
+            let (name, family) = match (str_field(&p["familyName"]), str_field(&p["givenName"])) {
+                (Some(family), Some(given)) => (format!("{}, {}", family, given), family),
+                _ => {
+                    let name = str_field(&p["name"])?;
+                    if p["nameType"].as_str() == Some("Organizational") {
+                        (format!("{{{}}}", name), name)
+                    } else {
+                        // Personal names are usually already given as "Family, Given".
+                        (name.to_owned(), name.split(',').next().unwrap())
+                    }
+                }
+            };
+            first.get_or_insert_with(|| family.to_owned());
+            Some(name)
+        })
+        .collect::<Vec<_>>();
+    if names.is_empty() {
+        None
+    } else {
+        Some((names.join(" and "), first.unwrap()))
+    }
+}
+
+/// The URL of the source repository of a software record, if it links to one.
+fn repository(attrs: &Value) -> Option<&str> {
+    attrs["relatedIdentifiers"]
+        .as_array()?
+        .iter()
+        .filter(|r| r["relatedIdentifierType"].as_str() == Some("URL"))
+        .filter_map(|r| str_field(&r["relatedIdentifier"]))
+        .find(|u| {
+            ["github.com", "gitlab", "bitbucket.org", "codeberg.org"]
+                .iter()
+                .any(|h| u.contains(h))
+        })
+}
+
+/// A Software Heritage persistent identifier for the record, if it has one.
+fn swhid(attrs: &Value) -> Option<&str> {
+    let ids = attrs["identifiers"].as_array().into_iter().flatten();
+    let related = attrs["relatedIdentifiers"].as_array().into_iter().flatten();
+    ids.filter_map(|i| str_field(&i["identifier"]))
+        .chain(related.filter_map(|r| str_field(&r["relatedIdentifier"])))
+        .find(|i| i.starts_with("swh:"))
+}
+
+/// The license of a record, as a URL if DataCite has one and as its name otherwise.
+pub fn license(attrs: &Value) -> Option<String> {
+    attrs["rightsList"]
+        .as_array()?
+        .iter()
+        .find_map(|r| str_field(&r["rightsUri"]).or_else(|| str_field(&r["rights"])))
+        .map(str::to_owned)
+}
+
+/// Builds an entry for dataset and software records, or returns `None` for any other resource
+/// type. Biblatex output uses `@dataset`/`@software`; plain BibTeX falls back to `@misc` with
+/// `howpublished`.
+pub fn dataset_entry(attrs: &Value, format: Format) -> Option<Entry> {
+    let kind = match attrs["types"]["resourceTypeGeneral"].as_str()? {
+        "Dataset" => "dataset",
+        "Software" | "ComputationalNotebook" => "software",
+        _ => return None,
+    };
+
+    let title = str_field(&attrs["titles"][0]["title"])?;
+    let year = attrs["publicationYear"]
+        .as_i64()
+        .map(|y| y.to_string())
+        .or_else(|| str_field(&attrs["publicationYear"]).map(str::to_owned));
+    let publisher =
+        str_field(&attrs["publisher"]).or_else(|| str_field(&attrs["publisher"]["name"]));
+    let authors = creators(attrs);
+
+    let key = match (&authors, &year) {
+        (Some((_, family)), Some(year)) => format!("{}_{}", family.replace(' ', ""), year),
+        (Some((_, family)), None) => family.replace(' ', ""),
+        (None, _) => str_field(&attrs["doi"]).unwrap_or("dataset").to_owned(),
+    };
+
+    let mut entry = match format {
+        Format::Biblatex => Entry::new(kind, &key),
+        Format::Bibtex => Entry::new("misc", &key),
+    };
+    entry.set("title", title);
+    if let Some((names, _)) = &authors {
+        entry.set("author", names);
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    if let Some(version) = str_field(&attrs["version"]) {
+        entry.set("version", version);
+    }
+    if let Some(publisher) = publisher {
+        match format {
+            Format::Biblatex => entry.set("publisher", publisher),
+            Format::Bibtex => entry.set("howpublished", publisher),
+        }
+    }
+    if kind == "software" {
+        if let Some(repo) = repository(attrs) {
+            entry.set("repository", repo);
+        }
+    }
+    if let Some(language) = str_field(&attrs["language"]) {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(swhid) = swhid(attrs) {
+        entry.set("swhid", swhid);
+    }
+    if let Some(doi) = str_field(&attrs["doi"]) {
+        entry.set("doi", doi);
+    }
+    if let Some(url) = str_field(&attrs["url"]) {
+        entry.set("url", url);
+    }
+    Some(entry)
+}
+
+/// Builds a `@misc` entry for any DataCite record, for when DOIs are resolved through DataCite
+/// rather than doi.org. Datasets and software should go through [`dataset_entry`] first.
+pub fn misc_entry(attrs: &Value, format: Format) -> Option<Entry> {
+    let title = str_field(&attrs["titles"][0]["title"])?;
+    let year = attrs["publicationYear"]
+        .as_i64()
+        .map(|y| y.to_string())
+        .or_else(|| str_field(&attrs["publicationYear"]).map(str::to_owned));
+    let authors = creators(attrs);
+    let key = match (&authors, &year) {
+        (Some((_, family)), Some(year)) => format!("{}_{}", family.replace(' ', ""), year),
+        (Some((_, family)), None) => family.replace(' ', ""),
+        (None, _) => str_field(&attrs["doi"]).unwrap_or("misc").to_owned(),
+    };
+
+    let mut entry = Entry::new("misc", &key);
+    entry.set("title", title);
+    if let Some((names, _)) = &authors {
+        entry.set("author", names);
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    let publisher =
+        str_field(&attrs["publisher"]).or_else(|| str_field(&attrs["publisher"]["name"]));
+    if let Some(publisher) = publisher {
+        match format {
+            Format::Biblatex => entry.set("publisher", publisher),
+            Format::Bibtex => entry.set("howpublished", publisher),
+        }
+    }
+    if let Some(language) = str_field(&attrs["language"]) {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = str_field(&attrs["doi"]) {
+        entry.set("doi", doi);
+    }
+    if let Some(url) = str_field(&attrs["url"]) {
+        entry.set("url", url);
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn software() -> Value {
+        serde_json::from_str(
+            r#"{
+                "doi": "10.5281/zenodo.1234",
+                "types": {"resourceTypeGeneral": "Software"},
+                "titles": [{"title": "astro-tools: v1.2"}],
+                "creators": [
+                    {"name": "Shen, Jeff", "nameType": "Personal"},
+                    {"name": "Astropy Collaboration", "nameType": "Organizational"}
+                ],
+                "publisher": "Zenodo",
+                "publicationYear": 2021,
+                "version": "v1.2",
+                "relatedIdentifiers": [
+                    {"relatedIdentifier": "https://github.com/al-jshen/astro-tools/tree/v1.2",
+                     "relatedIdentifierType": "URL", "relationType": "IsSupplementTo"}
+                ],
+                "url": "https://zenodo.org/record/1234"
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_software_entry() {
+        let entry = dataset_entry(&software(), Format::Biblatex).unwrap();
+        assert_eq!(entry.kind, "software");
+        assert_eq!(entry.key, "Shen_2021");
+        assert_eq!(
+            entry.get("author"),
+            Some("Shen, Jeff and {Astropy Collaboration}")
+        );
+        assert_eq!(entry.get("version"), Some("v1.2"));
+        assert_eq!(entry.get("publisher"), Some("Zenodo"));
+        assert_eq!(
+            entry.get("repository"),
+            Some("https://github.com/al-jshen/astro-tools/tree/v1.2")
+        );
+
+        let legacy = dataset_entry(&software(), Format::Bibtex).unwrap();
+        assert_eq!(legacy.kind, "misc");
+        assert_eq!(legacy.get("howpublished"), Some("Zenodo"));
+        assert!(legacy.get("publisher").is_none());
+
+        let rights = serde_json::json!({"rightsList": [
+            {"rights": "Creative Commons Attribution 4.0 International"}
+        ]});
+        assert_eq!(
+            license(&rights).as_deref(),
+            Some("Creative Commons Attribution 4.0 International")
+        );
+    }
+
+    #[test]
+    fn test_is_dataset_like() {
+        let parse = |src| crate::bibtex::parse_entry(src).unwrap();
+        assert!(is_dataset_like(&parse(
+            "@misc{https://doi.org/10.5281/zenodo.1234, doi={10.5281/ZENODO.1234}}"
+        )));
+        assert!(is_dataset_like(&parse(
+            "@software{astro, title={astro-tools}}"
+        )));
+        assert!(!is_dataset_like(&parse(
+            "@misc{Doe_2020, doi={10.1037/e123}, title={Poster}}"
+        )));
+        assert!(!is_dataset_like(&parse(
+            "@article{Doe_2020, title={Paper}}"
+        )));
+    }
+}
@@ -0,0 +1,202 @@
+//! `--diff`: the changes `sync`, `mail` and `enrich` would make to a file, shown as a unified diff
+//! and only written once confirmed, or straight away with `--yes`, so that automated changes can
+//! be reviewed first.
+
+use crate::{color, i18n, Options};
+use clap::{Arg, ArgMatches};
+use std::io::BufRead;
+
+/// Lines of context around each change.
+const CONTEXT: usize = 3;
+
+/// The most pairs of lines compared to find what changed between a file's common start and end;
+/// beyond that, the lines in between are shown as replaced outright.
+const MAX_CELLS: usize = 4_000_000;
+
+/// Whether to show changes before writing them, and whether to write them without asking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Preview {
+    pub diff: bool,
+    pub yes: bool,
+}
+
+impl Preview {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        Preview {
+            diff: matches.is_present("diff"),
+            yes: matches.is_present("yes"),
+        }
+    }
+
+    /// Shows the changes to `file` if asked to, and whether to write them. Without `--yes` they
+    /// are only written if the user agrees, which they can't when standard input is closed.
+    pub fn confirm(self, file: &str, old: &str, new: &str, opts: &Options) -> bool {
+        if !self.diff {
+            return true;
+        }
+        let diff = unified(file, old, new);
+        print!("{}", if opts.color { color::diff(&diff) } else { diff });
+        if self.yes {
+            return true;
+        }
+        eprint!("{} ", i18n::message("diff-confirm", &[("file", &file)]));
+        let mut line = String::new();
+        // `j` is what the German prompt offers.
+        let yes = std::io::stdin().lock().read_line(&mut line).is_ok()
+            && matches!(
+                line.trim().to_lowercase().as_str(),
+                "y" | "yes" | "j" | "ja"
+            );
+        if !yes {
+            eprintln!("{}", i18n::message("diff-declined", &[("file", &file)]));
+        }
+        yes
+    }
+}
+
+/// The `--diff` and `--yes` options of the subcommands that rewrite a `.bib` file.
+pub fn args() -> [Arg<'static, 'static>; 2] {
+    [
+        Arg::with_name("diff")
+            .long("diff")
+            .help(i18n::text("help-diff")),
+        Arg::with_name("yes")
+            .long("yes")
+            .short("y")
+            .requires("diff")
+            .help(i18n::text("help-yes")),
+    ]
+}
+
+/// How a line of the new file came about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Same,
+    Removed,
+    Added,
+}
+
+/// The lines of `old` and `new` as a sequence of kept, removed and added lines. Only the lines
+/// between their common start and end are compared, which is usually a small part of a `.bib`
+/// file.
+fn ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let start = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let end = old[start..]
+        .iter()
+        .rev()
+        .zip(new[start..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (&old[start..old.len() - end], &new[start..new.len() - end]);
+
+    let mut ops = old[..start]
+        .iter()
+        .map(|&l| (Op::Same, l))
+        .collect::<Vec<_>>();
+    if a.len() * b.len() > MAX_CELLS {
+        ops.extend(a.iter().map(|&l| (Op::Removed, l)));
+        ops.extend(b.iter().map(|&l| (Op::Added, l)));
+    } else {
+        // The longest common subsequence of the lines after each pair of positions.
+        let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = match a[i] == b[j] {
+                    true => lcs[i + 1][j + 1] + 1,
+                    false => lcs[i + 1][j].max(lcs[i][j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                ops.push((Op::Same, a[i]));
+                i += 1;
+                j += 1;
+            } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push((Op::Removed, a[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Added, b[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(old[old.len() - end..].iter().map(|&l| (Op::Same, l)));
+    ops
+}
+
+/// The changes from `old` to `new` as a unified diff of `file`, empty if there are none.
+pub fn unified(file: &str, old: &str, new: &str) -> String {
+    let old = old.lines().collect::<Vec<_>>();
+    let new = new.lines().collect::<Vec<_>>();
+    let ops = ops(&old, &new);
+    let changed = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Same)
+        .map(|(n, _)| n)
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Changes whose context overlaps share a hunk.
+    let mut hunks = Vec::<(usize, usize)>::new();
+    for &n in &changed {
+        let (start, end) = (n.saturating_sub(CONTEXT), (n + CONTEXT + 1).min(ops.len()));
+        match hunks.last_mut() {
+            Some((_, last)) if start <= *last => *last = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", file, file);
+    for (start, end) in hunks {
+        let line = |skip: Op| ops[..start].iter().filter(|(op, _)| *op != skip).count();
+        let count = |skip: Op| ops[start..end].iter().filter(|(op, _)| *op != skip).count();
+        let (old_count, new_count) = (count(Op::Added), count(Op::Removed));
+        // An empty side of a hunk is numbered by the line before it.
+        let first = |line: usize, count: usize| if count == 0 { line } else { line + 1 };
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            first(line(Op::Added), old_count),
+            old_count,
+            first(line(Op::Removed), new_count),
+            new_count
+        ));
+        for (op, line) in &ops[start..end] {
+            let sign = match op {
+                Op::Same => ' ',
+                Op::Removed => '-',
+                Op::Added => '+',
+            };
+            out.push(sign);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nC\nd\ne\nf\ng\nh\ni\nj\nk\n";
+        assert_eq!(
+            unified("refs.bib", old, new),
+            "--- refs.bib\n+++ refs.bib\n\
+             @@ -1,6 +1,6 @@\n a\n b\n-c\n+C\n d\n e\n f\n\
+             @@ -8,3 +8,4 @@\n h\n i\n j\n+k\n"
+        );
+        assert_eq!(
+            unified("refs.bib", "", "@misc{a}\n"),
+            "--- refs.bib\n+++ refs.bib\n@@ -0,0 +1,1 @@\n+@misc{a}\n"
+        );
+        assert_eq!(unified("refs.bib", old, old), "");
+    }
+}
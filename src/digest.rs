@@ -0,0 +1,123 @@
+//! `--hash`: a SHA-256 of the metadata an entry was built from, written to its `sourcehash` field
+//! so that a regenerated bibliography can be checked against the one it replaces, and
+//! `d2b verify --check-hashes` can tell when a source has silently changed its record.
+//!
+//! The hash covers the entry as the backend returned it, before keys, configured fields, hooks and
+//! fix-ups are applied, so it only changes when the source does.
+
+use crate::bibtex::Entry;
+use crate::Format;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// The field the hash is written to.
+pub const FIELD: &str = "sourcehash";
+
+/// The hash of an entry's type and fields, as `sha256:<hex>`. Fields are taken in name order with
+/// their whitespace collapsed, and the key is left out, so that neither the order a backend lists
+/// fields in nor how the key is generated changes the hash.
+pub fn source_hash(entry: &Entry) -> String {
+    let mut fields = entry
+        .fields
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), value.as_str()))
+        .filter(|(name, _)| name != FIELD)
+        .collect::<Vec<_>>();
+    fields.sort();
+    let mut canonical = entry.kind.to_lowercase();
+    canonical.push('\n');
+    for (name, value) in fields {
+        canonical.push_str(&name);
+        canonical.push('=');
+        canonical.push_str(&value.split_whitespace().collect::<Vec<_>>().join(" "));
+        canonical.push('\n');
+    }
+    let hex = Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("sha256:{}", hex)
+}
+
+/// What `--hash` writes: the hash, and the backend and format the entry was built with, so that
+/// `d2b verify --check-hashes` fetches it the same way again whatever options it is run with, and
+/// `--race`, `--best-of` or `--biblatex` don't make an unchanged source look drifted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stamp {
+    pub hash: String,
+    /// The backend the entry came from, or the backends joined with ` + ` for `--merge-sources`.
+    /// Empty in fields written before it was recorded.
+    pub source: String,
+    pub format: Option<Format>,
+}
+
+impl Stamp {
+    pub fn new(entry: &Entry, source: &str, format: Format) -> Self {
+        Stamp {
+            hash: source_hash(entry),
+            source: source.to_owned(),
+            format: Some(format),
+        }
+    }
+
+    /// Reads a `sourcehash` field, which is a bare hash if it was written before the backend and
+    /// format were recorded.
+    pub fn parse(field: &str) -> Option<Self> {
+        let mut parts = field.split(';').map(str::trim);
+        let hash = parts
+            .next()
+            .filter(|h| h.starts_with("sha256:"))?
+            .to_owned();
+        let source = parts.next().unwrap_or_default().to_owned();
+        let format = parts.next().map(|f| match f {
+            "biblatex" => Format::Biblatex,
+            _ => Format::Bibtex,
+        });
+        Some(Stamp {
+            hash,
+            source,
+            format,
+        })
+    }
+}
+
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.hash)?;
+        if let Some(format) = self.format {
+            let format = match format {
+                Format::Bibtex => "bibtex",
+                Format::Biblatex => "biblatex",
+            };
+            write!(f, "; {}; {}", self.source, format)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex;
+
+    #[test]
+    fn test_source_hash() {
+        let a = bibtex::parse_entry("@article{Doe_2020, title={A  Title}, year={2020}}").unwrap();
+        let b = bibtex::parse_entry(
+            "@Article{doe2020, year={2020}, title={A Title}, sourcehash={sha256:0}}",
+        )
+        .unwrap();
+        assert_eq!(source_hash(&a), source_hash(&b));
+        let c = bibtex::parse_entry("@article{Doe_2020, title={A Title}, year={2021}}").unwrap();
+        assert_ne!(source_hash(&a), source_hash(&c));
+
+        let stamp = Stamp::new(&a, "api.crossref.org + doi.org", Format::Biblatex);
+        assert!(stamp.hash.starts_with("sha256:") && stamp.hash.len() == 7 + 64);
+        let field = stamp.to_string();
+        assert!(field.ends_with("; api.crossref.org + doi.org; biblatex"));
+        assert_eq!(Stamp::parse(&field), Some(stamp.clone()));
+        let legacy = Stamp::parse(&stamp.hash).unwrap();
+        assert_eq!((legacy.source.as_str(), legacy.format), ("", None));
+        assert_eq!(Stamp::parse("md5:0"), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! `d2b enrich`: fills in the fields that entries of an existing `.bib` file are missing, from the
+//! metadata of a DOI or arXiv ID found anywhere in them (`doi`, `eprint`, a URL, a note, ...).
+//!
+//! Fields that are already there are left alone, so manual edits survive, unless `--overwrite` is
+//! given. Entries that don't change keep their exact text.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{self, Error, Kind};
+use crate::{atomic, diff, i18n, input_id, resolve, Options, ARXIV_IDENT_RE, DOI_RE};
+use clap::ArgMatches;
+use futures::{stream::FuturesUnordered, StreamExt};
+
+/// The fields an identifier is looked for in first, before any other.
+const ID_FIELDS: &[&str] = &["doi", "eprint", "url", "note", "howpublished"];
+
+/// The identifier in one field, as an input: only `doi` and `eprint` are taken at face value, and
+/// other fields have to contain a DOI or a clearly marked arXiv ID.
+fn field_id(entry: &Entry, name: &str, value: &str) -> Option<String> {
+    let id = match name.to_ascii_lowercase().as_str() {
+        "doi" => input_id(value)?,
+        "eprint" => {
+            let archive = entry.get("archiveprefix").unwrap_or("arxiv");
+            if !archive.eq_ignore_ascii_case("arxiv") {
+                return None;
+            }
+            input_id(&format!("arxiv:{}", value.trim()))?
+        }
+        _ => {
+            if let Some(doi) = DOI_RE[0].find(value) {
+                input_id(doi.as_str().trim_end_matches(['.', ',', ';', '}']))?
+            } else if ARXIV_IDENT_RE.is_match(value) {
+                input_id(value)?
+            } else {
+                return None;
+            }
+        }
+    };
+    Some(id).filter(|id| id.starts_with("doi:") || id.starts_with("arxiv:"))
+}
+
+/// The DOI or arXiv ID of an entry, from the fields most likely to hold one first.
+pub fn find_id(entry: &Entry) -> Option<String> {
+    let rank = |name: &str| {
+        ID_FIELDS
+            .iter()
+            .position(|f| f.eq_ignore_ascii_case(name))
+            .unwrap_or(ID_FIELDS.len())
+    };
+    let mut fields = entry.fields.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|(name, _)| rank(name));
+    fields
+        .into_iter()
+        .find_map(|(name, value)| field_id(entry, name, value.as_str()))
+}
+
+/// Copies the fields of `fetched` that `entry` doesn't have, or all of them with `overwrite`, and
+/// returns the names of those that changed. The key is always kept.
+pub fn fill(entry: &mut Entry, fetched: &Entry, overwrite: bool) -> Vec<String> {
+    let mut filled = Vec::new();
+    if overwrite && !entry.kind.eq_ignore_ascii_case(&fetched.kind) {
+        entry.kind = fetched.kind.clone();
+        filled.push("type".to_owned());
+    }
+    for (name, value) in &fetched.fields {
+        let missing = entry.get(name).is_none_or(|v| v.trim().is_empty());
+        if missing || (overwrite && entry.get(name) != Some(value.as_str())) {
+            match entry
+                .fields
+                .iter_mut()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            {
+                Some((_, v)) => *v = value.clone(),
+                None => entry.fields.push((name.clone(), value.clone())),
+            }
+            filled.push(name.to_lowercase());
+        }
+    }
+    filled
+}
+
+/// Runs `d2b enrich`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let bib = matches.value_of("bib").unwrap();
+    let _lock = atomic::lock(bib).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unwritable", &[("file", &bib), ("error", &e)]),
+        )
+        .exit(opts.error_format)
+    });
+    let src = std::fs::read_to_string(bib).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unreadable", &[("file", &bib), ("error", &e)]),
+        )
+        .exit(opts.error_format)
+    });
+    let overwrite = matches.is_present("overwrite");
+    let preview = diff::Preview::from_matches(matches);
+    let chunks = bibtex::split_file(&src);
+
+    let mut futures = chunks
+        .iter()
+        .enumerate()
+        .filter_map(|(n, chunk)| Some((n, find_id(chunk.entry.as_ref()?)?)))
+        .map(|(n, id)| {
+            let opts = opts.clone();
+            async move { (n, resolve(id, opts).await) }
+        })
+        .collect::<FuturesUnordered<_>>();
+    let mut fetched = vec![None; chunks.len()];
+    let mut errors = Vec::new();
+    let mut looked_up = 0;
+    while let Some((n, resolved)) = futures.next().await {
+        looked_up += 1;
+        match resolved {
+            Ok(resolved) => fetched[n] = resolved.into_iter().next().map(|r| r.entry),
+            Err(e) => errors.push(e),
+        }
+    }
+    for e in &errors {
+        e.report(opts.error_format);
+    }
+
+    let mut out = String::new();
+    let mut enriched = 0;
+    for (chunk, fetched) in chunks.iter().zip(&fetched) {
+        let (mut entry, fetched) = match (chunk.entry.clone(), fetched) {
+            (Some(entry), Some(fetched)) => (entry, fetched),
+            _ => {
+                out.push_str(chunk.text);
+                continue;
+            }
+        };
+        let filled = fill(&mut entry, fetched, overwrite);
+        if filled.is_empty() {
+            out.push_str(chunk.text);
+            continue;
+        }
+        eprintln!(
+            "{}",
+            i18n::message(
+                "enrich-filled",
+                &[("key", &entry.key), ("fields", &filled.join(", "))],
+            )
+        );
+        out.push_str(&entry.to_string());
+        enriched += 1;
+    }
+
+    if out != src {
+        if !preview.confirm(bib, &src, &out, &opts) {
+            return;
+        }
+        atomic::write(bib, &out).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &bib), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        });
+    }
+    eprintln!(
+        "{}",
+        i18n::message(
+            "enrich-summary",
+            &[
+                ("file", &bib),
+                ("enriched", &enriched),
+                ("checked", &looked_up)
+            ],
+        )
+    );
+    let status = error::status(looked_up - errors.len(), &errors);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_id() {
+        let entry = bibtex::parse_entry(
+            "@misc{a, title={T}, note={See doi:10.1145/359545.359563.}, url={https://example.com}}",
+        )
+        .unwrap();
+        assert_eq!(find_id(&entry).unwrap(), "doi:10.1145/359545.359563");
+
+        let entry = bibtex::parse_entry("@misc{a, note={arXiv:1712.01815v2}, eprint={1712.01815}}")
+            .unwrap();
+        assert_eq!(find_id(&entry).unwrap(), "arxiv:1712.01815");
+
+        let entry =
+            bibtex::parse_entry("@misc{a, pages={1234.56789}, url={https://example.com}}").unwrap();
+        assert_eq!(find_id(&entry), None);
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut entry =
+            bibtex::parse_entry("@article{Mine, title={My {Title}}, pages={}, doi={10.1/x}}")
+                .unwrap();
+        let fetched = bibtex::parse_entry(
+            "@inproceedings{Theirs, title={Their title}, pages={1--10}, volume={3}, doi={10.1/x}}",
+        )
+        .unwrap();
+
+        let mut kept = entry.clone();
+        assert_eq!(fill(&mut kept, &fetched, false), ["pages", "volume"]);
+        assert_eq!(kept.key, "Mine");
+        assert_eq!(kept.kind, "article");
+        assert_eq!(kept.get("title"), Some("My {Title}"));
+        assert_eq!(kept.get("pages"), Some("1--10"));
+
+        assert_eq!(
+            fill(&mut entry, &fetched, true),
+            ["type", "title", "pages", "volume"]
+        );
+        assert_eq!(entry.key, "Mine");
+        assert_eq!(entry.get("title"), Some("Their title"));
+    }
+}
@@ -0,0 +1,180 @@
+//! Errors, how they are reported, and the exit statuses they lead to.
+//!
+//! | Status | Meaning                                                   |
+//! |--------|-----------------------------------------------------------|
+//! | 0      | Everything succeeded.                                     |
+//! | 1      | Some inputs failed; the rest were written out.            |
+//! | 2      | Every input failed.                                       |
+//! | 3      | Every input failed because the network was unavailable.   |
+//! | 4      | The command line or configuration was invalid.            |
+
+use crate::i18n;
+use serde_json::json;
+use std::fmt;
+
+pub const OK: i32 = 0;
+pub const PARTIAL: i32 = 1;
+pub const FAILED: i32 = 2;
+pub const NETWORK: i32 = 3;
+pub const USAGE: i32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Bad arguments or configuration.
+    Usage,
+    /// An input that isn't a DOI, arXiv ID, ISBN or URL.
+    InvalidInput,
+    /// A well-formed identifier that the resolver has no record of.
+    NotFound,
+    /// The resolver couldn't be reached.
+    Network,
+    /// The resolver answered, but with an error status.
+    Service,
+    /// The resolver's answer couldn't be understood.
+    Parse,
+    /// The entry failed `--strict` validation.
+    Validation,
+    /// A local file couldn't be read or written.
+    Io,
+    /// A `--post-hook` command failed.
+    Hook,
+}
+
+impl Kind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Usage => "usage",
+            Kind::InvalidInput => "invalid-input",
+            Kind::NotFound => "not-found",
+            Kind::Network => "network",
+            Kind::Service => "service",
+            Kind::Parse => "parse",
+            Kind::Validation => "validation",
+            Kind::Io => "io",
+            Kind::Hook => "hook",
+        }
+    }
+}
+
+/// How errors are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Text,
+    /// One JSON object per line, with `kind`, `input` and `message`.
+    Json,
+}
+
+impl ErrorFormat {
+    pub const NAMES: &'static [&'static str] = &["text", "json"];
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "json" => ErrorFormat::Json,
+            _ => ErrorFormat::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Error {
+    pub kind: Kind,
+    /// The input being resolved when the error happened, if any.
+    pub input: Option<String>,
+    pub message: String,
+}
+
+impl Error {
+    pub fn new(kind: Kind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            input: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_input(mut self, input: &str) -> Self {
+        self.input = Some(input.trim().to_owned());
+        self
+    }
+
+    /// The exit status for this error on its own.
+    pub fn status(&self) -> i32 {
+        match self.kind {
+            Kind::Usage => USAGE,
+            Kind::Network => NETWORK,
+            _ => FAILED,
+        }
+    }
+
+    pub fn report(&self, format: ErrorFormat) {
+        match format {
+            ErrorFormat::Text => eprintln!("{}: {}", i18n::text("error"), self),
+            ErrorFormat::Json => eprintln!(
+                "{}",
+                json!({
+                    "kind": self.kind.name(),
+                    "input": self.input,
+                    "message": self.message,
+                })
+            ),
+        }
+    }
+
+    /// Reports the error and exits with its status.
+    pub fn exit(&self, format: ErrorFormat) -> ! {
+        self.report(format);
+        std::process::exit(self.status())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.input {
+            Some(input) => write!(f, "{}: {}", input, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        let kind = if e.is_decode() {
+            Kind::Parse
+        } else {
+            Kind::Network
+        };
+        Error::new(kind, e.to_string())
+    }
+}
+
+/// The exit status for a run in which `succeeded` inputs resolved and `errors` happened.
+pub fn status(succeeded: usize, errors: &[Error]) -> i32 {
+    if errors.is_empty() {
+        OK
+    } else if succeeded > 0 {
+        PARTIAL
+    } else if errors.iter().all(|e| e.kind == Kind::Network) {
+        NETWORK
+    } else if errors.iter().all(|e| e.kind == Kind::Usage) {
+        USAGE
+    } else {
+        FAILED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status() {
+        let network = Error::new(Kind::Network, "connection refused").with_input(" 10.1/x ");
+        let missing = Error::new(Kind::NotFound, "no such DOI");
+        assert_eq!(status(3, &[]), OK);
+        assert_eq!(status(0, &[network.clone(), missing.clone()]), FAILED);
+        assert_eq!(status(2, &[missing]), PARTIAL);
+        assert_eq!(status(0, &[network.clone(), network.clone()]), NETWORK);
+        assert_eq!(Error::new(Kind::Usage, "bad").status(), USAGE);
+        assert_eq!(network.to_string(), "10.1/x: connection refused");
+    }
+}
@@ -0,0 +1,190 @@
+//! Entries built from Europe PMC, which indexes PubMed, PubMed Central and most preprint servers of
+//! the life sciences, and has an article's abstract and MeSH terms in the same record as its
+//! citation. Articles are looked up by PMID, PMCID or DOI.
+
+use crate::bibtex::{self, Entry};
+use crate::http::SendWithRetry;
+use crate::{screening, CLIENT};
+use serde_json::Value;
+
+/// The kinds of identifier Europe PMC can be searched by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Id {
+    Pmid,
+    Pmcid,
+    Doi,
+}
+
+/// The search query for an identifier.
+pub fn query(kind: Id, id: &str) -> String {
+    match kind {
+        Id::Pmid => format!("EXT_ID:{} AND SRC:MED", id),
+        Id::Pmcid => format!("PMCID:{}", id.to_uppercase()),
+        Id::Doi => format!("DOI:\"{}\"", id),
+    }
+}
+
+/// Fetches the first result, with its full record, of a search.
+pub async fn fetch(kind: Id, id: &str) -> Option<Value> {
+    let url = reqwest::Url::parse_with_params(
+        "https://www.ebi.ac.uk/europepmc/webservices/rest/search",
+        &[
+            ("query", query(kind, id).as_str()),
+            ("resultType", "core"),
+            ("format", "json"),
+        ],
+    )
+    .unwrap();
+    let res = CLIENT.get(url).send_with_retry().await.ok()?;
+    if !res.status().is_success() {
+        return None;
+    }
+    let mut json: Value = serde_json::from_str(&res.text().await.ok()?).ok()?;
+    Some(json["resultList"]["result"][0].take()).filter(|r| !r.is_null())
+}
+
+fn str_field<'a>(v: &'a Value, field: &str) -> Option<&'a str> {
+    v[field].as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Builds an entry from a Europe PMC record: an `@article` for journal articles and an
+/// `@online` preprint otherwise, with the abstract and the MeSH headings (as `keywords`).
+pub fn entry(record: &Value) -> Option<Entry> {
+    let authors = record["authorList"]["author"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(
+            |a| match (str_field(a, "lastName"), str_field(a, "firstName")) {
+                (Some(family), Some(given)) => Some(format!("{}, {}", family, given)),
+                (Some(family), None) => Some(family.to_owned()),
+                _ => str_field(a, "collectiveName").map(|n| format!("{{{}}}", n)),
+            },
+        )
+        .collect::<Vec<_>>();
+    let year = str_field(record, "pubYear");
+    let family = authors
+        .first()
+        .map_or("anonymous", |a| bibtex::family_name(a))
+        .trim_matches(['{', '}'])
+        .replace(' ', "");
+    let key = match year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    };
+
+    let journal_info = &record["journalInfo"];
+    let journal = str_field(&journal_info["journal"], "title");
+    let mut entry = Entry::new(
+        if journal.is_some() {
+            "article"
+        } else {
+            "online"
+        },
+        &key,
+    );
+    entry.set("title", str_field(record, "title")?.trim_end_matches('.'));
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    match journal {
+        Some(journal) => entry.set("journal", journal),
+        None => {
+            if let Some(server) = str_field(&record["bookOrReportDetails"], "publisher") {
+                entry.set("organization", server);
+            }
+        }
+    }
+    if let Some(volume) = str_field(journal_info, "volume") {
+        entry.set("volume", volume);
+    }
+    if let Some(issue) = str_field(journal_info, "issue") {
+        entry.set("number", issue);
+    }
+    if let Some(pages) = str_field(record, "pageInfo") {
+        entry.set("pages", &pages.replace("--", "-").replace('-', "--"));
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    if let Some(doi) = str_field(record, "doi") {
+        entry.set("doi", doi);
+    }
+    if let Some(pmid) = str_field(record, "pmid") {
+        entry.set("pmid", pmid);
+    }
+    if let Some(pmcid) = str_field(record, "pmcid") {
+        entry.set("pmcid", pmcid);
+    }
+    if let Some(abstract_) = str_field(record, "abstractText") {
+        entry.set("abstract", &screening::strip_markup(abstract_));
+    }
+    let mesh = record["meshHeadingList"]["meshHeading"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|h| str_field(h, "descriptorName"))
+        .collect::<Vec<_>>();
+    if !mesh.is_empty() {
+        entry.set("keywords", &mesh.join(", "));
+    }
+    let url = match (str_field(record, "doi"), str_field(record, "pmid")) {
+        (Some(doi), _) => format!("https://doi.org/{}", doi),
+        (None, Some(pmid)) => format!("https://europepmc.org/article/MED/{}", pmid),
+        (None, None) => format!(
+            "https://europepmc.org/article/{}/{}",
+            str_field(record, "source")?,
+            str_field(record, "id")?
+        ),
+    };
+    entry.set("url", &url);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry() {
+        let record = serde_json::json!({
+            "id": "32015508", "source": "MED", "pmid": "32015508", "pmcid": "PMC7094943",
+            "doi": "10.1038/s41586-020-2008-3",
+            "title": "A new coronavirus associated with human respiratory disease in China.",
+            "authorList": {"author": [
+                {"fullName": "Wu F", "firstName": "Fan", "lastName": "Wu"},
+                {"collectiveName": "COVID-19 Consortium"}
+            ]},
+            "journalInfo": {"volume": "579", "issue": "7798", "journal": {"title": "Nature"}},
+            "pubYear": "2020", "pageInfo": "265-269",
+            "abstractText": "<h4>Background</h4>Emerging <i>infectious</i> diseases &amp; more.",
+            "meshHeadingList": {"meshHeading": [
+                {"descriptorName": "Humans"}, {"descriptorName": "Coronavirus Infections"}
+            ]}
+        });
+        assert_eq!(
+            entry(&record).unwrap().to_string(),
+            "@article{Wu_2020,\n  \
+             title={A new coronavirus associated with human respiratory disease in China},\n  \
+             author={Wu, Fan and {COVID-19 Consortium}},\n  journal={Nature},\n  volume={579},\n  \
+             number={7798},\n  pages={265--269},\n  year={2020},\n  \
+             doi={10.1038/s41586-020-2008-3},\n  pmid={32015508},\n  pmcid={PMC7094943},\n  \
+             abstract={Background Emerging infectious diseases & more.},\n  \
+             keywords={Humans, Coronavirus Infections},\n  \
+             url={https://doi.org/10.1038/s41586-020-2008-3}\n}"
+        );
+        assert_eq!(query(Id::Pmcid, "pmc7094943"), "PMCID:PMC7094943");
+
+        let preprint = serde_json::json!({
+            "id": "PPR123", "source": "PPR", "title": "Untitled",
+            "bookOrReportDetails": {"publisher": "bioRxiv"}
+        });
+        let entry = entry(&preprint).unwrap();
+        assert_eq!(entry.kind, "online");
+        assert_eq!(entry.key, "anonymous");
+        assert_eq!(
+            entry.get("url"),
+            Some("https://europepmc.org/article/PPR/PPR123")
+        );
+    }
+}
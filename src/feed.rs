@@ -0,0 +1,449 @@
+//! Atom and RSS feeds, read into one model of a feed and its items so that the entries of arXiv's
+//! API, podcast feeds and the feeds of repositories and custom resolvers are all built the same
+//! way: [`parse`] the feed, then map its items onto entries with the [`Source`] they came from.
+//!
+//! Atom feeds are read with `atom_syndication`. RSS has no such parser here, and only the handful
+//! of elements citations need are pulled out of it.
+
+use crate::bibtex::{self, Entry};
+use crate::{extract_arxiv, podcast, text, Format};
+use chrono::{DateTime, Datelike, FixedOffset};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref RSS_ITEM_RE: Regex = Regex::new(r"(?is)<item\b.*?</item>").unwrap();
+    static ref ENCLOSURE_RE: Regex = Regex::new(r"(?is)<enclosure\s[^>]*>").unwrap();
+    static ref ATTR_RE: Regex =
+        Regex::new(r#"(?is)([a-z:_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    /// arXiv's own categories, as opposed to the ACM and MSC classes some papers list alongside.
+    static ref ARXIV_CATEGORY_RE: Regex = Regex::new(r"^[a-z-]+(?:\.[A-Za-z-]+)?$").unwrap();
+    /// Namespaced elements with plain text content, such as `<itunes:duration>` and `<arxiv:doi>`.
+    static ref EXTENSION_RE: Regex = Regex::new(
+        r"(?is)<([a-z][a-z0-9]*:[a-z][a-z0-9_-]*)(?:\s[^>]*)?>([^<]*)</([a-z][a-z0-9]*:[a-z][a-z0-9_-]*)>"
+    )
+    .unwrap();
+}
+
+/// A feed: its title and author, and its items in feed order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Feed {
+    pub title: String,
+    pub author: Option<String>,
+    pub items: Vec<Item>,
+}
+
+/// An Atom entry or RSS item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Item {
+    /// The Atom `id`, or the RSS `guid` (falling back to the `link`).
+    pub id: String,
+    /// The title as the feed gives it, which may still contain HTML entities.
+    pub title: String,
+    pub authors: Vec<String>,
+    pub published: Option<DateTime<FixedOffset>>,
+    pub updated: Option<DateTime<FixedOffset>>,
+    /// The page of the item: its `alternate` link in Atom.
+    pub link: Option<String>,
+    pub summary: Option<String>,
+    pub categories: Vec<String>,
+    /// Namespaced elements, by prefixed name (`arxiv:doi`), in document order.
+    pub extensions: Vec<(String, String)>,
+    /// The URLs and MIME types of the item's enclosures.
+    pub enclosures: Vec<(String, String)>,
+}
+
+impl Item {
+    /// The first value of a namespaced element.
+    pub fn extension(&self, name: &str) -> Option<&str> {
+        self.extensions
+            .iter()
+            .find(|(n, v)| n == name && !v.is_empty())
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// The attributes of a tag, with names lowercased and entities decoded.
+pub fn attrs(tag: &str) -> Vec<(String, String)> {
+    ATTR_RE
+        .captures_iter(tag)
+        .map(|a| {
+            let value = a.get(2).or_else(|| a.get(3)).unwrap().as_str();
+            (a[1].to_ascii_lowercase(), text::decode_entities(value))
+        })
+        .collect()
+}
+
+/// The text of the first `<name>` element, without CDATA markers or entities.
+fn element(xml: &str, name: &str) -> Option<String> {
+    let re = Regex::new(&format!(
+        r"(?is)<{}(?:\s[^>]*)?>(.*?)</{}>",
+        regex::escape(name),
+        regex::escape(name)
+    ))
+    .unwrap();
+    let inner = re.captures(xml)?.get(1)?.as_str().trim();
+    let inner = inner
+        .strip_prefix("<![CDATA[")
+        .and_then(|i| i.strip_suffix("]]>"))
+        .unwrap_or(inner);
+    Some(text::collapse_whitespace(&text::decode_entities(inner))).filter(|t| !t.is_empty())
+}
+
+/// The text of every `<name>` element.
+fn elements(xml: &str, name: &str) -> Vec<String> {
+    let close = format!("</{}>", name);
+    xml.split(&close)
+        .filter_map(|part| element(&format!("{}{}", part, close), name))
+        .collect()
+}
+
+fn from_atom(feed: &atom_syndication::Feed) -> Feed {
+    let items = feed
+        .entries()
+        .iter()
+        .map(|entry| Item {
+            id: entry.id().to_owned(),
+            title: entry.title().as_str().to_owned(),
+            authors: entry
+                .authors()
+                .iter()
+                .map(|a| a.name().to_owned())
+                .collect(),
+            published: entry.published().copied(),
+            updated: Some(*entry.updated()),
+            link: entry
+                .links()
+                .iter()
+                .find(|l| l.rel() == "alternate")
+                .map(|l| l.href().to_owned()),
+            summary: entry.summary().map(|s| s.as_str().to_owned()),
+            categories: entry
+                .categories()
+                .iter()
+                .map(|c| c.term().to_owned())
+                .collect(),
+            extensions: entry
+                .extensions()
+                .iter()
+                .flat_map(|(ns, elements)| {
+                    elements.iter().flat_map(move |(name, values)| {
+                        values.iter().filter_map(move |v| {
+                            // Some, like `<arxiv:primary_category term="cs.LG"/>`, have their
+                            // value in a `term` instead.
+                            let value = v
+                                .value()
+                                .or_else(|| v.attrs().get("term").map(|t| t.as_str()))?;
+                            Some((format!("{}:{}", ns, name), value.trim().to_owned()))
+                        })
+                    })
+                })
+                .collect(),
+            enclosures: entry
+                .links()
+                .iter()
+                .filter(|l| l.rel() == "enclosure")
+                .map(|l| {
+                    (
+                        l.href().to_owned(),
+                        l.mime_type().unwrap_or_default().to_owned(),
+                    )
+                })
+                .collect(),
+        })
+        .collect();
+    Feed {
+        title: feed.title().as_str().to_owned(),
+        author: feed.authors().first().map(|a| a.name().to_owned()),
+        items,
+    }
+}
+
+fn from_rss(body: &str) -> Feed {
+    let channel = &body[..body.find("<item").unwrap_or(body.len())];
+    let items = RSS_ITEM_RE
+        .find_iter(body)
+        .filter_map(|item| {
+            let item = item.as_str();
+            let link = element(item, "link");
+            let mut authors = elements(item, "dc:creator");
+            if authors.is_empty() {
+                authors.extend(element(item, "author"));
+            }
+            Some(Item {
+                id: element(item, "guid").or_else(|| link.clone())?,
+                title: element(item, "title")?,
+                authors,
+                published: element(item, "pubDate")
+                    .and_then(|d| DateTime::parse_from_rfc2822(&d).ok()),
+                updated: None,
+                link,
+                summary: element(item, "description"),
+                categories: elements(item, "category"),
+                extensions: EXTENSION_RE
+                    .captures_iter(item)
+                    .filter(|c| c[1].eq_ignore_ascii_case(&c[3]))
+                    .map(|c| (c[1].to_owned(), text::decode_entities(c[2].trim())))
+                    .collect(),
+                enclosures: ENCLOSURE_RE
+                    .find_iter(item)
+                    .filter_map(|e| {
+                        let attrs = attrs(e.as_str());
+                        let attr = |name: &str| {
+                            attrs
+                                .iter()
+                                .find(|(n, _)| n == name)
+                                .map(|(_, v)| v.clone())
+                        };
+                        Some((attr("url")?, attr("type").unwrap_or_default()))
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+    Feed {
+        title: element(channel, "title").unwrap_or_default(),
+        author: element(channel, "itunes:author").or_else(|| element(channel, "managingEditor")),
+        items,
+    }
+}
+
+/// Parses an Atom or RSS feed.
+pub fn parse(body: &str) -> Result<Feed, String> {
+    match body.parse::<atom_syndication::Feed>() {
+        Ok(feed) => Ok(from_atom(&feed)),
+        Err(_) if body.contains("<rss") || body.contains("<channel") => Ok(from_rss(body)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Where a feed comes from, which decides how its items are cited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The arXiv API: `@article`s with the eprint, its archive and primary class, and all of its
+    /// categories in `categories`.
+    Arxiv,
+    /// A podcast: `@audio` episodes of the show. Items without audio are not episodes.
+    Podcast,
+    /// Any other feed, such as a repository's or a custom resolver's: `@misc`es.
+    Repository,
+}
+
+impl Source {
+    /// The entry of an item of a feed from this source, if it has what the source needs.
+    pub fn entry(self, feed: &Feed, item: &Item, format: Format) -> Option<Entry> {
+        match self {
+            Source::Arxiv => arxiv_entry(item),
+            Source::Podcast => podcast::episode(feed, item).map(|e| podcast::entry(&e, format)),
+            Source::Repository => repository_entry(item),
+        }
+    }
+}
+
+/// The archives arXiv has since folded into others, with the category each became. Papers filed
+/// under them keep the old name in their ID, and sometimes in their categories.
+const ARXIV_ARCHIVES: &[(&str, &str)] = &[
+    ("acc-phys", "physics.acc-ph"),
+    ("adap-org", "nlin.AO"),
+    ("alg-geom", "math.AG"),
+    ("ao-sci", "physics.ao-ph"),
+    ("atom-ph", "physics.atom-ph"),
+    ("bayes-an", "physics.data-an"),
+    ("chao-dyn", "nlin.CD"),
+    ("chem-ph", "physics.chem-ph"),
+    ("cmp-lg", "cs.CL"),
+    ("comp-gas", "nlin.CG"),
+    ("dg-ga", "math.DG"),
+    ("funct-an", "math.FA"),
+    ("mtrl-th", "cond-mat.mtrl-sci"),
+    ("patt-sol", "nlin.PS"),
+    ("plasm-ph", "physics.plasm-ph"),
+    ("q-alg", "math.QA"),
+    ("solv-int", "nlin.SI"),
+    ("supr-con", "cond-mat.supr-con"),
+];
+
+/// The current name of an arXiv category.
+pub fn modern_category(category: &str) -> &str {
+    ARXIV_ARCHIVES
+        .iter()
+        .find(|(old, _)| old.eq_ignore_ascii_case(category))
+        .map_or(category, |(_, new)| new)
+}
+
+/// An arXiv API entry. Names are split at their last word, which is how arXiv lists them.
+fn arxiv_entry(item: &Item) -> Option<Entry> {
+    let published = item.published?;
+    if item.authors.is_empty() || item.id.is_empty() {
+        return None;
+    }
+    let names = item
+        .authors
+        .iter()
+        .map(|a| {
+            let words = a.split_whitespace().collect::<Vec<_>>();
+            let (family, given) = words.split_last()?;
+            Some((family.to_string(), given.join(" ")))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let authors = names
+        .iter()
+        .map(|(family, given)| format!("{}, {}", family, given))
+        .collect::<Vec<_>>();
+
+    let year = published.year().to_string();
+    let key = format!("{}_{}", names[0].0, year);
+    let title = text::collapse_whitespace(&text::decode_entities(&item.title));
+    // Decoding may have produced characters that are special to LaTeX.
+    let title = bibtex::escape_bare(&title, &['&', '%', '#']);
+    let id = extract_arxiv(&item.id)?;
+
+    let mut entry = Entry::new("article", &key);
+    entry.set("title", &title);
+    entry.set("author", &authors.join(" and "));
+    entry.set("year", &year);
+    entry.set("eprint", &id);
+    entry.set("archivePrefix", "arXiv");
+    // Old IDs name their archive, which is the primary class if the feed gives none.
+    let archive = id.split_once('/').map(|(archive, _)| archive);
+    let mut categories = Vec::new();
+    for category in item
+        .extension("arxiv:primary_category")
+        .into_iter()
+        .chain(item.categories.iter().map(String::as_str))
+        .chain(archive)
+    {
+        let category = modern_category(category);
+        if ARXIV_CATEGORY_RE.is_match(category) && !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    entry.set("primaryClass", categories.first()?);
+    entry.set("categories", &categories.join(", "));
+    Some(entry)
+}
+
+/// The arXiv categories of an entry: its `primaryClass`, then the rest of its `categories`.
+pub fn categories(entry: &Entry) -> Vec<&str> {
+    let mut categories = entry.get("primaryClass").into_iter().collect::<Vec<_>>();
+    for category in entry.get("categories").unwrap_or_default().split(',') {
+        let category = category.trim();
+        if !category.is_empty() && !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+/// Whether an entry is in an arXiv category, or in any category of an archive (`cs`, `math`).
+pub fn in_category(entry: &Entry, wanted: &str) -> bool {
+    categories(entry).iter().any(|c| {
+        c.eq_ignore_ascii_case(wanted)
+            || c.split('.')
+                .next()
+                .unwrap_or_default()
+                .eq_ignore_ascii_case(wanted)
+    })
+}
+
+/// A `@misc` from any feed item, dated by its publication (or last update) and linked to its page.
+fn repository_entry(item: &Item) -> Option<Entry> {
+    let authors = item
+        .authors
+        .iter()
+        .map(|a| bibtex::name_last_first(a))
+        .collect::<Vec<_>>();
+    let year = item
+        .published
+        .or(item.updated)
+        .map(|d| d.format("%Y").to_string());
+    let family = authors
+        .first()
+        .map_or("anonymous", |a| bibtex::family_name(a))
+        .replace(' ', "");
+    let key = match &year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    };
+    let mut entry = Entry::new("misc", &key);
+    entry.set("title", item.title.trim());
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    entry.set("url", item.link.as_deref().unwrap_or(&item.id));
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let atom = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom" xmlns:arxiv="http://arxiv.org/schemas/atom">
+              <title>ArXiv Query</title><id>q</id><updated>2017-12-06T00:00:00Z</updated>
+              <entry>
+                <id>http://arxiv.org/abs/1712.01815v1</id>
+                <updated>2017-12-05T18:38:42Z</updated>
+                <published>2017-12-05T18:38:42Z</published>
+                <title>Mastering Chess and Shogi by Self-Play with a General
+                  Reinforcement Learning Algorithm</title>
+                <author><name>David Silver</name></author>
+                <author><name>Thomas Hubert</name></author>
+                <arxiv:doi>10.1126/science.aar6404</arxiv:doi>
+                <link href="http://arxiv.org/abs/1712.01815v1" rel="alternate" type="text/html"/>
+                <arxiv:primary_category term="cs.AI" scheme="http://arxiv.org/schemas/atom"/>
+                <category term="cs.LG" scheme="http://arxiv.org/schemas/atom"/>
+                <category term="cs.AI" scheme="http://arxiv.org/schemas/atom"/>
+                <category term="I.2.6" scheme="http://arxiv.org/schemas/atom"/>
+              </entry>
+            </feed>"#;
+        let feed = parse(atom).unwrap();
+        let item = &feed.items[0];
+        assert_eq!(item.extension("arxiv:doi"), Some("10.1126/science.aar6404"));
+        assert_eq!(
+            Source::Arxiv.entry(&feed, item, Format::Bibtex).unwrap().to_string(),
+            "@article{Silver_2017,\n  title={Mastering Chess and Shogi by Self-Play with a General \
+             Reinforcement Learning Algorithm},\n  author={Silver, David and Hubert, Thomas},\n  \
+             year={2017},\n  eprint={1712.01815v1},\n  archivePrefix={arXiv},\n  \
+             primaryClass={cs.AI},\n  categories={cs.AI, cs.LG}\n}"
+        );
+        let entry = Source::Arxiv.entry(&feed, item, Format::Bibtex).unwrap();
+        assert_eq!(categories(&entry), ["cs.AI", "cs.LG"]);
+        assert!(in_category(&entry, "cs.lg") && in_category(&entry, "cs"));
+        assert!(!in_category(&entry, "stat.ML"));
+
+        let old = Item {
+            id: "http://arxiv.org/abs/alg-geom/9201001v1".to_owned(),
+            title: "Mirror symmetry".to_owned(),
+            authors: vec!["Jane Doe".to_owned()],
+            published: item.published,
+            ..Default::default()
+        };
+        let entry = Source::Arxiv.entry(&feed, &old, Format::Bibtex).unwrap();
+        assert_eq!(entry.get("eprint"), Some("alg-geom/9201001v1"));
+        assert_eq!(entry.get("primaryClass"), Some("math.AG"));
+
+        let rss = r#"<rss version="2.0" xmlns:dc="http://purl.org/dc/elements/1.1/"><channel>
+            <title>Repository feed</title>
+            <item><title>A dataset &amp; its code</title><link>https://repo.example.org/r/1</link>
+              <dc:creator>Jane Doe</dc:creator><dc:creator>John Roe</dc:creator>
+              <pubDate>Tue, 02 Jan 2024 10:00:00 +0000</pubDate><category>data</category></item>
+        </channel></rss>"#;
+        let feed = parse(rss).unwrap();
+        assert_eq!(feed.title, "Repository feed");
+        assert_eq!(
+            Source::Repository
+                .entry(&feed, &feed.items[0], Format::Bibtex)
+                .unwrap()
+                .to_string(),
+            "@misc{Doe_2024,\n  title={A dataset & its code},\n  author={Doe, Jane and Roe, John},\n  \
+             year={2024},\n  url={https://repo.example.org/r/1}\n}"
+        );
+        assert!(parse("<html></html>").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+//! The HTTP layer the core fetches through. The command line implements it with reqwest and its
+//! retry logic; a browser build would implement it with `fetch`.
+
+use crate::bibtex::{self, Entry};
+use std::fmt::Display;
+use std::future::Future;
+
+/// The `Accept` header doi.org content negotiation answers with BibTeX for.
+pub const BIBTEX: &str = "text/bibliography; style=bibtex";
+
+/// A response, read to the end.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub status: u16,
+    pub body: String,
+}
+
+pub trait Fetch {
+    type Error: Display;
+
+    /// GETs `url` with the given `Accept` header.
+    fn get(&self, url: &str, accept: &str) -> impl Future<Output = Result<Page, Self::Error>>;
+}
+
+/// The doi.org URL of a DOI, with each path segment percent-encoded.
+pub fn doi_url(doi: &str) -> String {
+    let mut url = "https://doi.org".to_owned();
+    for segment in doi.split('/') {
+        url.push('/');
+        for c in segment.chars() {
+            if c.is_ascii_graphic() && !"\"#<>?`{}%".contains(c) {
+                url.push(c);
+            } else {
+                for b in c.to_string().bytes() {
+                    url.push_str(&format!("%{:02X}", b));
+                }
+            }
+        }
+    }
+    url
+}
+
+/// Resolves a DOI through doi.org content negotiation.
+pub async fn negotiate_doi<F: Fetch>(fetch: &F, doi: &str) -> Result<Entry, String> {
+    let page = fetch
+        .get(&doi_url(doi), BIBTEX)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !(200..300).contains(&page.status) {
+        return Err(format!("doi.org answered {}", page.status));
+    }
+    bibtex::parse_entry(&page.body).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture;
+
+    impl Fetch for Fixture {
+        type Error = String;
+
+        async fn get(&self, url: &str, accept: &str) -> Result<Page, String> {
+            assert_eq!(accept, BIBTEX);
+            match url {
+                "https://doi.org/10.1000/a%20b%23c" => Ok(Page {
+                    status: 200,
+                    body: "@article{Shen_2021, title={A b}, year={2021}}".to_owned(),
+                }),
+                _ => Ok(Page {
+                    status: 404,
+                    body: String::new(),
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn test_negotiate_doi() {
+        let entry = futures::executor::block_on(negotiate_doi(&Fixture, "10.1000/a b#c")).unwrap();
+        assert_eq!(entry.key, "Shen_2021");
+        assert!(futures::executor::block_on(negotiate_doi(&Fixture, "10.1000/x")).is_err());
+    }
+}
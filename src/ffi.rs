@@ -0,0 +1,132 @@
+//! A C ABI around the core, so that scripts in other languages can resolve DOIs without spawning
+//! d2b. From Python, with `ctypes`:
+//!
+//! ```python
+//! lib = ctypes.CDLL("libd2b.so")
+//! lib.d2b_resolve_to_bibtex.restype = ctypes.c_void_p
+//! ptr = lib.d2b_resolve_to_bibtex(b"10.18637/jss.v076.i01")
+//! bibtex = ctypes.string_at(ptr).decode()
+//! lib.d2b_free(ptr)
+//! ```
+//!
+//! Strings returned by d2b are owned by the caller and must be freed with [`d2b_free`].
+
+use crate::fetch::{self, Fetch, Page};
+use crate::text;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+lazy_static! {
+    static ref DOI_RE: Regex = Regex::new(r"10\.\d{4,9}/\S+").unwrap();
+    static ref CLIENT: reqwest::blocking::Client = reqwest::blocking::Client::new();
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+struct Blocking;
+
+impl Fetch for Blocking {
+    type Error = reqwest::Error;
+
+    async fn get(&self, url: &str, accept: &str) -> Result<Page, reqwest::Error> {
+        let res = CLIENT
+            .get(url)
+            .header(reqwest::header::ACCEPT, accept)
+            .send()?;
+        Ok(Page {
+            status: res.status().as_u16(),
+            body: res.text()?,
+        })
+    }
+}
+
+/// The DOI in an input such as `doi:10.1000/xyz` or `https://doi.org/10.1000%2Fxyz`.
+fn doi_of(input: &str) -> Option<String> {
+    DOI_RE
+        .find(&text::percent_decode(input))
+        .map(|m| m.as_str().trim_end_matches('/').to_lowercase())
+}
+
+fn resolve(id: *const c_char) -> Result<String, String> {
+    if id.is_null() {
+        return Err("no identifier given".to_owned());
+    }
+    // SAFETY: the caller passes a NUL-terminated string, as documented on the exported function.
+    let id = unsafe { CStr::from_ptr(id) }
+        .to_str()
+        .map_err(|e| e.to_string())?;
+    let doi = doi_of(id).ok_or_else(|| format!("{}: not a DOI", id.trim()))?;
+    let entry = futures::executor::block_on(fetch::negotiate_doi(&Blocking, &doi))?;
+    Ok(entry.to_string())
+}
+
+fn set_error(message: Option<String>) {
+    let message = message.map(|m| CString::new(m.replace('\0', "")).unwrap());
+    LAST_ERROR.with(|e| *e.borrow_mut() = message);
+}
+
+/// Resolves a DOI to a BibTeX entry. Returns null on failure, with the reason available from
+/// [`d2b_last_error`].
+///
+/// # Safety
+///
+/// `id` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn d2b_resolve_to_bibtex(id: *const c_char) -> *mut c_char {
+    match resolve(id) {
+        Ok(bibtex) => {
+            set_error(None);
+            CString::new(bibtex).map_or(ptr::null_mut(), CString::into_raw)
+        }
+        Err(e) => {
+            set_error(Some(e));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The reason the last call on this thread failed, or null. The string stays valid until the
+/// next call and must not be freed.
+#[no_mangle]
+pub extern "C" fn d2b_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |m| m.as_ptr()))
+}
+
+/// Frees a string returned by d2b.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by d2b that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn d2b_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            doi_of("https://doi.org/10.18637%2FJSS.v076.i01/").as_deref(),
+            Some("10.18637/jss.v076.i01")
+        );
+        let input = CString::new("arxiv:1111.4246").unwrap();
+        unsafe {
+            assert!(d2b_resolve_to_bibtex(input.as_ptr()).is_null());
+            let error = CStr::from_ptr(d2b_last_error()).to_str().unwrap();
+            assert_eq!(error, "arxiv:1111.4246: not a DOI");
+            assert!(d2b_resolve_to_bibtex(ptr::null()).is_null());
+            d2b_free(ptr::null_mut());
+        }
+    }
+}
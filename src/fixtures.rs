@@ -0,0 +1,159 @@
+//! Recording responses and replaying them (`--record DIR`, `--replay DIR`), so that tests of d2b
+//! and of pipelines built on it don't depend on live services.
+//!
+//! Each response is stored as `HOST-HASH.json`, with its status, headers and final URL, and
+//! `HOST-HASH.body`, where the hash covers the method, URL, `Accept` header and request body.
+//! Replaying a request that wasn't recorded warns and answers 501.
+
+use crate::i18n;
+use reqwest::header::{ACCEPT, CONTENT_LENGTH, TRANSFER_ENCODING};
+use reqwest::{Request, Response, ResponseBuilderExt, Url};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    method: String,
+    /// The URL the response came from, after redirects.
+    url: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is the same across Rust versions.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The name a request's response is stored under.
+pub fn name(req: &Request) -> String {
+    let mut key = format!("{} {}\n", req.method(), req.url()).into_bytes();
+    if let Some(accept) = req.headers().get(ACCEPT) {
+        key.extend_from_slice(accept.as_bytes());
+    }
+    if let Some(body) = req.body().and_then(|b| b.as_bytes()) {
+        key.push(b'\n');
+        key.extend_from_slice(body);
+    }
+    format!(
+        "{}-{:016x}",
+        req.url().host_str().unwrap_or("local"),
+        fnv1a(&key)
+    )
+}
+
+fn response(fixture: &Fixture, body: Vec<u8>) -> Response {
+    let mut builder = http02::Response::builder().status(fixture.status);
+    if let Ok(url) = Url::parse(&fixture.url) {
+        builder = builder.url(url);
+    }
+    for (name, value) in &fixture.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    Response::from(builder.body(body).unwrap())
+}
+
+/// The recorded response to a request.
+pub fn replay(dir: &Path, req: &Request) -> Response {
+    let name = name(req);
+    let recorded = std::fs::read_to_string(dir.join(format!("{}.json", name)))
+        .ok()
+        .and_then(|src| serde_json::from_str::<Fixture>(&src).ok())
+        .and_then(|f| Some((std::fs::read(dir.join(format!("{}.body", name))).ok()?, f)));
+    match recorded {
+        Some((body, fixture)) => response(&fixture, body),
+        None => {
+            eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message(
+                    "fixture-missing",
+                    &[("url", &req.url()), ("name", &dir.join(&name).display())]
+                )
+            );
+            let fixture = Fixture {
+                method: req.method().to_string(),
+                url: req.url().to_string(),
+                status: 501,
+                headers: Vec::new(),
+            };
+            response(&fixture, Vec::new())
+        }
+    }
+}
+
+/// Stores a response, and returns an equivalent one since reading it consumes the original.
+pub async fn record(
+    dir: &Path,
+    name: &str,
+    method: &str,
+    res: Response,
+) -> reqwest::Result<Response> {
+    let fixture = Fixture {
+        method: method.to_owned(),
+        url: res.url().to_string(),
+        status: res.status().as_u16(),
+        headers: res
+            .headers()
+            .iter()
+            .filter(|(name, _)| *name != CONTENT_LENGTH && *name != TRANSFER_ENCODING)
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_owned())))
+            .collect(),
+    };
+    let body = res.bytes().await?.to_vec();
+    let written = std::fs::create_dir_all(dir)
+        .and_then(|_| {
+            std::fs::write(
+                dir.join(format!("{}.json", name)),
+                serde_json::to_string_pretty(&fixture).unwrap(),
+            )
+        })
+        .and_then(|_| std::fs::write(dir.join(format!("{}.body", name)), &body));
+    if let Err(e) = written {
+        eprintln!(
+            "{}: {}",
+            i18n::text("warning"),
+            i18n::message(
+                "file-unwritable",
+                &[("file", &dir.display()), ("error", &e)]
+            )
+        );
+    }
+    Ok(response(&fixture, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Client;
+
+    #[test]
+    fn test_replay() {
+        let client = Client::new();
+        let req = |accept: &str| {
+            client
+                .get("https://doi.org/10.1000/xyz")
+                .header(ACCEPT, accept)
+                .build()
+                .unwrap()
+        };
+        let bibtex = name(&req("application/x-bibtex"));
+        assert!(bibtex.starts_with("doi.org-"));
+        assert_eq!(bibtex, name(&req("application/x-bibtex")));
+        assert_ne!(bibtex, name(&req("text/html")));
+
+        let fixture = Fixture {
+            method: "GET".to_owned(),
+            url: "https://example.org/landing".to_owned(),
+            status: 429,
+            headers: vec![("retry-after".to_owned(), "5".to_owned())],
+        };
+        let res = response(&fixture, b"busy".to_vec());
+        assert_eq!(res.status().as_u16(), 429);
+        assert_eq!(res.url().as_str(), "https://example.org/landing");
+        assert_eq!(res.headers()["retry-after"], "5");
+        assert_eq!(futures::executor::block_on(res.text()).unwrap(), "busy");
+    }
+}
@@ -0,0 +1,417 @@
+//! `d2b refs` and `d2b cited-by`: the works a paper cites, or the works citing it, resolved like
+//! any other input, to turn one seed paper into a starter bibliography. `d2b related` does the
+//! same for the papers Semantic Scholar recommends reading alongside it.
+//!
+//! The citation graph comes from Semantic Scholar, or from OpenAlex for papers it doesn't know.
+//! Works with neither a DOI nor an arXiv ID can't be resolved and are skipped.
+
+use crate::auth;
+use crate::config::Config;
+use crate::error::{self, Error, Kind};
+use crate::http::SendWithRetry;
+use crate::{i18n, input_id, input_key, merge_duplicates, render, resolve, Options, CLIENT};
+use clap::ArgMatches;
+use futures::{stream::FuturesUnordered, StreamExt};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{RequestBuilder, StatusCode, Url};
+use serde_json::Value;
+
+/// The DOI prefix arXiv registers its papers under, which is how OpenAlex knows them.
+pub const ARXIV_DOI_PREFIX: &str = "10.48550/arxiv.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The works a paper cites.
+    References,
+    /// The works citing a paper.
+    Citations,
+}
+
+impl Direction {
+    /// The Semantic Scholar endpoint, and the field of each item holding the other paper.
+    fn semantic_scholar(self) -> (&'static str, &'static str) {
+        match self {
+            Direction::References => ("references", "citedPaper"),
+            Direction::Citations => ("citations", "citingPaper"),
+        }
+    }
+
+    /// The OpenAlex filter listing the other papers.
+    fn openalex(self) -> &'static str {
+        match self {
+            Direction::References => "cited_by",
+            Direction::Citations => "cites",
+        }
+    }
+}
+
+/// The Semantic Scholar paper ID for an identifier in the form [`input_id`] gives.
+pub fn semantic_scholar_id(id: &str) -> Option<String> {
+    match id.split_once(':')? {
+        ("doi", doi) => Some(format!("DOI:{}", doi)),
+        ("arxiv", arxiv) => Some(format!("ARXIV:{}", arxiv)),
+        _ => None,
+    }
+}
+
+/// The DOI OpenAlex knows a work by, for an identifier in the form [`input_id`] gives.
+pub fn openalex_doi(id: &str) -> Option<String> {
+    match id.split_once(':')? {
+        ("doi", doi) => Some(doi.to_lowercase()),
+        ("arxiv", arxiv) => Some(format!("{}{}", ARXIV_DOI_PREFIX, arxiv)),
+        _ => None,
+    }
+}
+
+/// The input to resolve a paper listed by Semantic Scholar from: its DOI, else its arXiv ID.
+pub fn semantic_scholar_input(paper: &Value) -> Option<String> {
+    let ids = &paper["externalIds"];
+    match (ids["DOI"].as_str(), ids["ArXiv"].as_str()) {
+        (Some(doi), _) => Some(format!("doi:{}", doi)),
+        (None, Some(arxiv)) => Some(format!("arxiv:{}", arxiv)),
+        (None, None) => None,
+    }
+}
+
+/// The input to resolve a work listed by OpenAlex from: its DOI, as an arXiv ID if it's one of
+/// arXiv's.
+pub fn openalex_input(work: &Value) -> Option<String> {
+    let doi = work["doi"].as_str()?.trim_start_matches("https://doi.org/");
+    match doi.to_lowercase().strip_prefix(ARXIV_DOI_PREFIX) {
+        Some(arxiv) => Some(format!("arxiv:{}", arxiv)),
+        None => Some(format!("doi:{}", doi)),
+    }
+}
+
+pub async fn get_json(req: RequestBuilder) -> Result<Value, Option<StatusCode>> {
+    let res = req.send_with_retry().await.map_err(|_| None)?;
+    if !res.status().is_success() {
+        return Err(Some(res.status()));
+    }
+    let body = res.text().await.map_err(|_| None)?;
+    serde_json::from_str(&body).map_err(|_| None)
+}
+
+/// The inputs for the papers Semantic Scholar lists, with `None` for those that can't be
+/// resolved, or `None` altogether if it doesn't know the seed paper.
+async fn from_semantic_scholar(
+    id: &str,
+    direction: Direction,
+    limit: usize,
+    token: Option<&auth::Token>,
+) -> Option<Vec<Option<String>>> {
+    let (endpoint, field) = direction.semantic_scholar();
+    let mut url = Url::parse("https://api.semanticscholar.org/graph/v1/paper/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(semantic_scholar_id(id)?.split('/'))
+        .push(endpoint);
+
+    let mut papers = Vec::new();
+    while papers.len() < limit {
+        let mut req = CLIENT.get(url.clone()).query(&[
+            ("fields", "externalIds".to_owned()),
+            ("offset", papers.len().to_string()),
+            ("limit", (limit - papers.len()).min(1000).to_string()),
+        ]);
+        if let Some(token) = token {
+            req = req.header("x-api-key", token.secret());
+        }
+        let json = match get_json(req).await {
+            Ok(json) => json,
+            Err(Some(StatusCode::NOT_FOUND)) if papers.is_empty() => return None,
+            Err(_) => break,
+        };
+        let items = match json["data"].as_array() {
+            Some(items) if !items.is_empty() => items,
+            _ => break,
+        };
+        papers.extend(items.iter().map(|i| semantic_scholar_input(&i[field])));
+        if json["next"].is_null() {
+            break;
+        }
+    }
+    papers.truncate(limit);
+    Some(papers)
+}
+
+/// The inputs for the works OpenAlex lists, with `None` for those that can't be resolved.
+async fn from_openalex(id: &str, direction: Direction, limit: usize) -> Vec<Option<String>> {
+    let doi = match openalex_doi(id) {
+        Some(doi) => doi,
+        None => return Vec::new(),
+    };
+    let mut url = Url::parse("https://api.openalex.org/works/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(format!("doi:{}", doi).split('/'));
+    let seed = match get_json(CLIENT.get(url)).await {
+        Ok(seed) => seed,
+        Err(_) => return Vec::new(),
+    };
+    let openalex_id = match seed["id"].as_str().and_then(|i| i.rsplit('/').next()) {
+        Some(openalex_id) => openalex_id.to_owned(),
+        None => return Vec::new(),
+    };
+
+    let mut works = Vec::new();
+    let mut cursor = "*".to_owned();
+    while works.len() < limit {
+        let req = CLIENT.get("https://api.openalex.org/works").query(&[
+            (
+                "filter",
+                format!("{}:{}", direction.openalex(), openalex_id),
+            ),
+            ("select", "doi".to_owned()),
+            ("per-page", "200".to_owned()),
+            ("cursor", cursor.clone()),
+        ]);
+        let json = match get_json(req).await {
+            Ok(json) => json,
+            Err(_) => break,
+        };
+        let items = match json["results"].as_array() {
+            Some(items) if !items.is_empty() => items,
+            _ => break,
+        };
+        works.extend(items.iter().map(openalex_input));
+        match json["meta"]["next_cursor"].as_str() {
+            Some(next) if next != cursor => cursor = next.to_owned(),
+            _ => break,
+        }
+    }
+    works.truncate(limit);
+    works
+}
+
+/// The seed paper's identifier and the `--limit`, or exits if either is bad.
+fn seed_and_limit(matches: &ArgMatches, opts: &Options) -> (String, usize) {
+    let seed = matches.value_of("input").unwrap();
+    let id = input_id(seed)
+        .filter(|id| semantic_scholar_id(id).is_some())
+        .unwrap_or_else(|| {
+            Error::new(Kind::InvalidInput, i18n::text("graph-invalid-input"))
+                .with_input(seed)
+                .exit(opts.error_format)
+        });
+    let limit = matches
+        .value_of("limit")
+        .unwrap()
+        .parse()
+        .ok()
+        .filter(|&l| l > 0)
+        .unwrap_or_else(|| {
+            Error::new(Kind::Usage, i18n::text("invalid-limit")).exit(opts.error_format)
+        });
+    (id, limit)
+}
+
+/// Resolves and prints the listed works in the order given, each after the comment `comment`
+/// gives for its input, and exits with the status for any that failed.
+pub async fn print_all(
+    listed: Vec<Option<String>>,
+    comment: impl Fn(&str) -> Option<String>,
+    opts: &Options,
+) {
+    let skipped = listed.iter().filter(|i| i.is_none()).count();
+    if skipped > 0 {
+        eprintln!(
+            "{}: {}",
+            i18n::text("warning"),
+            i18n::message("graph-skipped", &[("count", &skipped)])
+        );
+    }
+    let mut seen = std::collections::HashSet::new();
+    let inputs = listed
+        .into_iter()
+        .flatten()
+        .filter(|i| seen.insert(input_key(i)))
+        .collect::<Vec<_>>();
+
+    let mut futures = inputs
+        .iter()
+        .map(|i| resolve(i.clone(), opts.clone()))
+        .collect::<FuturesUnordered<_>>();
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(val) = futures.next().await {
+        match val {
+            Ok(val) => resolved.extend(val),
+            Err(e) => errors.push(e),
+        }
+    }
+    for e in &errors {
+        e.report(opts.error_format);
+    }
+    // Keep the order the works were listed in, so that runs are deterministic.
+    resolved.sort_by_key(|r| inputs.iter().position(|i| *i == r.input));
+    for r in merge_duplicates(resolved) {
+        if let Some(comment) = comment(&r.input) {
+            println!("% {}", comment);
+        }
+        println!("{}", render(&r, opts));
+    }
+    let status = error::status(inputs.len() - errors.len(), &errors);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+/// Runs `d2b refs` or `d2b cited-by`.
+pub async fn run(matches: &ArgMatches<'_>, direction: Direction, config: &Config, opts: Options) {
+    let (id, limit) = seed_and_limit(matches, &opts);
+    let token = auth::token("semanticscholar", config);
+    let listed = match from_semantic_scholar(&id, direction, limit, token.as_ref()).await {
+        Some(listed) => listed,
+        None => from_openalex(&id, direction, limit).await,
+    };
+    print_all(listed, |_| None, &opts).await;
+}
+
+/// The cosine similarity of two embeddings, or `None` if they can't be compared.
+pub fn similarity(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    let norms =
+        a.iter().map(|x| x * x).sum::<f64>().sqrt() * b.iter().map(|y| y * y).sum::<f64>().sqrt();
+    if norms == 0.0 {
+        None
+    } else {
+        Some(dot / norms)
+    }
+}
+
+/// The SPECTER embeddings of papers by Semantic Scholar ID, `None` for those it has none for.
+async fn embeddings(ids: &[String], token: Option<&auth::Token>) -> Vec<Option<Vec<f64>>> {
+    let mut req = CLIENT
+        .post("https://api.semanticscholar.org/graph/v1/paper/batch")
+        .query(&[("fields", "embedding.specter_v2")])
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::json!({ "ids": ids }).to_string());
+    if let Some(token) = token {
+        req = req.header("x-api-key", token.secret());
+    }
+    let papers = get_json(req).await.unwrap_or_default();
+    (0..ids.len())
+        .map(|n| {
+            papers[n]["embedding"]["vector"]
+                .as_array()?
+                .iter()
+                .map(Value::as_f64)
+                .collect()
+        })
+        .collect()
+}
+
+/// Runs `d2b related`: Semantic Scholar's recommendations for a paper, best first, each with its
+/// similarity to the seed paper in a comment.
+pub async fn related(matches: &ArgMatches<'_>, config: &Config, opts: Options) {
+    let (id, limit) = seed_and_limit(matches, &opts);
+    let token = auth::token("semanticscholar", config);
+    let not_found = || -> ! {
+        Error::new(
+            Kind::NotFound,
+            i18n::message("no-record", &[("resolver", &"Semantic Scholar")]),
+        )
+        .with_input(&id)
+        .exit(opts.error_format)
+    };
+
+    let seed = semantic_scholar_id(&id).unwrap();
+    let mut url =
+        Url::parse("https://api.semanticscholar.org/recommendations/v1/papers/forpaper/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(seed.split('/'));
+    let mut req = CLIENT.get(url).query(&[
+        ("fields", "externalIds".to_owned()),
+        ("limit", limit.min(500).to_string()),
+    ]);
+    if let Some(token) = &token {
+        req = req.header("x-api-key", token.secret());
+    }
+    let json = get_json(req).await.unwrap_or_else(|_| not_found());
+    let papers = match json["recommendedPapers"].as_array() {
+        Some(papers) => papers,
+        None => not_found(),
+    };
+
+    let mut ids = vec![seed];
+    ids.extend(
+        papers
+            .iter()
+            .filter_map(|p| p["paperId"].as_str().map(str::to_owned)),
+    );
+    let embeddings = embeddings(&ids, token.as_ref()).await;
+    let mut scores = std::collections::HashMap::new();
+    let listed = papers
+        .iter()
+        .map(|paper| {
+            let input = semantic_scholar_input(paper)?;
+            let n = ids
+                .iter()
+                .position(|i| paper["paperId"].as_str() == Some(i))?;
+            if let (Some(seed), Some(other)) = (&embeddings[0], &embeddings[n]) {
+                if let Some(score) = similarity(seed, other) {
+                    scores.insert(input.clone(), score);
+                }
+            }
+            Some(input)
+        })
+        .collect();
+    print_all(
+        listed,
+        |input| {
+            let score = scores.get(input)?;
+            Some(format!("similarity to {}: {:.3}", id, score))
+        },
+        &opts,
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(
+            semantic_scholar_id("doi:10.1145/359545.359563").unwrap(),
+            "DOI:10.1145/359545.359563"
+        );
+        assert_eq!(
+            semantic_scholar_id("arxiv:1712.01815").unwrap(),
+            "ARXIV:1712.01815"
+        );
+        assert_eq!(semantic_scholar_id("url:https://example.com"), None);
+        assert_eq!(
+            openalex_doi("arxiv:1712.01815").unwrap(),
+            "10.48550/arxiv.1712.01815"
+        );
+
+        let paper = json!({"paperId": "abc", "externalIds": {"ArXiv": "1712.01815", "DBLP": "x"}});
+        assert_eq!(semantic_scholar_input(&paper).unwrap(), "arxiv:1712.01815");
+        assert_eq!(semantic_scholar_input(&json!({"externalIds": null})), None);
+
+        let work = json!({"doi": "https://doi.org/10.48550/arXiv.1712.01815"});
+        assert_eq!(openalex_input(&work).unwrap(), "arxiv:1712.01815");
+        let work = json!({"doi": "https://doi.org/10.1145/359545.359563"});
+        assert_eq!(openalex_input(&work).unwrap(), "doi:10.1145/359545.359563");
+        assert_eq!(openalex_input(&json!({"doi": null})), None);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity(&[1.0, 0.0], &[2.0, 0.0]), Some(1.0));
+        assert_eq!(similarity(&[1.0, 0.0], &[0.0, 3.0]), Some(0.0));
+        assert_eq!(similarity(&[1.0, 0.0], &[1.0]), None);
+        assert_eq!(similarity(&[0.0, 0.0], &[1.0, 1.0]), None);
+    }
+}
@@ -0,0 +1,112 @@
+//! `--post-hook CMD`: each entry is piped through a shell command as JSON, and the entry the
+//! command writes back replaces it, for transformations d2b has no option for.
+//!
+//! The command gets `{"input": ..., "type": ..., "key": ..., "fields": {...}}` on stdin, with
+//! the fields in order, and should print an object of the same shape; `input` is informational
+//! and may be left out. A command that fails or prints something else fails the input.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{Error, Kind};
+use crate::i18n;
+use serde_json::{json, Map, Value};
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+pub fn to_json(entry: &Entry, input: &str) -> Value {
+    let fields = entry
+        .fields
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.as_str().to_owned())))
+        .collect::<Map<_, _>>();
+    json!({
+        "input": input.trim(),
+        "type": entry.kind,
+        "key": entry.key,
+        "fields": fields,
+    })
+}
+
+/// Reads back an entry the hook printed. Fields it left as they were in `original` keep being
+/// written the same way, so that bare values such as `month = jan` stay bare.
+pub fn from_json(value: &Value, original: &Entry) -> Option<Entry> {
+    let mut entry = Entry::new(value["type"].as_str()?, value["key"].as_str()?);
+    for (name, value) in value["fields"].as_object()? {
+        let value = value.as_str()?;
+        let unchanged = original
+            .fields
+            .iter()
+            .find(|(n, v)| n == name && v.as_str() == value);
+        match unchanged {
+            Some((_, v)) => entry.fields.push((name.clone(), v.clone())),
+            None => entry
+                .fields
+                .push((name.clone(), bibtex::Value::Text(value.to_owned()))),
+        }
+    }
+    Some(entry)
+}
+
+fn shell(cmd: &str) -> Command {
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
+    }
+}
+
+pub async fn run(cmd: &str, entry: &Entry, input: &str) -> Result<Entry, Error> {
+    let fail = |reason: &dyn std::fmt::Display| {
+        Error::new(
+            Kind::Hook,
+            i18n::message("hook-failed", &[("command", &cmd), ("error", reason)]),
+        )
+    };
+    let mut child = shell(cmd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| fail(&e))?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin
+        .write_all(to_json(entry, input).to_string().as_bytes())
+        .await
+        .map_err(|e| fail(&e))?;
+    drop(stdin);
+    let out = child.wait_with_output().await.map_err(|e| fail(&e))?;
+    if !out.status.success() {
+        return Err(fail(&out.status));
+    }
+    serde_json::from_slice(&out.stdout)
+        .ok()
+        .as_ref()
+        .and_then(|value| from_json(value, entry))
+        .ok_or_else(|| fail(&i18n::text("hook-bad-output")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let entry = bibtex::parse_entry("@article{Shen_2021, title={Zebras}, month=jan}").unwrap();
+        let mut json = to_json(&entry, " 10.1000/xyz ");
+        assert_eq!(
+            json.to_string(),
+            r#"{"input":"10.1000/xyz","type":"article","key":"Shen_2021","fields":{"title":"Zebras","month":"jan"}}"#
+        );
+        assert_eq!(from_json(&json, &entry).unwrap(), entry);
+
+        json["fields"]["title"] = json!("Zebras & horses");
+        let changed = from_json(&json, &entry).unwrap();
+        assert_eq!(changed.get("title"), Some("Zebras & horses"));
+        assert_eq!(changed.fields[1], entry.fields[1]);
+        let bad = json!({"type": "article", "key": "x", "fields": {"year": 2021}});
+        assert!(from_json(&bad, &entry).is_none());
+    }
+}
@@ -0,0 +1,71 @@
+//! Minimal extraction of `<meta>` metadata from HTML pages.
+//!
+//! Repositories and publishers embed Highwire (`citation_*`), Dublin Core (`DC.*`) and OpenGraph
+//! (`og:*`) tags in their landing pages, which is usually all that's needed to cite them.
+
+use crate::text::decode_entities;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref META_RE: Regex = Regex::new(r"(?is)<meta\s[^>]*>").unwrap();
+    static ref ATTR_RE: Regex =
+        Regex::new(r#"(?is)([a-z:_-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+}
+
+/// All `<meta name=... content=...>` (or `property=`) pairs in the page, in document order, with
+/// names lowercased and entities in the content decoded.
+pub fn meta_tags(html: &str) -> Vec<(String, String)> {
+    META_RE
+        .find_iter(html)
+        .filter_map(|tag| {
+            let mut name = None;
+            let mut content = None;
+            for attr in ATTR_RE.captures_iter(tag.as_str()) {
+                let value = attr.get(2).or_else(|| attr.get(3)).unwrap().as_str();
+                match attr[1].to_ascii_lowercase().as_str() {
+                    "name" | "property" | "itemprop" => name = Some(value.to_ascii_lowercase()),
+                    "content" => content = Some(decode_entities(value.trim())),
+                    _ => {}
+                }
+            }
+            Some((name?, content?))
+        })
+        .collect()
+}
+
+/// The first value of the meta tag called `name`, if present and non-empty.
+pub fn first<'a>(tags: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    tags.iter()
+        .find(|(n, v)| n == name && !v.is_empty())
+        .map(|(_, v)| v.as_str())
+}
+
+/// Every non-empty value of the meta tag called `name`.
+pub fn all<'a>(tags: &'a [(String, String)], name: &str) -> Vec<&'a str> {
+    tags.iter()
+        .filter(|(n, v)| n == name && !v.is_empty())
+        .map(|(_, v)| v.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_meta_tags() {
+        let html = r#"<html><head>
+            <meta name="citation_title" content="On Things &amp; Stuff">
+            <META content='Doe, Jane' NAME="citation_author" />
+            <meta name="citation_author" content="John Smith">
+            <meta property="og:type" content="article">
+            <meta charset="utf-8">
+        </head></html>"#;
+        let tags = meta_tags(html);
+        assert_eq!(tags.len(), 4);
+        assert_eq!(first(&tags, "citation_title"), Some("On Things & Stuff"));
+        assert_eq!(all(&tags, "citation_author"), ["Doe, Jane", "John Smith"]);
+        assert_eq!(first(&tags, "og:type"), Some("article"));
+    }
+}
@@ -0,0 +1,266 @@
+//! The shared HTTP client, and sending requests politely: arXiv, doi.org and the registries answer
+//! bursts with 429 or 503 and a `Retry-After` header, which is waited out rather than treated as a
+//! failure.
+//!
+//! Each host has its own queue, with its own limit on requests in flight and, for arXiv, a minimum
+//! interval between them, so that waiting on one service doesn't hold up requests to the others.
+
+use crate::{fixtures, metrics};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, RequestBuilder, Response, StatusCode, Url};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// How many times a throttled request is retried.
+const MAX_RETRIES: u32 = 4;
+/// The longest wait that is worth it; services asking for more are given up on.
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+/// Connection settings for the shared client, from the command line. Some institutional proxies
+/// break HTTP/2 or long-lived connections, and these work around them.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    /// Never negotiate HTTP/2.
+    pub http1: bool,
+    /// How long idle connections are kept for reuse; zero disables reuse.
+    pub pool_idle_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+    /// Print each response's protocol, peer and timing to stderr.
+    pub debug: bool,
+    /// Store every response in this directory.
+    pub record: Option<PathBuf>,
+    /// Answer requests from responses stored in this directory instead of the network.
+    pub replay: Option<PathBuf>,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+lazy_static! {
+    static ref QUEUES: Mutex<HashMap<String, Arc<Queue>>> = Mutex::new(HashMap::new());
+}
+
+/// How many requests a host gets at once, and how long to leave between starting them.
+fn limits(host: &str) -> (usize, Duration) {
+    match host {
+        // arXiv asks API clients for no more than one request every three seconds.
+        "export.arxiv.org" | "arxiv.org" => (1, Duration::from_secs(3)),
+        "doi.org" | "api.crossref.org" | "api.datacite.org" => (16, Duration::ZERO),
+        _ => (4, Duration::ZERO),
+    }
+}
+
+/// The requests to one host.
+struct Queue {
+    host: String,
+    permits: Semaphore,
+    interval: Duration,
+    /// When the next request may start.
+    next: tokio::sync::Mutex<Instant>,
+}
+
+impl Queue {
+    fn for_url(url: &Url) -> Arc<Queue> {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let mut queues = QUEUES.lock().unwrap();
+        queues
+            .entry(host)
+            .or_insert_with_key(|host| {
+                let (permits, interval) = limits(host);
+                Arc::new(Queue {
+                    host: host.clone(),
+                    permits: Semaphore::new(permits),
+                    interval,
+                    next: tokio::sync::Mutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+
+    /// Waits for a turn to send a request.
+    async fn acquire(&self) -> SemaphorePermit<'_> {
+        let permit = self.permits.acquire().await.unwrap();
+        let mut next = self.next.lock().await;
+        let now = Instant::now();
+        if *next > now {
+            tokio::time::sleep(*next - now).await;
+        }
+        *next = Instant::now() + self.interval;
+        permit
+    }
+
+    /// Holds back every request to the host, after it asked for a break.
+    async fn hold_off(&self, wait: Duration) {
+        let mut next = self.next.lock().await;
+        *next = (*next).max(Instant::now() + wait);
+    }
+}
+
+fn seconds(matches: &ArgMatches, name: &str) -> Result<Option<Duration>, String> {
+    matches
+        .value_of(name)
+        .map(|v| {
+            v.parse::<f64>()
+                .ok()
+                .filter(|s| s.is_finite() && *s >= 0.0)
+                .map(Duration::from_secs_f64)
+                .ok_or_else(|| format!("--{}: {}", name, v))
+        })
+        .transpose()
+}
+
+impl Settings {
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self, String> {
+        Ok(Settings {
+            http1: matches.is_present("http1"),
+            pool_idle_timeout: seconds(matches, "pool-idle-timeout")?,
+            tcp_keepalive: seconds(matches, "tcp-keepalive")?,
+            debug: matches.is_present("http-debug"),
+            record: matches.value_of("record").map(PathBuf::from),
+            replay: matches.value_of("replay").map(PathBuf::from),
+        })
+    }
+}
+
+/// Sets how the shared client is built. Only has an effect before its first use.
+pub fn configure(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+/// Builds the shared client from the configured settings.
+pub fn client() -> Client {
+    let s = settings();
+    let mut builder = Client::builder().tcp_keepalive(s.tcp_keepalive);
+    match s.pool_idle_timeout {
+        Some(timeout) if timeout.is_zero() => builder = builder.pool_max_idle_per_host(0),
+        Some(timeout) => builder = builder.pool_idle_timeout(timeout),
+        None => {}
+    }
+    if s.http1 {
+        builder = builder.http1_only();
+    }
+    builder
+        .build()
+        .expect("Could not initialize the HTTP client")
+}
+
+/// How long a `Retry-After` header asks to wait, given as seconds or as an HTTP date.
+fn retry_after(headers: &HeaderMap, now: DateTime<Utc>) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&Utc) - now)
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+pub trait SendWithRetry {
+    /// Like `send`, but waits and retries when the server says it's overloaded.
+    fn send_with_retry(self) -> impl Future<Output = reqwest::Result<Response>> + Send;
+}
+
+impl SendWithRetry for RequestBuilder {
+    async fn send_with_retry(self) -> reqwest::Result<Response> {
+        let (client, req) = self.build_split();
+        let mut req = req?;
+        let settings = settings();
+        if let Some(dir) = &settings.replay {
+            return Ok(fixtures::replay(dir, &req));
+        }
+        let recording = settings
+            .record
+            .as_ref()
+            .map(|dir| (dir, fixtures::name(&req), req.method().to_string()));
+        let queue = Queue::for_url(req.url());
+        let mut attempt = 0;
+        let res = loop {
+            let retry = req.try_clone();
+            let permit = queue.acquire().await;
+            let start = Instant::now();
+            let res = client.execute(req).await?;
+            drop(permit);
+            metrics::record(&queue.host, start.elapsed());
+            if settings.debug {
+                eprintln!(
+                    "http: {} {} ({:?}, {}, {} ms)",
+                    res.status().as_u16(),
+                    res.url(),
+                    res.version(),
+                    res.remote_addr()
+                        .map_or_else(|| "unknown peer".to_owned(), |a| a.to_string()),
+                    start.elapsed().as_millis()
+                );
+            }
+            let throttled = matches!(
+                res.status(),
+                StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+            );
+            req = match retry {
+                Some(retry) if throttled && attempt < MAX_RETRIES => retry,
+                _ => break res,
+            };
+            // Without a Retry-After, back off exponentially from a second.
+            let wait = retry_after(res.headers(), Utc::now())
+                .unwrap_or_else(|| Duration::from_secs(1 << attempt));
+            if wait > MAX_WAIT {
+                break res;
+            }
+            queue.hold_off(wait).await;
+            attempt += 1;
+        };
+        match recording {
+            Some((dir, name, method)) => fixtures::record(dir, &name, &method, res).await,
+            None => Ok(res),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(limits("export.arxiv.org"), (1, Duration::from_secs(3)));
+        assert_eq!(limits("api.crossref.org").1, Duration::ZERO);
+        assert!(limits("api.crossref.org").0 > limits("example.org").0);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let now = DateTime::parse_from_rfc3339("2015-10-21T07:27:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let header = |v: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(v));
+            headers
+        };
+        assert_eq!(retry_after(&header("5"), now), Some(Duration::from_secs(5)));
+        assert_eq!(
+            retry_after(&header("Wed, 21 Oct 2015 07:28:00 GMT"), now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            retry_after(&header("Wed, 21 Oct 2015 07:00:00 GMT"), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&header("soon"), now), None);
+        assert_eq!(retry_after(&HeaderMap::new(), now), None);
+    }
+}
@@ -0,0 +1,104 @@
+//! Translations of user-facing messages.
+//!
+//! Messages live in `locales/<language>.ftl`, in a one-line-per-message subset of Fluent. The
+//! language is taken from `$D2B_LANG`, else the usual `$LC_ALL`, `$LC_MESSAGES` and `$LANG`, and
+//! any message a locale lacks falls back to English. To add a language, translate `en.ftl` and
+//! list the new file in [`LOCALES`].
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("zh", include_str!("../locales/zh.ftl")),
+];
+
+lazy_static! {
+    static ref ARG_RE: Regex = Regex::new(r"\{\s*\$([A-Za-z_-]+)\s*\}").unwrap();
+    static ref CATALOG: HashMap<&'static str, &'static str> = catalog(&language());
+}
+
+/// The messages in an `.ftl` file.
+fn parse(src: &'static str) -> impl Iterator<Item = (&'static str, &'static str)> {
+    src.lines().filter_map(|line| {
+        let line = line.trim();
+        if line.starts_with('#') {
+            return None;
+        }
+        let (id, message) = line.split_once('=')?;
+        Some((id.trim(), message.trim()))
+    })
+}
+
+/// The language code of the user's locale, e.g. `de` for `de_DE.UTF-8`.
+fn language() -> String {
+    ["D2B_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .map(|v| {
+            v.split(['_', '-', '.', '@'])
+                .next()
+                .unwrap()
+                .to_ascii_lowercase()
+        })
+        .unwrap_or_default()
+}
+
+/// The messages for `lang`, with English filling in any gaps.
+fn catalog(lang: &str) -> HashMap<&'static str, &'static str> {
+    let mut messages = parse(LOCALES[0].1).collect::<HashMap<_, _>>();
+    if let Some((_, src)) = LOCALES.iter().find(|(code, _)| *code == lang) {
+        messages.extend(parse(src));
+    }
+    messages
+}
+
+/// The message with the given id. Unknown ids are returned as is.
+pub fn text(id: &'static str) -> &'static str {
+    CATALOG.get(id).copied().unwrap_or(id)
+}
+
+fn format(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    ARG_RE
+        .replace_all(message, |c: &Captures| {
+            args.iter()
+                .find(|(name, _)| *name == &c[1])
+                .map_or_else(|| c[0].to_owned(), |(_, value)| value.to_string())
+        })
+        .into_owned()
+}
+
+/// The message with the given id, with its `{ $name }` arguments filled in.
+pub fn message(id: &'static str, args: &[(&str, &dyn Display)]) -> String {
+    format(text(id), args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let english = catalog("en");
+        for (lang, src) in &LOCALES[1..] {
+            for (id, _) in parse(src) {
+                assert!(english.contains_key(id), "{}: unknown id {}", lang, id);
+            }
+        }
+
+        let german = catalog("de");
+        assert_eq!(german["error"], "Fehler");
+        assert_eq!(catalog("xx")["error"], "error");
+        assert_eq!(
+            format(
+                english["sync-summary"],
+                &[("file", &"refs.bib"), ("added", &2)]
+            ),
+            "refs.bib: 2 added, { $removed } removed"
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! `d2b ids`: every identifier a work is known by (DOI, arXiv ID, ADS bibcode, PubMed and PubMed
+//! Central IDs, Semantic Scholar and OpenAlex IDs), to match up records from tools that each use
+//! their own. Semantic Scholar and OpenAlex are asked first, and the ADS with the user's token if
+//! there is one, each with the identifiers the others found.
+
+use crate::config::Config;
+use crate::error::{Error, Kind};
+use crate::graph::{self, ARXIV_DOI_PREFIX};
+use crate::{auth, i18n, input_id, Options, CLIENT};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::Url;
+use serde_json::{Map, Value};
+
+lazy_static! {
+    /// ADS bibcodes: year, journal, volume, qualifier, page and author initial in 19 characters.
+    static ref BIBCODE_RE: Regex = Regex::new(
+        r"^(?:(?:ads|bibcode):\s*|https?://(?:ui\.)?adsabs\.harvard\.edu/abs/)?(\d{4}[A-Za-z&][A-Za-z&.]{4}[\w.]{4}[\w.][\w.]{4}[A-Z.])(?:/.*)?$"
+    )
+    .unwrap();
+    static ref S2_RE: Regex = Regex::new(
+        r"^(?:s2:\s*|https?://(?:www\.)?semanticscholar\.org/paper/(?:[^/]+/)?)?([0-9a-f]{40})/?$"
+    )
+    .unwrap();
+    static ref OPENALEX_RE: Regex =
+        Regex::new(r"(?i)^(?:openalex:\s*|https?://(?:api\.)?openalex\.org/(?:works/)?)?(W\d+)/?$")
+            .unwrap();
+}
+
+/// The identifiers of a work, as they are written without a prefix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ids {
+    /// The DOI of the published version, leaving out the DOI arXiv gives every paper.
+    pub doi: Option<String>,
+    pub arxiv: Option<String>,
+    pub bibcode: Option<String>,
+    pub pmid: Option<String>,
+    pub pmcid: Option<String>,
+    pub s2: Option<String>,
+    pub openalex: Option<String>,
+}
+
+/// Sets an identifier unless it's known already, since the source asked first is the one that was
+/// asked by it.
+fn fill(id: &mut Option<String>, value: Option<&str>) {
+    if id.is_none() {
+        *id = value
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_owned);
+    }
+}
+
+impl Ids {
+    /// The identifiers in an input, or `None` if it isn't an identifier of a work.
+    pub fn parse(input: &str) -> Option<Self> {
+        let input = input.trim();
+        let mut ids = Ids::default();
+        if let Some(c) = BIBCODE_RE.captures(input) {
+            ids.bibcode = Some(c[1].to_owned());
+        } else if let Some(c) = S2_RE.captures(input) {
+            ids.s2 = Some(c[1].to_owned());
+        } else if let Some(c) = OPENALEX_RE.captures(input) {
+            ids.openalex = Some(c[1].to_uppercase());
+        } else {
+            match input_id(input)?.split_once(':')? {
+                ("doi", doi) => ids.add_doi(doi),
+                ("arxiv", arxiv) => ids.arxiv = Some(arxiv.to_owned()),
+                ("pmid", pmid) => ids.pmid = Some(pmid.to_owned()),
+                ("pmcid", pmcid) => ids.pmcid = Some(pmcid.to_uppercase()),
+                _ => return None,
+            }
+        }
+        Some(ids)
+    }
+
+    /// Adds a DOI, as the arXiv ID if it's the DOI arXiv gave a paper.
+    fn add_doi(&mut self, doi: &str) {
+        let doi = doi.trim_start_matches("https://doi.org/");
+        match doi.to_lowercase().strip_prefix(ARXIV_DOI_PREFIX) {
+            Some(arxiv) => fill(&mut self.arxiv, Some(arxiv)),
+            None => fill(&mut self.doi, Some(doi)),
+        }
+    }
+
+    /// The identifiers with their names, in a fixed order, for those that are known.
+    pub fn rows(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("doi", &self.doi),
+            ("arxiv", &self.arxiv),
+            ("bibcode", &self.bibcode),
+            ("pmid", &self.pmid),
+            ("pmcid", &self.pmcid),
+            ("s2", &self.s2),
+            ("openalex", &self.openalex),
+        ]
+        .iter()
+        .filter_map(|&(name, id)| Some((name, id.as_deref()?)))
+        .collect()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let ids = self
+            .rows()
+            .into_iter()
+            .map(|(name, id)| (name.to_owned(), Value::String(id.to_owned())))
+            .collect::<Map<_, _>>();
+        Value::Object(ids)
+    }
+
+    /// Adds what a Semantic Scholar paper lists in its `externalIds`.
+    pub fn add_semantic_scholar(&mut self, paper: &Value) {
+        let ids = &paper["externalIds"];
+        if let Some(doi) = ids["DOI"].as_str() {
+            self.add_doi(doi);
+        }
+        fill(&mut self.arxiv, ids["ArXiv"].as_str());
+        fill(&mut self.pmid, ids["PubMed"].as_str());
+        let pmcid = ids["PubMedCentral"]
+            .as_str()
+            .map(|id| match id.starts_with("PMC") {
+                true => id.to_owned(),
+                false => format!("PMC{}", id),
+            });
+        fill(&mut self.pmcid, pmcid.as_deref());
+        fill(&mut self.s2, paper["paperId"].as_str());
+    }
+
+    /// Adds what an OpenAlex work lists in its `ids`, which are URLs.
+    pub fn add_openalex(&mut self, work: &Value) {
+        let ids = &work["ids"];
+        let last = |name: &str| {
+            ids[name]
+                .as_str()
+                .and_then(|u| u.trim_end_matches('/').rsplit('/').next())
+        };
+        if let Some(doi) = ids["doi"].as_str() {
+            self.add_doi(doi);
+        }
+        fill(&mut self.pmid, last("pmid"));
+        let pmcid = last("pmcid").map(|id| match id.to_uppercase().starts_with("PMC") {
+            true => id.to_uppercase(),
+            false => format!("PMC{}", id),
+        });
+        fill(&mut self.pmcid, pmcid.as_deref());
+        fill(&mut self.openalex, last("openalex"));
+    }
+
+    /// Adds what an ADS record lists, whose `identifier`s include its arXiv ID and other
+    /// bibcodes.
+    pub fn add_ads(&mut self, doc: &Value) {
+        fill(&mut self.bibcode, doc["bibcode"].as_str());
+        for doi in doc["doi"].as_array().into_iter().flatten() {
+            if let Some(doi) = doi.as_str() {
+                self.add_doi(doi);
+            }
+        }
+        for id in doc["identifier"].as_array().into_iter().flatten() {
+            if let Some(arxiv) = id.as_str().and_then(|id| id.strip_prefix("arXiv:")) {
+                fill(&mut self.arxiv, Some(arxiv));
+            }
+        }
+    }
+
+    /// The Semantic Scholar paper ID to look the work up by.
+    fn semantic_scholar_id(&self) -> Option<String> {
+        self.s2
+            .clone()
+            .or_else(|| self.doi.as_ref().map(|doi| format!("DOI:{}", doi)))
+            .or_else(|| self.arxiv.as_ref().map(|id| format!("ARXIV:{}", id)))
+            .or_else(|| self.pmid.as_ref().map(|id| format!("PMID:{}", id)))
+            .or_else(|| {
+                self.pmcid
+                    .as_ref()
+                    .map(|id| format!("PMCID:{}", id.trim_start_matches("PMC")))
+            })
+    }
+
+    /// The OpenAlex ID, or external ID, to look the work up by.
+    fn openalex_id(&self) -> Option<String> {
+        self.openalex
+            .clone()
+            .or_else(|| self.doi.as_ref().map(|doi| format!("doi:{}", doi)))
+            .or_else(|| {
+                let arxiv = self.arxiv.as_ref()?;
+                Some(format!("doi:{}{}", ARXIV_DOI_PREFIX, arxiv.to_lowercase()))
+            })
+            .or_else(|| self.pmid.as_ref().map(|id| format!("pmid:{}", id)))
+            .or_else(|| self.pmcid.as_ref().map(|id| format!("pmcid:{}", id)))
+    }
+
+    /// The ADS query that finds the work.
+    fn ads_query(&self) -> Option<String> {
+        self.bibcode
+            .as_ref()
+            .map(|b| format!("bibcode:\"{}\"", b))
+            .or_else(|| self.doi.as_ref().map(|doi| format!("doi:\"{}\"", doi)))
+            .or_else(|| {
+                self.arxiv
+                    .as_ref()
+                    .map(|id| format!("identifier:\"arXiv:{}\"", id))
+            })
+    }
+}
+
+async fn semantic_scholar(ids: &mut Ids, token: Option<&auth::Token>) {
+    let Some(id) = ids.semantic_scholar_id() else {
+        return;
+    };
+    let mut url = Url::parse("https://api.semanticscholar.org/graph/v1/paper/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(id.split('/'));
+    let mut req = CLIENT.get(url).query(&[("fields", "paperId,externalIds")]);
+    if let Some(token) = token {
+        req = req.header("x-api-key", token.secret());
+    }
+    if let Ok(paper) = graph::get_json(req).await {
+        ids.add_semantic_scholar(&paper);
+    }
+}
+
+async fn openalex(ids: &mut Ids) {
+    let Some(id) = ids.openalex_id() else {
+        return;
+    };
+    let mut url = Url::parse("https://api.openalex.org/works/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(id.split('/'));
+    if let Ok(work) = graph::get_json(CLIENT.get(url).query(&[("select", "ids")])).await {
+        ids.add_openalex(&work);
+    }
+}
+
+async fn ads(ids: &mut Ids, token: &auth::Token) {
+    let Some(query) = ids.ads_query() else {
+        return;
+    };
+    let req = CLIENT
+        .get("https://api.adsabs.harvard.edu/v1/search/query")
+        .query(&[
+            ("q", query.as_str()),
+            ("fl", "bibcode,doi,identifier"),
+            ("rows", "1"),
+        ])
+        .bearer_auth(token.secret());
+    if let Ok(json) = graph::get_json(req).await {
+        if let Some(doc) = json["response"]["docs"].get(0) {
+            ids.add_ads(doc);
+        }
+    }
+}
+
+/// Looks up the other identifiers of a work. A bibcode can only be looked up in the ADS, so it's
+/// asked first for those.
+pub async fn lookup(mut ids: Ids, config: &Config) -> Ids {
+    let ads_token = auth::token("ads", config);
+    let from_ads = ids.bibcode.is_some();
+    if let (true, Some(token)) = (from_ads, &ads_token) {
+        ads(&mut ids, token).await;
+    }
+    semantic_scholar(&mut ids, auth::token("semanticscholar", config).as_ref()).await;
+    openalex(&mut ids).await;
+    if let (false, Some(token)) = (from_ads, &ads_token) {
+        ads(&mut ids, token).await;
+    }
+    ids
+}
+
+/// Runs `d2b ids`.
+pub async fn run(matches: &ArgMatches<'_>, config: &Config, opts: Options) {
+    let input = matches.value_of("input").unwrap();
+    let ids = Ids::parse(input).unwrap_or_else(|| {
+        Error::new(
+            Kind::Usage,
+            i18n::message("ids-unknown-input", &[("input", &input)]),
+        )
+        .exit(opts.error_format)
+    });
+    let ids = lookup(ids, config).await;
+    if matches.is_present("json") {
+        println!("{}", ids.to_json());
+        return;
+    }
+    let width = ids
+        .rows()
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    for (name, id) in ids.rows() {
+        println!("{:width$}  {}", name, id, width = width);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(
+            Ids::parse("https://ui.adsabs.harvard.edu/abs/2016Natur.529..484S/abstract")
+                .and_then(|ids| ids.bibcode),
+            Some("2016Natur.529..484S".to_owned())
+        );
+        assert_eq!(
+            Ids::parse("https://openalex.org/W2257979135").and_then(|ids| ids.openalex),
+            Some("W2257979135".to_owned())
+        );
+        assert_eq!(
+            Ids::parse("doi:10.48550/arXiv.1712.01815").unwrap(),
+            Ids {
+                arxiv: Some("1712.01815".to_owned()),
+                ..Default::default()
+            }
+        );
+        assert!(Ids::parse("not an id").is_none());
+
+        let mut ids = Ids::parse("10.1038/nature16961").unwrap();
+        ids.add_semantic_scholar(&json!({
+            "paperId": "846aedd869a00c09b40f1f1f35673cb22bc87490",
+            "externalIds": {"DOI": "10.1038/NATURE16961", "PubMed": "26819042", "CorpusId": 515925}
+        }));
+        ids.add_openalex(&json!({"ids": {
+            "openalex": "https://openalex.org/W2257979135",
+            "doi": "https://doi.org/10.1038/nature16961",
+            "pmid": "https://pubmed.ncbi.nlm.nih.gov/26819042"
+        }}));
+        ids.add_ads(&json!({
+            "bibcode": "2016Natur.529..484S",
+            "doi": ["10.1038/nature16961"],
+            "identifier": ["2016Natur.529..484S", "10.1038/nature16961"]
+        }));
+        assert_eq!(
+            ids.to_json(),
+            json!({
+                "doi": "10.1038/nature16961",
+                "bibcode": "2016Natur.529..484S",
+                "pmid": "26819042",
+                "s2": "846aedd869a00c09b40f1f1f35673cb22bc87490",
+                "openalex": "W2257979135"
+            })
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! `d2b import`: regenerates a clean `.bib` file from a library exported from a reference manager,
+//! by resolving the DOI or arXiv ID of every item again, as a one-shot way off Zotero or Mendeley.
+//!
+//! Zotero RDF and JSON exports (CSL JSON, Better BibTeX, Mendeley) are read item by item, taking
+//! one identifier from each, so that the items without one, which have to be moved over by hand,
+//! can be named.
+
+use crate::bibtex;
+use crate::error::{self, Error, Kind};
+use crate::mail::identifiers;
+use crate::{atomic, i18n, input_key, merge_duplicates, render, resolve, text, Options};
+use clap::ArgMatches;
+use futures::{stream::FuturesUnordered, StreamExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(r"<(/?)([\w:.-]+)[^>]*?(/?)>").unwrap();
+    static ref ABOUT_RE: Regex = Regex::new(r#"^<[^>]*\brdf:about\s*=\s*"([^"]*)""#).unwrap();
+    static ref IDENTIFIER_RE: Regex =
+        Regex::new(r"(?s)<dc:identifier>(.*?)</dc:identifier>").unwrap();
+    static ref TITLE_RE: Regex = Regex::new(r"(?s)<dc:title>(.*?)</dc:title>").unwrap();
+}
+
+/// The fields of a JSON item an identifier is looked for in, most reliable first.
+const ID_FIELDS: &[&str] = &[
+    "DOI", "doi", "arXiv", "arxiv", "URL", "url", "extra", "note",
+];
+
+/// The DOI or arXiv ID of an exported item, as an input.
+fn item_id(item: &Value) -> Option<String> {
+    let mut candidates = Vec::new();
+    // Mendeley keeps identifiers in an object of their own.
+    if let Some(ids) = item["identifiers"].as_object() {
+        candidates.extend(ids.get("doi").and_then(Value::as_str).map(str::to_owned));
+        candidates.extend(
+            ids.get("arxiv")
+                .and_then(Value::as_str)
+                .map(|a| format!("arXiv:{}", a)),
+        );
+    }
+    for field in ID_FIELDS {
+        if let Some(value) = item[field].as_str() {
+            candidates.push(if field.eq_ignore_ascii_case("arxiv") {
+                format!("arXiv:{}", value)
+            } else {
+                value.to_owned()
+            });
+        }
+    }
+    candidates
+        .iter()
+        .find_map(|c| identifiers(c).into_iter().next())
+}
+
+/// The identifiers of the items of a JSON export, and the titles of the items that have none.
+pub fn json_ids(export: &Value) -> (Vec<String>, Vec<String>) {
+    let items = match export {
+        Value::Array(items) => items,
+        _ => match ["items", "documents"]
+            .iter()
+            .find_map(|k| export[k].as_array())
+        {
+            Some(items) => items,
+            None => return (Vec::new(), Vec::new()),
+        },
+    };
+    let mut ids = Vec::new();
+    let mut missing = Vec::new();
+    for item in items {
+        match item_id(item) {
+            Some(id) => ids.push(id),
+            None => missing.push(item["title"].as_str().unwrap_or("?").to_owned()),
+        }
+    }
+    (ids, missing)
+}
+
+/// The items of a Zotero RDF export: its outermost `bib:*` elements, leaving out notes
+/// (`bib:Memo`). Attachments are `z:Attachment`s, and journals and series are nested in the items
+/// they contain.
+fn rdf_items(src: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    // The start and name of the item being read, and how deep in elements of that name it is.
+    let mut open: Option<(usize, &str, usize)> = None;
+    for tag in TAG_RE.captures_iter(src) {
+        let whole = tag.get(0).unwrap();
+        let name = tag.get(2).unwrap().as_str();
+        let (closing, empty) = (&tag[1] == "/", &tag[3] == "/");
+        match &mut open {
+            None if !closing && name.starts_with("bib:") && name != "bib:Memo" => {
+                if empty {
+                    items.push(whole.as_str());
+                } else {
+                    open = Some((whole.start(), name, 1));
+                }
+            }
+            Some((start, item, depth)) if name == *item && !empty => {
+                if closing {
+                    *depth -= 1;
+                } else {
+                    *depth += 1;
+                }
+                if *depth == 0 {
+                    items.push(&src[*start..whole.end()]);
+                    open = None;
+                }
+            }
+            _ => {}
+        }
+    }
+    items
+}
+
+/// The DOI or arXiv ID of a Zotero RDF item, from its `rdf:about` or a `dc:identifier`.
+fn rdf_item_id(item: &str) -> Option<String> {
+    let about = ABOUT_RE.captures(item).map(|c| c[1].to_owned());
+    let ids = IDENTIFIER_RE
+        .captures_iter(item)
+        .map(|c| TAG_RE.replace_all(&c[1], " ").into_owned());
+    about
+        .into_iter()
+        .chain(ids)
+        .find_map(|c| identifiers(&text::decode_entities(&c)).into_iter().next())
+}
+
+/// The identifiers of the items of a Zotero RDF export, and the titles of the items that have none.
+pub fn rdf_ids(src: &str) -> (Vec<String>, Vec<String>) {
+    let mut ids = Vec::new();
+    let mut missing = Vec::new();
+    for item in rdf_items(src) {
+        match rdf_item_id(item) {
+            Some(id) => ids.push(id),
+            None => missing.push(
+                TITLE_RE
+                    .captures(item)
+                    .map_or_else(|| "?".to_owned(), |c| text::decode_entities(c[1].trim())),
+            ),
+        }
+    }
+    (ids, missing)
+}
+
+/// Runs `d2b import`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let file = matches.value_of("export").unwrap();
+    let src = std::fs::read_to_string(file).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unreadable", &[("file", &file), ("error", &e)]),
+        )
+        .exit(opts.error_format)
+    });
+    let (inputs, missing) = if src.trim_start().starts_with(['[', '{']) {
+        let export = serde_json::from_str(&src).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Usage,
+                i18n::message("import-invalid", &[("file", &file), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        });
+        json_ids(&export)
+    } else {
+        rdf_ids(&src)
+    };
+    for title in &missing {
+        eprintln!(
+            "{}: {}",
+            i18n::text("warning"),
+            i18n::message("import-no-id", &[("title", title)])
+        );
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let inputs = inputs
+        .into_iter()
+        .filter(|i| seen.insert(input_key(i)))
+        .collect::<Vec<_>>();
+    let mut futures = inputs
+        .iter()
+        .map(|i| resolve(i.clone(), opts.clone()))
+        .collect::<FuturesUnordered<_>>();
+    let mut resolved = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(val) = futures.next().await {
+        match val {
+            Ok(val) => resolved.extend(val),
+            Err(e) => errors.push(e),
+        }
+    }
+    for e in &errors {
+        e.report(opts.error_format);
+    }
+    // Keep the order of the export, so that imports are deterministic.
+    resolved.sort_by_key(|r| inputs.iter().position(|i| *i == r.input));
+    let mut resolved = merge_duplicates(resolved);
+    let mut entries = resolved.iter().map(|r| r.entry.clone()).collect::<Vec<_>>();
+    bibtex::disambiguate_keys(&mut entries);
+    for (r, entry) in resolved.iter_mut().zip(entries) {
+        r.entry = entry;
+    }
+
+    let out = resolved
+        .iter()
+        .map(|r| render(r, &opts))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        + "\n";
+    match matches.value_of("output") {
+        Some(path) => atomic::write(path, &out).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &path), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        }),
+        None => print!("{}", out),
+    }
+    eprintln!(
+        "{}",
+        i18n::message(
+            "import-summary",
+            &[
+                ("entries", &resolved.len()),
+                ("items", &(inputs.len() + missing.len())),
+            ],
+        )
+    );
+    let status = error::status(inputs.len() - errors.len(), &errors);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_ids() {
+        let csl = serde_json::json!([
+            { "title": "A", "DOI": "10.1145/359545.359563" },
+            { "title": "B", "URL": "https://arxiv.org/abs/1712.01815v1" },
+            { "title": "C", "extra": "arXiv: 2101.00001" },
+            { "title": "No ID", "URL": "https://example.com" },
+        ]);
+        assert_eq!(
+            json_ids(&csl),
+            (
+                vec![
+                    "doi:10.1145/359545.359563".to_owned(),
+                    "arxiv:1712.01815v1".to_owned(),
+                    "arxiv:2101.00001".to_owned()
+                ],
+                vec!["No ID".to_owned()]
+            )
+        );
+
+        let mendeley = serde_json::json!({ "documents": [
+            { "title": "D", "identifiers": { "arxiv": "hep-th/9901001" } },
+        ]});
+        assert_eq!(json_ids(&mendeley).0, ["arxiv:hep-th/9901001"]);
+
+        let rdf = r##"<rdf:RDF>
+            <bib:Article rdf:about="https://doi.org/10.1000/abc">
+              <dc:title>Q&amp;A</dc:title><dc:identifier>DOI 10.1000/xyz</dc:identifier>
+              <dcterms:isPartOf><bib:Journal><dc:identifier>ISSN 1234-5678</dc:identifier>
+              </bib:Journal></dcterms:isPartOf>
+              <dcterms:abstract>Extends doi:10.1000/older.</dcterms:abstract>
+            </bib:Article>
+            <z:Attachment><rdf:value>https://arxiv.org/pdf/1712.01815</rdf:value></z:Attachment>
+            <bib:Memo rdf:about="#item_3"><rdf:value>See 10.1000/note</rdf:value></bib:Memo>
+            <bib:Document rdf:about="#item_4">
+              <dc:identifier><dcterms:URI><rdf:value>https://arxiv.org/abs/1712.01815v1</rdf:value>
+              </dcterms:URI></dc:identifier>
+            </bib:Document>
+            <bib:Book rdf:about="urn:isbn:9780262033848"><dc:title>Algorithms</dc:title></bib:Book>
+            </rdf:RDF>"##;
+        assert_eq!(
+            rdf_ids(rdf),
+            (
+                vec![
+                    "doi:10.1000/abc".to_owned(),
+                    "arxiv:1712.01815v1".to_owned()
+                ],
+                vec!["Algorithms".to_owned()]
+            )
+        );
+    }
+}
@@ -0,0 +1,348 @@
+//! The `language` field, and romanization of Cyrillic and CJK author names.
+//!
+//! Romanization is deliberately simple: Cyrillic follows BGN/PCGN, Hangul the Revised
+//! Romanization, kana Hepburn, and Han characters their Mandarin pinyin reading (so Japanese names
+//! written in kanji come out wrong and need fixing by hand).
+
+use crate::bibtex::Entry;
+use crate::Format;
+use pinyin::ToPinyin;
+
+/// Babel language names for the ISO 639-1 codes registries use, since that's what biblatex's
+/// `language` field expects.
+const LANGUAGES: &[(&str, &str)] = &[
+    ("zh", "chinese"),
+    ("cs", "czech"),
+    ("nl", "dutch"),
+    ("en", "english"),
+    ("fr", "french"),
+    ("de", "german"),
+    ("el", "greek"),
+    ("it", "italian"),
+    ("ja", "japanese"),
+    ("ko", "korean"),
+    ("pl", "polish"),
+    ("pt", "portuguese"),
+    ("ru", "russian"),
+    ("es", "spanish"),
+    ("sv", "swedish"),
+    ("tr", "turkish"),
+    ("uk", "ukrainian"),
+];
+
+/// Normalizes a language given as an ISO code (`en`, `en-US`, `eng`) or name to a babel name.
+/// Anything unrecognized is returned as is.
+pub fn language_name(lang: &str) -> String {
+    let lang = lang.trim();
+    let code = lang.split(['-', '_']).next().unwrap();
+    let code = code.to_ascii_lowercase();
+    let code = match code.as_str() {
+        "eng" => "en",
+        "ger" | "deu" => "de",
+        "fre" | "fra" => "fr",
+        "rus" => "ru",
+        "chi" | "zho" => "zh",
+        "jpn" => "ja",
+        "kor" => "ko",
+        "spa" => "es",
+        c => c,
+    };
+    LANGUAGES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| (*name).to_owned())
+        .unwrap_or_else(|| lang.to_owned())
+}
+
+const CYRILLIC: &[(char, &str)] = &[
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('ґ', "g"),
+    ('д', "d"),
+    ('е', "e"),
+    ('ё', "ë"),
+    ('є', "ye"),
+    ('ж', "zh"),
+    ('з', "z"),
+    ('и', "i"),
+    ('і', "i"),
+    ('ї', "yi"),
+    ('й', "y"),
+    ('к', "k"),
+    ('л', "l"),
+    ('м', "m"),
+    ('н', "n"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('у', "u"),
+    ('ў', "w"),
+    ('ф', "f"),
+    ('х', "kh"),
+    ('ц', "ts"),
+    ('ч', "ch"),
+    ('ш', "sh"),
+    ('щ', "shch"),
+    ('ъ', ""),
+    ('ы', "y"),
+    ('ь', ""),
+    ('э', "e"),
+    ('ю', "yu"),
+    ('я', "ya"),
+];
+
+const HANGUL_INITIALS: [&str; 19] = [
+    "g", "kk", "n", "d", "tt", "r", "m", "b", "pp", "s", "ss", "", "j", "jj", "ch", "k", "t", "p",
+    "h",
+];
+const HANGUL_VOWELS: [&str; 21] = [
+    "a", "ae", "ya", "yae", "eo", "e", "yeo", "ye", "o", "wa", "wae", "oe", "yo", "u", "wo", "we",
+    "wi", "yu", "eu", "ui", "i",
+];
+const HANGUL_FINALS: [&str; 28] = [
+    "", "k", "k", "k", "n", "n", "n", "t", "l", "k", "m", "l", "l", "l", "p", "l", "m", "p", "p",
+    "t", "t", "ng", "t", "t", "k", "t", "p", "t",
+];
+
+/// Hepburn romanization of the hiragana block, indexed from U+3041. Katakana are mapped onto
+/// hiragana first. Small kana are handled separately.
+const KANA: [&str; 86] = [
+    "a", "a", "i", "i", "u", "u", "e", "e", "o", "o", "ka", "ga", "ki", "gi", "ku", "gu", "ke",
+    "ge", "ko", "go", "sa", "za", "shi", "ji", "su", "zu", "se", "ze", "so", "zo", "ta", "da",
+    "chi", "ji", "tsu", "tsu", "zu", "te", "de", "to", "do", "na", "ni", "nu", "ne", "no", "ha",
+    "ba", "pa", "hi", "bi", "pi", "fu", "bu", "pu", "he", "be", "pe", "ho", "bo", "po", "ma", "mi",
+    "mu", "me", "mo", "ya", "ya", "yu", "yu", "yo", "yo", "ra", "ri", "ru", "re", "ro", "wa", "wa",
+    "wi", "we", "wo", "n", "vu", "ka", "ke",
+];
+
+fn is_cyrillic(c: char) -> bool {
+    ('\u{400}'..='\u{4ff}').contains(&c)
+}
+
+fn is_hangul(c: char) -> bool {
+    ('\u{ac00}'..='\u{d7a3}').contains(&c)
+}
+
+fn is_kana(c: char) -> bool {
+    ('\u{3041}'..='\u{30fc}').contains(&c)
+}
+
+fn is_han(c: char) -> bool {
+    ('\u{4e00}'..='\u{9fff}').contains(&c) || ('\u{3400}'..='\u{4dbf}').contains(&c)
+}
+
+fn is_cjk(c: char) -> bool {
+    is_hangul(c) || is_kana(c) || is_han(c)
+}
+
+/// Whether `s` contains anything this module knows how to romanize.
+pub fn needs_romanization(s: &str) -> bool {
+    s.chars().any(|c| is_cyrillic(c) || is_cjk(c))
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn romanize_kana(kana: &[char], out: &mut String) {
+    let mut geminate = false;
+    for (i, &c) in kana.iter().enumerate() {
+        // Katakana sit 0x60 above their hiragana counterparts.
+        let c = match c {
+            '\u{30a1}'..='\u{30f6}' => char::from_u32(c as u32 - 0x60).unwrap(),
+            c => c,
+        };
+        let small_y = matches!(
+            kana.get(i + 1),
+            Some('ゃ' | 'ゅ' | 'ょ' | 'ャ' | 'ュ' | 'ョ')
+        );
+        let syllable = match c {
+            'っ' | 'ッ' => {
+                geminate = true;
+                continue;
+            }
+            'ー' => {
+                if let Some(vowel) = out.chars().last() {
+                    out.push(vowel);
+                }
+                continue;
+            }
+            'ゃ' | 'ゅ' | 'ょ' if i > 0 => continue,
+            c => match KANA.get((c as usize).wrapping_sub(0x3041)) {
+                Some(syllable) => (*syllable).to_owned(),
+                None => {
+                    out.push(c);
+                    continue;
+                }
+            },
+        };
+        let syllable = if small_y {
+            let y = match kana[i + 1] {
+                'ゃ' | 'ャ' => "a",
+                'ゅ' | 'ュ' => "u",
+                _ => "o",
+            };
+            let stem = syllable.trim_end_matches('i');
+            if stem.ends_with("sh") || stem.ends_with("ch") || stem == "j" {
+                format!("{}{}", stem, y)
+            } else {
+                format!("{}y{}", stem, y)
+            }
+        } else {
+            syllable
+        };
+        if geminate {
+            let first = if syllable.starts_with("ch") {
+                't'
+            } else {
+                syllable.chars().next().unwrap()
+            };
+            out.push(first);
+            geminate = false;
+        }
+        out.push_str(&syllable);
+    }
+}
+
+/// Romanizes a single word, keeping characters outside the supported scripts.
+pub fn romanize(word: &str) -> String {
+    let chars = word.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_cyrillic(c) {
+            let lower = c.to_lowercase().next().unwrap();
+            let latin = CYRILLIC
+                .iter()
+                .find(|(k, _)| *k == lower)
+                .map_or(String::new(), |(_, v)| (*v).to_owned());
+            if c.is_uppercase() {
+                out.push_str(&capitalize(&latin));
+            } else {
+                out.push_str(&latin);
+            }
+        } else if is_hangul(c) {
+            let s = c as usize - 0xac00;
+            out.push_str(HANGUL_INITIALS[s / (21 * 28)]);
+            out.push_str(HANGUL_VOWELS[s % (21 * 28) / 28]);
+            out.push_str(HANGUL_FINALS[s % 28]);
+        } else if is_kana(c) {
+            let start = i;
+            while i < chars.len() && is_kana(chars[i]) {
+                i += 1;
+            }
+            romanize_kana(&chars[start..i], &mut out);
+            continue;
+        } else if is_han(c) {
+            match c.to_pinyin() {
+                Some(p) => out.push_str(p.plain()),
+                None => out.push(c),
+            }
+        } else {
+            out.push(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+fn romanize_part(part: &str) -> String {
+    part.split(' ')
+        .map(|w| {
+            if w.chars().any(is_cjk) {
+                capitalize(&romanize(w))
+            } else {
+                romanize(w)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Romanizes a single name, given either as `Family, Given` or in natural order. CJK names
+/// written without a separator are taken to start with a one-character family name.
+pub fn romanize_name(name: &str) -> String {
+    let name = name.trim();
+    if let Some((family, given)) = name.split_once(',') {
+        return format!("{}, {}", romanize_part(family), romanize_part(given.trim()));
+    }
+    let chars = name.chars().collect::<Vec<_>>();
+    if chars.len() > 1 && chars.iter().all(|&c| is_han(c) || is_hangul(c)) {
+        let family = chars[..1].iter().collect::<String>();
+        let given = chars[1..].iter().collect::<String>();
+        return format!("{}, {}", romanize_part(&family), romanize_part(&given));
+    }
+    romanize_part(name)
+}
+
+/// Romanizes the `author` and `editor` name lists of an entry, keeping the originals in
+/// `origauthor`/`origeditor` for biblatex, or in `note` for plain BibTeX.
+pub fn romanize_entry(entry: &mut Entry, format: Format) {
+    let mut originals = Vec::new();
+    for field in &["author", "editor"] {
+        let names = match entry.get(field) {
+            Some(names) if needs_romanization(names) => names.to_owned(),
+            _ => continue,
+        };
+        let romanized = names
+            .split(" and ")
+            .map(romanize_name)
+            .collect::<Vec<_>>()
+            .join(" and ");
+        entry.set(field, &romanized);
+        match format {
+            Format::Biblatex => entry.set(&format!("orig{}", field), &names),
+            Format::Bibtex => originals.push(names.replace(" and ", "; ")),
+        }
+    }
+    if !originals.is_empty() {
+        entry.append_note(&format!("Original names: {}", originals.join("; ")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_romanize_name() {
+        assert_eq!(romanize_name("Иванов, Иван Щукин"), "Ivanov, Ivan Shchukin");
+        assert_eq!(romanize_name("王小明"), "Wang, Xiaoming");
+        assert_eq!(romanize_name("김, 민준"), "Gim, Minjun");
+        assert_eq!(romanize_name("さとう, はっとり"), "Satou, Hattori");
+        assert_eq!(romanize_name("Shen, Jeff"), "Shen, Jeff");
+        assert_eq!(language_name("en-US"), "english");
+        assert_eq!(language_name("fra"), "french");
+        assert_eq!(language_name("Esperanto"), "Esperanto");
+    }
+
+    #[test]
+    fn test_romanize_entry() {
+        let mut entry = Entry::new("article", "Ivanov_2020");
+        entry.set("author", "Иванов, Иван and Smith, John");
+        let mut biblatex = entry.clone();
+
+        romanize_entry(&mut entry, Format::Bibtex);
+        assert_eq!(entry.get("author"), Some("Ivanov, Ivan and Smith, John"));
+        assert_eq!(
+            entry.get("note"),
+            Some("Original names: Иванов, Иван; Smith, John")
+        );
+
+        romanize_entry(&mut biblatex, Format::Biblatex);
+        assert_eq!(
+            biblatex.get("origauthor"),
+            Some("Иванов, Иван and Smith, John")
+        );
+        assert!(biblatex.get("note").is_none());
+    }
+}
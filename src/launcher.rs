@@ -0,0 +1,149 @@
+//! `--format alfred` and `--format dmenu`: entries as choices in a launcher. `alfred` writes the
+//! JSON of Alfred's script filters, which Raycast's script commands accept too, with the BibTeX as
+//! what the chosen item copies or pastes; `dmenu` writes one line per entry, for dmenu or `rofi
+//! -dmenu`, ending in a tab and the input it came from, to resolve the chosen one again.
+
+use crate::bibtex::Entry;
+use crate::error::Error;
+use crate::{notes, render, Options, Resolved};
+use serde_json::{json, Value};
+
+/// How many authors the subtitle names before "et al.".
+const AUTHORS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Launcher {
+    Alfred,
+    Dmenu,
+}
+
+impl Launcher {
+    /// The launcher for an output format, if it's one of theirs.
+    pub fn new(format: &str) -> Option<Self> {
+        match format {
+            "alfred" => Some(Launcher::Alfred),
+            "dmenu" => Some(Launcher::Dmenu),
+            _ => None,
+        }
+    }
+
+    /// The choices for the entries, and for Alfred the inputs that failed to resolve.
+    pub fn output(self, resolved: &[Resolved], errors: &[Error], opts: &Options) -> String {
+        match self {
+            Launcher::Alfred => {
+                let mut items = resolved
+                    .iter()
+                    .map(|r| alfred_item(r, opts))
+                    .collect::<Vec<_>>();
+                items.extend(errors.iter().map(|e| {
+                    json!({
+                        "title": e.input.as_deref().unwrap_or_default(),
+                        "subtitle": e.message,
+                        "valid": false,
+                    })
+                }));
+                json!({ "items": items }).to_string()
+            }
+            Launcher::Dmenu => resolved
+                .iter()
+                .map(|r| format!("{} — {}\t{}", title(r), subtitle(&r.entry), r.input.trim()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+fn title(r: &Resolved) -> String {
+    r.entry.get("title").map(notes::plain).unwrap_or_default()
+}
+
+/// The authors, venue and year of an entry.
+pub fn subtitle(entry: &Entry) -> String {
+    let authors = notes::authors(entry);
+    let mut names = authors
+        .iter()
+        .take(AUTHORS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if authors.len() > AUTHORS {
+        names.push_str(" et al.");
+    }
+    vec![
+        Some(names).filter(|n| !n.is_empty()),
+        notes::venue(entry),
+        entry.get("year").map(notes::plain),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join(" · ")
+}
+
+fn alfred_item(r: &Resolved, opts: &Options) -> Value {
+    let bibtex = render(r, opts);
+    let mut item = json!({
+        "uid": r.entry.key,
+        "title": title(r),
+        "subtitle": subtitle(&r.entry),
+        "arg": bibtex,
+        "text": { "copy": bibtex, "largetype": bibtex },
+        "mods": {
+            "cmd": { "arg": r.entry.key, "subtitle": "Copy the key" }
+        },
+    });
+    if let Some(url) = r.entry.get("url") {
+        item["quicklookurl"] = json!(url);
+    }
+    item
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::error::Kind;
+    use crate::provenance::Provenance;
+    use crate::IdType;
+    use clap::ArgMatches;
+
+    #[test]
+    fn test_output() {
+        let mut entry = Entry::new("article", "Hoare_1978");
+        entry.set("author", "Hoare, C. A. R.");
+        entry.set("title", "Communicating {Sequential} Processes");
+        entry.set("journal", "Communications of the ACM");
+        entry.set("year", "1978");
+        entry.set("url", "https://doi.org/10.1145/359576.359585");
+        let resolved = [Resolved {
+            input: "10.1145/359576.359585".to_owned(),
+            resolver: IdType::Doi,
+            source: "doi.org".to_owned(),
+            entry,
+            merged: Vec::new(),
+            provenance: Provenance::default(),
+        }];
+        let errors = [Error::new(Kind::NotFound, "no such DOI").with_input("10.9999/x")];
+        let opts = Options::from_matches(&ArgMatches::default(), &Config::default());
+
+        assert_eq!(
+            Launcher::Dmenu.output(&resolved, &errors, &opts),
+            "Communicating Sequential Processes — C. A. R. Hoare · Communications of the ACM · \
+             1978\t10.1145/359576.359585"
+        );
+        let alfred: Value =
+            serde_json::from_str(&Launcher::Alfred.output(&resolved, &errors, &opts)).unwrap();
+        let items = alfred["items"].as_array().unwrap();
+        assert_eq!(items[0]["uid"], "Hoare_1978");
+        assert!(items[0]["arg"]
+            .as_str()
+            .unwrap()
+            .starts_with("@article{Hoare_1978,"));
+        assert_eq!(
+            items[0]["quicklookurl"],
+            "https://doi.org/10.1145/359576.359585"
+        );
+        assert_eq!(items[1]["title"], "10.9999/x");
+        assert_eq!(items[1]["valid"], false);
+    }
+}
@@ -0,0 +1,13 @@
+//! The parts of d2b that need neither an async runtime nor a network stack: parsing and writing
+//! BibTeX, cleaning up text and scraping citation meta tags. They build for
+//! `wasm32-unknown-unknown` with `--lib --no-default-features`, so that a browser extension or web
+//! playground can reuse them, fetching through its own [`fetch::Fetch`] implementation. The
+//! `ffi` feature adds a C ABI on top.
+
+pub mod bibfile;
+pub mod bibtex;
+pub mod fetch;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod html;
+pub mod text;
@@ -0,0 +1,559 @@
+//! A local SQLite library of every entry resolved through `d2b lib add`, with tags and timestamps,
+//! from which `.bib` files can be regenerated, and an Atom feed of the latest additions published.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{self, Error, ErrorFormat, Kind};
+use crate::{atomic, i18n, merge_duplicates, notes, resolve, work_id, Options, Resolved};
+use atom_syndication::{Content, Feed, FixedDateTime, Link, Person};
+use chrono::TimeZone;
+use clap::{Arg, ArgMatches};
+use futures::{stream::FuturesUnordered, StreamExt};
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::{Path, PathBuf};
+
+const SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS entries (
+        id INTEGER PRIMARY KEY,
+        key TEXT NOT NULL,
+        work TEXT UNIQUE,
+        input TEXT NOT NULL,
+        bibtex TEXT NOT NULL,
+        added TEXT NOT NULL,
+        updated TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS tags (
+        entry INTEGER NOT NULL REFERENCES entries(id) ON DELETE CASCADE,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (entry, tag)
+    );
+";
+
+/// An entry stored in the library.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub key: String,
+    pub entry: Entry,
+    pub tags: Vec<String>,
+    pub added: String,
+}
+
+pub struct Library {
+    conn: Connection,
+}
+
+/// `$D2B_LIBRARY`, or `library.sqlite` in the platform's data directory.
+pub fn default_path() -> PathBuf {
+    if let Some(path) = std::env::var_os("D2B_LIBRARY") {
+        return PathBuf::from(path);
+    }
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    data.join("d2b").join("library.sqlite")
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+impl Library {
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        if let Some(dir) = path.parent() {
+            // A missing directory surfaces as an error from SQLite below.
+            let _ = std::fs::create_dir_all(dir);
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Library { conn })
+    }
+
+    /// Adds an entry, or updates the stored one for the same work (keeping when it was first
+    /// added), and tags it. An entry without a DOI or URL to tell its work by is taken to be the
+    /// stored one without either that was added from the same input, or else has the same key.
+    pub fn add(&self, input: &str, entry: &Entry, tags: &[&str]) -> rusqlite::Result<()> {
+        let time = now();
+        let work = work_id(entry);
+        let existing = match &work {
+            Some(work) => self
+                .conn
+                .query_row(
+                    "SELECT id FROM entries WHERE work = ?1",
+                    params![work],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?,
+            None => self
+                .conn
+                .query_row(
+                    "SELECT id FROM entries WHERE work IS NULL AND (input = ?1 OR key = ?2)
+                     ORDER BY input = ?1 DESC, id LIMIT 1",
+                    params![input, entry.key],
+                    |row| row.get::<_, i64>(0),
+                )
+                .optional()?,
+        };
+        let id = match existing {
+            Some(id) => {
+                self.conn.execute(
+                    "UPDATE entries SET key = ?1, input = ?2, bibtex = ?3, updated = ?4 WHERE id = ?5",
+                    params![entry.key, input, entry.to_string(), time, id],
+                )?;
+                id
+            }
+            None => {
+                self.conn.execute(
+                    "INSERT INTO entries (key, work, input, bibtex, added, updated)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                    params![entry.key, work, input, entry.to_string(), time],
+                )?;
+                self.conn.last_insert_rowid()
+            }
+        };
+        for tag in tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tags (entry, tag) VALUES (?1, ?2)",
+                params![id, tag],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Every stored entry in the order they were added, or only those with any of `tags`.
+    pub fn list(&self, tags: &[&str]) -> rusqlite::Result<Vec<Record>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT e.id, e.key, e.bibtex, e.added FROM entries e ORDER BY e.added, e.id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut entry_tags = self
+            .conn
+            .prepare("SELECT tag FROM tags WHERE entry = ?1 ORDER BY tag")?;
+        let mut records = Vec::new();
+        for (id, key, src, added) in rows {
+            // Entries are stored as d2b printed them, so they always parse.
+            let entry = match bibtex::parse_entry(&src) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let record_tags = entry_tags
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            if !tags.is_empty() && !record_tags.iter().any(|t| tags.contains(&t.as_str())) {
+                continue;
+            }
+            records.push(Record {
+                key,
+                entry,
+                tags: record_tags,
+                added,
+            });
+        }
+        Ok(records)
+    }
+
+    /// Tags the entries with the given key, returning whether there were any.
+    pub fn tag(&self, key: &str, tags: &[&str]) -> rusqlite::Result<bool> {
+        for tag in tags {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO tags (entry, tag) SELECT id, ?2 FROM entries WHERE key = ?1",
+                params![key, tag],
+            )?;
+        }
+        self.contains(key)
+    }
+
+    /// Removes tags from the entries with the given key, returning whether there were any.
+    pub fn untag(&self, key: &str, tags: &[&str]) -> rusqlite::Result<bool> {
+        for tag in tags {
+            self.conn.execute(
+                "DELETE FROM tags WHERE tag = ?2 AND entry IN (SELECT id FROM entries WHERE key = ?1)",
+                params![key, tag],
+            )?;
+        }
+        self.contains(key)
+    }
+
+    fn contains(&self, key: &str) -> rusqlite::Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM entries WHERE key = ?1)",
+            params![key],
+            |row| row.get(0),
+        )
+    }
+
+    /// Every tag in use, with the number of entries that have it.
+    pub fn tags(&self) -> rusqlite::Result<Vec<(String, usize)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT tag, COUNT(*) FROM tags GROUP BY tag ORDER BY tag")?;
+        let tags = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))?
+            .collect();
+        tags
+    }
+
+    /// Removes the entries with the given key, returning how many there were.
+    pub fn remove(&self, key: &str) -> rusqlite::Result<usize> {
+        self.conn
+            .execute("DELETE FROM entries WHERE key = ?1", params![key])
+    }
+}
+
+/// The entries of `records` as a `.bib` file. Different works can be stored under the same key,
+/// so those get `a`, `b`, ... suffixes.
+pub fn export(records: &[Record]) -> String {
+    let mut entries = records.iter().map(|r| r.entry.clone()).collect::<Vec<_>>();
+    bibtex::disambiguate_keys(&mut entries);
+    entries
+        .iter()
+        .map(Entry::to_string)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// How many of the latest entries `d2b lib feed` includes by default.
+pub const FEED_LENGTH: usize = 20;
+
+/// When an entry was added, as stored: local time, without an offset.
+fn added_time(added: &str) -> FixedDateTime {
+    chrono::NaiveDateTime::parse_from_str(added, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|t| chrono::Local.from_local_datetime(&t).earliest())
+        .unwrap_or_else(chrono::Local::now)
+        .into()
+}
+
+/// Where an entry can be read: its DOI, or its URL.
+fn entry_link(entry: &Entry) -> Option<String> {
+    entry
+        .get("doi")
+        .map(|doi| format!("https://doi.org/{}", doi))
+        .or_else(|| entry.get("url").map(str::to_owned))
+}
+
+/// An Atom feed of `records`, newest first, with each entry's BibTeX as its content. `link` is
+/// where the feed will be published, if anywhere.
+pub fn feed(records: &[Record], title: &str, link: Option<&str>) -> Feed {
+    let mut feed = Feed::default();
+    feed.set_title(title.to_owned());
+    feed.set_id(link.unwrap_or("urn:d2b:library"));
+    if let Some(link) = link {
+        let mut self_link = Link::default();
+        self_link.set_href(link);
+        self_link.set_rel("self");
+        feed.set_links(vec![self_link]);
+    }
+    let entries = records
+        .iter()
+        .rev()
+        .map(|record| {
+            let entry = &record.entry;
+            let mut item = atom_syndication::Entry::default();
+            item.set_title(notes::plain(entry.get("title").unwrap_or(&record.key)));
+            let link = entry_link(entry);
+            item.set_id(
+                link.clone()
+                    .unwrap_or_else(|| format!("urn:d2b:{}", record.key)),
+            );
+            if let Some(link) = link {
+                let mut alternate = Link::default();
+                alternate.set_href(link);
+                item.set_links(vec![alternate]);
+            }
+            item.set_authors(
+                entry
+                    .get("author")
+                    .into_iter()
+                    .flat_map(|a| a.split(" and "))
+                    .map(|name| {
+                        let mut person = Person::default();
+                        person.set_name(notes::plain(name.trim()));
+                        person
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            item.set_updated(added_time(&record.added));
+            let mut content = Content::default();
+            content.set_content_type("text".to_owned());
+            content.set_value(entry.to_string());
+            item.set_content(content);
+            item
+        })
+        .collect::<Vec<_>>();
+    feed.set_updated(
+        entries
+            .first()
+            .map(|e| *e.updated())
+            .unwrap_or_else(|| chrono::Local::now().into()),
+    );
+    feed.set_entries(entries);
+    feed
+}
+
+fn fail(e: impl std::fmt::Display, format: ErrorFormat) -> ! {
+    Error::new(Kind::Io, i18n::message("library-error", &[("error", &e)])).exit(format)
+}
+
+/// The `--db` argument, shared by every command that uses the library.
+pub fn db_arg() -> Arg<'static, 'static> {
+    Arg::with_name("db")
+        .long("db")
+        .takes_value(true)
+        .help(i18n::text("help-db"))
+}
+
+/// A repeatable `--tag` argument.
+pub fn tag_arg(help: &'static str) -> Arg<'static, 'static> {
+    Arg::with_name("tag")
+        .short("t")
+        .long("tag")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help(help)
+}
+
+/// The library `--db` names, or the default one, exiting if it can't be opened.
+pub fn open(matches: &ArgMatches, format: ErrorFormat) -> Library {
+    let path = matches
+        .value_of("db")
+        .map(PathBuf::from)
+        .unwrap_or_else(default_path);
+    Library::open(&path).unwrap_or_else(|e| fail(e, format))
+}
+
+fn tags<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    matches.values_of("tag").into_iter().flatten().collect()
+}
+
+/// Adds resolved entries to the library, with `tags`.
+pub fn store(matches: &ArgMatches, resolved: &[Resolved], tags: &[&str], opts: &Options) {
+    let lib = open(matches, opts.error_format);
+    for r in resolved {
+        lib.add(&r.input, &r.entry, tags)
+            .unwrap_or_else(|e| fail(e, opts.error_format));
+    }
+}
+
+/// Runs `d2b lib ...`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let format = opts.error_format;
+    let lib = open(matches, format);
+
+    match matches.subcommand() {
+        ("add", Some(sub)) => {
+            let mut futures = sub
+                .values_of("input")
+                .unwrap()
+                .map(|p| resolve(p.to_owned(), opts.clone()))
+                .collect::<FuturesUnordered<_>>();
+            let mut resolved = Vec::new();
+            let mut errors = Vec::new();
+            while let Some(val) = futures.next().await {
+                match val {
+                    Ok(val) => resolved.extend(val),
+                    Err(e) => errors.push(e),
+                }
+            }
+            for e in &errors {
+                e.report(format);
+            }
+            for r in merge_duplicates(resolved) {
+                lib.add(&r.input, &r.entry, &tags(sub))
+                    .unwrap_or_else(|e| fail(e, format));
+                eprintln!(
+                    "{}",
+                    i18n::message("library-added", &[("key", &r.entry.key)])
+                );
+            }
+            let status = error::status(
+                sub.values_of("input").unwrap().len() - errors.len(),
+                &errors,
+            );
+            if status != error::OK {
+                std::process::exit(status);
+            }
+        }
+        ("ls", Some(sub)) => {
+            for record in lib.list(&tags(sub)).unwrap_or_else(|e| fail(e, format)) {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    record.key,
+                    record.entry.get("year").unwrap_or(""),
+                    record.entry.get("title").unwrap_or(""),
+                    record.tags.join(","),
+                    record.added,
+                );
+            }
+        }
+        ("rm", Some(sub)) => {
+            for key in sub.values_of("key").unwrap() {
+                if lib.remove(key).unwrap_or_else(|e| fail(e, format)) == 0 {
+                    eprintln!(
+                        "{}: {}",
+                        i18n::text("warning"),
+                        i18n::message("no-entry-with-key", &[("key", &key)])
+                    );
+                }
+            }
+        }
+        ("tag", Some(sub)) | ("untag", Some(sub)) => {
+            let key = sub.value_of("key").unwrap();
+            let tags = sub.values_of("tags").unwrap().collect::<Vec<_>>();
+            let found = if matches.subcommand_name() == Some("tag") {
+                lib.tag(key, &tags)
+            } else {
+                lib.untag(key, &tags)
+            };
+            if !found.unwrap_or_else(|e| fail(e, format)) {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("no-entry-with-key", &[("key", &key)])
+                );
+            }
+        }
+        ("tags", Some(_)) => {
+            for (tag, count) in lib.tags().unwrap_or_else(|e| fail(e, format)) {
+                println!("{}\t{}", tag, count);
+            }
+        }
+        ("export", Some(sub)) => {
+            let records = lib.list(&tags(sub)).unwrap_or_else(|e| fail(e, format));
+            let bib = export(&records);
+            match sub.value_of("output") {
+                Some(file) => atomic::write(file, bib + "\n").unwrap_or_else(|e| fail(e, format)),
+                None if bib.is_empty() => {}
+                None => println!("{}", bib),
+            }
+        }
+        ("feed", Some(sub)) => {
+            let length = sub
+                .value_of("limit")
+                .map(|n| {
+                    n.parse().ok().filter(|&n| n > 0).unwrap_or_else(|| {
+                        Error::new(Kind::Usage, i18n::text("invalid-limit")).exit(format)
+                    })
+                })
+                .unwrap_or(FEED_LENGTH);
+            let records = lib.list(&tags(sub)).unwrap_or_else(|e| fail(e, format));
+            let latest = &records[records.len().saturating_sub(length)..];
+            let title = sub
+                .value_of("title")
+                .unwrap_or_else(|| i18n::text("library-feed-title"));
+            let xml = feed(latest, title, sub.value_of("link")).to_string();
+            match sub.value_of("output") {
+                Some(file) => atomic::write(file, xml + "\n").unwrap_or_else(|e| fail(e, format)),
+                None => println!("{}", xml),
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, doi: &str) -> Entry {
+        let mut entry = Entry::new("article", key);
+        entry.set("title", "A Title");
+        entry.set("doi", doi);
+        entry
+    }
+
+    #[test]
+    fn test_library() {
+        let lib = Library::open_in_memory().unwrap();
+        lib.add("10.1000/a", &entry("Shen_2021", "10.1000/a"), &["thesis"])
+            .unwrap();
+        lib.add("10.1000/b", &entry("Doe_2020", "10.1000/b"), &[])
+            .unwrap();
+        // The same work again updates the stored entry rather than duplicating it.
+        lib.add("10.1000/A", &entry("Shen_2021b", "10.1000/A"), &["review"])
+            .unwrap();
+
+        let all = lib.list(&[]).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0].key, "Shen_2021b");
+        assert_eq!(all[0].tags, ["review", "thesis"]);
+        assert_eq!(all[0].entry.get("title"), Some("A Title"));
+
+        let tagged = lib.list(&["thesis"]).unwrap();
+        assert_eq!(tagged.len(), 1);
+
+        assert!(lib.tag("Doe_2020", &["ml", "thesis"]).unwrap());
+        assert!(!lib.tag("Nobody_1900", &["ml"]).unwrap());
+        assert_eq!(lib.list(&["thesis", "review"]).unwrap().len(), 2);
+        assert!(lib.untag("Doe_2020", &["thesis"]).unwrap());
+        assert_eq!(
+            lib.tags().unwrap(),
+            [
+                ("ml".to_owned(), 1),
+                ("review".to_owned(), 1),
+                ("thesis".to_owned(), 1)
+            ]
+        );
+
+        let xml = feed(
+            &lib.list(&[]).unwrap(),
+            "Lab",
+            Some("https://lab.example/refs.xml"),
+        )
+        .to_string();
+        let parsed = xml.parse::<Feed>().unwrap();
+        assert_eq!(parsed.id(), "https://lab.example/refs.xml");
+        let ids = parsed.entries().iter().map(|e| e.id()).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            ["https://doi.org/10.1000/b", "https://doi.org/10.1000/A"]
+        );
+        assert!(parsed.entries()[0]
+            .content()
+            .and_then(|c| c.value())
+            .unwrap()
+            .starts_with("@article{Doe_2020,"));
+
+        assert_eq!(lib.remove("Doe_2020").unwrap(), 1);
+        assert_eq!(lib.remove("Doe_2020").unwrap(), 0);
+        assert_eq!(lib.list(&[]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_export() {
+        let lib = Library::open_in_memory().unwrap();
+        let mut book = Entry::new("book", "Doe_2020");
+        book.set("title", "A Book");
+        // Without a DOI or URL, the same input or key is the same entry.
+        lib.add("isbn:9780262033848", &book, &[]).unwrap();
+        lib.add("isbn:9780262033848", &book, &["ml"]).unwrap();
+        lib.add("9780262033848", &book, &[]).unwrap();
+        assert_eq!(lib.list(&[]).unwrap().len(), 1);
+
+        lib.add("10.1000/a", &entry("Doe_2020", "10.1000/a"), &[])
+            .unwrap();
+        let bib = export(&lib.list(&[]).unwrap());
+        assert!(bib.starts_with("@book{Doe_2020a,"));
+        assert!(bib.contains("@article{Doe_2020b,"));
+    }
+}
@@ -0,0 +1,134 @@
+//! `d2b list`: BibTeX for everything a journal or funder published in a window of dates, paged
+//! from CrossRef, for reading groups and journal-club archives.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{Error, Kind};
+use crate::provenance::Provenance;
+use crate::{crossref, finish, i18n, render, thesis, IdType, Options, Resolved};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    /// The dates CrossRef's `from-pub-date` and `until-pub-date` filters take.
+    static ref DATE_RE: Regex = Regex::new(r"^\d{4}(?:-\d{2}(?:-\d{2})?)?$").unwrap();
+}
+
+/// The CrossRef filter for works published between `from` and `until`, both inclusive.
+pub fn date_filter(from: Option<&str>, until: Option<&str>) -> Result<String, String> {
+    let mut filter = Vec::new();
+    for (name, date) in [("from-pub-date", from), ("until-pub-date", until)] {
+        if let Some(date) = date {
+            if !DATE_RE.is_match(date) {
+                return Err(date.to_owned());
+            }
+            filter.push(format!("{}:{}", name, date));
+        }
+    }
+    Ok(filter.join(","))
+}
+
+/// Builds an entry for any CrossRef work, the way `--resolver crossref` does.
+pub fn work_entry(work: &Value) -> Option<Entry> {
+    let key = crossref::make_key(work);
+    crossref::book_entry(work, &key)
+        .or_else(|| crossref::paper_entry(work))
+        .or_else(|| thesis::from_crossref(work, &key))
+        .or_else(|| crossref::article_entry(work))
+}
+
+/// The CrossRef listing of the works of a journal or funder. The ID goes in as one path segment,
+/// so that the `/` of a funder DOI such as `10.13039/100000001` is escaped.
+fn works_url(collection: &str, id: &str) -> reqwest::Url {
+    let mut url = reqwest::Url::parse("https://api.crossref.org/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend([collection, id, "works"]);
+    url
+}
+
+/// Runs `d2b list`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let (collection, id, input) = match (matches.value_of("journal"), matches.value_of("funder")) {
+        (Some(issn), _) => ("journals", issn.trim(), format!("journal:{}", issn.trim())),
+        // Clap requires one of the two.
+        (None, funder) => {
+            let funder = funder.unwrap().trim();
+            ("funders", funder, format!("funder:{}", funder))
+        }
+    };
+    let url = works_url(collection, id);
+    let filter =
+        date_filter(matches.value_of("from"), matches.value_of("until")).unwrap_or_else(|date| {
+            Error::new(
+                Kind::Usage,
+                i18n::message("invalid-date", &[("arg", &date)]),
+            )
+            .exit(opts.error_format)
+        });
+    let limit = matches.value_of("limit").map(|limit| {
+        limit.parse().ok().filter(|&l| l > 0).unwrap_or_else(|| {
+            Error::new(Kind::Usage, i18n::text("invalid-limit")).exit(opts.error_format)
+        })
+    });
+
+    let mut entries = crossref::works(url.as_str(), &filter, limit)
+        .await
+        .unwrap_or_else(|e| e.with_input(&input).exit(opts.error_format))
+        .iter()
+        .filter_map(work_entry)
+        .collect::<Vec<_>>();
+    // A journal issue easily has several papers by authors with the same family name.
+    bibtex::disambiguate_keys(&mut entries);
+
+    for entry in entries {
+        let mut provenance = Provenance::default();
+        match finish(entry, IdType::Doi, &input, &opts, &mut provenance).await {
+            Ok(entry) => {
+                let resolved = Resolved {
+                    input: input.clone(),
+                    resolver: IdType::Doi,
+                    source: "api.crossref.org".to_owned(),
+                    entry,
+                    merged: Vec::new(),
+                    provenance,
+                };
+                println!("{}", render(&resolved, &opts));
+            }
+            Err(e) => e.report(opts.error_format),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_filter() {
+        assert_eq!(
+            date_filter(Some("2024-01"), Some("2024-03-31")).unwrap(),
+            "from-pub-date:2024-01,until-pub-date:2024-03-31"
+        );
+        assert_eq!(
+            date_filter(None, Some("2024")).unwrap(),
+            "until-pub-date:2024"
+        );
+        assert_eq!(date_filter(None, None).unwrap(), "");
+        assert_eq!(date_filter(Some("Jan 2024"), None).unwrap_err(), "Jan 2024");
+    }
+
+    #[test]
+    fn test_works_url() {
+        assert_eq!(
+            works_url("funders", "10.13039/100000001").as_str(),
+            "https://api.crossref.org/funders/10.13039%2F100000001/works"
+        );
+        assert_eq!(
+            works_url("journals", "0028-0836").as_str(),
+            "https://api.crossref.org/journals/0028-0836/works"
+        );
+    }
+}
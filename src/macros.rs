@@ -0,0 +1,113 @@
+//! `--journal-strings`: writes journal names as the `@string` macros of a style's abbreviation
+//! file (`journal = apj` rather than `journal = {The Astrophysical Journal}`), so that the journal
+//! is abbreviated, or not, the way the style wants it.
+//!
+//! The macros are those of AASTeX (`aas`) or IEEEtran (`ieee`), or the `@string`s of any `.bib`
+//! file. The definitions aren't written out: the style's own file, or the one given, goes
+//! alongside the output in `\bibliography`.
+
+use crate::i18n;
+use d2b::bibfile::Bibliography;
+use d2b::bibtex::{Entry, Value};
+use std::collections::HashMap;
+
+/// The fields that hold a journal's name.
+const JOURNAL_FIELDS: &[&str] = &["journal", "journaltitle"];
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("aas", include_str!("../strings/aas.bib")),
+    ("ieee", include_str!("../strings/ieee.bib")),
+];
+
+/// A journal name reduced to lowercase letters and digits, without a leading "The" and with "&"
+/// read as "and", so that CrossRef's spelling matches the macro file's.
+fn normalize(name: &str) -> String {
+    let name = crate::notes::plain(name)
+        .to_lowercase()
+        .replace("&amp;", " and ")
+        .replace('&', " and ");
+    let name = name.trim();
+    name.strip_prefix("the ")
+        .unwrap_or(name)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+#[derive(Debug, Default)]
+pub struct JournalMacros {
+    /// Macro names by normalized journal name.
+    names: HashMap<String, String>,
+}
+
+impl JournalMacros {
+    /// The macros of a `.bib` file's `@string`s.
+    pub fn parse(src: &str) -> Self {
+        let bib = Bibliography::parse(src);
+        let strings = bib.strings();
+        let names = bib
+            .items()
+            .filter_map(|item| match item {
+                d2b::bibfile::Item::String(name, _) => {
+                    Some((normalize(strings.get(&name.to_lowercase())?), name.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        JournalMacros { names }
+    }
+
+    /// The built-in macros of a style, or those of a file.
+    pub fn load(name: &str) -> Result<Self, String> {
+        if let Some((_, src)) = BUILTIN.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) {
+            return Ok(Self::parse(src));
+        }
+        std::fs::read_to_string(name)
+            .map(|src| Self::parse(&src))
+            .map_err(|e| i18n::message("file-unreadable", &[("file", &name), ("error", &e)]))
+    }
+
+    /// Replaces the journal name of an entry with its macro, if there is one.
+    pub fn apply(&self, entry: &mut Entry) {
+        for (name, value) in &mut entry.fields {
+            if !JOURNAL_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(name)) {
+                continue;
+            }
+            if let Some(macro_name) = self.names.get(&normalize(value.as_str())) {
+                *value = Value::Raw(macro_name.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let aas = JournalMacros::load("aas").unwrap();
+        let mut entry = Entry::new("article", "a");
+        entry.set("journal", "Astronomy &amp; Astrophysics");
+        entry.set("title", "The Astrophysical Journal");
+        aas.apply(&mut entry);
+        assert_eq!(entry.fields[0].1, Value::Raw("aap".to_owned()));
+        assert_eq!(entry.get("title"), Some("The Astrophysical Journal"));
+
+        let custom = JournalMacros::parse(
+            "@string{pub = {Journal of }}\n@string{jss = pub # {Statistical Software}}",
+        );
+        let mut entry = Entry::new("article", "b");
+        entry.set("journal", "journal of statistical software");
+        custom.apply(&mut entry);
+        assert_eq!(entry.to_string(), "@article{b,\n  journal=jss\n}");
+
+        let mut entry = Entry::new("article", "c");
+        entry.set("journal", "The Astrophysical Journal Letters");
+        JournalMacros::load("ieee").unwrap().apply(&mut entry);
+        assert_eq!(
+            entry.get("journal"),
+            Some("The Astrophysical Journal Letters")
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! `d2b mail`: turns alert emails (arXiv listings, journal tables of contents, ...) into a reading
+//! list, by collecting every DOI and arXiv ID in an mbox file or Maildir and appending the entries
+//! a `.bib` file doesn't have yet, as `d2b sync` would.
+
+use crate::error::{Error, Kind};
+use crate::{diff, i18n, input_id, sync, Options, DOI_RE};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::path::Path;
+
+lazy_static! {
+    /// The line that starts each message of an mbox file.
+    static ref FROM_LINE_RE: Regex = Regex::new(r"(?m)^From .*\r?\n").unwrap();
+    static ref BOUNDARY_RE: Regex = Regex::new(r#"(?i)boundary="?([^";\s]+)"?"#).unwrap();
+    static ref ARXIV_MAIL_RE: Regex = Regex::new(
+        r"(?i)(?:\barxiv:\s*|arxiv\.org/(?:abs|pdf)/)(\d{4}\.\d{4,5}(?:v\d+)?|[a-z-]+/\d{7}(?:v\d+)?)"
+    )
+    .unwrap();
+}
+
+/// The messages of an mbox file.
+pub fn mbox_messages(src: &str) -> Vec<&str> {
+    FROM_LINE_RE
+        .split(src)
+        .filter(|m| !m.trim().is_empty())
+        .collect()
+}
+
+/// The messages of a Maildir, from both `new` and `cur`, or of an mbox file.
+fn read_mailbox(path: &Path) -> std::io::Result<Vec<String>> {
+    if !path.is_dir() {
+        let src = String::from_utf8_lossy(&std::fs::read(path)?).into_owned();
+        return Ok(mbox_messages(&src).into_iter().map(str::to_owned).collect());
+    }
+    let mut files = Vec::new();
+    for dir in ["new", "cur"] {
+        let dir = path.join(dir);
+        if dir.is_dir() {
+            for file in std::fs::read_dir(dir)? {
+                files.push(file?.path());
+            }
+        }
+    }
+    files.sort();
+    files
+        .iter()
+        .map(|f| Ok(String::from_utf8_lossy(&std::fs::read(f)?).into_owned()))
+        .collect()
+}
+
+/// A header of a message or MIME part, unfolded.
+fn header(headers: &str, name: &str) -> Option<String> {
+    let mut value: Option<String> = None;
+    for line in headers.lines() {
+        if let Some(value) = value.as_mut() {
+            if line.starts_with([' ', '\t']) {
+                value.push(' ');
+                value.push_str(line.trim());
+                continue;
+            }
+            break;
+        }
+        if let Some((n, v)) = line.split_once(':') {
+            if n.trim().eq_ignore_ascii_case(name) {
+                value = Some(v.trim().to_owned());
+            }
+        }
+    }
+    value
+}
+
+fn decode_quoted_printable(s: &str) -> String {
+    let s = s.replace("=\r\n", "").replace("=\n", "");
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'=', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn decode_base64(s: &str) -> String {
+    let mut out = Vec::new();
+    let mut buf = 0u32;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => continue,
+        };
+        buf = buf << 6 | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The decoded text of a message or MIME part, with the text of every part of a multipart one.
+/// Attachments other than text are skipped.
+pub fn message_text(message: &str) -> String {
+    let message = message.replace("\r\n", "\n");
+    let (headers, body) = message.split_once("\n\n").unwrap_or((&message, ""));
+    let content_type = header(headers, "content-type").unwrap_or_default();
+    if content_type.to_lowercase().starts_with("multipart/") {
+        let boundary = match BOUNDARY_RE.captures(&content_type) {
+            Some(c) => format!("--{}", &c[1]),
+            None => return body.to_owned(),
+        };
+        return body
+            .split(boundary.as_str())
+            .skip(1)
+            .filter(|part| !part.starts_with("--"))
+            .map(|part| message_text(part.trim_start_matches('\n')))
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    if !content_type.is_empty() && !content_type.to_lowercase().starts_with("text/") {
+        return String::new();
+    }
+    let encoding = header(headers, "content-transfer-encoding").unwrap_or_default();
+    let body = match encoding.to_lowercase().as_str() {
+        "quoted-printable" => decode_quoted_printable(body),
+        "base64" => decode_base64(body),
+        _ => body.to_owned(),
+    };
+    format!(
+        "{}\n{}",
+        header(headers, "subject").unwrap_or_default(),
+        body
+    )
+}
+
+/// The DOIs and arXiv IDs in a text, as inputs, each once, in the order they first appear.
+pub fn identifiers(text: &str) -> Vec<String> {
+    let dois = DOI_RE[0].find_iter(text).map(|m| {
+        // In emails a DOI is usually in a link, which ends at the query, or in running text.
+        let doi = m
+            .as_str()
+            .split(['?', '&', '#', '"', '<', '>'])
+            .next()
+            .unwrap();
+        (
+            m.start(),
+            format!(
+                "doi:{}",
+                doi.trim_end_matches(['.', ',', ';', ':', ')', ']', '\''])
+            ),
+        )
+    });
+    let arxiv = ARXIV_MAIL_RE
+        .captures_iter(text)
+        .map(|c| (c.get(0).unwrap().start(), format!("arxiv:{}", &c[1])));
+    let mut found = dois.chain(arxiv).collect::<Vec<_>>();
+    found.sort_by_key(|(start, _)| *start);
+
+    let mut ids = Vec::new();
+    let mut inputs = Vec::new();
+    for (_, input) in found {
+        // Listings link each paper more than once, e.g. to its abstract and its PDF.
+        let id = match input_id(&input) {
+            Some(id) => id,
+            None => continue,
+        };
+        if !ids.contains(&id) {
+            ids.push(id);
+            inputs.push(input);
+        }
+    }
+    inputs
+}
+
+/// Runs `d2b mail`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let mailbox = matches.value_of("mailbox").unwrap();
+    let messages = read_mailbox(Path::new(mailbox)).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unreadable", &[("file", &mailbox), ("error", &e)]),
+        )
+        .exit(opts.error_format)
+    });
+    let text = messages
+        .iter()
+        .map(|m| message_text(m))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let inputs = identifiers(&text);
+    eprintln!(
+        "{}",
+        i18n::message(
+            "mail-found",
+            &[("ids", &inputs.len()), ("messages", &messages.len())]
+        )
+    );
+    let bib = matches.value_of("bib").unwrap();
+    let preview = diff::Preview::from_matches(matches);
+    sync::update(
+        bib,
+        &inputs,
+        false,
+        sync::on_conflict(matches),
+        preview,
+        opts,
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mbox() {
+        let mbox = "From no-reply@arxiv.org Mon Jan  1 00:00:00 2024\n\
+                    Subject: cs daily\n\n\
+                    arXiv:2101.00001\nTitle: A\n\\\\ ( https://arxiv.org/abs/2101.00001 )\n\
+                    arXiv:hep-th/9901001v2\n\n\
+                    From alerts@nature.com Mon Jan  1 00:00:00 2024\n\
+                    Subject: Nature TOC\n\
+                    Content-Type: multipart/alternative;\n boundary=\"XYZ\"\n\n\
+                    --XYZ\nContent-Type: text/plain; charset=utf-8\n\
+                    Content-Transfer-Encoding: quoted-printable\n\n\
+                    Read https://doi.org/10.1038/s41586-021-03819-=\n2?utm_source=3Dtoc.\n\
+                    --XYZ\nContent-Type: text/html\nContent-Transfer-Encoding: base64\n\n\
+                    PGEgaHJlZj0iaHR0cHM6Ly9kb2kub3JnLzEwLjExNDUvMzU5NTQ1LjM1OTU2MyI+PC9hPg==\n\
+                    --XYZ\nContent-Type: image/png\n\n10.9999/not-text\n--XYZ--\n";
+        let messages = mbox_messages(mbox);
+        assert_eq!(messages.len(), 2);
+        let text = messages
+            .iter()
+            .map(|m| message_text(m))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(
+            identifiers(&text),
+            [
+                "arxiv:2101.00001",
+                "arxiv:hep-th/9901001v2",
+                "doi:10.1038/s41586-021-03819-2",
+                "doi:10.1145/359545.359563",
+            ]
+        );
+    }
+}
@@ -1,17 +1,100 @@
 use arrayvec::ArrayVec;
 use async_recursion::async_recursion;
-use atom_syndication::Feed;
-use chrono::Datelike;
-use clap::{crate_authors, crate_description, crate_name, crate_version, Arg, Error, ErrorKind};
+use clap::{
+    crate_authors, crate_description, crate_name, crate_version, AppSettings, Arg, ArgGroup,
+    ArgMatches, ErrorKind, SubCommand,
+};
+use config::Config;
+use d2b::bibtex::Entry;
+use d2b::{bibtex, fetch, html, text};
+use error::{ErrorFormat, Kind};
 use futures::{stream::FuturesUnordered, StreamExt};
+use http::SendWithRetry;
 use indicatif::ProgressBar;
 use lazy_static::lazy_static;
+use provenance::{Provenance, D2B};
 use regex::Regex;
 use reqwest::{
     header::ACCEPT,
     {Client, Response},
 };
+use resolvers::Fetched;
 use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+mod archive;
+mod atomic;
+mod auth;
+mod catalogs;
+mod checkpoint;
+mod clipboard;
+mod color;
+mod complete;
+mod config;
+mod convert;
+mod crossref;
+mod csl;
+mod datacite;
+mod diff;
+mod digest;
+mod enrich;
+mod error;
+mod europepmc;
+mod feed;
+mod fixtures;
+mod graph;
+mod hook;
+mod http;
+mod i18n;
+mod ids;
+mod import;
+mod language;
+mod launcher;
+mod library;
+mod list;
+mod macros;
+mod mail;
+mod mcp;
+mod merge;
+mod metrics;
+mod ndjson;
+mod news;
+mod notes;
+#[cfg(feature = "ocr")]
+mod ocr;
+mod pdf;
+mod podcast;
+mod provenance;
+mod publisher;
+#[cfg(feature = "qr")]
+mod qr;
+mod registries;
+mod rekey;
+mod rename;
+mod report;
+mod repository;
+mod resolvers;
+mod screening;
+mod script;
+mod style;
+mod sync;
+mod table;
+mod template;
+mod thesis;
+mod undo;
+mod verify;
+mod video;
+mod watch;
+mod webhook;
+
+/// The subcommands of features that can be left out of a build.
+fn optional_subcommands() -> Vec<clap::App<'static, 'static>> {
+    vec![
+        #[cfg(feature = "qr")]
+        qr::subcommand(),
+    ]
+}
 
 #[tokio::main]
 async fn main() {
@@ -19,100 +102,1653 @@ async fn main() {
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
+        .setting(AppSettings::ArgsNegateSubcommands)
         .arg(
             Arg::with_name("file")
                 .short("f")
                 .long("file")
-                .help("Read DOIs or arXiv identifiers from a file (one per line).")
+                .help(i18n::text("help-file"))
                 .takes_value(true)
                 .conflicts_with("input"),
         )
         .arg(
             Arg::with_name("input")
-                .help("DOI(s) or arXiv identifier(s) to search for, separated by spaces.")
+                .help(i18n::text("help-input"))
                 .conflicts_with("file")
                 .index(1)
                 .min_values(1),
         )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .global(true)
+                .takes_value(true)
+                .help(i18n::text("help-config")),
+        )
         .arg(
             Arg::with_name("escape")
                 .short("e")
                 .long("escape")
-                .help("Escapes the \"&\" (ampersand) character."),
+                .global(true)
+                .help(i18n::text("help-escape")),
+        )
+        .arg(
+            Arg::with_name("annotate")
+                .short("a")
+                .long("annotate")
+                .global(true)
+                .help(i18n::text("help-annotate")),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .global(true)
+                .help(i18n::text("help-strict")),
+        )
+        .arg(
+            Arg::with_name("strict-ids")
+                .long("strict-ids")
+                .global(true)
+                .help(i18n::text("help-strict-ids")),
+        )
+        .arg(
+            Arg::with_name("no-backup")
+                .long("no-backup")
+                .global(true)
+                .help(i18n::text("help-no-backup")),
+        )
+        .arg(
+            Arg::with_name("no-sanitize")
+                .long("no-sanitize")
+                .global(true)
+                .help(i18n::text("help-no-sanitize")),
+        )
+        .arg(
+            Arg::with_name("strip-math")
+                .long("strip-math")
+                .global(true)
+                .help(i18n::text("help-strip-math")),
+        )
+        .arg(
+            Arg::with_name("dblp")
+                .long("dblp")
+                .global(true)
+                .help(i18n::text("help-dblp")),
+        )
+        .arg(
+            Arg::with_name("error-format")
+                .long("error-format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(ErrorFormat::NAMES)
+                .default_value("text")
+                .help(i18n::text("help-error-format")),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .global(true)
+                .takes_value(true)
+                .possible_values(color::When::NAMES)
+                .default_value("auto")
+                .help(i18n::text("help-color")),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .global(true)
+                .takes_value(true)
+                .possible_values(Format::NAMES)
+                .default_value("bibtex")
+                .help(i18n::text("help-format")),
+        )
+        .arg(
+            Arg::with_name("screening")
+                .long("screening")
+                .takes_value(true)
+                .value_name("TOOL")
+                .possible_values(screening::Tool::NAMES)
+                .help(i18n::text("help-screening")),
+        )
+        .arg(
+            Arg::with_name("columns")
+                .long("columns")
+                .global(true)
+                .takes_value(true)
+                .value_name("LIST")
+                .default_value(table::DEFAULT_COLUMNS)
+                .help(i18n::text("help-columns")),
+        )
+        .arg(
+            Arg::with_name("prefer")
+                .long("prefer")
+                .global(true)
+                .takes_value(true)
+                .possible_values(Prefer::NAMES)
+                .default_value("doi")
+                .help(i18n::text("help-prefer")),
+        )
+        .arg(
+            Arg::with_name("romanize")
+                .long("romanize")
+                .global(true)
+                .help(i18n::text("help-romanize")),
+        )
+        .arg(
+            Arg::with_name("with-funding")
+                .long("with-funding")
+                .global(true)
+                .help(i18n::text("help-with-funding")),
+        )
+        .arg(
+            Arg::with_name("with-license")
+                .long("with-license")
+                .global(true)
+                .help(i18n::text("help-with-license")),
+        )
+        .arg(
+            Arg::with_name("archive")
+                .long("archive")
+                .global(true)
+                .help(i18n::text("help-archive")),
+        )
+        .arg(
+            Arg::with_name("hash")
+                .long("hash")
+                .global(true)
+                .help(i18n::text("help-hash")),
+        )
+        .arg(
+            Arg::with_name("follow-published")
+                .long("follow-published")
+                .global(true)
+                .help(i18n::text("help-follow-published")),
+        )
+        .arg(
+            Arg::with_name("emit-cite")
+                .long("emit-cite")
+                .global(true)
+                .takes_value(true)
+                .possible_values(CiteStyle::NAMES)
+                .help(i18n::text("help-emit-cite")),
+        )
+        .arg(
+            Arg::with_name("with-papers")
+                .long("with-papers")
+                .global(true)
+                .help(i18n::text("help-with-papers")),
+        )
+        .arg(
+            Arg::with_name("both")
+                .long("both")
+                .global(true)
+                .help(i18n::text("help-both")),
+        )
+        .subcommand(
+            SubCommand::with_name("clipwatch")
+                .about(i18n::text("about-clipwatch"))
+                .arg(
+                    Arg::with_name("append")
+                        .long("append")
+                        .help(i18n::text("help-append")),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .help(i18n::text("help-clipwatch-interval"))
+                        .takes_value(true)
+                        .default_value("500"),
+                )
+                .arg(webhook::arg()),
+        )
+        .arg(
+            Arg::with_name("sort")
+                .long("sort")
+                .takes_value(true)
+                .possible_values(SortBy::NAMES)
+                .default_value("input")
+                .help(i18n::text("help-sort")),
+        )
+        .arg(
+            Arg::with_name("group-by")
+                .long("group-by")
+                .takes_value(true)
+                .possible_values(GroupBy::NAMES)
+                .help(i18n::text("help-group-by")),
+        )
+        .arg(
+            Arg::with_name("ndjson")
+                .long("ndjson")
+                .conflicts_with_all(&["input", "file"])
+                .help(i18n::text("help-ndjson")),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("32")
+                .help(i18n::text("help-jobs")),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(i18n::text("help-checkpoint")),
+        )
+        .arg(
+            Arg::with_name("summary")
+                .long("summary")
+                .help(i18n::text("help-summary")),
+        )
+        .arg(
+            Arg::with_name("metrics-file")
+                .long("metrics-file")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(i18n::text("help-metrics-file")),
+        )
+        .arg(
+            Arg::with_name("insert-key-only")
+                .long("insert-key-only")
+                .conflicts_with("ndjson")
+                .help(i18n::text("help-insert-key-only")),
+        )
+        .arg(
+            Arg::with_name("bib")
+                .long("bib")
+                .takes_value(true)
+                .value_name("FILE")
+                .requires("insert-key-only")
+                .help(i18n::text("help-insert-bib")),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(i18n::text("help-report")),
+        )
+        .arg(
+            Arg::with_name("fetch-pdf")
+                .long("fetch-pdf")
+                .takes_value(true)
+                .value_name("DIR")
+                .help(i18n::text("help-fetch-pdf")),
+        )
+        .arg(
+            Arg::with_name("obsidian")
+                .long("obsidian")
+                .takes_value(true)
+                .value_name("DIR")
+                .help(i18n::text("help-obsidian")),
+        )
+        .arg(
+            Arg::with_name("hugo")
+                .long("hugo")
+                .takes_value(true)
+                .value_name("DIR")
+                .help(i18n::text("help-hugo")),
+        )
+        .arg(
+            Arg::with_name("http1")
+                .long("http1")
+                .global(true)
+                .help(i18n::text("help-http1")),
+        )
+        .arg(
+            Arg::with_name("pool-idle-timeout")
+                .long("pool-idle-timeout")
+                .global(true)
+                .takes_value(true)
+                .value_name("SECS")
+                .help(i18n::text("help-pool-idle-timeout")),
+        )
+        .arg(
+            Arg::with_name("tcp-keepalive")
+                .long("tcp-keepalive")
+                .global(true)
+                .takes_value(true)
+                .value_name("SECS")
+                .help(i18n::text("help-tcp-keepalive")),
+        )
+        .arg(
+            Arg::with_name("doi-resolver")
+                .long("doi-resolver")
+                .takes_value(true)
+                .possible_values(&["doi.org", "crossref", "datacite", "europepmc"])
+                .help(i18n::text("help-doi-resolver")),
+        )
+        .arg(
+            Arg::with_name("race")
+                .long("race")
+                .conflicts_with("doi-resolver")
+                .help(i18n::text("help-race")),
+        )
+        .arg(
+            Arg::with_name("best-of")
+                .long("best-of")
+                .conflicts_with_all(&["doi-resolver", "race"])
+                .help(i18n::text("help-best-of")),
+        )
+        .arg(
+            Arg::with_name("merge-sources")
+                .long("merge-sources")
+                .conflicts_with_all(&["doi-resolver", "race"])
+                .help(i18n::text("help-merge-sources")),
+        )
+        .arg(
+            Arg::with_name("journal-strings")
+                .long("journal-strings")
+                .takes_value(true)
+                .value_name("STYLE|FILE")
+                .help(i18n::text("help-journal-strings")),
+        )
+        .arg(
+            Arg::with_name("template")
+                .long("template")
+                .takes_value(true)
+                .value_name("FILE")
+                .help(i18n::text("help-template")),
+        )
+        .arg(
+            Arg::with_name("only-category")
+                .long("only-category")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("CATEGORY")
+                .help(i18n::text("help-only-category")),
+        )
+        .arg(
+            Arg::with_name("style")
+                .long("style")
+                .takes_value(true)
+                .possible_values(style::Style::NAMES)
+                .conflicts_with("template")
+                .help(i18n::text("help-style")),
+        )
+        .arg(
+            Arg::with_name("post-hook")
+                .long("post-hook")
+                .global(true)
+                .takes_value(true)
+                .value_name("CMD")
+                .help(i18n::text("help-post-hook")),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIR")
+                .conflicts_with("replay")
+                .help(i18n::text("help-record")),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .global(true)
+                .takes_value(true)
+                .value_name("DIR")
+                .help(i18n::text("help-replay")),
+        )
+        .arg(
+            Arg::with_name("http-debug")
+                .long("http-debug")
+                .global(true)
+                .help(i18n::text("help-http-debug")),
+        )
+        .arg(library::db_arg())
+        .arg(library::tag_arg(i18n::text("help-tag")))
+        .subcommand(
+            SubCommand::with_name("rename")
+                .about(i18n::text("about-rename"))
+                .arg(Arg::with_name("files").required(true).min_values(1))
+                .arg(
+                    Arg::with_name("template")
+                        .long("template")
+                        .takes_value(true)
+                        .default_value(rename::DEFAULT_TEMPLATE)
+                        .help(i18n::text("help-rename-template")),
+                )
+                .arg(
+                    Arg::with_name("apply")
+                        .long("apply")
+                        .help(i18n::text("help-rename-apply")),
+                )
+                .args(&rename::ocr_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("undo")
+                .about(i18n::text("about-undo"))
+                .arg(Arg::with_name("dir").help(i18n::text("help-undo-dir")))
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help(i18n::text("help-undo-list")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("rekey")
+                .about(i18n::text("about-rekey"))
+                .arg(
+                    Arg::with_name("files")
+                        .required(true)
+                        .min_values(1)
+                        .help(i18n::text("help-rekey-files")),
+                )
+                .arg(
+                    Arg::with_name("key-format")
+                        .long("key-format")
+                        .takes_value(true)
+                        .default_value(rekey::DEFAULT_FORMAT)
+                        .help(i18n::text("help-key-format")),
+                )
+                .arg(
+                    Arg::with_name("apply")
+                        .long("apply")
+                        .help(i18n::text("help-rekey-apply")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("sync")
+                .about(i18n::text("about-sync"))
+                .arg(
+                    Arg::with_name("manifest")
+                        .required(true)
+                        .help(i18n::text("help-manifest")),
+                )
+                .arg(
+                    Arg::with_name("bib")
+                        .required(true)
+                        .help(i18n::text("help-update-bib")),
+                )
+                .arg(
+                    Arg::with_name("prune")
+                        .long("prune")
+                        .help(i18n::text("help-prune")),
+                )
+                .arg(sync::conflict_arg())
+                .args(&diff::args()),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about(i18n::text("about-import"))
+                .arg(
+                    Arg::with_name("export")
+                        .required(true)
+                        .help(i18n::text("help-import-export")),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help(i18n::text("help-output")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("mail")
+                .about(i18n::text("about-mail"))
+                .arg(
+                    Arg::with_name("mailbox")
+                        .required(true)
+                        .help(i18n::text("help-mailbox")),
+                )
+                .arg(
+                    Arg::with_name("bib")
+                        .required(true)
+                        .help(i18n::text("help-append-bib")),
+                )
+                .arg(sync::conflict_arg())
+                .args(&diff::args()),
+        )
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about(i18n::text("about-verify"))
+                .arg(
+                    Arg::with_name("bib")
+                        .required(true)
+                        .help(i18n::text("help-check-bib")),
+                )
+                .arg(
+                    Arg::with_name("require-doi")
+                        .long("require-doi")
+                        .help(i18n::text("help-require-doi")),
+                )
+                .arg(
+                    Arg::with_name("require-resolvable")
+                        .long("require-resolvable")
+                        .help(i18n::text("help-require-resolvable")),
+                )
+                .arg(
+                    Arg::with_name("check-hashes")
+                        .long("check-hashes")
+                        .help(i18n::text("help-check-hashes")),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help(i18n::text("help-verify-json")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("enrich")
+                .about(i18n::text("about-enrich"))
+                .arg(
+                    Arg::with_name("bib")
+                        .required(true)
+                        .help(i18n::text("help-update-bib")),
+                )
+                .arg(
+                    Arg::with_name("overwrite")
+                        .long("overwrite")
+                        .help(i18n::text("help-overwrite")),
+                )
+                .args(&diff::args()),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about(i18n::text("about-merge"))
+                .arg(Arg::with_name("files").required(true).min_values(1))
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help(i18n::text("help-output")),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("convert")
+                .about(i18n::text("about-convert"))
+                .arg(Arg::with_name("files").required(true).min_values(1))
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .takes_value(true)
+                        .required(true)
+                        .possible_values(convert::Target::NAMES)
+                        .help(i18n::text("help-convert-to")),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .help(i18n::text("help-output")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about(i18n::text("about-list"))
+                .arg(
+                    Arg::with_name("journal")
+                        .long("journal")
+                        .takes_value(true)
+                        .help(i18n::text("help-list-journal")),
+                )
+                .arg(
+                    Arg::with_name("funder")
+                        .long("funder")
+                        .takes_value(true)
+                        .help(i18n::text("help-list-funder")),
+                )
+                .group(
+                    ArgGroup::with_name("source")
+                        .args(&["journal", "funder"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .takes_value(true)
+                        .help(i18n::text("help-list-from")),
+                )
+                .arg(
+                    Arg::with_name("until")
+                        .long("until")
+                        .takes_value(true)
+                        .help(i18n::text("help-list-until")),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .help(i18n::text("help-limit")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("ids")
+                .about(i18n::text("about-ids"))
+                .arg(
+                    Arg::with_name("input")
+                        .required(true)
+                        .help(i18n::text("help-ids-input")),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help(i18n::text("help-ids-json")),
+                ),
+        )
+        .subcommand(mcp::subcommand())
+        .subcommand(complete::subcommand())
+        .subcommand(
+            SubCommand::with_name("refs")
+                .about(i18n::text("about-refs"))
+                .arg(
+                    Arg::with_name("input")
+                        .required(true)
+                        .help(i18n::text("help-paper")),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value("100")
+                        .help(i18n::text("help-limit")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("cited-by")
+                .about(i18n::text("about-cited-by"))
+                .arg(
+                    Arg::with_name("input")
+                        .required(true)
+                        .help(i18n::text("help-paper")),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value("100")
+                        .help(i18n::text("help-limit")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("related")
+                .about(i18n::text("about-related"))
+                .arg(
+                    Arg::with_name("input")
+                        .required(true)
+                        .help(i18n::text("help-paper")),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .long("limit")
+                        .takes_value(true)
+                        .default_value("10")
+                        .help(i18n::text("help-related-limit")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch-ids")
+                .about(i18n::text("about-watch-ids"))
+                .arg(
+                    Arg::with_name("ids")
+                        .required(true)
+                        .help(i18n::text("help-watch-ids")),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .help(i18n::text("help-watch-ids-interval")),
+                )
+                .arg(
+                    Arg::with_name("state")
+                        .long("state")
+                        .takes_value(true)
+                        .help(i18n::text("help-watch-ids-state")),
+                )
+                .arg(webhook::arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("auth")
+                .about(i18n::text("about-auth"))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("set")
+                        .about(i18n::text("about-auth-set"))
+                        .arg(Arg::with_name("service").required(true))
+                        .arg(Arg::with_name("token").help(i18n::text("help-auth-token"))),
+                )
+                .subcommand(
+                    SubCommand::with_name("rm")
+                        .about(i18n::text("about-auth-rm"))
+                        .arg(Arg::with_name("service").required(true)),
+                )
+                .subcommand(SubCommand::with_name("ls").about(i18n::text("about-auth-ls"))),
+        )
+        .subcommand(
+            SubCommand::with_name("lib")
+                .about(i18n::text("about-lib"))
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .arg(library::db_arg())
+                .subcommand(
+                    SubCommand::with_name("add")
+                        .about(i18n::text("about-lib-add"))
+                        .arg(Arg::with_name("input").required(true).min_values(1))
+                        .arg(library::tag_arg(i18n::text("help-lib-add-tag"))),
+                )
+                .subcommand(
+                    SubCommand::with_name("ls")
+                        .about(i18n::text("about-lib-ls"))
+                        .arg(library::tag_arg(i18n::text("help-lib-ls-tag"))),
+                )
+                .subcommand(
+                    SubCommand::with_name("rm")
+                        .about(i18n::text("about-lib-rm"))
+                        .arg(Arg::with_name("key").required(true).min_values(1)),
+                )
+                .subcommand(
+                    SubCommand::with_name("tag")
+                        .about(i18n::text("about-lib-tag"))
+                        .arg(Arg::with_name("key").required(true))
+                        .arg(Arg::with_name("tags").required(true).min_values(1)),
+                )
+                .subcommand(
+                    SubCommand::with_name("untag")
+                        .about(i18n::text("about-lib-untag"))
+                        .arg(Arg::with_name("key").required(true))
+                        .arg(Arg::with_name("tags").required(true).min_values(1)),
+                )
+                .subcommand(SubCommand::with_name("tags").about(i18n::text("about-lib-tags")))
+                .subcommand(
+                    SubCommand::with_name("export")
+                        .about(i18n::text("about-lib-export"))
+                        .arg(library::tag_arg(i18n::text("help-lib-export-tag")))
+                        .arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help(i18n::text("help-output")),
+                        ),
+                )
+                .subcommand(
+                    SubCommand::with_name("feed")
+                        .about(i18n::text("about-lib-feed"))
+                        .arg(library::tag_arg(i18n::text("help-lib-feed-tag")))
+                        .arg(
+                            Arg::with_name("limit")
+                                .short("n")
+                                .long("limit")
+                                .takes_value(true)
+                                .help(i18n::text("help-feed-limit")),
+                        )
+                        .arg(
+                            Arg::with_name("title")
+                                .long("title")
+                                .takes_value(true)
+                                .help(i18n::text("help-feed-title")),
+                        )
+                        .arg(
+                            Arg::with_name("link")
+                                .long("link")
+                                .takes_value(true)
+                                .help(i18n::text("help-feed-link")),
+                        )
+                        .arg(
+                            Arg::with_name("output")
+                                .short("o")
+                                .long("output")
+                                .takes_value(true)
+                                .help(i18n::text("help-output")),
+                        ),
+                ),
+        )
+        .subcommands(optional_subcommands())
+        .get_matches_safe()
+        .unwrap_or_else(|e| match e.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => e.exit(),
+            _ => {
+                eprintln!("{}", e.message);
+                std::process::exit(error::USAGE)
+            }
+        });
+
+    let error_format = ErrorFormat::from_name(matches.value_of("error-format").unwrap_or("text"));
+    let config = Config::load(matches.value_of("config")).unwrap_or_else(|e| {
+        error::Error::new(
+            Kind::Usage,
+            i18n::message("config-unreadable", &[("error", &e)]),
+        )
+        .exit(error_format)
+    });
+    let opts = Options::from_matches(&matches, &config);
+    match http::Settings::from_matches(&matches) {
+        Ok(settings) => http::configure(settings),
+        Err(arg) => error::Error::new(
+            Kind::Usage,
+            i18n::message("invalid-seconds", &[("arg", &arg)]),
+        )
+        .exit(opts.error_format),
+    }
+
+    if let Some(sub) = matches.subcommand_matches("rename") {
+        rename::run(sub, opts).await;
+        return;
+    }
+
+    if matches.is_present("no-backup") {
+        undo::disable();
+    }
+
+    if let Some(sub) = matches.subcommand_matches("undo") {
+        undo::run(sub, opts);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("rekey") {
+        rekey::run(sub, opts);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("sync") {
+        sync::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("import") {
+        import::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("mail") {
+        mail::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("verify") {
+        verify::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("enrich") {
+        enrich::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("merge") {
+        merge::run(sub, opts);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("convert") {
+        convert::run(sub, opts);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("list") {
+        list::run(sub, opts).await;
+        return;
+    }
+
+    #[cfg(feature = "qr")]
+    if let Some(sub) = matches.subcommand_matches("qr") {
+        qr::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("ids") {
+        ids::run(sub, &config, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("complete") {
+        complete::run(sub, opts).await;
+        return;
+    }
+
+    if matches.subcommand_matches("mcp").is_some() {
+        mcp::run(opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("refs") {
+        graph::run(sub, graph::Direction::References, &config, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("cited-by") {
+        graph::run(sub, graph::Direction::Citations, &config, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("related") {
+        graph::related(sub, &config, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("watch-ids") {
+        watch::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("auth") {
+        auth::run(sub, &config, opts.error_format);
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("lib") {
+        library::run(sub, opts).await;
+        return;
+    }
+
+    if let Some(sub) = matches.subcommand_matches("clipwatch") {
+        let interval = sub
+            .value_of("interval")
+            .unwrap()
+            .parse()
+            .unwrap_or_else(|_| {
+                error::Error::new(Kind::Usage, i18n::text("invalid-interval"))
+                    .exit(opts.error_format)
+            });
+        let error_format = opts.error_format;
+        clipboard::watch(
+            opts,
+            sub.is_present("append"),
+            Duration::from_millis(interval),
+            sub.value_of("notify-url"),
+        )
+        .await
+        .unwrap_or_else(|e| e.exit(error_format));
+        return;
+    }
+
+    if matches.is_present("ndjson") {
+        let jobs = matches
+            .value_of("jobs")
+            .unwrap()
+            .parse()
+            .ok()
+            .filter(|&j| j > 0)
+            .unwrap_or_else(|| {
+                error::Error::new(Kind::Usage, i18n::text("invalid-jobs")).exit(opts.error_format)
+            });
+        ndjson::run(opts, jobs).await;
+        return;
+    }
+
+    let pats = if let Some(pats) = matches.values_of("input") {
+        // The first spelling of each input is kept, in the order given, for `--sort input`.
+        let mut seen = std::collections::HashSet::new();
+        pats.filter(|p| seen.insert(input_key(p)))
+            .map(|x| x.to_owned())
+            .collect::<Vec<String>>()
+    } else if let Some(filename) = matches.value_of("file") {
+        read_file(filename).unwrap_or_else(|e| e.exit(opts.error_format))
+    } else {
+        error::Error::new(Kind::Usage, i18n::text("missing-arguments")).exit(opts.error_format)
+    };
+
+    if matches.is_present("insert-key-only") {
+        let bib = matches
+            .value_of("bib")
+            .map(PathBuf::from)
+            .or_else(|| config.bib())
+            .unwrap_or_else(|| {
+                error::Error::new(Kind::Usage, i18n::text("no-bib-configured"))
+                    .exit(opts.error_format)
+            });
+        let bib = bib.to_string_lossy();
+        let policy = sync::OnConflict::Suffix;
+        let applied = sync::apply(&bib, &pats, false, policy, Default::default(), opts).await;
+        // One line, so that several keys can go into a single citation command.
+        let keys = applied.keys.iter().map(|(_, key)| key.as_str());
+        let keys = keys.collect::<Vec<_>>();
+        if !keys.is_empty() {
+            println!("{}", keys.join(","));
+        }
+        let status = error::status(applied.resolved, &applied.errors);
+        if status != error::OK {
+            std::process::exit(status);
+        }
+        return;
+    }
+
+    let start = std::time::Instant::now();
+    let mut checkpoint = matches.value_of("checkpoint").map(|path| {
+        checkpoint::Checkpoint::open(path).unwrap_or_else(|e| e.exit(opts.error_format))
+    });
+
+    // Everything is collected before printing so that inputs resolving to the same work (an arXiv
+    // ID and its DOI, say) can be merged.
+    let mut resolved = Vec::new();
+    let mut todo = Vec::new();
+    for p in &pats {
+        match checkpoint.as_mut().and_then(|c| c.take(p)) {
+            Some(done) => resolved.extend(done),
+            None => todo.push(p),
+        }
+    }
+
+    let bar = ProgressBar::new(todo.len() as u64);
+
+    let mut futures = todo
+        .iter()
+        .map(|p| resolve((*p).clone(), opts.clone()))
+        .collect::<FuturesUnordered<_>>();
+
+    let mut errors = Vec::new();
+    while let Some(val) = futures.next().await {
+        bar.inc(1);
+        match val {
+            Ok(val) => {
+                if let Some(checkpoint) = &mut checkpoint {
+                    checkpoint.record(&val);
+                }
+                resolved.extend(val)
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    bar.finish_and_clear();
+    for e in &errors {
+        e.report(opts.error_format);
+    }
+    let status = error::status(pats.len() - errors.len(), &errors);
+    if matches.is_present("summary") || matches.is_present("metrics-file") {
+        let counts = metrics::Counts {
+            resolved: todo.len() - errors.len(),
+            cached: pats.len() - todo.len(),
+            failed: errors.len(),
+            elapsed: start.elapsed(),
+        };
+        let latencies = metrics::latencies();
+        if matches.is_present("summary") {
+            eprintln!("{}", metrics::summary(&counts, &latencies));
+        }
+        if let Some(path) = matches.value_of("metrics-file") {
+            if let Err(e) = std::fs::write(path, metrics::prometheus(&counts, &latencies)) {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("file-unwritable", &[("file", &path), ("error", &e)])
+                );
+            }
+        }
+    }
+    // Resolution finishes in any order; putting entries back in input order first makes which
+    // duplicate is kept, and so the output, deterministic.
+    SortBy::Input.sort(&mut resolved, &pats);
+    let mut resolved = merge_duplicates(resolved);
+    if let Some(wanted) = matches.values_of("only-category") {
+        let wanted = wanted.collect::<Vec<_>>();
+        resolved.retain(|r| wanted.iter().any(|c| feed::in_category(&r.entry, c)));
+    }
+    if let Some(path) = matches.value_of("report") {
+        let style = opts.style.unwrap_or(style::Style::Iso690);
+        let today = chrono::Local::today().format("%Y-%m-%d").to_string();
+        let html = report::html(&pats, &resolved, &errors, style, &today);
+        if let Err(e) = std::fs::write(path, html) {
+            eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message("file-unwritable", &[("file", &path), ("error", &e)])
+            );
+        }
+    }
+    if let Some(dir) = matches.value_of("fetch-pdf") {
+        for r in &mut resolved {
+            match pdf::fetch(&mut r.entry, Path::new(dir)).await {
+                Ok(path) => eprintln!(
+                    "{}",
+                    i18n::message(
+                        "pdf-saved",
+                        &[("key", &r.entry.key), ("path", &path.display())],
+                    )
+                ),
+                Err(e) => eprintln!("{}: {}: {}", i18n::text("warning"), r.entry.key, e),
+            }
+        }
+    }
+    if let Some(tags) = matches.values_of("tag") {
+        library::store(&matches, &resolved, &tags.collect::<Vec<_>>(), &opts);
+    }
+    if let Some(dir) = matches.value_of("obsidian") {
+        let tags = matches
+            .values_of("tag")
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        notes::write_all(Path::new(dir), resolved.iter().map(|r| &r.entry), |entry| {
+            vec![(
+                pdf::file_name(&entry.key, "md"),
+                notes::obsidian(entry, &tags),
+            )]
+        });
+    }
+    if let Some(dir) = matches.value_of("hugo") {
+        let tags = matches
+            .values_of("tag")
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        notes::write_all(Path::new(dir), resolved.iter().map(|r| &r.entry), |entry| {
+            notes::hugo_bundle(entry, &tags)
+        });
+    }
+    opts.sort.sort(&mut resolved, &pats);
+    if let Some(group_by) = opts.group_by {
+        group_by.sort(&mut resolved);
+    }
+    if let Some(tool) = opts.screening {
+        let abstracts =
+            futures::future::join_all(resolved.iter().map(|r| screening::abstract_of(&r.entry)))
+                .await;
+        let entries = resolved
+            .iter()
+            .map(|r| &r.entry)
+            .zip(abstracts)
+            .collect::<Vec<_>>();
+        println!("{}", tool.export(&entries));
+    } else if let Some(launcher) = opts.launcher {
+        println!("{}", launcher.output(&resolved, &errors, &opts));
+    } else if let Some(table) = &opts.table {
+        let citations = if table.wants_citations() {
+            table::citations(&resolved.iter().map(|r| &r.entry).collect::<Vec<_>>()).await
+        } else {
+            Default::default()
+        };
+        println!("{}", table.header());
+        for r in &resolved {
+            let doi = r.entry.get("doi").map(str::to_lowercase);
+            println!(
+                "{}",
+                table.row(&r.entry, doi.and_then(|d| citations.get(&d).copied()))
+            );
+        }
+    } else {
+        // Only BibTeX is highlighted; styles and templates can write anything.
+        let highlight = opts.color
+            && opts.style.is_none()
+            && opts.template.is_none()
+            && opts.formatter.is_none();
+        let paint = |text: String| match highlight {
+            true => color::highlight(&text),
+            false => text,
+        };
+        let mut section = None;
+        for r in &resolved {
+            if let Some(group_by) = opts.group_by {
+                let label = group_by.label(&r.entry);
+                if section.as_ref() != Some(&label) {
+                    println!("{}\n", paint(format!("% ==== {} ====", label)));
+                    section = Some(label);
+                }
+            }
+            println!("{}", paint(render(r, &opts)));
+        }
+    }
+    // Citation commands go to stderr so that stdout stays a valid .bib file.
+    if let Some(style) = opts.emit_cite {
+        for r in &resolved {
+            eprintln!("{}", style.command(&r.entry.key));
+        }
+    }
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+lazy_static! {
+    pub static ref DOI_IDENT_RE: Regex = Regex::new(r"(?i)doi(?::|\.org|/)").unwrap();
+    // DOI suffixes may contain any printable character, e.g. `10.1002/(SICI)1097-4636(199706)35:4<467::AID-JBM6>3.0.CO;2-F`.
+    pub static ref DOI_RE: ArrayVec<Regex, 1> = [r"10\.\d{4,9}/\S+"]
+        .iter()
+        .map(|re| Regex::new(re).unwrap())
+        .collect();
+    pub static ref ARXIV_IDENT_RE: Regex = Regex::new(r"(?i)arxiv(?-i)(?:\s*:|.org)").unwrap();
+    // Versions are sometimes copied with a space or a capital `V`, and pre-2007 IDs with the
+    // subject class (`math.AG/0501001`); [`extract_arxiv`] normalizes both away.
+    pub static ref ARXIV_RE: ArrayVec<Regex, 2> = [
+        r"\d{4}\.\d{4,5}(?:\s?[vV]\d+)?",
+        r"(?i)[a-z]+(?:-[a-z]+)?(?:\.[a-z]+(?:-[a-z]+)?)?/\d{7}(?:\s?v\d+)?",
+    ]
+    .iter()
+    .map(|re| Regex::new(re).unwrap())
+    .collect();
+    pub static ref ISBN_RE: Regex = Regex::new(
+        r"(?i)\bisbn(?:-1[03])?:?\s*(\d[\d\s-]{8,15}[\dx])\b|^\s*(97[89](?:-?\d){10})\s*$"
+    )
+    .unwrap();
+    static ref EXACT_DOI_RE: Regex =
+        Regex::new(r"(?i)^(?:doi:|https?://(?:dx\.)?doi\.org/)?10\.\d{4,9}/\S+$").unwrap();
+    static ref EXACT_ARXIV_RE: Regex = Regex::new(
+        r"(?i)^(?:arxiv:|https?://(?:www\.)?arxiv\.org/(?:abs|pdf)/)?(?:\d{4}\.\d{4,5}|[a-z]+(?:-[a-z]+)?(?:\.[a-z]+(?:-[a-z]+)?)?/\d{7})(?:v\d+)?$"
+    )
+    .unwrap();
+    static ref ARXIV_LICENSE_RE: Regex = Regex::new(r"(?s)<license>(.*?)</license>").unwrap();
+    static ref ARXIV_VERSION_RE: Regex = Regex::new(r"v\d+$").unwrap();
+    pub static ref CLIENT: Client = http::client();
+}
+
+/// Output options shared by every way of resolving identifiers.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub escape: bool,
+    pub annotate: bool,
+    pub strict: bool,
+    pub strict_ids: bool,
+    pub sanitize: bool,
+    pub strip_math: bool,
+    pub dblp: bool,
+    pub format: Format,
+    pub with_papers: bool,
+    pub both: bool,
+    pub prefer: Prefer,
+    pub romanize: bool,
+    pub with_funding: bool,
+    pub with_license: bool,
+    pub archive: bool,
+    pub hash: bool,
+    pub follow_published: bool,
+    pub race: bool,
+    pub best_of: bool,
+    pub merge_sources: bool,
+    pub emit_cite: Option<CiteStyle>,
+    pub group_by: Option<GroupBy>,
+    pub sort: SortBy,
+    /// Extra fields from the configuration file, as name/template pairs.
+    pub fields: Vec<(String, String)>,
+    pub error_format: ErrorFormat,
+    /// Whether BibTeX written to standard output is highlighted.
+    pub color: bool,
+    /// A command every entry is piped through, as JSON.
+    pub post_hook: Option<String>,
+    /// The `--template` entries are written out with.
+    pub template: Option<std::sync::Arc<template::Template>>,
+    /// The reference style entries are written out in, instead of BibTeX.
+    pub style: Option<style::Style>,
+    /// The script entries are written out with, if one is configured for the format.
+    pub formatter: Option<std::sync::Arc<script::Formatter>>,
+    pub resolvers: resolvers::Priorities,
+    /// Resolvers defined in the configuration file.
+    pub custom: Vec<resolvers::Custom>,
+    /// The table entries are written out as, for `--format csv` and `tsv`.
+    pub table: Option<table::Table>,
+    /// The launcher entries are offered to, for `--format alfred` and `dmenu`.
+    pub launcher: Option<launcher::Launcher>,
+    pub screening: Option<screening::Tool>,
+    /// The `@string` macros journal names are written as.
+    pub journal_strings: Option<std::sync::Arc<macros::JournalMacros>>,
+    /// The API keys of Scopus and Web of Science, whose IDs are resolved to DOIs through them, and
+    /// the WorldCat token OCLC numbers are looked up with.
+    pub scopus_token: Option<auth::Token>,
+    pub wos_token: Option<auth::Token>,
+    pub worldcat_token: Option<auth::Token>,
+}
+
+/// The dialect entries are written in. The tabular and launcher formats are built from BibTeX
+/// entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Bibtex,
+    Biblatex,
+}
+
+impl Format {
+    const NAMES: &'static [&'static str] = &["bibtex", "biblatex", "csv", "tsv", "alfred", "dmenu"];
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "biblatex" => Format::Biblatex,
+            _ => Format::Bibtex,
+        }
+    }
+}
+
+/// Which entry to emit for an arXiv paper that also has a journal DOI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefer {
+    Arxiv,
+    Doi,
+}
 
-    let pats = if let Some(pats) = matches.values_of("input") {
-        let mut pats = pats.collect::<Vec<_>>();
-        pats.sort();
-        pats.dedup();
-        pats.into_iter()
-            .map(|x| x.to_owned())
-            .collect::<Vec<String>>()
-    } else if let Some(filename) = matches.value_of("file") {
-        read_file(filename)
-    } else {
-        Error::with_description("Missing arguments!", ErrorKind::MissingRequiredArgument).exit();
-    };
+impl Prefer {
+    const NAMES: &'static [&'static str] = &["doi", "arxiv"];
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "arxiv" => Prefer::Arxiv,
+            _ => Prefer::Doi,
+        }
+    }
+}
 
-    let bar = ProgressBar::new(pats.len() as u64);
+/// The syntax of the citation commands printed by `--emit-cite`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CiteStyle {
+    Latex,
+    Typst,
+    Pandoc,
+}
 
-    let mut futures = pats
-        .into_iter()
-        .map(|p| get_bibtex(p, matches.is_present("escape")))
-        .collect::<FuturesUnordered<_>>();
+impl CiteStyle {
+    const NAMES: &'static [&'static str] = &["latex", "typst", "pandoc"];
 
-    while let Some(val) = futures.next().await {
-        bar.inc(1);
-        bar.println(val);
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "latex" => Some(CiteStyle::Latex),
+            "typst" => Some(CiteStyle::Typst),
+            "pandoc" => Some(CiteStyle::Pandoc),
+            _ => None,
+        }
     }
 
-    bar.finish_and_clear();
+    pub fn command(self, key: &str) -> String {
+        match self {
+            CiteStyle::Latex => format!("\\cite{{{}}}", key),
+            CiteStyle::Typst => format!("#cite(<{}>)", key),
+            CiteStyle::Pandoc => format!("@{}", key),
+        }
+    }
 }
 
-lazy_static! {
-    pub static ref DOI_IDENT_RE: Regex = Regex::new(r"doi(?::|.org)").unwrap();
-    pub static ref DOI_RE: ArrayVec<Regex, 2> = [r"10.\d{4,9}/[-\._;()/:\w\d]+", r"10.1002/[^\s]+"]
-        .iter()
-        .map(|re| Regex::new(re).unwrap())
-        .collect();
-    pub static ref DOI_FMT: Regex = Regex::new(r",(\s?\w+=\{.+?\})").unwrap();
-    pub static ref ARXIV_IDENT_RE: Regex = Regex::new(r"(?i)arxiv(?-i)(?::|.org)").unwrap();
-    pub static ref ARXIV_RE: ArrayVec<Regex, 2> = [
-        r"\d{4}\.\d{4,5}(?:v\d+)?",
-        r"[a-z]+(?:-[a-z]+)?/\d{7}(?:v\d+)?",
-    ]
-    .iter()
-    .map(|re| Regex::new(re).unwrap())
-    .collect();
-    pub static ref CLIENT: Client = Client::new();
+/// The order `--sort` puts entries in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    Input,
+    Key,
+    Year,
+    Author,
+}
+
+impl SortBy {
+    const NAMES: &'static [&'static str] = &["input", "key", "year", "author"];
+
+    fn from_name(name: &str) -> Self {
+        match name {
+            "key" => SortBy::Key,
+            "year" => SortBy::Year,
+            "author" => SortBy::Author,
+            _ => SortBy::Input,
+        }
+    }
+
+    /// Sorts entries, falling back to their keys for ties. `inputs` gives the order for
+    /// `SortBy::Input`; entries from the same input keep the order they were resolved in.
+    fn sort(self, resolved: &mut [Resolved], inputs: &[String]) {
+        let key = |r: &Resolved| r.entry.key.to_lowercase();
+        match self {
+            SortBy::Input => {
+                resolved.sort_by_cached_key(|r| inputs.iter().position(|i| *i == r.input))
+            }
+            SortBy::Key => resolved.sort_by_cached_key(key),
+            SortBy::Year => {
+                resolved.sort_by_cached_key(|r| (r.entry.get("year").map(str::to_owned), key(r)))
+            }
+            SortBy::Author => resolved.sort_by_cached_key(|r| {
+                let author = r
+                    .entry
+                    .get("author")
+                    .or_else(|| r.entry.get("editor"))
+                    .and_then(|a| a.split(" and ").next())
+                    .map(|a| bibtex::family_name(a).to_lowercase());
+                (author, r.entry.get("year").map(str::to_owned), key(r))
+            }),
+        }
+    }
+}
+
+/// How `--group-by` splits the output into commented sections.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Year,
+    Type,
+}
+
+impl GroupBy {
+    const NAMES: &'static [&'static str] = &["year", "type"];
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "year" => Some(GroupBy::Year),
+            "type" => Some(GroupBy::Type),
+            _ => None,
+        }
+    }
+
+    fn label(self, entry: &Entry) -> String {
+        match self {
+            GroupBy::Year => entry.get("year").unwrap_or("undated").to_owned(),
+            GroupBy::Type => entry.kind.to_lowercase(),
+        }
+    }
+
+    /// Orders entries by section, keeping their relative order within each. Undated entries go
+    /// last.
+    fn sort(self, resolved: &mut [Resolved]) {
+        resolved.sort_by_cached_key(|r| {
+            let label = self.label(&r.entry);
+            (label == "undated", label)
+        });
+    }
+}
+
+impl Options {
+    fn from_matches(matches: &ArgMatches, config: &Config) -> Self {
+        Options {
+            escape: matches.is_present("escape"),
+            annotate: matches.is_present("annotate"),
+            strict: matches.is_present("strict"),
+            strict_ids: matches.is_present("strict-ids"),
+            sanitize: !matches.is_present("no-sanitize"),
+            strip_math: matches.is_present("strip-math"),
+            dblp: matches.is_present("dblp"),
+            with_papers: matches.is_present("with-papers"),
+            both: matches.is_present("both"),
+            romanize: matches.is_present("romanize"),
+            with_funding: matches.is_present("with-funding"),
+            with_license: matches.is_present("with-license"),
+            archive: matches.is_present("archive"),
+            hash: matches.is_present("hash"),
+            follow_published: matches.is_present("follow-published"),
+            race: matches.is_present("race"),
+            best_of: matches.is_present("best-of") || matches.is_present("merge-sources"),
+            merge_sources: matches.is_present("merge-sources"),
+            emit_cite: matches.value_of("emit-cite").and_then(CiteStyle::from_name),
+            group_by: matches.value_of("group-by").and_then(GroupBy::from_name),
+            sort: SortBy::from_name(matches.value_of("sort").unwrap_or("input")),
+            fields: config.fields(),
+            format: Format::from_name(matches.value_of("format").unwrap_or("bibtex")),
+            prefer: Prefer::from_name(matches.value_of("prefer").unwrap_or("doi")),
+            post_hook: matches.value_of("post-hook").map(str::to_owned),
+            template: matches.value_of("template").map(|path| {
+                template::Template::load(Path::new(path))
+                    .map(std::sync::Arc::new)
+                    .unwrap_or_else(|e| {
+                        error::Error::new(Kind::Usage, e).exit(ErrorFormat::from_name(
+                            matches.value_of("error-format").unwrap_or("text"),
+                        ))
+                    })
+            }),
+            style: matches.value_of("style").and_then(style::Style::from_name),
+            formatter: config
+                .formatter(matches.value_of("format").unwrap_or("bibtex"))
+                .map(|path| {
+                    script::Formatter::load(&path)
+                        .map(std::sync::Arc::new)
+                        .unwrap_or_else(|e| {
+                            error::Error::new(
+                                Kind::Usage,
+                                i18n::message("config-unreadable", &[("error", &e)]),
+                            )
+                            .exit(ErrorFormat::from_name(
+                                matches.value_of("error-format").unwrap_or("text"),
+                            ))
+                        })
+                }),
+            resolvers: {
+                let mut resolvers = config.priorities();
+                if let Some(backend) = matches
+                    .value_of("doi-resolver")
+                    .and_then(resolvers::Backend::from_doi_name)
+                {
+                    resolvers.doi = vec![backend];
+                    resolvers.doi_fixed = true;
+                }
+                resolvers
+            },
+            custom: config.custom.clone(),
+            table: table::Table::new(
+                matches.value_of("format").unwrap_or("bibtex"),
+                matches
+                    .value_of("columns")
+                    .unwrap_or(table::DEFAULT_COLUMNS),
+            ),
+            launcher: launcher::Launcher::new(matches.value_of("format").unwrap_or("bibtex")),
+            screening: matches
+                .value_of("screening")
+                .and_then(screening::Tool::from_name),
+            journal_strings: matches.value_of("journal-strings").map(|name| {
+                macros::JournalMacros::load(name)
+                    .map(std::sync::Arc::new)
+                    .unwrap_or_else(|e| {
+                        error::Error::new(Kind::Io, e).exit(ErrorFormat::from_name(
+                            matches.value_of("error-format").unwrap_or("text"),
+                        ))
+                    })
+            }),
+            scopus_token: auth::token("scopus", config),
+            wos_token: auth::token("wos", config),
+            worldcat_token: auth::token("worldcat", config),
+            error_format: ErrorFormat::from_name(
+                matches.value_of("error-format").unwrap_or("text"),
+            ),
+            color: matches
+                .value_of("color")
+                .and_then(color::When::from_name)
+                .is_some_and(color::When::enabled),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-enum IdType {
+pub enum IdType {
     Doi,
     Arxiv,
+    /// A book or proceedings volume, looked up on CrossRef.
+    Isbn,
+    /// A landing page in a repository that exposes citation meta tags.
+    Url,
 }
 
-fn read_file(filename: &str) -> Vec<String> {
-    let file = std::fs::File::open(filename).expect("Could not read file!");
-    let buf = BufReader::new(file);
-    buf.lines()
-        .map(|l| l.expect("Could not parse line in file!"))
-        .collect()
+impl IdType {
+    fn resolver(&self) -> &'static str {
+        match self {
+            IdType::Doi => "doi.org",
+            IdType::Arxiv => "arxiv.org",
+            IdType::Isbn => "api.crossref.org",
+            IdType::Url => "the web",
+        }
+    }
 }
 
-fn extract_id<const N: usize>(re_arr: &ArrayVec<Regex, N>, pat: &str) -> String {
-    let m = re_arr
-        .iter()
-        .filter_map(|re| re.captures(&pat))
-        .map(|m| m.get(0).unwrap().as_str())
-        .collect::<ArrayVec<_, 1>>();
-    if m.len() == 0 {
-        Error::with_description("Invalid DOI or arXiv ID!", ErrorKind::ValueValidation).exit();
+pub fn read_file(filename: &str) -> Result<Vec<String>, error::Error> {
+    let unreadable = |e: std::io::Error| {
+        error::Error::new(
+            Kind::Io,
+            i18n::message("file-unreadable", &[("file", &filename), ("error", &e)]),
+        )
+    };
+    let file = std::fs::File::open(filename).map_err(unreadable)?;
+    BufReader::new(file)
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(unreadable)
+}
+
+fn extract_id<const N: usize>(re_arr: &ArrayVec<Regex, N>, pat: &str) -> Option<String> {
+    let m = re_arr.iter().find_map(|re| re.find(pat))?;
+    Some(m.as_str().trim_end_matches('/').to_owned())
+}
+
+/// Strips what a copied identifier tends to pick up at its end: the full stop or comma of the
+/// sentence it ended, quotes, and the closing bracket around it. Brackets the identifier opens
+/// itself, as in `10.1002/(SICI)...`, are kept.
+fn trim_trailing(id: &str) -> &str {
+    let mut id = id;
+    loop {
+        let trimmed = id.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']' | '}' | '>')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    '}' => '{',
+                    _ => '<',
+                };
+                if trimmed.matches(close).count() > trimmed.matches(open).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed == id {
+            return id;
+        }
+        id = trimmed;
+    }
+}
+
+/// Whether an input is exactly an identifier of its type, for `--strict-ids`: a bare or prefixed
+/// DOI or arXiv ID, their URLs, an ISBN or a URL, with nothing around it.
+fn is_exact(pat: &str, idtype: IdType) -> bool {
+    let pat = pat.trim();
+    if trim_trailing(pat) != pat {
+        return false;
+    }
+    match idtype {
+        IdType::Doi if !pat.starts_with("http") || pat.contains("doi.org/") => {
+            EXACT_DOI_RE.is_match(&text::percent_decode(pat))
+        }
+        IdType::Arxiv => EXACT_ARXIV_RE.is_match(pat),
+        IdType::Doi | IdType::Isbn | IdType::Url => !pat.contains(char::is_whitespace),
     }
-    let id = m.get(0).unwrap().trim_end_matches("/").to_owned();
-    id
+}
+
+/// Extracts an arXiv ID from `pat` in the form arXiv's API takes: without spaces, with a lowercase
+/// version, and for pre-2007 IDs with a lowercase archive and without the subject class.
+pub fn extract_arxiv(pat: &str) -> Option<String> {
+    let id = extract_id(&ARXIV_RE, pat)?.replace(char::is_whitespace, "");
+    Some(match id.split_once('/') {
+        Some((archive, number)) => {
+            let archive = archive.split('.').next().unwrap_or(archive);
+            format!("{}/{}", archive, number).to_lowercase()
+        }
+        None => id.to_lowercase(),
+    })
+}
+
+/// Extracts a DOI from `pat`, percent-decoding it first so that DOIs copied out of URLs
+/// (`10.1000%2Fxyz`) are recognized. DOIs are case-insensitive, so the result is lowercased.
+fn extract_doi(pat: &str) -> Option<String> {
+    extract_id(&DOI_RE, &text::percent_decode(pat)).map(|d| trim_trailing(&d).to_lowercase())
+}
+
+/// Extracts an ISBN from `pat`, with hyphens and spaces removed.
+fn extract_isbn(pat: &str) -> Option<String> {
+    let c = ISBN_RE.captures(pat)?;
+    Some(
+        c.get(1)
+            .or_else(|| c.get(2))
+            .unwrap()
+            .as_str()
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_uppercase(),
+    )
 }
 
 async fn request_info(id: &str, idtype: IdType) -> Result<Response, reqwest::Error> {
@@ -120,134 +1756,738 @@ async fn request_info(id: &str, idtype: IdType) -> Result<Response, reqwest::Err
     match idtype {
         IdType::Doi => {
             CLIENT
-                .get(&format!("https://doi.org/{}", id))
-                .header(ACCEPT, "text/bibliography; style=bibtex")
-                .send()
+                .get(fetch::doi_url(id))
+                .header(ACCEPT, fetch::BIBTEX)
+                .send_with_retry()
                 .await
         }
         IdType::Arxiv => {
             CLIENT
-                .get(&format!("http://export.arxiv.org/api/query?id_list={}", id))
-                .send()
+                .get(format!("http://export.arxiv.org/api/query?id_list={}", id))
+                .send_with_retry()
+                .await
+        }
+        IdType::Isbn => {
+            CLIENT
+                .get("https://api.crossref.org/works")
+                .query(&[
+                    ("filter", format!("isbn:{}", id)),
+                    ("rows", "100".to_owned()),
+                ])
+                .send_with_retry()
+                .await
+        }
+        IdType::Url => {
+            CLIENT
+                .get(id)
+                .header(ACCEPT, "text/html")
+                .send_with_retry()
                 .await
         }
     }
 }
 
-fn print_doi(input: &str) -> String {
-    DOI_FMT
-        .replace_all(input.trim(), ",\n  $1")
-        .replace("}}", "}\n}")
+fn print_doi(input: &str) -> Result<Entry, error::Error> {
+    bibtex::parse_entry(input)
+        .map_err(|e| error::Error::new(Kind::Parse, i18n::message("bad-bibtex", &[("error", &e)])))
 }
 
-async fn print_arxiv(input: &Feed) -> String {
-    if input.entries().is_empty() {
-        Error::with_description("Invalid DOI or arXiv ID!", ErrorKind::InvalidValue).exit();
-    }
+/// Picks the volume out of a CrossRef ISBN search. Chapters and papers share the ISBN of their
+/// volume, so only book and proceedings records are considered.
+fn print_isbn(input: &str) -> Result<Entry, error::Error> {
+    serde_json::from_str::<serde_json::Value>(input)
+        .ok()
+        .and_then(|json| {
+            json["message"]["items"].as_array()?.iter().find_map(|w| {
+                crossref::book_entry(w, &crossref::make_key(w))
+                    .filter(|e| e.kind == "book" || e.kind == "proceedings")
+            })
+        })
+        .ok_or_else(|| error::Error::new(Kind::NotFound, i18n::text("no-book")))
+}
 
-    let entry = &input.entries()[0];
+fn print_url(input: &str, url: &str, opts: &Options) -> Result<Entry, error::Error> {
+    let tags = html::meta_tags(input);
+    thesis::from_meta(&tags, url)
+        .or_else(|| news::from_page(input, url, opts.format))
+        .or_else(|| {
+            opts.archive
+                .then(|| archive::page_entry(input, url, opts.format))
+                .flatten()
+        })
+        .ok_or_else(|| {
+            error::Error::new(
+                Kind::NotFound,
+                i18n::message("no-metadata", &[("url", &url)]),
+            )
+        })
+}
 
-    let extensions = entry.extensions();
+async fn print_arxiv(input: &feed::Feed, opts: &Options) -> Result<Vec<Fetched>, error::Error> {
+    let not_found = || error::Error::new(Kind::NotFound, i18n::text("no-arxiv"));
+    let item = input.items.first().ok_or_else(not_found)?;
+    let mut formatted = feed::Source::Arxiv
+        .entry(input, item, opts.format)
+        .ok_or_else(not_found)?;
 
-    if entry.authors().is_empty() || entry.published().is_none() || entry.id().len() == 0 {
-        Error::with_description("Invalid DOI or arXiv ID!", ErrorKind::InvalidValue).exit();
+    let doi = item.extension("arxiv:doi").map(str::to_owned);
+    let doi = doi.filter(|_| opts.both || opts.prefer == Prefer::Doi);
+    if let (Some(doi), false) = (&doi, opts.both) {
+        return resolvers::resolve(doi, IdType::Doi, opts).await;
     }
 
-    assert!(extensions.contains_key("arxiv"));
-    let arxiv_extension = extensions.get("arxiv").unwrap();
-    if arxiv_extension.contains_key("doi") {
-        let doi = arxiv_extension.get("doi").unwrap()[0].value().unwrap();
-        let res = request_info(doi, IdType::Doi).await;
-        return handle_response(res, IdType::Doi).await;
+    if opts.strip_math {
+        let title = text::strip_math(formatted.get("title").unwrap_or_default());
+        formatted.set("title", &title);
     }
 
-    let mut firstauth = "".to_owned();
+    match doi {
+        Some(doi) => {
+            let mut resolved = resolvers::resolve(&doi, IdType::Doi, opts).await?;
+            link_preprint(&mut resolved[0].entry, &mut formatted, opts.format);
+            resolved.push(Fetched::new(IdType::Arxiv, "arxiv.org", formatted));
+            Ok(resolved)
+        }
+        None => Ok(vec![Fetched::new(IdType::Arxiv, "arxiv.org", formatted)]),
+    }
+}
 
-    let mut authors = "".to_owned();
-    for (i, a) in entry.authors().iter().enumerate() {
-        let name_vec = a.name().split_whitespace().collect::<Vec<_>>();
-        assert!(!name_vec.is_empty());
-        let n = name_vec.len();
-        if i == 0 {
-            firstauth = name_vec[n - 1].to_owned();
+/// Whether a new-style arXiv ID could have been issued: its month is 01 to 12, it's no older than
+/// April 2007, and its number has four digits before 2015 and five since.
+fn plausible_arxiv(id: &str) -> bool {
+    let (yymm, number) = match id.split_once('.') {
+        Some(parts) => parts,
+        None => return true,
+    };
+    let number = number.split('v').next().unwrap_or(number);
+    let (yy, mm) = match (yymm.get(..2), yymm.get(2..)) {
+        (Some(yy), Some(mm)) => (yy.parse::<u32>(), mm.parse::<u32>()),
+        _ => return false,
+    };
+    match (yy, mm) {
+        (Ok(yy), Ok(mm)) if (1..=12).contains(&mm) && yy * 100 + mm >= 704 => {
+            number.len() == if yy < 15 { 4 } else { 5 }
         }
-        let ending = if i != entry.authors().len() - 1 {
-            " and "
-        } else {
-            ""
-        };
-        authors.push_str(&format!(
-            "{}, {}{}",
-            name_vec[n - 1],
-            name_vec[..n - 1].join(" "),
-            ending
-        ));
+        _ => false,
     }
+}
 
-    let categories = &entry.categories;
-    assert!(!categories.is_empty());
-    let class = categories[0].term();
+/// Resolves a bare number that looks like an arXiv ID, such as `2105.11572`, that could as well be
+/// a typo or the suffix of a DOI. It's looked up on arXiv if it could be an arXiv ID at all, and
+/// otherwise, or if arXiv doesn't have it, as a DOI suffix at DataCite.
+async fn confirm_arxiv(id: &str, opts: &Options) -> Result<Vec<Fetched>, error::Error> {
+    if plausible_arxiv(id) {
+        match resolvers::resolve(id, IdType::Arxiv, opts).await {
+            Err(e) if e.kind == Kind::NotFound => {}
+            result => return result,
+        }
+    }
+    let doi = datacite::find_suffix(id)
+        .await
+        .ok_or_else(|| error::Error::new(Kind::InvalidInput, i18n::text("unconfirmed-arxiv")))?;
+    eprintln!(
+        "{}: {}",
+        i18n::text("warning"),
+        i18n::message("guessed-doi", &[("input", &id), ("doi", &doi)])
+    );
+    resolvers::resolve(&doi, IdType::Doi, opts).await
+}
 
-    let year = entry.published().unwrap().year().to_string();
-    let key = format!("{}_{}", firstauth, year);
-    let title = format!("{}", &entry.title.as_str().replace("\n ", ""));
-    let id = extract_id(&ARXIV_RE, entry.id());
+/// Cross-references a published entry and its arXiv eprint, through `related` for biblatex and a
+/// `note` for plain BibTeX. The eprint's key is suffixed with its archive (arXiv unless
+/// `archivePrefix` says otherwise) if the two would otherwise clash.
+fn link_preprint(published: &mut Entry, eprint: &mut Entry, format: Format) {
+    let archive = eprint.get("archivePrefix").unwrap_or("arXiv").to_owned();
+    if eprint.key.eq_ignore_ascii_case(&published.key) {
+        eprint.key = format!("{}_{}", eprint.key, archive.replace(' ', ""));
+    }
+    match format {
+        Format::Biblatex => {
+            published.set("related", &eprint.key);
+            eprint.set("related", &published.key);
+        }
+        Format::Bibtex => {
+            let id = eprint.get("eprint").unwrap_or_default().to_owned();
+            published.append_note(&format!("Preprint: {}:{}", archive, id));
+            match published.get("doi").map(str::to_owned) {
+                Some(doi) => eprint.append_note(&format!("Published version: doi:{}", doi)),
+                None => {
+                    eprint.append_note(&format!("Published version: \\cite{{{}}}", published.key))
+                }
+            }
+        }
+    }
+}
 
-    let formatted = format!(
-        "@article{{{},title={{{}}},author={{{}}},year={{{}}},eprint={{{}}},archivePrefix={{arXiv}},primaryClass={{{}}}}}",
-        key, title, authors, year, id, class
-    );
+/// The DOI of the published version of a preprint DOI (bioRxiv, SSRN, Research Square, ...), if
+/// CrossRef records one. A relation to another preprint, such as another version of the same
+/// one, doesn't count.
+async fn published_version(entry: &Entry) -> Option<String> {
+    let doi = entry.get("doi")?.to_lowercase();
+    let published = crossref::published_version(&crossref::fetch_work(&doi).await?)?;
+    if published.eq_ignore_ascii_case(&doi) {
+        return None;
+    }
+    let work = crossref::fetch_work(&published.to_lowercase()).await?;
+    crossref::is_published(&work).then_some(published)
+}
 
-    print_doi(&formatted)
+/// Turns failed requests and error statuses from `resolver` into errors.
+fn check_response(
+    res: Result<Response, reqwest::Error>,
+    resolver: &str,
+) -> Result<Response, error::Error> {
+    let res = res.map_err(|e| {
+        error::Error::new(
+            Kind::Network,
+            i18n::message("unreachable", &[("resolver", &resolver), ("error", &e)]),
+        )
+    })?;
+    let status = res.status();
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        return Err(error::Error::new(
+            Kind::NotFound,
+            i18n::message("no-record", &[("resolver", &resolver)]),
+        ));
+    }
+    if !status.is_success() {
+        return Err(error::Error::new(
+            Kind::Service,
+            i18n::message(
+                "service-error",
+                &[("resolver", &resolver), ("status", &status)],
+            ),
+        ));
+    }
+    Ok(res)
 }
 
+/// Returns the formatted entries together with the resolver that actually produced each, which
+/// differs from `idtype` when an arXiv record is redirected to its published DOI. There is more
+/// than one entry only when `--both` keeps an eprint alongside its published version.
 #[async_recursion]
-async fn handle_response(res: Result<Response, reqwest::Error>, idtype: IdType) -> String {
-    if res.is_err() {
-        Error::with_description("Invalid DOI or arXiv ID!", ErrorKind::InvalidValue).exit();
-    }
-    let res = res.unwrap().text_with_charset("utf-8").await.unwrap();
+async fn handle_response(
+    res: Result<Response, reqwest::Error>,
+    idtype: IdType,
+    opts: &Options,
+) -> Result<Vec<Fetched>, error::Error> {
+    let res = check_response(res, idtype.resolver())?;
+    let url = res.url().to_string();
+    let res = res.text_with_charset("utf-8").await?;
     if res.contains("cannot be found") {
-        Error::with_description("Invalid DOI or arXiv ID!", ErrorKind::InvalidValue).exit();
+        return Err(error::Error::new(
+            Kind::NotFound,
+            i18n::message("no-record", &[("resolver", &idtype.resolver())]),
+        ));
     }
     match idtype {
-        IdType::Doi => print_doi(&res),
-        IdType::Arxiv => print_arxiv(&res.parse::<Feed>().unwrap()).await,
+        IdType::Doi => Ok(vec![Fetched::new(
+            IdType::Doi,
+            idtype.resolver(),
+            print_doi(&res)?,
+        )]),
+        IdType::Arxiv => {
+            let feed = feed::parse(&res).map_err(|e| {
+                error::Error::new(Kind::Parse, i18n::message("bad-feed", &[("error", &e)]))
+            })?;
+            print_arxiv(&feed, opts).await
+        }
+        IdType::Isbn => Ok(vec![Fetched::new(
+            IdType::Isbn,
+            idtype.resolver(),
+            print_isbn(&res)?,
+        )]),
+        IdType::Url => {
+            let entry = match podcast::from_page(&res, &url, opts.format).await {
+                Some(episode) => episode,
+                None => print_url(&res, &url, opts)?,
+            };
+            Ok(vec![Fetched::new(IdType::Url, idtype.resolver(), entry)])
+        }
     }
 }
 
-async fn get_bibtex(pat: String, escape: bool) -> String {
-    tokio::spawn(async move {
-        let (id, idtype) =
-            if DOI_IDENT_RE.is_match(&pat) || DOI_RE.iter().any(|re| re.is_match(&pat)) {
-                (extract_id(&DOI_RE, &pat), IdType::Doi)
-            } else if ARXIV_IDENT_RE.is_match(&pat) || ARXIV_RE.iter().any(|re| re.is_match(&pat)) {
-                (extract_id(&ARXIV_RE, &pat), IdType::Arxiv)
-            } else {
-                Error::with_description(
-                    "Please enter a valid DOI or arXiv ID!",
-                    ErrorKind::InvalidValue,
-                )
-                .exit();
-            };
-        let res = request_info(&id, idtype).await;
-        if escape {
-            handle_response(res, idtype).await.replace("&", "\\&")
+/// Rebuilds entries whose negotiated BibTeX is known to lose information (chapters, datasets,
+/// theses) from the richer CrossRef or DataCite JSON record of their DOI, or for figshare and
+/// Dryad datasets, from the repository's own record. `work` is the CrossRef record of the DOI, if
+/// it was fetched.
+async fn rebuild_from_registry(
+    entry: Entry,
+    work: Option<&serde_json::Value>,
+    opts: &Options,
+    provenance: &mut Provenance,
+) -> Entry {
+    let doi = match entry.get("doi") {
+        Some(doi) => doi.to_owned(),
+        None => return entry,
+    };
+    if crossref::is_book_like(&entry.kind) || thesis::is_thesis_like(&entry.kind) {
+        if let Some(work) = work {
+            let rebuilt = crossref::book_entry(work, &entry.key)
+                .or_else(|| thesis::from_crossref(work, &entry.key));
+            if let Some(rebuilt) = rebuilt {
+                provenance.replace(&rebuilt, resolvers::Backend::Crossref.host());
+                return rebuilt;
+            }
+        }
+    }
+    if datacite::is_dataset_like(&entry) {
+        if let Some(rebuilt) = repository::fetch(&doi, opts.format).await {
+            provenance.replace(&rebuilt, repository::host(&doi));
+            return rebuilt;
+        }
+    }
+    if datacite::is_dataset_like(&entry) || thesis::is_thesis_like(&entry.kind) {
+        if let Some(attrs) = datacite::fetch_doi(&doi).await {
+            let rebuilt = datacite::dataset_entry(&attrs, opts.format)
+                .or_else(|| thesis::from_datacite(&attrs));
+            if let Some(rebuilt) = rebuilt {
+                provenance.replace(&rebuilt, resolvers::Backend::Datacite.host());
+                return rebuilt;
+            }
+        }
+    }
+    entry
+}
+
+pub fn annotation(input: &str, source: &str) -> String {
+    format!(
+        "% {} fetched by d2b from {} on {}",
+        input.trim(),
+        source,
+        chrono::Local::today().format("%Y-%m-%d")
+    )
+}
+
+fn classify(pat: &str) -> Option<IdType> {
+    let pat = &text::percent_decode(pat);
+    if DOI_IDENT_RE.is_match(pat) || DOI_RE.iter().any(|re| re.is_match(pat)) {
+        Some(IdType::Doi)
+    } else if ARXIV_IDENT_RE.is_match(pat) || ARXIV_RE.iter().any(|re| re.is_match(pat)) {
+        Some(IdType::Arxiv)
+    } else if ISBN_RE.is_match(pat) {
+        Some(IdType::Isbn)
+    } else if pat.trim_start().starts_with("http://") || pat.trim_start().starts_with("https://") {
+        Some(IdType::Url)
+    } else {
+        None
+    }
+}
+
+/// A resolved entry, together with the input it came from and the resolver that produced it.
+#[derive(Debug, Clone)]
+pub struct Resolved {
+    pub input: String,
+    pub resolver: IdType,
+    /// The host or configured resolver the entry was fetched from.
+    pub source: String,
+    pub entry: Entry,
+    /// Other inputs that resolved to the same work and were merged into this one.
+    pub merged: Vec<String>,
+    /// The sources of the fields that didn't come from `source`.
+    pub provenance: Provenance,
+}
+
+/// What identifies the work behind an entry across inputs: its DOI, else its arXiv ID without the
+/// version, else its URL.
+pub fn work_id(entry: &Entry) -> Option<String> {
+    entry_ids(entry).into_iter().next()
+}
+
+/// Every identifier of the work behind an entry, in the forms [`work_id`] and [`input_id`] use, so
+/// that an existing entry can be matched to whichever input produced it.
+pub fn entry_ids(entry: &Entry) -> Vec<String> {
+    let mut ids = Vec::new();
+    if let Some(doi) = entry.get("doi") {
+        ids.push(format!("doi:{}", doi.to_lowercase()));
+    }
+    if let Some(eprint) = entry.get("eprint") {
+        ids.push(format!(
+            "arxiv:{}",
+            ARXIV_VERSION_RE.replace(eprint, "").to_lowercase()
+        ));
+    }
+    if let Some(url) = entry.get("url") {
+        ids.push(format!("url:{}", url.trim_end_matches('/').to_lowercase()));
+    }
+    if let Some(isbn) = entry.get("isbn") {
+        let digits = isbn
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>();
+        ids.push(format!("isbn:{}", digits.to_uppercase()));
+    }
+    ids
+}
+
+/// The identifier an input refers to, in the form [`entry_ids`] uses, without resolving it.
+pub fn input_id(pat: &str) -> Option<String> {
+    if let Some((registry, id)) = registries::Registry::parse(pat) {
+        return Some(registry.input_id(&id));
+    }
+    Some(match classify(pat)? {
+        IdType::Doi => format!("doi:{}", extract_doi(pat)?),
+        IdType::Arxiv => {
+            let id = extract_arxiv(pat)?;
+            format!("arxiv:{}", ARXIV_VERSION_RE.replace(&id, ""))
+        }
+        IdType::Isbn => format!("isbn:{}", extract_isbn(pat)?),
+        IdType::Url => format!("url:{}", pat.trim().trim_end_matches('/').to_lowercase()),
+    })
+}
+
+/// What two inputs share if they are the same input. DOIs are case-insensitive, so `10.1000/ABC`
+/// and `10.1000/abc` are one input, and registry records are compared by [`Registry::input_id`],
+/// but URLs and everything else are taken as given, since their case can matter.
+///
+/// [`Registry::input_id`]: registries::Registry::input_id
+pub fn input_key(pat: &str) -> String {
+    if let Some((registry, id)) = registries::Registry::parse(pat) {
+        return registry.input_id(&id);
+    }
+    match classify(pat) {
+        Some(IdType::Doi) => extract_doi(pat).map(|doi| format!("doi:{}", doi)),
+        _ => None,
+    }
+    .unwrap_or_else(|| pat.trim().to_owned())
+}
+
+/// Merges entries for the same work that came from different inputs, keeping the first.
+pub fn merge_duplicates(resolved: Vec<Resolved>) -> Vec<Resolved> {
+    let mut merged: Vec<Resolved> = Vec::new();
+    for r in resolved {
+        let id = work_id(&r.entry);
+        let existing = id.as_ref().and_then(|id| {
+            merged
+                .iter_mut()
+                .find(|m| work_id(&m.entry).as_ref() == Some(id))
+        });
+        match existing {
+            Some(existing) => {
+                if r.input != existing.input && !existing.merged.contains(&r.input) {
+                    existing.merged.push(r.input);
+                }
+            }
+            None => merged.push(r),
+        }
+    }
+    merged
+}
+
+/// Fetches the license of an arXiv eprint, which the search API used by `print_arxiv` omits, from
+/// arXiv's OAI-PMH interface.
+async fn arxiv_license(id: &str) -> Option<String> {
+    let body = CLIENT
+        .get("http://export.arxiv.org/oai2")
+        .query(&[
+            ("verb", "GetRecord"),
+            ("identifier", &format!("oai:arXiv.org:{}", id)),
+            ("metadataPrefix", "arXiv"),
+        ])
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    ARXIV_LICENSE_RE
+        .captures(&body)
+        .map(|c| c[1].trim().to_owned())
+}
+
+/// Adds the funding acknowledgements and license that the registries record for the entry, as
+/// requested by `--with-funding` and `--with-license`. `work` is the CrossRef record of its DOI, if
+/// it was fetched.
+async fn add_registry_metadata(
+    entry: &mut Entry,
+    work: Option<&serde_json::Value>,
+    resolver: IdType,
+    opts: &Options,
+    provenance: &mut Provenance,
+) {
+    let doi = entry.get("doi").map(str::to_lowercase);
+
+    if opts.with_funding {
+        if let Some(funding) = work.and_then(crossref::funding) {
+            match opts.format {
+                Format::Biblatex => {
+                    entry.set("funding", &funding);
+                    provenance.set("funding", resolvers::Backend::Crossref.host());
+                }
+                Format::Bibtex => entry.append_note(&format!("Funding: {}", funding)),
+            }
+        }
+    }
+
+    if opts.with_license && entry.get("license").is_none() {
+        let mut license = work
+            .and_then(crossref::license)
+            .map(|l| (l, resolvers::Backend::Crossref.host()));
+        if license.is_none() {
+            if let Some(doi) = &doi {
+                license = datacite::fetch_doi(doi)
+                    .await
+                    .and_then(|attrs| datacite::license(&attrs))
+                    .map(|l| (l, resolvers::Backend::Datacite.host()));
+            }
+        }
+        if license.is_none() && matches!(resolver, IdType::Arxiv) {
+            if let Some(id) = entry.get("eprint").map(str::to_owned) {
+                license = arxiv_license(&id)
+                    .await
+                    .map(|l| (l, resolvers::Backend::Arxiv.host()));
+            }
+        }
+        if let Some((license, source)) = license {
+            entry.set("license", &license);
+            provenance.set("license", source);
+        }
+    }
+}
+
+/// Fixes up, sanitizes and validates an entry before it is printed, crediting the fields it adds
+/// in `provenance`.
+pub async fn finish(
+    entry: Entry,
+    resolver: IdType,
+    pat: &str,
+    opts: &Options,
+    provenance: &mut Provenance,
+) -> Result<Entry, error::Error> {
+    // Fetched once, for both rebuilding the entry and the metadata asked for with --with-funding
+    // and --with-license.
+    let wants_work = crossref::is_book_like(&entry.kind)
+        || thesis::is_thesis_like(&entry.kind)
+        || opts.with_funding
+        || opts.with_license;
+    let work = match entry.get("doi") {
+        Some(doi) if wants_work => crossref::fetch_work(&doi.to_lowercase()).await,
+        _ => None,
+    };
+    let mut entry = rebuild_from_registry(entry, work.as_ref(), opts, provenance).await;
+    let before = entry.clone();
+    if let Some(p) = entry.get("doi").and_then(publisher::Publisher::from_doi) {
+        // Only conference papers have a venue acronym worth asking DBLP for.
+        let venue = if opts.dblp && publisher::is_conference(&entry) {
+            publisher::dblp_venue(&entry.get("doi").unwrap().to_lowercase()).await
         } else {
-            handle_response(res, idtype).await
+            None
+        };
+        publisher::fix_conference(&mut entry, p, venue.as_deref());
+        let source = if venue.is_some() { "dblp.org" } else { D2B };
+        provenance.record(&before, &entry, source);
+    }
+    if opts.romanize {
+        let before = entry.clone();
+        language::romanize_entry(&mut entry, opts.format);
+        provenance.record(&before, &entry, D2B);
+    }
+    if opts.with_funding || opts.with_license {
+        add_registry_metadata(&mut entry, work.as_ref(), resolver, opts, provenance).await;
+    }
+    // arXiv titles are cleaned up when the entry is built.
+    if opts.sanitize && !matches!(resolver, IdType::Arxiv) {
+        bibtex::sanitize(&mut entry);
+    }
+    if opts.escape {
+        bibtex::escape_fields(&mut entry, &['&']);
+    }
+    if let Some(macros) = &opts.journal_strings {
+        macros.apply(&mut entry);
+    }
+    let before = entry.clone();
+    config::add_fields(&mut entry, &opts.fields, pat);
+    provenance.record(&before, &entry, "config");
+    if let Some(cmd) = &opts.post_hook {
+        let before = entry.clone();
+        entry = hook::run(cmd, &entry, pat).await?;
+        provenance.record(&before, &entry, "hook");
+    }
+    let before = entry.clone();
+    let problems = bibtex::validate(&mut entry, !opts.strict);
+    provenance.record(&before, &entry, D2B);
+    for problem in &problems {
+        eprintln!("{}: {}: {}", i18n::text("warning"), entry.key, problem);
+    }
+    if opts.strict && !problems.is_empty() {
+        return Err(error::Error::new(
+            Kind::Validation,
+            i18n::text("invalid-entry"),
+        ));
+    }
+    Ok(entry)
+}
+
+/// Resolves a single input into one or more entries (several when `--with-papers` expands a
+/// proceedings volume). Errors carry the input they happened for.
+pub async fn resolve(pat: String, opts: Options) -> Result<Vec<Resolved>, error::Error> {
+    let input = pat.clone();
+    tokio::spawn(async move {
+        let fetched = match opts.custom.iter().find(|c| c.matches(&pat)) {
+            Some(custom) => custom.fetch(&pat).await?,
+            None if registries::Registry::parse(&pat).is_some() => {
+                let (registry, id) = registries::Registry::parse(&pat).unwrap();
+                registry.fetch(&id, &opts).await?
+            }
+            None => {
+                let idtype = classify(&pat).ok_or_else(|| {
+                    error::Error::new(Kind::InvalidInput, i18n::text("invalid-input"))
+                })?;
+                if opts.strict_ids && !is_exact(&pat, idtype) {
+                    return Err(error::Error::new(
+                        Kind::InvalidInput,
+                        i18n::text("inexact-identifier"),
+                    ));
+                }
+                let id = match idtype {
+                    IdType::Doi => extract_doi(&pat),
+                    IdType::Arxiv => extract_arxiv(&pat),
+                    IdType::Isbn => extract_isbn(&pat),
+                    IdType::Url => Some(pat.trim().to_owned()),
+                }
+                .ok_or_else(|| {
+                    error::Error::new(Kind::InvalidInput, i18n::text("malformed-identifier"))
+                })?;
+                match idtype {
+                    // A bare number is only a guess at an arXiv ID.
+                    IdType::Arxiv if !ARXIV_IDENT_RE.is_match(&pat) && !id.contains('/') => {
+                        confirm_arxiv(&id, &opts).await?
+                    }
+                    _ => resolvers::resolve(&id, idtype, &opts).await?,
+                }
+            }
+        };
+        let mut resolved = Vec::new();
+        for fetched in fetched {
+            let (resolver, source) = (fetched.idtype, fetched.source);
+            let mut provenance = fetched.provenance;
+            let mut entry = fetched.entry;
+            let hash = opts
+                .hash
+                .then(|| digest::Stamp::new(&entry, &source, opts.format).to_string());
+            if opts.archive && matches!(resolver, IdType::Url) && entry.get("doi").is_none() {
+                let snapshot = archive::snapshot(pat.trim()).await;
+                if snapshot.is_none() {
+                    eprintln!(
+                        "{}: {}",
+                        i18n::text("warning"),
+                        i18n::message("no-snapshot", &[("url", &pat.trim())])
+                    );
+                }
+                let today = chrono::Local::today().format("%Y-%m-%d").to_string();
+                let before = entry.clone();
+                archive::add_snapshot(&mut entry, snapshot.as_deref(), &today, opts.format);
+                provenance.record(&before, &entry, "web.archive.org");
+            }
+            let mut entry = finish(entry, resolver, &pat, &opts, &mut provenance).await?;
+            if let Some(hash) = hash {
+                entry.set(digest::FIELD, &hash);
+                provenance.set(digest::FIELD, D2B);
+            }
+
+            let mut papers = Vec::new();
+            if opts.with_papers && entry.kind.eq_ignore_ascii_case("proceedings") {
+                if let Some(doi) = entry.get("doi") {
+                    papers = crossref::proceedings_papers(&doi.to_lowercase()).await;
+                }
+                // Papers of one volume often have first authors with the same family name.
+                bibtex::disambiguate_keys(&mut papers);
+            }
+
+            resolved.push(Resolved {
+                input: pat.clone(),
+                resolver,
+                source,
+                entry,
+                merged: Vec::new(),
+                provenance,
+            });
+            for paper in papers {
+                let mut provenance = Provenance::default();
+                let key = paper.key.clone();
+                // One paper failing --strict or its --post-hook leaves the rest of the volume.
+                let entry = match finish(paper, IdType::Isbn, &pat, &opts, &mut provenance).await {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        eprintln!("{}: {}", i18n::text("warning"), e.with_input(&key));
+                        continue;
+                    }
+                };
+                resolved.push(Resolved {
+                    input: pat.clone(),
+                    resolver: IdType::Isbn,
+                    source: IdType::Isbn.resolver().to_owned(),
+                    entry,
+                    merged: Vec::new(),
+                    provenance,
+                });
+            }
         }
+        Ok(resolved)
     })
     .await
     .unwrap()
+    .map_err(|e: error::Error| e.with_input(&input))
+}
+
+/// Formats a resolved entry for output.
+pub fn render(resolved: &Resolved, opts: &Options) -> String {
+    let mut out = String::new();
+    if opts.annotate {
+        out.push_str(&annotation(&resolved.input, &resolved.source));
+        out.push('\n');
+    }
+    if !resolved.merged.is_empty() {
+        out.push_str(&format!(
+            "% also requested as: {}\n",
+            resolved.merged.join(", ")
+        ));
+    }
+    let formatted = match (opts.style, &opts.template, &opts.formatter) {
+        (Some(style), _, _) => Some(Ok(style.format(&resolved.entry))),
+        (None, Some(template), _) => Some(template.render(resolved)),
+        (None, None, Some(formatter)) => Some(formatter.format(&resolved.entry, &resolved.input)),
+        (None, None, None) => None,
+    };
+    let formatted = formatted.and_then(|f| {
+        f.map_err(|e| eprintln!("{}: {}", i18n::text("warning"), e))
+            .ok()
+    });
+    match formatted {
+        Some(formatted) => out.push_str(&formatted),
+        None => out.push_str(&resolved.entry.to_string()),
+    }
+    out
+}
+
+pub async fn get_bibtex(pat: String, opts: Options) -> Result<String, error::Error> {
+    Ok(resolve(pat, opts.clone())
+        .await?
+        .iter()
+        .map(|r| render(r, &opts))
+        .collect::<Vec<_>>()
+        .join("\n\n"))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// `entry` as if resolved from `input` through doi.org.
+    fn resolved(input: &str, entry: Entry) -> Resolved {
+        Resolved {
+            input: input.to_owned(),
+            resolver: IdType::Doi,
+            source: "doi.org".to_owned(),
+            entry,
+            merged: Vec::new(),
+            provenance: Provenance::default(),
+        }
+    }
+
     #[test]
     fn test_extract_arxiv_id() {
-        let inputs = vec![
+        let inputs = [
             "arxiv:2105.11572",
             "https://arxiv.org/abs/1912.02599v2",
             "2105.11572",
@@ -255,14 +2495,19 @@ mod tests {
             "math/0506203",
             "hep-th/9910001",
             "https://arxiv.org/abs/hep-th/9910001v2",
+            "arXiv: 1712.01815 v2",
+            "(arXiv:1712.01815V1)",
+            "arXiv:math.AG/0501001",
+            "HEP-TH/9910001",
+            "cond-mat.str-el/0501001v3",
         ];
 
         let extracted_ids = inputs
             .iter()
-            .map(|pat| extract_id(&ARXIV_RE, pat))
+            .map(|pat| extract_arxiv(pat).unwrap())
             .collect::<Vec<_>>();
 
-        let true_ids = vec![
+        let true_ids = [
             "2105.11572",
             "1912.02599v2",
             "2105.11572",
@@ -270,8 +2515,233 @@ mod tests {
             "math/0506203",
             "hep-th/9910001",
             "hep-th/9910001v2",
+            "1712.01815v2",
+            "1712.01815v1",
+            "math/0501001",
+            "hep-th/9910001",
+            "cond-mat/0501001v3",
+        ];
+
+        assert_eq!(extracted_ids, true_ids);
+        assert!(matches!(
+            classify("arXiv : 1712.01815"),
+            Some(IdType::Arxiv)
+        ));
+        assert!(plausible_arxiv("2105.11572v2") && plausible_arxiv("0704.0001"));
+        assert!(!plausible_arxiv("1234.5678") && !plausible_arxiv("2105.1157"));
+        assert!(!plausible_arxiv("0612.1234") && !plausible_arxiv("1412.12345"));
+    }
+
+    #[test]
+    fn test_extract_doi() {
+        let inputs = [
+            "doi:10.18637/jss.v076.i01",
+            "https://dx.doi.org/10.18637/JSS.V076.I01",
+            "DOI:10.18637/jss.v076.i01",
+            "https://doi.org/10.18637%2Fjss.v076.i01",
+            "https://onlinelibrary.wiley.com/doi/10.1002/andp.19053220607",
+            "10.1002/(SICI)1097-4636(199706)35:4<467::AID-JBM6>3.0.CO;2-F",
+            "(see doi:doi:10.1145/359545.359563).",
+            "\"https://doi.org/10.1007/978-3-540-45069-6_1\",",
+        ];
+
+        let extracted_ids = inputs
+            .iter()
+            .map(|pat| extract_doi(pat).unwrap())
+            .collect::<Vec<_>>();
+
+        let true_ids = [
+            "10.18637/jss.v076.i01",
+            "10.18637/jss.v076.i01",
+            "10.18637/jss.v076.i01",
+            "10.18637/jss.v076.i01",
+            "10.1002/andp.19053220607",
+            "10.1002/(sici)1097-4636(199706)35:4<467::aid-jbm6>3.0.co;2-f",
+            "10.1145/359545.359563",
+            "10.1007/978-3-540-45069-6_1",
         ];
 
         assert_eq!(extracted_ids, true_ids);
+        assert!(is_exact(inputs[5], IdType::Doi) && is_exact(inputs[3], IdType::Doi));
+        assert!(!is_exact(inputs[6], IdType::Doi) && !is_exact("10.1000/xyz.", IdType::Doi));
+        assert!(is_exact("arXiv:hep-th/9910001v2", IdType::Arxiv));
+        assert!(!is_exact("see arXiv:1712.01815", IdType::Arxiv));
+        assert!(inputs
+            .iter()
+            .all(|pat| matches!(classify(pat), Some(IdType::Doi))));
+    }
+
+    #[test]
+    fn test_extract_isbn() {
+        let inputs = [
+            "isbn:978-3-030-58452-8",
+            "ISBN-13: 978 3 030 58452 8",
+            "9783030584528",
+            "ISBN 0-262-03384-X",
+        ];
+        let extracted = inputs
+            .iter()
+            .map(|pat| extract_isbn(pat).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            extracted,
+            [
+                "9783030584528",
+                "9783030584528",
+                "9783030584528",
+                "026203384X"
+            ]
+        );
+        assert!(inputs
+            .iter()
+            .all(|pat| matches!(classify(pat), Some(IdType::Isbn))));
+        assert!(matches!(
+            classify("https://link.springer.com/book/10.1007/978-3-030-58452-8"),
+            Some(IdType::Doi)
+        ));
+    }
+
+    #[test]
+    fn test_link_preprint() {
+        let mut published = Entry::new("article", "Silver_2018");
+        published.set("doi", "10.1126/science.aar6404");
+        let mut eprint = Entry::new("article", "Silver_2018");
+        eprint.set("eprint", "1712.01815");
+
+        let (mut p, mut e) = (published.clone(), eprint.clone());
+        link_preprint(&mut p, &mut e, Format::Bibtex);
+        assert_eq!(e.key, "Silver_2018_arXiv");
+        assert_eq!(p.get("note"), Some("Preprint: arXiv:1712.01815"));
+        assert_eq!(
+            e.get("note"),
+            Some("Published version: doi:10.1126/science.aar6404")
+        );
+
+        link_preprint(&mut published, &mut eprint, Format::Biblatex);
+        assert_eq!(published.get("related"), Some("Silver_2018_arXiv"));
+        assert_eq!(eprint.get("related"), Some("Silver_2018"));
+    }
+
+    #[test]
+    fn test_merge_duplicates() {
+        let with_id = |input: &str, field: &str, value: &str| {
+            let mut entry = Entry::new("article", "Shen_2021");
+            entry.set(field, value);
+            resolved(input, entry)
+        };
+        let merged = merge_duplicates(vec![
+            with_id("2105.11572", "doi", "10.1000/ABC"),
+            with_id("10.1000/abc", "doi", "10.1000/abc"),
+            with_id("arxiv:2105.11572v2", "eprint", "2105.11572v2"),
+            with_id("2105.11572v1", "eprint", "2105.11572v1"),
+            with_id("1111.4246", "eprint", "1111.4246"),
+        ]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].merged, ["10.1000/abc"]);
+        assert_eq!(merged[1].merged, ["2105.11572v1"]);
+        assert!(merged[2].merged.is_empty());
+        assert!(render(
+            &merged[0],
+            &Options::from_matches(&ArgMatches::default(), &Config::default())
+        )
+        .starts_with("% also requested as: 10.1000/abc\n@article{Shen_2021,"));
+    }
+
+    #[test]
+    fn test_input_key() {
+        assert_eq!(
+            input_key("10.1000/ABC"),
+            input_key("https://doi.org/10.1000/abc")
+        );
+        assert_eq!(input_key("PMC1234567"), input_key("pmc1234567"));
+        assert_ne!(
+            input_key("gbooks:AbCdEfGhIjK"),
+            input_key("gbooks:abcdefghijk")
+        );
+        assert_ne!(
+            input_key("https://youtu.be/dQw4w9WgXcQ"),
+            input_key("https://youtu.be/dqw4w9wgxcq")
+        );
+        assert_ne!(
+            input_key("https://example.com/Paper.pdf"),
+            input_key("https://example.com/paper.pdf")
+        );
+    }
+
+    #[test]
+    fn test_cite_commands() {
+        let commands = CiteStyle::NAMES
+            .iter()
+            .map(|n| CiteStyle::from_name(n).unwrap().command("Smith_2019"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            commands,
+            ["\\cite{Smith_2019}", "#cite(<Smith_2019>)", "@Smith_2019"]
+        );
+    }
+
+    #[test]
+    fn test_group_by() {
+        let dated = |kind: &str, year: Option<&str>| {
+            let mut entry = Entry::new(kind, "x");
+            if let Some(year) = year {
+                entry.set("year", year);
+            }
+            resolved("", entry)
+        };
+        let mut entries = vec![
+            dated("Article", None),
+            dated("book", Some("2023")),
+            dated("article", Some("2019")),
+            dated("misc", Some("2023")),
+        ];
+        GroupBy::Year.sort(&mut entries);
+        let labels = |entries: &[Resolved], g: GroupBy| {
+            entries
+                .iter()
+                .map(|r| g.label(&r.entry))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            labels(&entries, GroupBy::Year),
+            ["2019", "2023", "2023", "undated"]
+        );
+        assert_eq!(entries[1].entry.kind, "book");
+        GroupBy::Type.sort(&mut entries);
+        assert_eq!(
+            labels(&entries, GroupBy::Type),
+            ["article", "article", "book", "misc"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by() {
+        let authored = |input: &str, key: &str, author: &str, year: &str| {
+            let mut entry = Entry::new("article", key);
+            entry.set("author", author);
+            entry.set("year", year);
+            resolved(input, entry)
+        };
+        let mut entries = vec![
+            authored("b", "Zhu_2019", "Zhu, Wei", "2019"),
+            authored("a", "abe_2021", "Abe, Shinzo and Zhu, Wei", "2021"),
+            authored("c", "Lamport_1978", "Leslie Lamport", "1978"),
+        ];
+        let keys = |entries: &[Resolved]| {
+            entries
+                .iter()
+                .map(|r| r.entry.key.clone())
+                .collect::<Vec<_>>()
+        };
+        let inputs = ["c", "a", "b"].map(str::to_owned);
+
+        SortBy::Input.sort(&mut entries, &inputs);
+        assert_eq!(keys(&entries), ["Lamport_1978", "abe_2021", "Zhu_2019"]);
+        SortBy::Key.sort(&mut entries, &inputs);
+        assert_eq!(keys(&entries), ["abe_2021", "Lamport_1978", "Zhu_2019"]);
+        SortBy::Year.sort(&mut entries, &inputs);
+        assert_eq!(keys(&entries), ["Lamport_1978", "Zhu_2019", "abe_2021"]);
+        SortBy::Author.sort(&mut entries, &inputs);
+        assert_eq!(keys(&entries), ["abe_2021", "Lamport_1978", "Zhu_2019"]);
     }
 }
@@ -0,0 +1,295 @@
+//! `d2b mcp`: a Model Context Protocol server on stdin and stdout, so that LLM assistants can call
+//! d2b's resolvers as tools: `resolve_citation` turns an identifier into BibTeX (formatted by the
+//! global options, like any other output), and `search_papers` finds works by their title, authors
+//! or other bibliographic details through CrossRef, with the DOIs to resolve them by.
+//!
+//! Messages are JSON-RPC 2.0, one per line. Requests are answered one at a time, in order.
+
+use crate::{crossref, i18n, render, resolve, Options};
+use clap::{App, SubCommand};
+use serde_json::{json, Value};
+use std::io::Write;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// The protocol version answered to clients that don't ask for one.
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const DEFAULT_RESULTS: u64 = 5;
+const MAX_RESULTS: u64 = 20;
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("mcp").about(i18n::text("about-mcp"))
+}
+
+/// A tool call, with its arguments checked.
+#[derive(Debug, PartialEq, Eq)]
+enum Call {
+    Resolve(String),
+    Search(String, u64),
+}
+
+/// What to do with a message: answer it straight away (or not at all, for notifications), or make
+/// a tool call and answer the request with the given ID with its result.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Reply(Option<Value>),
+    Call(Value, Call),
+}
+
+fn result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// A tool's result, as the text the assistant reads.
+fn content(text: &str, is_error: bool) -> Value {
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+fn tools() -> Value {
+    json!({ "tools": [
+        {
+            "name": "resolve_citation",
+            "description": "Resolves a DOI, arXiv ID, ISBN, PubMed ID, URL or other identifier of a work into its BibTeX entry.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string", "description": "The identifier, e.g. 10.1145/359576.359585 or arXiv:1712.01815." }
+                },
+                "required": ["id"]
+            }
+        },
+        {
+            "name": "search_papers",
+            "description": "Searches CrossRef for works matching a title, authors or other bibliographic details, listing the DOI, title, year, authors and venue of each.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "The bibliographic details to search for." },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": MAX_RESULTS, "description": "How many works to list; 5 by default." }
+                },
+                "required": ["query"]
+            }
+        }
+    ]})
+}
+
+/// The tool call a `tools/call` request asks for.
+fn call(params: &Value) -> Result<Call, String> {
+    let args = &params["arguments"];
+    let string = |name: &str| {
+        args[name]
+            .as_str()
+            .filter(|s| !s.trim().is_empty())
+            .map(str::to_owned)
+            .ok_or_else(|| format!("missing string argument `{}`", name))
+    };
+    match params["name"].as_str() {
+        Some("resolve_citation") => Ok(Call::Resolve(string("id")?)),
+        Some("search_papers") => {
+            let limit = match &args["limit"] {
+                Value::Null => DEFAULT_RESULTS,
+                limit => limit
+                    .as_u64()
+                    .filter(|l| (1..=MAX_RESULTS).contains(l))
+                    .ok_or_else(|| format!("`limit` must be from 1 to {}", MAX_RESULTS))?,
+            };
+            Ok(Call::Search(string("query")?, limit))
+        }
+        Some(name) => Err(format!("unknown tool `{}`", name)),
+        None => Err("missing tool name".to_owned()),
+    }
+}
+
+/// What to do with a message.
+fn handle(message: &Value) -> Action {
+    let id = match message.get("id") {
+        Some(id) => id.clone(),
+        // Notifications, like `notifications/initialized`, get no answer.
+        None => return Action::Reply(None),
+    };
+    let params = &message["params"];
+    let reply = match message["method"].as_str().unwrap_or("") {
+        "initialize" => {
+            let version = params["protocolVersion"]
+                .as_str()
+                .unwrap_or(PROTOCOL_VERSION);
+            result(
+                id,
+                json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": { "name": "d2b", "version": env!("CARGO_PKG_VERSION") }
+                }),
+            )
+        }
+        "ping" => result(id, json!({})),
+        "tools/list" => result(id, tools()),
+        "tools/call" => match call(params) {
+            Ok(call) => return Action::Call(id, call),
+            Err(e) => error(id, INVALID_PARAMS, &e),
+        },
+        method => error(
+            id,
+            METHOD_NOT_FOUND,
+            &format!("unknown method `{}`", method),
+        ),
+    };
+    Action::Reply(Some(reply))
+}
+
+/// A line describing a work CrossRef found.
+fn search_line(work: &Value) -> Option<String> {
+    let doi = crossref::first_str(work, "DOI")?;
+    let mut line = format!(
+        "{}: {}",
+        doi,
+        crossref::first_str(work, "title").unwrap_or("(untitled)")
+    );
+    if let Some(year) = crossref::year(work) {
+        line.push_str(&format!(" ({})", year));
+    }
+    let authors = work["author"].as_array().map(Vec::as_slice).unwrap_or(&[]);
+    let mut families = authors
+        .iter()
+        .take(3)
+        .filter_map(|a| a["family"].as_str().or_else(|| a["name"].as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if authors.len() > 3 {
+        families.push_str(" et al.");
+    }
+    if !families.is_empty() {
+        line.push_str(&format!(", {}", families));
+    }
+    if let Some(venue) = crossref::first_str(work, "container-title") {
+        line.push_str(&format!(", {}", venue));
+    }
+    Some(line)
+}
+
+async fn search(query: &str, limit: u64) -> Value {
+    match crossref::search(query, limit).await {
+        Ok(works) => {
+            let lines = works.iter().filter_map(search_line).collect::<Vec<_>>();
+            match lines.is_empty() {
+                true => content("No works found.", false),
+                false => content(&lines.join("\n"), false),
+            }
+        }
+        Err(status) => {
+            let reason = status.map_or("no answer".to_owned(), |s| s.to_string());
+            content(&format!("CrossRef search failed: {}", reason), true)
+        }
+    }
+}
+
+async fn run_call(call: Call, opts: &Options) -> Value {
+    match call {
+        Call::Resolve(id) => match resolve(id, opts.clone()).await {
+            Ok(resolved) => {
+                let entries = resolved.iter().map(|r| render(r, opts)).collect::<Vec<_>>();
+                content(&entries.join("\n\n"), false)
+            }
+            Err(e) => content(&e.to_string(), true),
+        },
+        Call::Search(query, limit) => search(&query, limit).await,
+    }
+}
+
+/// Runs `d2b mcp` until stdin is closed.
+pub async fn run(opts: Options) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let stdout = std::io::stdout();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => match handle(&message) {
+                Action::Reply(reply) => reply,
+                Action::Call(id, call) => Some(result(id, run_call(call, &opts).await)),
+            },
+            Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+        };
+        if let Some(reply) = reply {
+            let mut out = stdout.lock();
+            if writeln!(out, "{}", reply)
+                .and_then(|_| out.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle() {
+        let reply = |message: Value| match handle(&message) {
+            Action::Reply(reply) => reply,
+            Action::Call(..) => panic!("unexpected tool call"),
+        };
+        let init = reply(json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}));
+        assert_eq!(init.unwrap()["result"]["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(
+            reply(json!({"jsonrpc": "2.0", "method": "notifications/initialized"})),
+            None
+        );
+        let list = reply(json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"})).unwrap();
+        assert_eq!(list["result"]["tools"][0]["name"], "resolve_citation");
+        let unknown = reply(json!({"jsonrpc": "2.0", "id": 3, "method": "resources/list"}));
+        assert_eq!(unknown.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+
+        let call = |arguments: Value| {
+            handle(&json!({
+                "jsonrpc": "2.0",
+                "id": "c",
+                "method": "tools/call",
+                "params": { "name": "search_papers", "arguments": arguments }
+            }))
+        };
+        assert_eq!(
+            call(json!({"query": "communicating sequential processes"})),
+            Action::Call(
+                json!("c"),
+                Call::Search(
+                    "communicating sequential processes".to_owned(),
+                    DEFAULT_RESULTS
+                )
+            )
+        );
+        match call(json!({"query": "csp", "limit": 100})) {
+            Action::Reply(Some(e)) => assert_eq!(e["error"]["code"], INVALID_PARAMS),
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_search_line() {
+        let work = json!({
+            "DOI": "10.1145/359576.359585",
+            "title": ["Communicating sequential processes"],
+            "author": [{"given": "C. A. R.", "family": "Hoare"}],
+            "issued": {"date-parts": [[1978, 8]]},
+            "container-title": ["Communications of the ACM"]
+        });
+        assert_eq!(
+            search_line(&work).unwrap(),
+            "10.1145/359576.359585: Communicating sequential processes (1978), Hoare, \
+             Communications of the ACM"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! `d2b merge`: combines `.bib` files, merging entries for the same work (by DOI, arXiv ID or
+//! title) and renaming entries for different works that share a key.
+//!
+//! Of two entries for the same work, the one with more fields wins, keeping its key, and the
+//! other only fills in the fields it lacks.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{Error, Kind};
+use crate::{atomic, enrich, entry_ids, i18n, notes, Options};
+use clap::ArgMatches;
+use std::collections::HashMap;
+
+/// Titles shorter than this, once normalized, are too generic ("Introduction") to match on.
+const MIN_TITLE_LEN: usize = 16;
+
+/// The outcome of a merge, for reporting.
+#[derive(Debug, Default, PartialEq)]
+pub struct Merged {
+    pub entries: Vec<Entry>,
+    /// The key that was kept and the key that was merged into it, for each duplicate.
+    pub duplicates: Vec<(String, String)>,
+    /// Keys that named different works, with what they were renamed to.
+    pub renamed: Vec<(String, Vec<String>)>,
+}
+
+/// A title reduced to lowercase letters and digits, so that case, braces and punctuation don't
+/// keep duplicates apart.
+pub fn normalized_title(entry: &Entry) -> Option<String> {
+    let title = notes::plain(entry.get("title")?)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect::<String>();
+    Some(title).filter(|t| t.chars().count() >= MIN_TITLE_LEN)
+}
+
+/// What identifies an entry's work for merging: its DOI and arXiv ID, and its title.
+fn merge_ids(entry: &Entry) -> Vec<String> {
+    let mut ids = entry_ids(entry)
+        .into_iter()
+        .filter(|id| id.starts_with("doi:") || id.starts_with("arxiv:"))
+        .collect::<Vec<_>>();
+    if let Some(title) = normalized_title(entry) {
+        ids.push(format!("title:{}", title));
+    }
+    ids
+}
+
+/// Whether two entries have different DOIs, or different arXiv IDs, and so are for different works
+/// however alike their titles are.
+fn conflicting_ids(a: &Entry, b: &Entry) -> bool {
+    let (a, b) = (merge_ids(a), merge_ids(b));
+    ["doi:", "arxiv:"].iter().any(|scheme| {
+        let ids = |ids: &[String]| {
+            ids.iter()
+                .filter(|id| id.starts_with(scheme))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let (a, b) = (ids(&a), ids(&b));
+        !a.is_empty() && !b.is_empty() && !a.iter().any(|id| b.contains(id))
+    })
+}
+
+fn completeness(entry: &Entry) -> usize {
+    entry
+        .fields
+        .iter()
+        .filter(|(_, v)| !v.as_str().trim().is_empty())
+        .count()
+}
+
+/// Merges entries for the same work, in the order they were first seen, then gives keys shared by
+/// different works `a`, `b`, ... suffixes.
+pub fn merge(entries: Vec<Entry>) -> Merged {
+    let mut merged = Merged::default();
+    let mut by_id = HashMap::new();
+    for entry in entries {
+        let ids = merge_ids(&entry);
+        let same = ids.iter().find_map(|id| {
+            let n = by_id.get(id).copied()?;
+            // A preface or an editorial can share its title with a different work.
+            let title_only =
+                id.starts_with("title:") && conflicting_ids(&entry, &merged.entries[n]);
+            Some(n).filter(|_| !title_only)
+        });
+        let n = match same {
+            Some(n) => {
+                let existing: &mut Entry = &mut merged.entries[n];
+                let (mut base, other) = if completeness(&entry) > completeness(existing) {
+                    (entry, existing.clone())
+                } else {
+                    (existing.clone(), entry)
+                };
+                enrich::fill(&mut base, &other, false);
+                merged
+                    .duplicates
+                    .push((base.key.clone(), other.key.clone()));
+                *existing = base;
+                n
+            }
+            None => {
+                merged.entries.push(entry);
+                merged.entries.len() - 1
+            }
+        };
+        for id in merge_ids(&merged.entries[n]).into_iter().chain(ids) {
+            by_id.entry(id).or_insert(n);
+        }
+    }
+
+    // Over all the entries, so that no suffixed key is one another entry already has.
+    let keys = merged
+        .entries
+        .iter()
+        .map(|e| e.key.clone())
+        .collect::<Vec<_>>();
+    bibtex::disambiguate_keys(&mut merged.entries);
+    for (n, key) in keys.iter().enumerate() {
+        if merged.entries[n].key == *key || keys[..n].contains(key) {
+            continue;
+        }
+        let renamed = (n..keys.len())
+            .filter(|&m| keys[m] == *key)
+            .map(|m| merged.entries[m].key.clone())
+            .collect();
+        merged.renamed.push((key.clone(), renamed));
+    }
+    merged
+}
+
+/// Runs `d2b merge`.
+pub fn run(matches: &ArgMatches, opts: Options) {
+    let files = matches.values_of("files").unwrap().collect::<Vec<_>>();
+    let mut entries = Vec::new();
+    // `@string`s and `@preamble`s are kept, once each, ahead of the entries.
+    let mut preamble = Vec::new();
+    for file in &files {
+        let src = std::fs::read_to_string(file).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unreadable", &[("file", file), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        });
+        for chunk in bibtex::split_file(&src) {
+            match chunk.entry {
+                Some(entry) => entries.push(entry),
+                None if chunk.text.starts_with('@') => {
+                    let text = chunk.text.trim().to_owned();
+                    if !preamble.contains(&text) {
+                        preamble.push(text);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+
+    let merged = merge(entries);
+    for (kept, dropped) in &merged.duplicates {
+        eprintln!(
+            "{}",
+            i18n::message("merge-duplicate", &[("kept", kept), ("dropped", dropped)])
+        );
+    }
+    for (key, renamed) in &merged.renamed {
+        eprintln!(
+            "{}: {}",
+            i18n::text("warning"),
+            i18n::message(
+                "merge-key-conflict",
+                &[("key", key), ("keys", &renamed.join(", "))]
+            )
+        );
+    }
+
+    let out = preamble
+        .into_iter()
+        .chain(merged.entries.iter().map(Entry::to_string))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        + "\n";
+    match matches.value_of("output") {
+        Some(path) => atomic::write(path, &out).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &path), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        }),
+        None => print!("{}", out),
+    }
+    eprintln!(
+        "{}",
+        i18n::message(
+            "merge-summary",
+            &[
+                ("entries", &merged.entries.len()),
+                ("files", &files.len()),
+                ("duplicates", &merged.duplicates.len()),
+            ],
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let entries = [
+            "@article{Lamport_1978, title={Time, Clocks, and the Ordering of Events}, doi={10.1145/359545.359563}}",
+            "@article{Doe_2020, title={Zebras}}",
+            "@article{lamport78, title={Time, clocks and the ordering of events}, journal={CACM}, year={1978}}",
+            "@misc{Doe_2020, title={Stripes}}",
+            "@article{L, title={Other}, doi={10.1145/359545.359563}}",
+        ]
+        .iter()
+        .map(|e| bibtex::parse_entry(e).unwrap())
+        .collect::<Vec<_>>();
+
+        let merged = merge(entries);
+        let keys = merged
+            .entries
+            .iter()
+            .map(|e| e.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["lamport78", "Doe_2020a", "Doe_2020b"]);
+        assert_eq!(merged.entries[0].get("doi"), Some("10.1145/359545.359563"));
+        assert_eq!(merged.entries[0].get("journal"), Some("CACM"));
+        assert_eq!(
+            merged.duplicates,
+            [
+                ("lamport78".to_owned(), "Lamport_1978".to_owned()),
+                ("lamport78".to_owned(), "L".to_owned())
+            ]
+        );
+        assert_eq!(
+            merged.renamed,
+            [(
+                "Doe_2020".to_owned(),
+                vec!["Doe_2020a".to_owned(), "Doe_2020b".to_owned()]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_same_title_different_dois() {
+        let entries = [
+            "@article{Smith_2020, title={Special issue on machine learning}, doi={10.1000/a}}",
+            "@article{Jones_2021, title={Special Issue on Machine Learning}, doi={10.1000/b}}",
+            "@article{Doe_2021, title={Special issue on machine learning}, year={2021}}",
+        ]
+        .iter()
+        .map(|e| bibtex::parse_entry(e).unwrap())
+        .collect::<Vec<_>>();
+
+        let merged = merge(entries);
+        assert_eq!(merged.entries.len(), 2);
+        assert_eq!(merged.entries[1].get("doi"), Some("10.1000/b"));
+        assert_eq!(
+            merged.duplicates,
+            [("Smith_2020".to_owned(), "Doe_2021".to_owned())]
+        );
+    }
+}
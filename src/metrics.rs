@@ -0,0 +1,154 @@
+//! What a batch run did: how many inputs resolved, how long each host took to answer. Printed with
+//! `--summary`, and written in the Prometheus text format with `--metrics-file` for a node
+//! exporter's textfile collector to pick up.
+
+use crate::i18n;
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+lazy_static! {
+    static ref LATENCIES: Mutex<BTreeMap<String, Vec<Duration>>> = Mutex::new(BTreeMap::new());
+}
+
+const QUANTILES: &[f64] = &[0.5, 0.9, 0.99];
+
+/// Records how long a request to `host` took.
+pub fn record(host: &str, latency: Duration) {
+    LATENCIES
+        .lock()
+        .unwrap()
+        .entry(host.to_owned())
+        .or_default()
+        .push(latency);
+}
+
+/// The latencies recorded so far, sorted, by host.
+pub fn latencies() -> BTreeMap<String, Vec<Duration>> {
+    let mut latencies = LATENCIES.lock().unwrap().clone();
+    for l in latencies.values_mut() {
+        l.sort();
+    }
+    latencies
+}
+
+/// The nearest-rank quantile of sorted latencies.
+fn quantile(sorted: &[Duration], q: f64) -> Duration {
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counts {
+    pub resolved: usize,
+    /// Taken from a `--checkpoint` file rather than resolved again.
+    pub cached: usize,
+    pub failed: usize,
+    pub elapsed: Duration,
+}
+
+pub fn summary(counts: &Counts, latencies: &BTreeMap<String, Vec<Duration>>) -> String {
+    let mut out = i18n::message(
+        "summary",
+        &[
+            ("resolved", &counts.resolved),
+            ("cached", &counts.cached),
+            ("failed", &counts.failed),
+            ("seconds", &format!("{:.1}", counts.elapsed.as_secs_f64())),
+        ],
+    );
+    let width = latencies.keys().map(String::len).max().unwrap_or(0);
+    for (host, sorted) in latencies {
+        write!(
+            out,
+            "\n  {:width$}  n={:<5}",
+            host,
+            sorted.len(),
+            width = width
+        )
+        .unwrap();
+        for q in QUANTILES {
+            let ms = quantile(sorted, *q).as_millis();
+            write!(out, "  p{} {} ms", (q * 100.0) as u32, ms).unwrap();
+        }
+    }
+    out
+}
+
+pub fn prometheus(counts: &Counts, latencies: &BTreeMap<String, Vec<Duration>>) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP d2b_inputs_total Inputs in the run, by outcome.\n");
+    out.push_str("# TYPE d2b_inputs_total counter\n");
+    for (outcome, n) in [
+        ("resolved", counts.resolved),
+        ("cached", counts.cached),
+        ("failed", counts.failed),
+    ] {
+        writeln!(out, "d2b_inputs_total{{outcome=\"{}\"}} {}", outcome, n).unwrap();
+    }
+    out.push_str("# HELP d2b_run_seconds How long the run took.\n");
+    out.push_str("# TYPE d2b_run_seconds gauge\n");
+    writeln!(out, "d2b_run_seconds {}", counts.elapsed.as_secs_f64()).unwrap();
+    out.push_str("# HELP d2b_request_duration_seconds How long requests took, by host.\n");
+    out.push_str("# TYPE d2b_request_duration_seconds summary\n");
+    for (host, sorted) in latencies {
+        for q in QUANTILES {
+            writeln!(
+                out,
+                "d2b_request_duration_seconds{{host=\"{}\",quantile=\"{}\"}} {}",
+                host,
+                q,
+                quantile(sorted, *q).as_secs_f64()
+            )
+            .unwrap();
+        }
+        let sum = sorted.iter().sum::<Duration>().as_secs_f64();
+        writeln!(
+            out,
+            "d2b_request_duration_seconds_sum{{host=\"{}\"}} {}",
+            host, sum
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "d2b_request_duration_seconds_count{{host=\"{}\"}} {}",
+            host,
+            sorted.len()
+        )
+        .unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let ms = Duration::from_millis;
+        let mut latencies = BTreeMap::new();
+        latencies.insert(
+            "doi.org".to_owned(),
+            (1..=10).map(|i| ms(i * 100)).collect(),
+        );
+        latencies.insert("export.arxiv.org".to_owned(), vec![ms(3000)]);
+        let counts = Counts {
+            resolved: 9,
+            cached: 1,
+            failed: 2,
+            elapsed: ms(12_340),
+        };
+        let summary = summary(&counts, &latencies);
+        assert!(summary.contains("doi.org           n=10     p50 500 ms  p90 900 ms  p99 1000 ms"));
+        assert!(summary.contains("export.arxiv.org  n=1      p50 3000 ms"));
+
+        let metrics = prometheus(&counts, &latencies);
+        assert!(metrics.contains("d2b_inputs_total{outcome=\"failed\"} 2\n"));
+        assert!(metrics
+            .contains("d2b_request_duration_seconds{host=\"doi.org\",quantile=\"0.9\"} 0.9\n"));
+        assert!(metrics.contains("d2b_request_duration_seconds_count{host=\"doi.org\"} 10\n"));
+    }
+}
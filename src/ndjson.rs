@@ -0,0 +1,138 @@
+//! `--ndjson`: identifiers are read as JSON lines on stdin and results written as JSON lines on
+//! stdout as soon as each resolves, for running d2b over large corpora in a pipeline.
+//!
+//! Each input line is a JSON string or an object with an `id`; any other members of the object
+//! are echoed back under `request`. Each output line has the `input` and either the `entries` it
+//! resolved to, with the source of each of their fields, or an `error`. Output isn't sorted or
+//! merged, and no more than `--jobs` inputs are in flight, so that a slow consumer slows down
+//! reading too.
+
+use crate::error::{self, Error, Kind};
+use crate::{i18n, render, resolve, Options, Resolved};
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+/// The input and extra request members of a line, or why it couldn't be read.
+fn parse_line(line: &str) -> Result<(String, Map<String, Value>), Error> {
+    let bad = || Error::new(Kind::InvalidInput, i18n::text("ndjson-bad-line")).with_input(line);
+    match serde_json::from_str(line).map_err(|_| bad())? {
+        Value::String(id) => Ok((id, Map::new())),
+        Value::Object(mut request) => match request.remove("id") {
+            Some(Value::String(id)) => Ok((id, request)),
+            _ => Err(bad()),
+        },
+        _ => Err(bad()),
+    }
+}
+
+fn record(
+    input: &str,
+    request: Map<String, Value>,
+    result: &Result<Vec<Resolved>, Error>,
+    opts: &Options,
+) -> Value {
+    let mut record = json!({ "input": input.trim() });
+    if !request.is_empty() {
+        record["request"] = Value::Object(request);
+    }
+    match result {
+        Ok(resolved) => {
+            record["entries"] = resolved
+                .iter()
+                .map(|r| {
+                    json!({
+                        "key": r.entry.key,
+                        "type": r.entry.kind,
+                        "source": r.source,
+                        "provenance": r.provenance.to_json(&r.entry, &r.source),
+                        "bibtex": render(r, opts),
+                    })
+                })
+                .collect();
+        }
+        Err(e) => {
+            record["error"] = json!({ "kind": e.kind.name(), "message": e.message });
+        }
+    }
+    record
+}
+
+pub async fn run(opts: Options, jobs: usize) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut pending = FuturesUnordered::new();
+    let mut eof = false;
+    let mut succeeded = 0;
+    let mut errors = Vec::new();
+    let stdout = std::io::stdout();
+    loop {
+        let record = tokio::select! {
+            line = lines.next_line(), if !eof && pending.len() < jobs => {
+                match line {
+                    Ok(Some(line)) if line.trim().is_empty() => continue,
+                    Ok(Some(line)) => match parse_line(&line) {
+                        Ok((input, request)) => {
+                            let opts = opts.clone();
+                            pending.push(async move {
+                                let result = resolve(input.clone(), opts.clone()).await;
+                                let record = record(&input, request, &result, &opts);
+                                (record, result.err())
+                            });
+                            continue;
+                        }
+                        Err(e) => (record(&line, Map::new(), &Err(e.clone()), &opts), Some(e)),
+                    },
+                    Ok(None) => {
+                        eof = true;
+                        continue;
+                    }
+                    Err(e) => {
+                        errors.push(Error::new(Kind::Io, e.to_string()));
+                        eof = true;
+                        continue;
+                    }
+                }
+            }
+            Some(done) = pending.next(), if !pending.is_empty() => done,
+            else => break,
+        };
+        match record.1 {
+            Some(e) => errors.push(e),
+            None => succeeded += 1,
+        }
+        let mut out = stdout.lock();
+        // A consumer that has gone away doesn't want the rest.
+        if writeln!(out, "{}", record.0)
+            .and_then(|_| out.flush())
+            .is_err()
+        {
+            break;
+        }
+    }
+    let status = error::status(succeeded, &errors);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(parse_line(r#""10.1000/xyz""#).unwrap().0, "10.1000/xyz");
+        let (id, request) = parse_line(r#"{"id": "1111.4246", "row": 7}"#).unwrap();
+        assert_eq!(id, "1111.4246");
+        assert_eq!(Value::Object(request), json!({"row": 7}));
+        for line in ["10.1000/xyz", "{\"doi\": \"10.1000/xyz\"}", "[1]"] {
+            let e = parse_line(line).unwrap_err();
+            assert_eq!(
+                (e.kind, e.input.as_deref()),
+                (Kind::InvalidInput, Some(line))
+            );
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Newspaper and magazine articles, from the schema.org JSON-LD (`NewsArticle`) or, failing that,
+//! the OpenGraph tags of their pages. Articles of the papers and magazines in [`OUTLETS`] are
+//! `@article`s in the outlet; other pages that call themselves news articles, and the articles of
+//! news sites that have no print edition, are `@online`s (or `@misc`es for BibTeX).
+
+use crate::bibtex::{name_last_first, Entry, Value as Field};
+use crate::{html, Format};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref LD_JSON_RE: Regex = Regex::new(
+        r#"(?is)<script[^>]*type\s*=\s*["']application/ld\+json["'][^>]*>(.*?)</script>"#
+    )
+    .unwrap();
+    static ref DATE_RE: Regex = Regex::new(r"^(\d{4})-(\d{2})-(\d{2})").unwrap();
+    static ref HOST_RE: Regex = Regex::new(r"(?i)^https?://(?:www\.)?([^/:?#]+)").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Medium {
+    Newspaper,
+    Magazine,
+    /// A news site without a print edition, or a wire service.
+    Online,
+}
+
+/// Major news outlets by domain, with the name they are cited under.
+pub const OUTLETS: &[(&str, &str, Medium)] = &[
+    ("nytimes.com", "The New York Times", Medium::Newspaper),
+    (
+        "washingtonpost.com",
+        "The Washington Post",
+        Medium::Newspaper,
+    ),
+    ("wsj.com", "The Wall Street Journal", Medium::Newspaper),
+    ("latimes.com", "Los Angeles Times", Medium::Newspaper),
+    ("usatoday.com", "USA Today", Medium::Newspaper),
+    ("theguardian.com", "The Guardian", Medium::Newspaper),
+    ("ft.com", "Financial Times", Medium::Newspaper),
+    ("thetimes.co.uk", "The Times", Medium::Newspaper),
+    ("lemonde.fr", "Le Monde", Medium::Newspaper),
+    ("zeit.de", "Die Zeit", Medium::Newspaper),
+    (
+        "faz.net",
+        "Frankfurter Allgemeine Zeitung",
+        Medium::Newspaper,
+    ),
+    ("economist.com", "The Economist", Medium::Magazine),
+    ("theatlantic.com", "The Atlantic", Medium::Magazine),
+    ("newyorker.com", "The New Yorker", Medium::Magazine),
+    ("wired.com", "Wired", Medium::Magazine),
+    ("spiegel.de", "Der Spiegel", Medium::Magazine),
+    ("bbc.co.uk", "BBC News", Medium::Online),
+    ("bbc.com", "BBC News", Medium::Online),
+    ("reuters.com", "Reuters", Medium::Online),
+    ("apnews.com", "Associated Press", Medium::Online),
+    ("bloomberg.com", "Bloomberg", Medium::Online),
+    ("cnn.com", "CNN", Medium::Online),
+    ("npr.org", "NPR", Medium::Online),
+    ("politico.com", "Politico", Medium::Online),
+    ("theverge.com", "The Verge", Medium::Online),
+];
+
+/// The outlet a URL belongs to, if it is one of [`OUTLETS`].
+pub fn outlet(url: &str) -> Option<(&'static str, Medium)> {
+    let host = HOST_RE.captures(url)?[1].to_lowercase();
+    OUTLETS
+        .iter()
+        .find(|(domain, _, _)| host == *domain || host.ends_with(&format!(".{}", domain)))
+        .map(|(_, name, medium)| (*name, *medium))
+}
+
+/// Whether a JSON-LD object is an article, by its `@type`.
+fn is_article(v: &Value) -> bool {
+    let types = match &v["@type"] {
+        Value::String(t) => vec![t.as_str()],
+        Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => return false,
+    };
+    types
+        .iter()
+        .any(|t| t.ends_with("NewsArticle") || *t == "Article")
+}
+
+/// The first article among the JSON-LD blocks of a page, looking into arrays and `@graph`s.
+fn ld_article(page: &str) -> Option<Value> {
+    fn find(v: Value) -> Option<Value> {
+        match v {
+            Value::Array(items) => items.into_iter().find_map(find),
+            v if is_article(&v) => Some(v),
+            mut v => match v["@graph"].take() {
+                Value::Null => None,
+                graph => find(graph),
+            },
+        }
+    }
+    LD_JSON_RE
+        .captures_iter(page)
+        .filter_map(|c| serde_json::from_str(c[1].trim()).ok())
+        .find_map(find)
+}
+
+/// The names in a JSON-LD `author`, which is a name, a person or organization, or a list of them.
+fn ld_names(v: &Value) -> Vec<String> {
+    match v {
+        Value::String(name) => vec![name.clone()],
+        Value::Array(items) => items.iter().flat_map(ld_names).collect(),
+        Value::Object(_) => v["name"].as_str().map(str::to_owned).into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// A byline as a list of names, without the "By" and splitting "A, B and C".
+fn split_byline(byline: &str) -> Vec<String> {
+    let byline = byline.trim();
+    let byline = byline
+        .strip_prefix("By ")
+        .or_else(|| byline.strip_prefix("by "))
+        .unwrap_or(byline);
+    byline
+        .split(" and ")
+        .flat_map(|part| part.split(", "))
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// A name as "Family, Given", or "Family, Jr., Given" for a name with a suffix.
+fn byline_name(name: &str) -> String {
+    let name = name.trim();
+    for suffix in [" Jr.", " Sr.", " II", " III", " IV"] {
+        if let Some(rest) = name.strip_suffix(suffix) {
+            let rest = name_last_first(rest.trim_end_matches(','));
+            if let Some((family, given)) = rest.split_once(", ") {
+                return format!("{}, {}, {}", family, suffix.trim(), given);
+            }
+        }
+    }
+    name_last_first(name)
+}
+
+/// A headline without the outlet's name, which OpenGraph titles often end with.
+fn strip_outlet(title: &str, outlet: &str) -> String {
+    for separator in [" - ", " | ", " – ", " — "] {
+        if let Some(stripped) = title.strip_suffix(&format!("{}{}", separator, outlet)) {
+            return stripped.trim().to_owned();
+        }
+    }
+    title.trim().to_owned()
+}
+
+/// Sets the date of an entry from a `YYYY-MM-DD` date: as `date` for biblatex, and as `year` and
+/// a `month` macro for BibTeX.
+pub fn set_date(entry: &mut Entry, date: &str, format: Format) {
+    match format {
+        Format::Biblatex => entry.set("date", date),
+        Format::Bibtex => {
+            entry.set("year", &date[..4.min(date.len())]);
+            const MONTHS: [&str; 12] = [
+                "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+            ];
+            if let Some(month) = date
+                .get(5..7)
+                .and_then(|m| m.parse::<usize>().ok())
+                .and_then(|m| MONTHS.get(m.wrapping_sub(1)))
+            {
+                entry
+                    .fields
+                    .push(("month".to_owned(), Field::Raw((*month).to_owned())));
+            }
+        }
+    }
+}
+
+/// Builds an entry for a news article from its page at `url`, or returns `None` if the page is
+/// neither from a known outlet nor marked up as a news article.
+pub fn from_page(page: &str, url: &str, format: Format) -> Option<Entry> {
+    let ld = ld_article(page);
+    let known = outlet(url);
+    if known.is_none() && ld.is_none() {
+        return None;
+    }
+    let tags = html::meta_tags(page);
+    let ld = ld.unwrap_or(Value::Null);
+
+    let (outlet, medium) = match known {
+        Some(known) => known,
+        None => {
+            let name = ld["publisher"]["name"]
+                .as_str()
+                .or_else(|| html::first(&tags, "og:site_name"))?;
+            (name, Medium::Online)
+        }
+    };
+    let title = ld["headline"]
+        .as_str()
+        .or_else(|| html::first(&tags, "og:title"))
+        .or_else(|| html::first(&tags, "twitter:title"))?;
+    let title = strip_outlet(&crate::text::decode_entities(title), outlet);
+    let mut authors = ld_names(&ld["author"]);
+    if authors.is_empty() {
+        authors = html::all(&tags, "article:author")
+            .into_iter()
+            .chain(html::all(&tags, "author"))
+            .chain(html::all(&tags, "byl"))
+            // Some sites give the author's profile page here.
+            .filter(|a| !a.starts_with("http"))
+            .flat_map(split_byline)
+            .collect();
+    }
+    let authors = authors.iter().map(|a| byline_name(a)).collect::<Vec<_>>();
+    let date = ld["datePublished"]
+        .as_str()
+        .or_else(|| html::first(&tags, "article:published_time"))
+        .and_then(|d| DATE_RE.captures(d));
+
+    let family = match authors.first() {
+        Some(first) => crate::bibtex::family_name(first).to_owned(),
+        None => outlet.trim_start_matches("The ").to_owned(),
+    }
+    .replace(' ', "");
+    let key = match &date {
+        Some(date) => format!("{}_{}", family, &date[1]),
+        None => family,
+    };
+    let kind = match (medium, format) {
+        (Medium::Newspaper | Medium::Magazine, _) => "article",
+        (Medium::Online, Format::Biblatex) => "online",
+        (Medium::Online, Format::Bibtex) => "misc",
+    };
+    let mut entry = Entry::new(kind, &key);
+    entry.set("title", &title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    match (kind, format) {
+        ("article", Format::Biblatex) => {
+            entry.set("journaltitle", outlet);
+            entry.set(
+                "entrysubtype",
+                if medium == Medium::Newspaper {
+                    "newspaper"
+                } else {
+                    "magazine"
+                },
+            );
+        }
+        ("article", Format::Bibtex) => entry.set("journal", outlet),
+        (_, Format::Biblatex) => entry.set("organization", outlet),
+        (_, Format::Bibtex) => entry.set("howpublished", outlet),
+    }
+    if let Some(date) = &date {
+        set_date(&mut entry, &date[0], format);
+    }
+    entry.set("url", url);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_page() {
+        let page = r#"<html><head>
+            <meta property="og:title" content="Coronavirus Declared a Pandemic - The New York Times">
+            <script type="application/ld+json">{"@context": "https://schema.org", "@graph": [
+                {"@type": "WebPage", "name": "x"},
+                {"@type": ["NewsArticle"], "headline": "W.H.O. Declares Coronavirus a Pandemic",
+                 "datePublished": "2020-03-11T16:23:00.000Z",
+                 "author": [{"@type": "Person", "name": "Donald G. McNeil Jr."}]}
+            ]}</script></head></html>"#;
+        let url = "https://www.nytimes.com/2020/03/11/health/coronavirus-pandemic-who.html";
+        assert_eq!(
+            from_page(page, url, Format::Biblatex).unwrap().to_string(),
+            "@article{McNeil_2020,\n  title={W.H.O. Declares Coronavirus a Pandemic},\n  \
+             author={McNeil, Jr., Donald G.},\n  journaltitle={The New York Times},\n  \
+             entrysubtype={newspaper},\n  date={2020-03-11},\n  url={https://www.nytimes.com/2020/03/11/health/coronavirus-pandemic-who.html}\n}"
+        );
+
+        let page = r#"<meta property="og:title" content="Markets rally | Reuters">
+            <meta name="author" content="By Jane Doe and John Roe">
+            <meta property="article:published_time" content="2021-06-02T10:00:00Z">"#;
+        let entry = from_page(page, "https://www.reuters.com/markets/x", Format::Bibtex).unwrap();
+        assert_eq!(
+            entry.to_string(),
+            "@misc{Doe_2021,\n  title={Markets rally},\n  author={Doe, Jane and Roe, John},\n  \
+             howpublished={Reuters},\n  year={2021},\n  month=jun,\n  \
+             url={https://www.reuters.com/markets/x}\n}"
+        );
+
+        assert!(from_page(
+            "<meta property=\"og:title\" content=\"X\">",
+            "https://example.com/x",
+            Format::Bibtex
+        )
+        .is_none());
+        assert_eq!(
+            outlet("https://edition.cnn.com/a"),
+            Some(("CNN", Medium::Online))
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Files written per entry alongside the usual output, each named after the entry's key. Files
+//! that already exist are left alone, since they may have been edited since.
+//!
+//! - `--obsidian DIR`: a Markdown literature note, in the layout Obsidian vaults commonly use: YAML
+//!   front matter with the title, authors, year, DOI and tags, then the BibTeX in a code block.
+//! - `--hugo DIR`: a publication page bundle, `KEY/index.md` and `KEY/cite.bib`, as the Hugo
+//!   academic themes (Wowchemy, Hugo Blox) lay out `content/publication/`.
+
+use crate::bibtex::Entry;
+use crate::{i18n, pdf, template, text};
+use std::fmt::Write;
+use std::io::{ErrorKind, Write as _};
+use std::path::{Path, PathBuf};
+
+/// A double-quoted YAML string. JSON's escapes are a subset of YAML's, so `serde_json` does it.
+pub fn yaml_str(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// A field value as plain text, without TeX grouping braces.
+pub fn plain(s: &str) -> String {
+    text::collapse_whitespace(&s.replace(['{', '}'], ""))
+}
+
+/// The authors of an entry as "Given Family" names.
+pub fn authors(entry: &Entry) -> Vec<String> {
+    template::people(entry.get("author"))
+        .iter()
+        .filter_map(|p| p["name"].as_str().map(plain))
+        .collect()
+}
+
+/// Where an entry was published: its journal, proceedings, publisher or institution.
+pub fn venue(entry: &Entry) -> Option<String> {
+    ["journal", "booktitle", "publisher", "school", "institution"]
+        .iter()
+        .find_map(|f| entry.get(f))
+        .map(plain)
+}
+
+/// Appends `name:` with a YAML list of `items`, or nothing if there are none.
+pub fn yaml_list(out: &mut String, name: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    writeln!(out, "{}:", name).unwrap();
+    for item in items {
+        writeln!(out, "  - {}", yaml_str(item)).unwrap();
+    }
+}
+
+/// The note for an entry.
+pub fn obsidian(entry: &Entry, tags: &[&str]) -> String {
+    let title = entry.get("title").map(plain).unwrap_or_default();
+    let mut out = String::from("---\n");
+    writeln!(out, "title: {}", yaml_str(&title)).unwrap();
+    yaml_list(&mut out, "authors", &authors(entry));
+    if let Some(year) = entry.get("year") {
+        let year = plain(year);
+        match year.parse::<u32>() {
+            Ok(year) => writeln!(out, "year: {}", year).unwrap(),
+            Err(_) => writeln!(out, "year: {}", yaml_str(&year)).unwrap(),
+        }
+    }
+    if let Some(doi) = entry.get("doi") {
+        writeln!(out, "doi: {}", yaml_str(doi)).unwrap();
+    }
+    if let Some(url) = entry.get("url") {
+        writeln!(out, "url: {}", yaml_str(url)).unwrap();
+    }
+    writeln!(out, "citekey: {}", yaml_str(&entry.key)).unwrap();
+    let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    yaml_list(&mut out, "tags", &tags);
+    writeln!(
+        out,
+        "---\n\n# {}\n\n```bibtex\n{}\n```",
+        title,
+        entry.to_string().trim_end()
+    )
+    .unwrap();
+    out
+}
+
+/// The CSL type the academic themes use for `publication_types`.
+pub fn publication_type(entry: &Entry) -> &'static str {
+    match entry.kind.to_lowercase().as_str() {
+        "article" => "article-journal",
+        "inproceedings" | "conference" => "paper-conference",
+        "book" => "book",
+        "incollection" | "inbook" => "chapter",
+        "phdthesis" | "mastersthesis" | "thesis" => "thesis",
+        "techreport" | "report" => "report",
+        "patent" => "patent",
+        _ => "article",
+    }
+}
+
+/// A month field (`3`, `mar` or `March`) as a number.
+pub fn month(s: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let s = plain(s).to_lowercase();
+    match s.parse::<u32>() {
+        Ok(m) => Some(m).filter(|m| (1..=12).contains(m)),
+        Err(_) => MONTHS
+            .iter()
+            .position(|m| s.starts_with(m))
+            .map(|i| i as u32 + 1),
+    }
+}
+
+/// The `index.md` of an entry's publication page.
+pub fn hugo(entry: &Entry, tags: &[&str]) -> String {
+    let title = entry.get("title").map(plain).unwrap_or_default();
+    let mut out = String::from("---\n");
+    writeln!(out, "title: {}", yaml_str(&title)).unwrap();
+    yaml_list(&mut out, "authors", &authors(entry));
+    if let Some(year) = entry
+        .get("year")
+        .map(plain)
+        .filter(|y| y.parse::<u32>().is_ok())
+    {
+        let month = entry.get("month").and_then(month).unwrap_or(1);
+        writeln!(out, "date: \"{}-{:02}-01\"", year, month).unwrap();
+    }
+    if let Some(doi) = entry.get("doi") {
+        writeln!(out, "doi: {}", yaml_str(doi)).unwrap();
+    }
+    writeln!(out, "publication_types: [\"{}\"]", publication_type(entry)).unwrap();
+    if let Some(venue) = venue(entry) {
+        writeln!(out, "publication: {}", yaml_str(&format!("*{}*", venue))).unwrap();
+    }
+    if let Some(abstract_) = entry.get("abstract") {
+        writeln!(out, "abstract: {}", yaml_str(&plain(abstract_))).unwrap();
+    }
+    let tags = tags.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    yaml_list(&mut out, "tags", &tags);
+    if let Some(url) = entry.get("url") {
+        writeln!(out, "url_source: {}", yaml_str(url)).unwrap();
+    }
+    out.push_str("---\n");
+    out
+}
+
+/// The files of an entry's publication page, by path relative to the content directory.
+pub fn hugo_bundle(entry: &Entry, tags: &[&str]) -> Vec<(String, String)> {
+    let dir = pdf::file_stem(&entry.key);
+    vec![
+        (format!("{}/index.md", dir), hugo(entry, tags)),
+        (format!("{}/cite.bib", dir), entry.to_string()),
+    ]
+}
+
+/// Writes `contents` to `dir/name`, unless that file already exists. Returns the path it was
+/// written to, if it was.
+pub fn save(dir: &Path, name: &str, contents: &str) -> std::io::Result<Option<PathBuf>> {
+    let path = dir.join(name);
+    std::fs::create_dir_all(path.parent().unwrap_or(dir))?;
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    {
+        Ok(mut file) => file.write_all(contents.as_bytes()).map(|_| Some(path)),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Saves the files `files` gives for each entry in `dir`, reporting each on stderr.
+pub fn write_all<'a>(
+    dir: &Path,
+    entries: impl IntoIterator<Item = &'a Entry>,
+    files: impl Fn(&Entry) -> Vec<(String, String)>,
+) {
+    for (entry, (name, contents)) in entries
+        .into_iter()
+        .flat_map(|e| files(e).into_iter().map(move |f| (e, f)))
+    {
+        match save(dir, &name, &contents) {
+            Ok(Some(path)) => eprintln!(
+                "{}",
+                i18n::message(
+                    "note-saved",
+                    &[("key", &entry.key), ("path", &path.display())]
+                )
+            ),
+            Ok(None) => eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message("note-exists", &[("path", &dir.join(&name).display())])
+            ),
+            Err(e) => eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message(
+                    "file-unwritable",
+                    &[("file", &dir.join(&name).display()), ("error", &e)]
+                )
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obsidian() {
+        let mut entry = Entry::new("article", "Carpenter_2017");
+        entry.set(
+            "author",
+            "Carpenter, Bob and Gelman, Andrew and {Stan Development Team}",
+        );
+        entry.set(
+            "title",
+            "{Stan}: A \"Probabilistic\"\n Programming Language",
+        );
+        entry.set("year", "2017");
+        entry.set("doi", "10.18637/jss.v076.i01");
+        let note = obsidian(&entry, &["stats", "ppl"]);
+        assert!(note.starts_with(
+            "---\n\
+             title: \"Stan: A \\\"Probabilistic\\\" Programming Language\"\n\
+             authors:\n  - \"Bob Carpenter\"\n  - \"Andrew Gelman\"\n  - \"Stan Development Team\"\n\
+             year: 2017\n\
+             doi: \"10.18637/jss.v076.i01\"\n\
+             citekey: \"Carpenter_2017\"\n\
+             tags:\n  - \"stats\"\n  - \"ppl\"\n\
+             ---\n\n\
+             # Stan: A \"Probabilistic\" Programming Language\n\n\
+             ```bibtex\n@article{Carpenter_2017,\n"
+        ));
+        assert!(note.ends_with("}\n```\n"));
+        assert!(!obsidian(&entry, &[]).contains("tags:"));
+    }
+
+    #[test]
+    fn test_hugo() {
+        let mut entry = Entry::new("inproceedings", "Shen:2021");
+        entry.set("author", "Shen, Jeff");
+        entry.set("title", "{Zebras}");
+        entry.set("booktitle", "Proceedings of {ICML}");
+        entry.set("year", "2021");
+        entry.set("month", "jul");
+        assert_eq!(
+            hugo(&entry, &[]),
+            "---\n\
+             title: \"Zebras\"\n\
+             authors:\n  - \"Jeff Shen\"\n\
+             date: \"2021-07-01\"\n\
+             publication_types: [\"paper-conference\"]\n\
+             publication: \"*Proceedings of ICML*\"\n\
+             ---\n"
+        );
+        let files = hugo_bundle(&entry, &[]);
+        assert_eq!(files[0].0, "Shen_2021/index.md");
+        assert_eq!(
+            files[1],
+            ("Shen_2021/cite.bib".to_owned(), entry.to_string())
+        );
+        assert_eq!(month("12"), Some(12));
+        assert_eq!(month("{September}"), Some(9));
+        assert_eq!(month("13"), None);
+    }
+}
@@ -0,0 +1,98 @@
+//! `d2b rename --ocr`: the identifiers of scanned papers, whose PDFs are only images of their
+//! pages. The first page is rendered with Poppler's `pdftoppm` and read with Tesseract, both left
+//! to their command-line tools as QR codes are left to ZBar's, and searched like a PDF's text.
+
+use crate::pdf;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// The resolution the page is rendered at; Tesseract reads best at 300 DPI.
+const DPI: &str = "300";
+
+/// Why a PDF couldn't be read.
+#[derive(Debug)]
+pub enum Failure {
+    /// `pdftoppm` or `tesseract`, named, isn't installed.
+    Missing(&'static str),
+    Io(io::Error),
+}
+
+/// Runs a tool, failing with what it printed on standard error if it does.
+fn run(tool: &'static str, command: &mut Command) -> Result<Output, Failure> {
+    let out = command.output().map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => Failure::Missing(tool),
+        _ => Failure::Io(e),
+    })?;
+    if !out.status.success() {
+        let message = String::from_utf8_lossy(&out.stderr).trim().to_owned();
+        return Err(Failure::Io(io::Error::other(message)));
+    }
+    Ok(out)
+}
+
+/// The text Tesseract reads on the first page of a PDF.
+pub fn first_page_text(pdf: &Path) -> Result<String, Failure> {
+    let dir = std::env::temp_dir().join(format!("d2b-ocr-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(Failure::Io)?;
+    let result = (|| {
+        let prefix = dir.join("page");
+        run(
+            "pdftoppm",
+            Command::new("pdftoppm")
+                .args(["-f", "1", "-l", "1", "-r", DPI, "-gray", "-png"])
+                .arg(pdf)
+                .arg(&prefix),
+        )?;
+        // The page number in the image's name is padded to the width of the last one.
+        let image = std::fs::read_dir(&dir)
+            .map_err(Failure::Io)?
+            .filter_map(Result::ok)
+            .map(|e| e.path())
+            .find(|p| p.extension().is_some_and(|e| e == "png"))
+            .ok_or_else(|| Failure::Io(io::Error::other("pdftoppm rendered no page")))?;
+        let out = run(
+            "tesseract",
+            Command::new("tesseract").arg(&image).arg("stdout"),
+        )?;
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    })();
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// The identifier in text read off a page. Tesseract keeps the line breaks of the page, so DOIs
+/// broken across lines after a hyphen or slash are joined up first.
+pub fn find_identifier(text: &str) -> Option<String> {
+    let joined = text.replace("-\n", "-").replace("/\n", "/");
+    pdf::find_identifier(joined.as_bytes())
+}
+
+/// The identifier of the paper a scanned PDF holds, read off its first page.
+pub fn extract_identifier(pdf: &Path) -> Result<Option<String>, Failure> {
+    Ok(find_identifier(&first_page_text(pdf)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_identifier() {
+        let page = "Lecture Notes in Examples 12 (2015) 1-10\n\
+                    https://doi.org/10.1007/978-3-\n319-24277-4_9\nReceived 2 May 2015";
+        assert_eq!(
+            find_identifier(page).as_deref(),
+            Some("doi:10.1007/978-3-319-24277-4_9")
+        );
+        assert_eq!(
+            find_identifier("DOI: 10.1093/\nmnras/stw2147").as_deref(),
+            Some("doi:10.1093/mnras/stw2147")
+        );
+        assert_eq!(
+            find_identifier("arXiv:2101.00001v2 [cs.LG] 4 Jan 2021").as_deref(),
+            Some("arxiv:2101.00001v2")
+        );
+        assert_eq!(find_identifier("Proceedings of the Royal Society"), None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Downloading the full text of resolved entries: from arXiv for eprints, and from the open-access
+//! location Unpaywall knows of for DOIs. Also finding the identifier of a paper in its PDF.
+
+use crate::bibtex::Entry;
+use crate::http::SendWithRetry;
+use crate::CLIENT;
+use flate2::read::ZlibDecoder;
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    static ref STREAM_RE: Regex = Regex::new(r"(?s)stream\r?\n(.*?)endstream").unwrap();
+    static ref META_DOI_RE: Regex = Regex::new(
+        r"(?i)(?:/doi\s*\(|prism:doi>|dc:identifier>\s*(?:doi:)?)\s*(10\.\d{4,9}/[^\s()<>]+)"
+    )
+    .unwrap();
+    static ref DOI_RE: Regex = Regex::new(r"10\.\d{4,9}/[^\s()<>\[\]{}\\]+").unwrap();
+    static ref ARXIV_STAMP_RE: Regex =
+        Regex::new(r"arXiv:\s*(\d{4}\.\d{4,5}(?:v\d+)?|[a-z-]+/\d{7}(?:v\d+)?)").unwrap();
+}
+
+/// The address sent to Unpaywall, which asks clients to identify themselves with one.
+fn unpaywall_email() -> String {
+    std::env::var("UNPAYWALL_EMAIL").unwrap_or_else(|_| "d2b@users.noreply.github.com".to_owned())
+}
+
+/// Finds an open-access PDF for `doi` through Unpaywall.
+async fn unpaywall_pdf(doi: &str) -> Option<String> {
+    let mut url = reqwest::Url::parse("https://api.unpaywall.org/v2/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .extend(doi.split('/'));
+    let body = CLIENT
+        .get(url)
+        .query(&[("email", unpaywall_email())])
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
+    let best = &json["best_oa_location"];
+    best["url_for_pdf"]
+        .as_str()
+        .or_else(|| {
+            json["oa_locations"]
+                .as_array()?
+                .iter()
+                .find_map(|l| l["url_for_pdf"].as_str())
+        })
+        .map(str::to_owned)
+}
+
+/// Where the full text of an entry can be downloaded from, if anywhere.
+async fn pdf_url(entry: &Entry) -> Option<String> {
+    if let Some(eprint) = entry.get("eprint") {
+        if entry
+            .get("archivePrefix")
+            .is_none_or(|p| p.eq_ignore_ascii_case("arxiv"))
+        {
+            return Some(format!("https://arxiv.org/pdf/{}", eprint));
+        }
+    }
+    unpaywall_pdf(&entry.get("doi")?.to_lowercase()).await
+}
+
+/// A file or directory name for an entry, from its key with anything that isn't safe in a path
+/// replaced.
+pub fn file_stem(key: &str) -> String {
+    let name = key
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.+".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    name.trim_start_matches('.').to_owned()
+}
+
+/// A file name for one of an entry's files.
+pub fn file_name(key: &str, extension: &str) -> String {
+    format!("{}.{}", file_stem(key), extension)
+}
+
+/// Downloads the full text of `entry` into `dir`, named after its key, and records the path in
+/// its `file` field.
+pub async fn fetch(entry: &mut Entry, dir: &Path) -> Result<PathBuf, String> {
+    let url = pdf_url(entry)
+        .await
+        .ok_or_else(|| "no open-access PDF found".to_owned())?;
+    let res = CLIENT
+        .get(&url)
+        .send_with_retry()
+        .await
+        .map_err(|e| e.to_string())?;
+    let bytes = res.bytes().await.map_err(|e| e.to_string())?;
+    if !bytes.starts_with(b"%PDF") {
+        return Err(format!("{} is not a PDF", url));
+    }
+    std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    let path = dir.join(file_name(&entry.key, "pdf"));
+    std::fs::write(&path, &bytes).map_err(|e| e.to_string())?;
+    entry.set("file", &path.to_string_lossy());
+    Ok(path)
+}
+
+/// The text of a PDF that identifiers can be found in: the file itself (for uncompressed metadata)
+/// followed by every Flate-compressed stream, decompressed, in file order.
+fn searchable_text(pdf: &[u8]) -> Vec<Vec<u8>> {
+    let mut chunks = vec![pdf.to_vec()];
+    for stream in STREAM_RE.captures_iter(pdf) {
+        let mut text = Vec::new();
+        if ZlibDecoder::new(&stream[1]).read_to_end(&mut text).is_ok() || !text.is_empty() {
+            chunks.push(text);
+        }
+    }
+    chunks
+}
+
+fn trim_doi(doi: &[u8]) -> String {
+    String::from_utf8_lossy(doi)
+        .trim_end_matches(|c: char| ".,;:'\"".contains(c))
+        .to_owned()
+}
+
+/// Finds the identifier of the paper in a PDF, as an input d2b can resolve: a DOI from the
+/// document metadata, else whichever of the arXiv stamp or the first DOI in the text comes first.
+pub fn extract_identifier(pdf: &[u8]) -> Option<String> {
+    let chunks = searchable_text(pdf);
+    if let Some(c) = chunks.iter().find_map(|t| META_DOI_RE.captures(t)) {
+        return Some(format!("doi:{}", trim_doi(&c[1])));
+    }
+    chunks.iter().find_map(|text| find_identifier(text))
+}
+
+/// The identifier in some text of a paper: whichever of the arXiv stamp or the first DOI comes
+/// first.
+pub fn find_identifier(text: &[u8]) -> Option<String> {
+    let arxiv = ARXIV_STAMP_RE.captures(text);
+    let doi = DOI_RE.find(text);
+    match (arxiv, doi) {
+        (Some(a), Some(d)) if d.start() < a.get(0).unwrap().start() => {
+            Some(format!("doi:{}", trim_doi(d.as_bytes())))
+        }
+        (Some(a), _) => Some(format!("arxiv:{}", String::from_utf8_lossy(&a[1]))),
+        (None, Some(d)) => Some(format!("doi:{}", trim_doi(d.as_bytes()))),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(file_name("Shen_2021", "pdf"), "Shen_2021.pdf");
+        assert_eq!(file_name("../etc/passwd", "pdf"), "_etc_passwd.pdf");
+        assert_eq!(file_name("Müller:2020", "pdf"), "Müller_2020.pdf");
+    }
+
+    #[test]
+    fn test_extract_identifier() {
+        use flate2::{write::ZlibEncoder, Compression};
+        use std::io::Write;
+
+        let compressed = |text: &[u8]| {
+            let mut enc = ZlibEncoder::new(Vec::new(), Compression::default());
+            enc.write_all(text).unwrap();
+            enc.finish().unwrap()
+        };
+        let pdf = |streams: &[&[u8]]| {
+            let mut pdf = b"%PDF-1.5\n".to_vec();
+            for s in streams {
+                pdf.extend_from_slice(b"1 0 obj <</Filter /FlateDecode>> stream\n");
+                pdf.extend(compressed(s));
+                pdf.extend_from_slice(b"endstream endobj\n");
+            }
+            pdf
+        };
+
+        let eprint = pdf(&[
+            b"BT (arXiv:1712.01815v1  [cs.AI]  5 Dec 2017) Tj ET",
+            b"[1] doi:10.1038/nature16961.",
+        ]);
+        assert_eq!(
+            extract_identifier(&eprint).as_deref(),
+            Some("arxiv:1712.01815v1")
+        );
+
+        let article = pdf(&[b"(Journal of Things, https://doi.org/10.1145/359327.359336.) Tj"]);
+        assert_eq!(
+            extract_identifier(&article).as_deref(),
+            Some("doi:10.1145/359327.359336")
+        );
+
+        let mut tagged = article.clone();
+        tagged.extend_from_slice(b"<< /doi (10.1000/xyz) >>");
+        assert_eq!(
+            extract_identifier(&tagged).as_deref(),
+            Some("doi:10.1000/xyz")
+        );
+
+        assert!(extract_identifier(&pdf(&[b"(Nothing here) Tj"])).is_none());
+    }
+}
@@ -0,0 +1,215 @@
+//! Podcast episodes, from the RSS feed of their show. An episode is found either from its page,
+//! which links to the feed, or by its GUID in the feed (`podcast:<feed URL>#<GUID>`). Episodes are
+//! `@audio`s in their show for biblatex and `@misc`es for BibTeX.
+
+use crate::bibtex::Entry;
+use crate::feed::{self, Feed, Item, Source};
+use crate::http::SendWithRetry;
+use crate::{news, video, Format, CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref LINK_TAG_RE: Regex = Regex::new(r"(?is)<link\s[^>]*>").unwrap();
+}
+
+/// The show and item fields of a feed that an episode is cited from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Episode {
+    pub show: String,
+    pub title: String,
+    pub hosts: Option<String>,
+    pub link: Option<String>,
+    pub guid: String,
+    /// The publication date, as `YYYY-MM-DD`.
+    pub date: Option<String>,
+    /// The running time, in seconds.
+    pub duration: Option<u64>,
+}
+
+/// An `itunes:duration`, which is either seconds or `[H:]MM:SS`, in seconds.
+fn parse_duration(d: &str) -> Option<u64> {
+    d.trim().split(':').try_fold(0, |total, part| {
+        Some(total * 60 + part.parse::<u64>().ok()?)
+    })
+}
+
+/// The episode an item of a show's feed is, if it has an audio enclosure, which tells episodes
+/// from the blog posts some shows mix into their feeds.
+pub fn episode(feed: &Feed, item: &Item) -> Option<Episode> {
+    if !item
+        .enclosures
+        .iter()
+        .any(|(_, mime)| mime.starts_with("audio/"))
+    {
+        return None;
+    }
+    Some(Episode {
+        show: feed.title.clone(),
+        title: item.title.clone(),
+        hosts: item
+            .extension("itunes:author")
+            .map(str::to_owned)
+            .or_else(|| feed.author.clone()),
+        link: item.link.clone(),
+        guid: item.id.clone(),
+        date: item.published.map(|d| d.format("%Y-%m-%d").to_string()),
+        duration: item.extension("itunes:duration").and_then(parse_duration),
+    })
+}
+
+/// The RSS feed a page links to, as an absolute URL.
+fn feed_link(page: &str, url: &str) -> Option<String> {
+    let href = LINK_TAG_RE.find_iter(page).find_map(|tag| {
+        let attrs = feed::attrs(tag.as_str());
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        (attr("rel")? == "alternate" && attr("type")? == "application/rss+xml")
+            .then(|| attr("href").map(str::to_owned))
+            .flatten()
+    })?;
+    reqwest::Url::parse(url)
+        .ok()?
+        .join(&href)
+        .ok()
+        .map(|u| u.to_string())
+}
+
+/// A URL without its scheme, `www.` and trailing slash, to compare episode links with.
+fn normalize(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    url.trim_start_matches("www.")
+        .trim_end_matches('/')
+        .to_lowercase()
+}
+
+async fn fetch_feed(url: &str) -> Option<Feed> {
+    let res = CLIENT.get(url).send_with_retry().await.ok()?;
+    if !res.status().is_success() {
+        return None;
+    }
+    feed::parse(&res.text().await.ok()?).ok()
+}
+
+/// The entry of the episode on the page at `url`, if the page links to a podcast feed with it.
+pub async fn from_page(page: &str, url: &str, format: Format) -> Option<Entry> {
+    let feed = fetch_feed(&feed_link(page, url)?).await?;
+    let url = normalize(url);
+    feed.items
+        .iter()
+        .filter(|item| {
+            item.link
+                .iter()
+                .chain([&item.id])
+                .any(|u| normalize(u) == url)
+        })
+        .find_map(|item| Source::Podcast.entry(&feed, item, format))
+}
+
+/// The entry of the episode with a GUID in the feed at `feed_url`.
+pub async fn from_guid(feed_url: &str, guid: &str, format: Format) -> Option<Entry> {
+    let feed = fetch_feed(feed_url).await?;
+    feed.items
+        .iter()
+        .filter(|item| item.id == guid)
+        .find_map(|item| Source::Podcast.entry(&feed, item, format))
+}
+
+/// Builds the entry of an episode.
+pub fn entry(episode: &Episode, format: Format) -> Entry {
+    let family = episode
+        .hosts
+        .as_deref()
+        .unwrap_or(&episode.show)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>();
+    let key = match &episode.date {
+        Some(date) => format!("{}_{}", family, &date[..4]),
+        None => family,
+    };
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("audio", &key),
+        Format::Bibtex => Entry::new("misc", &key),
+    };
+    entry.set("title", &episode.title);
+    // Like a video's uploader, a show's author is as often a network as a person.
+    if let Some(hosts) = &episode.hosts {
+        entry.set("author", &format!("{{{}}}", hosts));
+    }
+    match format {
+        Format::Biblatex => {
+            entry.set("maintitle", &episode.show);
+            entry.set("entrysubtype", "podcast");
+        }
+        Format::Bibtex => entry.set("howpublished", &format!("{} (podcast)", episode.show)),
+    }
+    if let Some(date) = &episode.date {
+        news::set_date(&mut entry, date, format);
+    }
+    entry.set(
+        "note",
+        &match episode.duration {
+            Some(duration) => format!("Podcast episode, {}", video::format_duration(duration)),
+            None => "Podcast episode".to_owned(),
+        },
+    );
+    if let Some(link) = episode.link.as_ref().or(Some(&episode.guid)) {
+        if link.starts_with("http") {
+            entry.set("url", link);
+        }
+    }
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_episodes() {
+        let feed = r#"<?xml version="1.0"?><rss version="2.0"><channel>
+            <title>Hidden Brain</title><itunes:author>NPR</itunes:author>
+            <link>https://hiddenbrain.org</link>
+            <item><title><![CDATA[You 2.0: Rethinking Resolutions &amp; Habits]]></title>
+              <link>https://hiddenbrain.org/podcast/you-2-0/</link>
+              <guid isPermaLink="false">a1b2c3</guid>
+              <pubDate>Mon, 08 Jan 2024 08:00:00 -0000</pubDate>
+              <itunes:duration>50:21</itunes:duration>
+              <enclosure url="https://cdn.example.com/ep.mp3" type="audio/mpeg" length="1"/>
+            </item>
+            <item><title>A blog post</title><guid>https://hiddenbrain.org/blog/x</guid></item>
+        </channel></rss>"#;
+        let feed = feed::parse(feed).unwrap();
+        let episodes = feed
+            .items
+            .iter()
+            .filter_map(|item| episode(&feed, item))
+            .collect::<Vec<_>>();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(
+            entry(&episodes[0], Format::Biblatex).to_string(),
+            "@audio{NPR_2024,\n  title={You 2.0: Rethinking Resolutions & Habits},\n  \
+             author={{NPR}},\n  maintitle={Hidden Brain},\n  entrysubtype={podcast},\n  \
+             date={2024-01-08},\n  note={Podcast episode, 50:21},\n  \
+             url={https://hiddenbrain.org/podcast/you-2-0/}\n}"
+        );
+        assert_eq!(
+            entry(&episodes[0], Format::Bibtex).get("howpublished"),
+            Some("Hidden Brain (podcast)")
+        );
+        assert_eq!(parse_duration("1:02:05"), Some(3725));
+        assert_eq!(
+            feed_link(
+                r#"<link rel="alternate" type="application/rss+xml" href="/feed.xml">"#,
+                "https://hiddenbrain.org/podcast/you-2-0/"
+            ),
+            Some("https://hiddenbrain.org/feed.xml".to_owned())
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Which source supplied each field of a resolved entry: the backend it was fetched from, the
+//! backends `--merge-sources` filled gaps from, the registries `--with-funding` and
+//! `--with-license` ask, and d2b itself or the configuration for fields added while finishing the
+//! entry. Written out as `provenance` in the `--ndjson` records.
+
+use crate::bibtex::Entry;
+use serde_json::{Map, Value};
+
+/// Fields added by d2b's own fix-ups rather than taken from a source.
+pub const D2B: &str = "d2b";
+
+/// The source of each field, by lowercased field name. Fields without one came from the source
+/// the entry was fetched from.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance(Vec<(String, String)>);
+
+impl Provenance {
+    /// Credits a field to a source, replacing what it was credited to.
+    pub fn set(&mut self, field: &str, source: &str) {
+        let field = field.to_lowercase();
+        match self.0.iter_mut().find(|(f, _)| *f == field) {
+            Some((_, s)) => *s = source.to_owned(),
+            None => self.0.push((field, source.to_owned())),
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(f, _)| f.eq_ignore_ascii_case(field))
+            .map(|(_, s)| s.as_str())
+    }
+
+    /// Credits every field of an entry to one source, as when the entry is rebuilt from it.
+    pub fn replace(&mut self, entry: &Entry, source: &str) {
+        self.0.clear();
+        for (name, _) in &entry.fields {
+            self.set(name, source);
+        }
+    }
+
+    /// Credits the fields `after` has and `before` didn't to a source. Fields that only changed
+    /// keep their source, since cleaning up a value doesn't make it d2b's.
+    pub fn record(&mut self, before: &Entry, after: &Entry, source: &str) {
+        for (name, _) in &after.fields {
+            if before.get(name).is_none() {
+                self.set(name, source);
+            }
+        }
+    }
+
+    /// Reads back what [`Provenance::to_json`] wrote.
+    pub fn from_json(json: &Value) -> Self {
+        let mut provenance = Provenance::default();
+        for (field, source) in json.as_object().into_iter().flatten() {
+            if let Some(source) = source.as_str() {
+                provenance.set(field, source);
+            }
+        }
+        provenance
+    }
+
+    /// The source of every field of an entry, in field order, as a JSON object.
+    pub fn to_json(&self, entry: &Entry, source: &str) -> Value {
+        let fields = entry
+            .fields
+            .iter()
+            .map(|(name, _)| {
+                let from = self.get(name).unwrap_or(source);
+                (name.to_lowercase(), Value::String(from.to_owned()))
+            })
+            .collect::<Map<_, _>>();
+        Value::Object(fields)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance() {
+        let mut before = Entry::new("article", "Doe_2020");
+        before.set("title", "A title");
+        before.set("year", "2020");
+        let mut after = before.clone();
+        after.set("title", "A Title");
+        after.set("license", "CC-BY-4.0");
+
+        let mut provenance = Provenance::default();
+        provenance.record(&before, &after, "api.datacite.org");
+        provenance.set("Year", D2B);
+        assert_eq!(
+            provenance.to_json(&after, "doi.org"),
+            serde_json::json!({"title": "doi.org", "year": "d2b", "license": "api.datacite.org"})
+        );
+        assert_eq!(
+            Provenance::from_json(&provenance.to_json(&after, "doi.org")).get("license"),
+            Some("api.datacite.org")
+        );
+        provenance.replace(&before, "api.crossref.org");
+        assert_eq!(provenance.get("license"), None);
+        assert_eq!(provenance.get("TITLE"), Some("api.crossref.org"));
+    }
+}
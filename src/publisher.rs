@@ -0,0 +1,154 @@
+//! Publisher-specific fixes for BibTeX negotiated through doi.org.
+//!
+//! ACM and IEEE conference papers regularly come back as `@article`s with the proceedings title in
+//! `journal`, and without the venue acronym that citation styles expect in `series`.
+
+use crate::bibtex::Entry;
+use crate::http::SendWithRetry;
+use crate::CLIENT;
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    // Not "proceedings" on its own, which journals such as the Proceedings of the IEEE have too.
+    static ref CONFERENCE_RE: Regex =
+        Regex::new(r"(?i)\b(conference|symposium|workshop|colloquium)\b").unwrap();
+    static ref ACRONYM_RE: Regex = Regex::new(r"\(([A-Z][A-Za-z0-9&/-]*[A-Z0-9])\)\s*$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Publisher {
+    Acm,
+    Ieee,
+}
+
+impl Publisher {
+    pub fn from_doi(doi: &str) -> Option<Self> {
+        if doi.starts_with("10.1145/") {
+            Some(Publisher::Acm)
+        } else if doi.starts_with("10.1109/") {
+            Some(Publisher::Ieee)
+        } else {
+            None
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Publisher::Acm => "Association for Computing Machinery",
+            Publisher::Ieee => "IEEE",
+        }
+    }
+}
+
+/// Looks up the venue acronym (e.g. `CHI`, `CVPR`) that DBLP records for `doi`.
+pub async fn dblp_venue(doi: &str) -> Option<String> {
+    let body = CLIENT
+        .get("https://dblp.org/search/publ/api")
+        .query(&[("q", doi), ("format", "json"), ("h", "1")])
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let json: serde_json::Value = serde_json::from_str(&body).ok()?;
+    let venue = &json["result"]["hits"]["hit"][0]["info"]["venue"];
+    // DBLP gives a list when a paper belongs to several venues (e.g. co-located workshops).
+    venue
+        .as_str()
+        .or_else(|| venue[0].as_str())
+        .map(str::to_owned)
+}
+
+/// Whether `entry` is a conference paper filed as an `@article`, with the proceedings title in
+/// `journal`.
+fn is_misfiled(entry: &Entry) -> bool {
+    entry.kind.eq_ignore_ascii_case("article")
+        && entry.get("booktitle").is_none()
+        && entry
+            .get("journal")
+            .is_some_and(|j| CONFERENCE_RE.is_match(j))
+}
+
+/// Whether `entry` is a conference paper, filed as one or not.
+pub fn is_conference(entry: &Entry) -> bool {
+    entry.kind.eq_ignore_ascii_case("inproceedings") || is_misfiled(entry)
+}
+
+/// Turns misfiled conference papers into `@inproceedings` and fills in `series` and `publisher`.
+/// `venue` is an acronym from an external source such as DBLP, which takes precedence over one
+/// guessed from the proceedings title.
+pub fn fix_conference(entry: &mut Entry, publisher: Publisher, venue: Option<&str>) {
+    if is_misfiled(entry) {
+        entry.kind = "inproceedings".to_owned();
+        entry.rename("journal", "booktitle");
+    }
+
+    if !entry.kind.eq_ignore_ascii_case("inproceedings") {
+        return;
+    }
+
+    if entry.get("series").is_none() {
+        let acronym = venue.map(str::to_owned).or_else(|| {
+            entry
+                .get("booktitle")
+                .and_then(|b| ACRONYM_RE.captures(b))
+                .map(|c| c[1].to_owned())
+        });
+        if let Some(acronym) = acronym {
+            entry.set("series", &acronym);
+        }
+    }
+    if entry.get("publisher").is_none() {
+        entry.set("publisher", publisher.name());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex::parse_entry;
+
+    #[test]
+    fn test_fix_ieee_conference() {
+        let mut entry = parse_entry(
+            "@article{He_2016, title={Deep Residual Learning for Image Recognition}, \
+             journal={2016 IEEE Conference on Computer Vision and Pattern Recognition (CVPR)}, \
+             DOI={10.1109/cvpr.2016.90}, year={2016}}",
+        )
+        .unwrap();
+        fix_conference(&mut entry, Publisher::Ieee, None);
+        assert_eq!(entry.kind, "inproceedings");
+        assert!(entry.get("journal").is_none());
+        assert_eq!(
+            entry.get("booktitle"),
+            Some("2016 IEEE Conference on Computer Vision and Pattern Recognition (CVPR)")
+        );
+        assert_eq!(entry.get("series"), Some("CVPR"));
+        assert_eq!(entry.get("publisher"), Some("IEEE"));
+    }
+
+    #[test]
+    fn test_journal_article_untouched() {
+        let src = "@article{Lamport_1978, journal={Communications of the ACM}, publisher={ACM}}";
+        let mut entry = parse_entry(src).unwrap();
+        fix_conference(&mut entry, Publisher::Acm, Some("CACM"));
+        assert_eq!(entry, parse_entry(src).unwrap());
+    }
+
+    #[test]
+    fn test_proceedings_journals_untouched() {
+        for journal in [
+            "Proceedings of the IEEE",
+            "Proceedings of the ACM on Programming Languages",
+            "Proceedings of the National Academy of Sciences",
+        ] {
+            let src = format!("@article{{a, journal={{{}}}}}", journal);
+            let mut entry = parse_entry(&src).unwrap();
+            assert!(!is_conference(&entry));
+            fix_conference(&mut entry, Publisher::Acm, None);
+            assert_eq!(entry, parse_entry(&src).unwrap());
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! `d2b qr`: the works behind the QR codes in photos, e.g. of the conference posters that link
+//! their paper with one. Decoding is left to ZBar's `zbarimg`, as the clipboard is left to each
+//! platform's tools, and the DOIs and arXiv IDs in what it reads are resolved like any other
+//! input. Codes that hold some other link are resolved as web pages.
+
+use crate::error::{Error, Kind};
+use crate::{graph, i18n, mail, Options};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::io;
+use std::process::Command;
+
+/// What `zbarimg` exits with when an image has no codes in it.
+const NO_CODES: i32 = 4;
+
+pub fn subcommand() -> App<'static, 'static> {
+    SubCommand::with_name("qr")
+        .about(i18n::text("about-qr"))
+        .arg(
+            Arg::with_name("images")
+                .required(true)
+                .min_values(1)
+                .help(i18n::text("help-images")),
+        )
+}
+
+/// The contents of the QR codes in an image, one per code.
+fn decode(image: &str) -> io::Result<Vec<String>> {
+    let out = Command::new("zbarimg")
+        .args(["--quiet", "--raw", "-Sdisable", "-Sqrcode.enable", image])
+        .output()?;
+    match out.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_owned)
+            .collect()),
+        Some(NO_CODES) => Ok(Vec::new()),
+        _ => Err(io::Error::other(
+            String::from_utf8_lossy(&out.stderr).trim().to_owned(),
+        )),
+    }
+}
+
+/// The inputs in the contents of a code: its DOIs and arXiv IDs, or else the link it holds.
+pub fn inputs(code: &str) -> Vec<String> {
+    let ids = mail::identifiers(code);
+    if !ids.is_empty() {
+        return ids;
+    }
+    match code.starts_with("http://") || code.starts_with("https://") {
+        true => vec![code.to_owned()],
+        false => Vec::new(),
+    }
+}
+
+/// Runs `d2b qr`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let mut found = Vec::new();
+    for image in matches.values_of("images").unwrap() {
+        let codes = decode(image).unwrap_or_else(|e| {
+            let message = match e.kind() {
+                io::ErrorKind::NotFound => i18n::text("qr-no-zbar").to_owned(),
+                _ => i18n::message("qr-unreadable", &[("file", &image), ("error", &e)]),
+            };
+            Error::new(Kind::Io, message).exit(opts.error_format)
+        });
+        let inputs = codes.iter().flat_map(|c| inputs(c)).collect::<Vec<_>>();
+        if inputs.is_empty() {
+            eprintln!(
+                "{}: {}",
+                i18n::text("warning"),
+                i18n::message("qr-none", &[("file", &image)])
+            );
+        }
+        found.extend(inputs.into_iter().map(|input| (input, image.to_owned())));
+    }
+    let listed = found.iter().map(|(input, _)| Some(input.clone())).collect();
+    let image = |input: &str| {
+        let (_, image) = found.iter().find(|(i, _)| i == input)?;
+        Some(format!("from a QR code in {}", image))
+    };
+    graph::print_all(listed, image, &opts).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inputs() {
+        assert_eq!(
+            inputs("https://doi.org/10.1145/359545.359563"),
+            ["doi:10.1145/359545.359563"]
+        );
+        assert_eq!(
+            inputs("https://arxiv.org/abs/1712.01815v2"),
+            ["arxiv:1712.01815v2"]
+        );
+        assert_eq!(
+            inputs("https://lab.example.org/posters/2024"),
+            ["https://lab.example.org/posters/2024"]
+        );
+        assert!(inputs("WIFI:S:conference;T:WPA;P:hunter2;;").is_empty());
+    }
+}
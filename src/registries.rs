@@ -0,0 +1,1012 @@
+//! Records of scientific databases that are cited in their own right, by an identifier of the
+//! database rather than a DOI: Protein Data Bank structures (`pdb:7BV2`), GenBank sequences
+//! (`genbank:MN908947.3`), ClinicalTrials.gov registrations (`NCT04280705`) and OSF projects and
+//! preprints (`osf:abc12`, including PsyArXiv, SocArXiv and engrXiv). Structures and sequences are
+//! `@dataset`s for biblatex and `@misc`es for BibTeX, the rest `@online`s and `@misc`es. Each is
+//! followed by the entries of its publications (the primary publication of a structure or
+//! sequence, the result publications of a trial, the published version of a preprint), which it
+//! names in `related`.
+//!
+//! Articles are also accepted by their PubMed and PubMed Central IDs (`pmid:32015508`,
+//! `PMC7094943`), which are looked up in Europe PMC, and by their Scopus EIDs
+//! (`2-s2.0-85012345678`) and Web of Science accession numbers (`WOS:000123456789012`), which are
+//! resolved through their DOIs with the user's API key for the service. Books are accepted by
+//! their Google Books volume IDs (`gbooks:zyTCAlFPjgYC`), Library of Congress Control Numbers
+//! (`lccn:2005047376`) and OCLC numbers (`oclc:60589465`, which needs a WorldCat token), and become
+//! `@book`s. Recorded talks and lectures are accepted by their YouTube and Vimeo URLs,
+//! and podcast episodes by their GUIDs in their show's feed (`podcast:<feed URL>#<GUID>`).
+//!
+//! The databases' own URLs are accepted as well.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{Error, Kind};
+use crate::http::SendWithRetry;
+use crate::resolvers::{self, Fetched};
+use crate::{
+    auth, catalogs, check_response, europepmc, i18n, podcast, video, Format, IdType, Options,
+    CLIENT,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::ACCEPT;
+use serde_json::Value;
+
+lazy_static! {
+    static ref PDB_RE: Regex = Regex::new(
+        r"(?i)^(?:pdb:\s*|https?://(?:www\.)?(?:rcsb\.org/structure|ebi\.ac\.uk/pdbe/entry/pdb)/)([0-9][a-z0-9]{3})/?$"
+    )
+    .unwrap();
+    static ref GENBANK_RE: Regex = Regex::new(
+        r"(?i)^(?:genbank:\s*|https?://www\.ncbi\.nlm\.nih\.gov/nuccore/)([a-z]{1,2}_?\d{5,8}(?:\.\d+)?)/?$"
+    )
+    .unwrap();
+    static ref NCT_RE: Regex = Regex::new(
+        r"(?i)^(?:https?://(?:www\.)?clinicaltrials\.gov/(?:study|ct2/show)/)?(NCT\d{8})/?$"
+    )
+    .unwrap();
+    static ref PMID_RE: Regex = Regex::new(
+        r"(?i)^(?:pmid:\s*|https?://(?:pubmed\.ncbi\.nlm\.nih\.gov|europepmc\.org/(?:abstract|article)/MED)/)(\d{1,8})/?$"
+    )
+    .unwrap();
+    static ref PMCID_RE: Regex = Regex::new(
+        r"(?i)^(?:pmcid:\s*|https?://(?:www\.ncbi\.nlm\.nih\.gov/pmc/articles|europepmc\.org/article/PMC)/)?(PMC\d+)/?$"
+    )
+    .unwrap();
+    static ref SCOPUS_RE: Regex = Regex::new(
+        r"(?i)^(?:(?:eid|scopus):\s*|https?://www\.scopus\.com/record/display\.uri\?(?:.*&)?eid=)?(2-s2\.0-\d{8,11})(?:&.*)?$"
+    )
+    .unwrap();
+    static ref WOS_RE: Regex = Regex::new(r"(?i)^(?:ut:\s*)?wos:(\d{15})$").unwrap();
+    /// Google Books volume IDs, which are case-sensitive, and the volume URLs of both the old and
+    /// the new interfaces.
+    static ref GOOGLE_BOOKS_RE: Regex = Regex::new(
+        r"^(?i:gbooks:\s*|https?://books\.google\.[a-z.]+/books(?:/about/[^?]*)?\?(?:.*&)?id=|https?://(?:www\.)?google\.[a-z.]+/books/edition/[^/]*/)([A-Za-z0-9_-]{12})(?:[&#/?].*)?$"
+    )
+    .unwrap();
+    static ref LCCN_RE: Regex = Regex::new(
+        r"(?i)^(?:lccn:\s*|https?://lccn\.loc\.gov/|https?://(?:www\.)?loc\.gov/item/)([a-z]{0,3}\s*\d{2,4}-?\d{1,6}(?:/[a-z0-9]+)?)/?$"
+    )
+    .unwrap();
+    static ref OCLC_RE: Regex = Regex::new(
+        r"(?i)^(?:(?:oclc:\s*|\(ocolc\)\s*|https?://(?:www\.)?worldcat\.org/(?:oclc|title)/|https?://search\.worldcat\.org/title/)(?:ocm|ocn|on)?|ocm|ocn)(\d{1,12})/?$"
+    )
+    .unwrap();
+    /// YouTube video IDs, which are case-sensitive, in watch, short, embed and live URLs.
+    static ref YOUTUBE_RE: Regex = Regex::new(
+        r"^(?i:https?://(?:(?:www\.|m\.)?youtube\.com/(?:watch\?(?:.*&)?v=|embed/|live/|shorts/)|youtu\.be/))([A-Za-z0-9_-]{11})(?:[&#/?].*)?$"
+    )
+    .unwrap();
+    static ref VIMEO_RE: Regex =
+        Regex::new(r"(?i)^https?://(?:www\.)?(?:vimeo\.com/|player\.vimeo\.com/video/)(\d+)(?:[#/?].*)?$").unwrap();
+    /// A podcast feed and the GUID of an episode in it, after the last `#`.
+    static ref PODCAST_RE: Regex = Regex::new(r"(?i)^podcast:\s*(https?://[^#\s]+#.+)$").unwrap();
+    /// OSF GUIDs, with the URLs of OSF and its preprint servers and the DOIs they mint for
+    /// preprints, which DOI negotiation describes poorly.
+    static ref OSF_RE: Regex = Regex::new(
+        r"(?i)^(?:osf:\s*|https?://(?:www\.)?(?:osf\.io/(?:preprints/[a-z]+/)?|(?:psyarxiv|socarxiv|engrxiv)\.com/)|(?:doi:\s*|https?://(?:dx\.)?doi\.org/)?10\.312\d\d/osf\.io/)([a-z0-9]{5})(?:_v\d+)?/?$"
+    )
+    .unwrap();
+    /// A year closing a parenthesis, as in GenBank's `JOURNAL` lines.
+    static ref YEAR_RE: Regex = Regex::new(r"\b((?:19|20)\d{2})\)").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registry {
+    Pdb,
+    GenBank,
+    ClinicalTrials,
+    Osf,
+    PubMed,
+    Pmc,
+    Scopus,
+    WebOfScience,
+    GoogleBooks,
+    Lccn,
+    Oclc,
+    YouTube,
+    Vimeo,
+    Podcast,
+}
+
+/// The names of OSF's preprint servers, by provider ID.
+const OSF_PROVIDERS: &[(&str, &str)] = &[
+    ("osf", "OSF Preprints"),
+    ("psyarxiv", "PsyArXiv"),
+    ("socarxiv", "SocArXiv"),
+    ("engrxiv", "engrXiv"),
+];
+
+impl Registry {
+    const ALL: &'static [Registry] = &[
+        Registry::Pdb,
+        Registry::GenBank,
+        Registry::ClinicalTrials,
+        Registry::Osf,
+        Registry::PubMed,
+        Registry::Pmc,
+        Registry::Scopus,
+        Registry::WebOfScience,
+        Registry::GoogleBooks,
+        Registry::Lccn,
+        Registry::Oclc,
+        Registry::YouTube,
+        Registry::Vimeo,
+        Registry::Podcast,
+    ];
+
+    fn pattern(self) -> &'static Regex {
+        match self {
+            Registry::Pdb => &PDB_RE,
+            Registry::GenBank => &GENBANK_RE,
+            Registry::ClinicalTrials => &NCT_RE,
+            Registry::Osf => &OSF_RE,
+            Registry::PubMed => &PMID_RE,
+            Registry::Pmc => &PMCID_RE,
+            Registry::Scopus => &SCOPUS_RE,
+            Registry::WebOfScience => &WOS_RE,
+            Registry::GoogleBooks => &GOOGLE_BOOKS_RE,
+            Registry::Lccn => &LCCN_RE,
+            Registry::Oclc => &OCLC_RE,
+            Registry::YouTube => &YOUTUBE_RE,
+            Registry::Vimeo => &VIMEO_RE,
+            Registry::Podcast => &PODCAST_RE,
+        }
+    }
+
+    /// The host an entry is credited to.
+    pub fn host(self) -> &'static str {
+        match self {
+            Registry::Pdb => "rcsb.org",
+            Registry::GenBank => "ncbi.nlm.nih.gov",
+            Registry::ClinicalTrials => "clinicaltrials.gov",
+            Registry::Osf => "osf.io",
+            Registry::PubMed | Registry::Pmc => "ebi.ac.uk",
+            Registry::Scopus => "api.elsevier.com",
+            Registry::WebOfScience => "api.clarivate.com",
+            Registry::GoogleBooks => "books.google.com",
+            Registry::Lccn => "loc.gov",
+            Registry::Oclc => "worldcat.org",
+            Registry::YouTube => "youtube.com",
+            Registry::Vimeo => "vimeo.com",
+            Registry::Podcast => "the podcast feed",
+        }
+    }
+
+    /// The registry an input names a record of, and the record's identifier.
+    pub fn parse(input: &str) -> Option<(Registry, String)> {
+        Self::ALL.iter().find_map(|&r| {
+            let c = r.pattern().captures(input.trim())?;
+            let id = match r {
+                Registry::Osf | Registry::Scopus => c[1].to_lowercase(),
+                Registry::GoogleBooks | Registry::YouTube | Registry::Vimeo | Registry::Podcast => {
+                    c[1].to_owned()
+                }
+                Registry::Lccn => catalogs::normalize_lccn(&c[1]),
+                Registry::Oclc => c[1].trim_start_matches('0').to_owned(),
+                _ => c[1].to_uppercase(),
+            };
+            Some((r, id))
+        })
+    }
+
+    /// The input a record is known by, to tell whether two inputs are the same.
+    pub fn input_id(self, id: &str) -> String {
+        match self {
+            Registry::Pdb => format!("pdb:{}", id.to_lowercase()),
+            Registry::GenBank => format!("genbank:{}", id.to_lowercase()),
+            Registry::ClinicalTrials => id.to_lowercase(),
+            Registry::Osf => format!("osf:{}", id),
+            Registry::PubMed => format!("pmid:{}", id),
+            Registry::Pmc => format!("pmcid:{}", id.to_lowercase()),
+            Registry::Scopus => format!("scopus:{}", id),
+            Registry::WebOfScience => format!("wos:{}", id),
+            Registry::GoogleBooks => format!("gbooks:{}", id),
+            Registry::Lccn => format!("lccn:{}", id),
+            Registry::Oclc => format!("oclc:{}", id),
+            Registry::YouTube => format!("youtube:{}", id),
+            Registry::Vimeo => format!("vimeo:{}", id),
+            Registry::Podcast => format!("podcast:{}", id),
+        }
+    }
+
+    async fn get_json(self, url: &str) -> Result<Value, Error> {
+        self.get_json_with(url, None).await
+    }
+
+    /// Fetches JSON, sending a header with the user's API key if one is given.
+    async fn get_json_with(self, url: &str, key: Option<(&str, String)>) -> Result<Value, Error> {
+        let mut req = CLIENT.get(url).header(ACCEPT, "application/json");
+        if let Some((header, value)) = key {
+            req = req.header(header, value);
+        }
+        let res = req.send_with_retry().await;
+        let body = check_response(res, self.host())?.text().await?;
+        serde_json::from_str(&body).map_err(|e| Error::new(Kind::Parse, e.to_string()))
+    }
+
+    /// The entry of a record, followed by those of its publications that resolve.
+    pub async fn fetch(self, id: &str, opts: &Options) -> Result<Vec<Fetched>, Error> {
+        let (mut entry, publications) = match self {
+            Registry::Pdb => {
+                let json = self
+                    .get_json(&format!("https://data.rcsb.org/rest/v1/core/entry/{}", id))
+                    .await?;
+                let entry = pdb_entry(&json, opts.format).ok_or_else(|| self.not_found())?;
+                let doi = json["rcsb_primary_citation"]["pdbx_database_id_doi"]
+                    .as_str()
+                    .map(str::to_owned);
+                (entry, doi.into_iter().collect())
+            }
+            Registry::GenBank => {
+                let url = reqwest::Url::parse_with_params(
+                    "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/efetch.fcgi",
+                    &[
+                        ("db", "nuccore"),
+                        ("id", id),
+                        ("rettype", "gb"),
+                        ("retmode", "text"),
+                    ],
+                )
+                .unwrap();
+                let res = CLIENT.get(url).send_with_retry().await;
+                let body = check_response(res, self.host())?.text().await?;
+                let record = genbank_record(&body).ok_or_else(|| self.not_found())?;
+                let doi = match &record.pubmed {
+                    Some(pmid) => pubmed_doi(pmid).await,
+                    None => None,
+                };
+                (record.entry(opts.format), doi.into_iter().collect())
+            }
+            Registry::ClinicalTrials => {
+                let json = self
+                    .get_json(&format!("https://clinicaltrials.gov/api/v2/studies/{}", id))
+                    .await?;
+                let entry = trial_entry(&json, opts.format).ok_or_else(|| self.not_found())?;
+                let mut dois = Vec::new();
+                for pmid in result_pmids(&json) {
+                    dois.extend(pubmed_doi(&pmid).await);
+                }
+                (entry, dois)
+            }
+            Registry::Osf => return self.fetch_osf(id, opts).await,
+            Registry::GoogleBooks => {
+                let volume = self
+                    .get_json(&format!(
+                        "https://www.googleapis.com/books/v1/volumes/{}",
+                        id
+                    ))
+                    .await?;
+                let entry =
+                    catalogs::google_books_entry(&volume).ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Isbn, self.host(), entry)]);
+            }
+            Registry::Lccn => {
+                let item = self
+                    .get_json(&format!("https://www.loc.gov/item/{}/?fo=json", id))
+                    .await?;
+                let entry = catalogs::loc_entry(&item).ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Isbn, self.host(), entry)]);
+            }
+            Registry::Oclc => {
+                let key = (
+                    "Authorization",
+                    format!("Bearer {}", self.token(opts)?.secret()),
+                );
+                let url = format!(
+                    "https://americas.discovery.api.oclc.org/worldcat/search/v2/bibs/{}",
+                    id
+                );
+                let record = self.get_json_with(&url, Some(key)).await?;
+                let entry = catalogs::worldcat_entry(&record).ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Isbn, self.host(), entry)]);
+            }
+            Registry::YouTube | Registry::Vimeo => {
+                let url = match self {
+                    Registry::YouTube => format!("https://www.youtube.com/watch?v={}", id),
+                    _ => format!("https://vimeo.com/{}", id),
+                };
+                let oembed = match self {
+                    Registry::YouTube => "https://www.youtube.com/oembed",
+                    _ => "https://vimeo.com/api/oembed.json",
+                };
+                let oembed = reqwest::Url::parse_with_params(
+                    oembed,
+                    &[("url", url.as_str()), ("format", "json")],
+                )
+                .unwrap();
+                let json = self.get_json(oembed.as_str()).await?;
+                // YouTube's oEmbed has no upload date or duration, but its watch page does.
+                let page = match self {
+                    Registry::YouTube => match CLIENT.get(&url).send_with_retry().await {
+                        Ok(res) => res.text().await.unwrap_or_default(),
+                        Err(_) => String::new(),
+                    },
+                    _ => String::new(),
+                };
+                let entry = video::entry(&json, &page, &url, opts.format)
+                    .ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Url, self.host(), entry)]);
+            }
+            Registry::Podcast => {
+                let (feed, guid) = id.rsplit_once('#').unwrap();
+                let entry = podcast::from_guid(feed, guid, opts.format)
+                    .await
+                    .ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Url, self.host(), entry)]);
+            }
+            Registry::Scopus | Registry::WebOfScience => {
+                let doi = self.doi(id, opts).await?;
+                return resolvers::resolve(&doi, IdType::Doi, opts).await;
+            }
+            Registry::PubMed | Registry::Pmc => {
+                let kind = match self {
+                    Registry::PubMed => europepmc::Id::Pmid,
+                    _ => europepmc::Id::Pmcid,
+                };
+                let entry = europepmc::fetch(kind, id)
+                    .await
+                    .as_ref()
+                    .and_then(europepmc::entry)
+                    .ok_or_else(|| self.not_found())?;
+                return Ok(vec![Fetched::new(IdType::Doi, self.host(), entry)]);
+            }
+        };
+
+        let mut fetched = vec![];
+        let mut related = Vec::new();
+        for doi in publications {
+            // The record is still worth citing if its publications can't be resolved.
+            if let Ok(papers) = resolvers::resolve(&doi, IdType::Doi, opts).await {
+                related.extend(papers.first().map(|p| p.entry.key.clone()));
+                fetched.extend(papers);
+            }
+        }
+        if !related.is_empty() {
+            entry.set("related", &related.join(","));
+        }
+        fetched.insert(0, Fetched::new(IdType::Url, self.host(), entry));
+        Ok(fetched)
+    }
+
+    /// An OSF preprint, after its published version if it has one, or an OSF project.
+    async fn fetch_osf(self, id: &str, opts: &Options) -> Result<Vec<Fetched>, Error> {
+        let api = "https://api.osf.io/v2";
+        let (json, kind) = match self.get_json(&format!("{}/preprints/{}/", api, id)).await {
+            Ok(json) => (json, "preprints"),
+            Err(e) if e.kind == Kind::NotFound => (
+                self.get_json(&format!("{}/nodes/{}/", api, id)).await?,
+                "nodes",
+            ),
+            Err(e) => return Err(e),
+        };
+        let contributors = self
+            .get_json(&format!(
+                "{}/{}/{}/contributors/?embed=users",
+                api, kind, id
+            ))
+            .await
+            .unwrap_or_default();
+        let mut entry =
+            osf_entry(&json, &contributors, opts.format).ok_or_else(|| self.not_found())?;
+
+        let published = json["data"]["attributes"]["doi"]
+            .as_str()
+            .filter(|_| kind == "preprints");
+        let mut fetched = match published {
+            Some(doi) => resolvers::resolve(doi, IdType::Doi, opts)
+                .await
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        if let Some(paper) = fetched.first_mut() {
+            crate::link_preprint(&mut paper.entry, &mut entry, opts.format);
+        }
+        fetched.push(Fetched::new(IdType::Url, self.host(), entry));
+        Ok(fetched)
+    }
+
+    /// The user's API key for a registry that needs one.
+    fn token(self, opts: &Options) -> Result<&auth::Token, Error> {
+        let (service, token) = match self {
+            Registry::Scopus => ("scopus", &opts.scopus_token),
+            Registry::WebOfScience => ("wos", &opts.wos_token),
+            _ => ("worldcat", &opts.worldcat_token),
+        };
+        token.as_ref().ok_or_else(|| {
+            Error::new(
+                Kind::Usage,
+                i18n::message(
+                    "auth-missing",
+                    &[("resolver", &self.host()), ("service", &service)],
+                ),
+            )
+        })
+    }
+
+    /// The DOI Scopus or Web of Science has for one of its records.
+    async fn doi(self, id: &str, opts: &Options) -> Result<String, Error> {
+        let header = match self {
+            Registry::Scopus => "X-ELS-APIKey",
+            _ => "X-ApiKey",
+        };
+        let key = (header, self.token(opts)?.secret().to_owned());
+        let (url, doi) = match self {
+            Registry::Scopus => (
+                format!(
+                    "https://api.elsevier.com/content/abstract/eid/{}?field=doi",
+                    id
+                ),
+                "/abstracts-retrieval-response/coredata/prism:doi",
+            ),
+            _ => (
+                format!(
+                    "https://api.clarivate.com/apis/wos-starter/v1/documents/WOS:{}",
+                    id
+                ),
+                "/identifiers/doi",
+            ),
+        };
+        let json = self.get_json_with(&url, Some(key)).await?;
+        json.pointer(doi)
+            .and_then(Value::as_str)
+            .map(str::to_owned)
+            .ok_or_else(|| {
+                Error::new(
+                    Kind::NotFound,
+                    i18n::message("no-doi", &[("resolver", &self.host())]),
+                )
+            })
+    }
+
+    fn not_found(self) -> Error {
+        Error::new(
+            Kind::NotFound,
+            i18n::message("no-record", &[("resolver", &self.host())]),
+        )
+    }
+}
+
+/// A `@dataset` for biblatex, or a `@misc` with `howpublished` for BibTeX.
+fn dataset(
+    format: Format,
+    key: &str,
+    title: &str,
+    authors: &[String],
+    year: Option<&str>,
+    publisher: &str,
+) -> Entry {
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("dataset", key),
+        Format::Bibtex => Entry::new("misc", key),
+    };
+    entry.set("title", title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    match format {
+        Format::Biblatex => entry.set("publisher", publisher),
+        Format::Bibtex => entry.set("howpublished", publisher),
+    }
+    entry
+}
+
+/// An entry from the RCSB entry JSON. The authors are those of the deposition, and the year that
+/// of its release.
+pub fn pdb_entry(json: &Value, format: Format) -> Option<Entry> {
+    let id = json["rcsb_id"].as_str()?.to_uppercase();
+    let title = json["struct"]["title"].as_str()?;
+    let authors = json["audit_author"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|a| a["name"].as_str())
+        .map(pdb_name)
+        .collect::<Vec<_>>();
+    let year = json["rcsb_accession_info"]["initial_release_date"]
+        .as_str()
+        .map(|d| &d[..4.min(d.len())]);
+    let mut entry = dataset(
+        format,
+        &format!("PDB_{}", id),
+        title,
+        &authors,
+        year,
+        "Protein Data Bank",
+    );
+    entry.set("note", &format!("PDB ID {}", id));
+    entry.set("doi", &format!("10.2210/pdb{}/pdb", id));
+    entry.set("url", &format!("https://www.rcsb.org/structure/{}", id));
+    Some(entry)
+}
+
+/// An entry from a ClinicalTrials.gov study JSON: the trial's title, its lead sponsor as the
+/// author (biblatex's `organization`), and the date it was registered.
+pub fn trial_entry(json: &Value, format: Format) -> Option<Entry> {
+    let protocol = &json["protocolSection"];
+    let identification = &protocol["identificationModule"];
+    let id = identification["nctId"].as_str()?;
+    let title = identification["officialTitle"]
+        .as_str()
+        .or_else(|| identification["briefTitle"].as_str())?;
+    let sponsor = protocol["sponsorCollaboratorsModule"]["leadSponsor"]["name"].as_str();
+    let registered = protocol["statusModule"]["studyFirstSubmitDate"].as_str();
+    let url = format!("https://clinicaltrials.gov/study/{}", id);
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("online", id),
+        Format::Bibtex => Entry::new("misc", id),
+    };
+    entry.set("title", title);
+    match format {
+        Format::Biblatex => {
+            if let Some(sponsor) = sponsor {
+                entry.set("organization", sponsor);
+            }
+            if let Some(registered) = registered {
+                entry.set("date", registered);
+            }
+            entry.set("note", &format!("ClinicalTrials.gov identifier {}", id));
+        }
+        Format::Bibtex => {
+            if let Some(sponsor) = sponsor {
+                entry.set("author", &format!("{{{}}}", sponsor));
+            }
+            entry.set("howpublished", "ClinicalTrials.gov");
+            if let Some(registered) = registered {
+                entry.set("year", &registered[..4.min(registered.len())]);
+            }
+            entry.set(
+                "note",
+                &match registered {
+                    Some(registered) => format!("Identifier {}, registered {}", id, registered),
+                    None => format!("Identifier {}", id),
+                },
+            );
+        }
+    }
+    entry.set("url", &url);
+    Some(entry)
+}
+
+/// An entry from the OSF JSON of a preprint or a project, with the bibliographic contributors of
+/// its contributor list. A preprint is an eprint of its server, with the preprint's DOI.
+pub fn osf_entry(json: &Value, contributors: &Value, format: Format) -> Option<Entry> {
+    let data = &json["data"];
+    let attributes = &data["attributes"];
+    let id = data["id"].as_str()?;
+    let title = attributes["title"].as_str()?.trim();
+    let authors = contributors["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|c| c["attributes"]["bibliographic"] != false)
+        .filter_map(|c| {
+            let user = &c["embeds"]["users"]["data"]["attributes"];
+            match (user["family_name"].as_str(), user["given_name"].as_str()) {
+                (Some(family), Some(given)) if !family.is_empty() => {
+                    Some(format!("{}, {}", family, given))
+                }
+                _ => user["full_name"].as_str().map(bibtex::name_last_first),
+            }
+        })
+        .collect::<Vec<_>>();
+    let preprint = data["type"] == "preprints";
+    let year = attributes["date_published"]
+        .as_str()
+        .or_else(|| attributes["date_created"].as_str())
+        .map(|d| &d[..4.min(d.len())]);
+    let family = authors
+        .first()
+        .map_or("anonymous", |a| bibtex::family_name(a))
+        .replace(' ', "");
+    let key = match year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    };
+
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("online", &key),
+        Format::Bibtex => Entry::new("misc", &key),
+    };
+    entry.set("title", title);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    if preprint {
+        let provider = data["relationships"]["provider"]["data"]["id"]
+            .as_str()
+            .unwrap_or("osf");
+        let server = OSF_PROVIDERS
+            .iter()
+            .find(|(p, _)| *p == provider)
+            .map_or(provider, |(_, name)| name);
+        entry.set("eprint", id);
+        entry.set("archivePrefix", server);
+        if let Some(doi) = data["links"]["preprint_doi"].as_str() {
+            entry.set("doi", doi.trim_start_matches("https://doi.org/"));
+        }
+    } else {
+        match format {
+            Format::Biblatex => entry.set("organization", "OSF"),
+            Format::Bibtex => entry.set("howpublished", "OSF"),
+        }
+    }
+    entry.set("url", &format!("https://osf.io/{}/", id));
+    Some(entry)
+}
+
+/// The PubMed IDs of the publications of a trial's results.
+fn result_pmids(json: &Value) -> Vec<String> {
+    json["protocolSection"]["referencesModule"]["references"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|r| r["type"] == "RESULT")
+        .filter_map(|r| r["pmid"].as_str())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// A name as the PDB and GenBank write them, "Yin, W." or "Wu,F.", as "Family, Given".
+fn pdb_name(name: &str) -> String {
+    match name.split_once(',') {
+        Some((family, given)) => format!("{}, {}", family.trim(), given.trim()),
+        None => name.trim().to_owned(),
+    }
+}
+
+/// What a GenBank flat file says about a sequence.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenBankRecord {
+    pub definition: String,
+    /// The accession with its version, e.g. `MN908947.3`.
+    pub version: String,
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+    /// The PubMed ID of the first reference that has one.
+    pub pubmed: Option<String>,
+}
+
+impl GenBankRecord {
+    pub fn entry(&self, format: Format) -> Entry {
+        let accession = self.version.split('.').next().unwrap_or(&self.version);
+        let mut entry = dataset(
+            format,
+            &format!("GenBank_{}", accession),
+            &self.definition,
+            &self.authors,
+            self.year.as_deref(),
+            "GenBank",
+        );
+        entry.set("note", &format!("GenBank accession {}", self.version));
+        entry.set(
+            "url",
+            &format!("https://www.ncbi.nlm.nih.gov/nuccore/{}", self.version),
+        );
+        entry
+    }
+}
+
+/// The fields of a GenBank flat file, each with its continuation lines joined, in order. The
+/// fields of a `REFERENCE` follow it, indented.
+fn flat_fields(src: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in src.lines() {
+        let (name, value) = line.split_at(12.min(line.len()));
+        if name.trim().is_empty() {
+            if let Some((_, last)) = fields.last_mut() {
+                last.push(' ');
+                last.push_str(value.trim());
+            }
+        } else {
+            fields.push((name.trim().to_owned(), value.trim().to_owned()));
+        }
+    }
+    fields
+}
+
+fn reference_field<'a>(reference: &[(&str, &'a str)], name: &str) -> Option<&'a str> {
+    reference.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+}
+
+/// A record from a GenBank flat file. The authors and year are those of the direct submission,
+/// which is how the sequence itself is credited.
+pub fn genbank_record(src: &str) -> Option<GenBankRecord> {
+    let fields = flat_fields(src);
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let mut record = GenBankRecord {
+        definition: field("DEFINITION")?.trim_end_matches('.').to_owned(),
+        version: field("VERSION")?.split_whitespace().next()?.to_owned(),
+        ..Default::default()
+    };
+
+    // Each reference's AUTHORS, TITLE, JOURNAL and PUBMED, in order.
+    let mut references: Vec<Vec<(&str, &str)>> = Vec::new();
+    for (name, value) in &fields {
+        match name.as_str() {
+            "REFERENCE" => references.push(Vec::new()),
+            "AUTHORS" | "TITLE" | "JOURNAL" | "PUBMED" => {
+                if let Some(reference) = references.last_mut() {
+                    reference.push((name, value));
+                }
+            }
+            _ => {}
+        }
+    }
+    record.pubmed = references
+        .iter()
+        .find_map(|r| reference_field(r, "PUBMED"))
+        .map(str::to_owned);
+    let submission = references
+        .iter()
+        .find(|r| reference_field(r, "TITLE") == Some("Direct Submission"))
+        .or_else(|| references.first());
+    if let Some(submission) = submission {
+        record.authors = reference_field(submission, "AUTHORS")
+            .unwrap_or_default()
+            .split(", ")
+            .flat_map(|a| a.split(" and "))
+            .filter(|a| !a.trim().is_empty())
+            .map(pdb_name)
+            .collect();
+        // "Submitted (05-JAN-2020) ...", or "Nature 579 (7798), 265-269 (2020)".
+        record.year = reference_field(submission, "JOURNAL")
+            .and_then(|j| YEAR_RE.captures_iter(j).next().map(|c| c[1].to_owned()));
+    }
+    Some(record)
+}
+
+/// The DOI of a PubMed article, from its summary.
+async fn pubmed_doi(pmid: &str) -> Option<String> {
+    let url = reqwest::Url::parse_with_params(
+        "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esummary.fcgi",
+        &[("db", "pubmed"), ("id", pmid), ("retmode", "json")],
+    )
+    .unwrap();
+    let res = CLIENT.get(url).send_with_retry().await.ok()?;
+    if !res.status().is_success() {
+        return None;
+    }
+    let json: Value = serde_json::from_str(&res.text().await.ok()?).ok()?;
+    json["result"][pmid]["articleids"]
+        .as_array()?
+        .iter()
+        .find(|id| id["idtype"] == "doi")
+        .and_then(|id| id["value"].as_str())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Registry::parse("pdb:7bv2"),
+            Some((Registry::Pdb, "7BV2".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("https://www.rcsb.org/structure/7BV2"),
+            Some((Registry::Pdb, "7BV2".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("genbank: MN908947.3"),
+            Some((Registry::GenBank, "MN908947.3".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("https://www.ncbi.nlm.nih.gov/nuccore/NC_045512"),
+            Some((Registry::GenBank, "NC_045512".to_owned()))
+        );
+        assert_eq!(Registry::parse("7BV2"), None);
+        assert_eq!(
+            Registry::parse("https://pubmed.ncbi.nlm.nih.gov/32015508/"),
+            Some((Registry::PubMed, "32015508".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("pmc7094943"),
+            Some((Registry::Pmc, "PMC7094943".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse(
+                "https://www.scopus.com/record/display.uri?eid=2-s2.0-85012345678&origin=resultslist"
+            ),
+            Some((Registry::Scopus, "2-s2.0-85012345678".to_owned()))
+        );
+        for input in [
+            "gbooks:zyTCAlFPjgYC",
+            "https://books.google.com/books?id=zyTCAlFPjgYC&printsec=frontcover",
+            "https://books.google.co.uk/books/about/The_Google_Story.html?id=zyTCAlFPjgYC",
+            "https://www.google.com/books/edition/The_Google_Story/zyTCAlFPjgYC?hl=en",
+        ] {
+            assert_eq!(
+                Registry::parse(input),
+                Some((Registry::GoogleBooks, "zyTCAlFPjgYC".to_owned()))
+            );
+        }
+        assert_eq!(
+            Registry::parse("https://lccn.loc.gov/85-2"),
+            Some((Registry::Lccn, "85000002".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("(OCoLC)ocm00012345"),
+            Some((Registry::Oclc, "12345".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("https://search.worldcat.org/title/60589465"),
+            Some((Registry::Oclc, "60589465".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("WOS:000123456789012"),
+            Some((Registry::WebOfScience, "000123456789012".to_owned()))
+        );
+        for input in [
+            "https://www.youtube.com/watch?v=_ahvzDzKdB0&t=60s",
+            "https://youtu.be/_ahvzDzKdB0",
+        ] {
+            assert_eq!(
+                Registry::parse(input),
+                Some((Registry::YouTube, "_ahvzDzKdB0".to_owned()))
+            );
+        }
+        assert_eq!(
+            Registry::parse("https://player.vimeo.com/video/76979871"),
+            Some((Registry::Vimeo, "76979871".to_owned()))
+        );
+        assert_eq!(
+            Registry::parse("podcast:https://feeds.npr.org/510308/podcast.xml#a1b2-c3"),
+            Some((
+                Registry::Podcast,
+                "https://feeds.npr.org/510308/podcast.xml#a1b2-c3".to_owned()
+            ))
+        );
+        assert_eq!(Registry::Pdb.input_id("7BV2"), "pdb:7bv2");
+    }
+
+    #[test]
+    fn test_pdb_entry() {
+        let json = serde_json::json!({
+            "rcsb_id": "7BV2",
+            "struct": {"title": "The complex structure of SARS-CoV-2 RdRp with Remdesivir"},
+            "audit_author": [{"name": "Yin, W."}, {"name": "Mao, C."}],
+            "rcsb_accession_info": {"initial_release_date": "2020-04-22T00:00:00+0000"},
+            "rcsb_primary_citation": {"pdbx_database_id_doi": "10.1126/science.abc1560"}
+        });
+        assert_eq!(
+            pdb_entry(&json, Format::Biblatex).unwrap().to_string(),
+            "@dataset{PDB_7BV2,\n  \
+             title={The complex structure of SARS-CoV-2 RdRp with Remdesivir},\n  \
+             author={Yin, W. and Mao, C.},\n  year={2020},\n  publisher={Protein Data Bank},\n  \
+             note={PDB ID 7BV2},\n  doi={10.2210/pdb7BV2/pdb},\n  \
+             url={https://www.rcsb.org/structure/7BV2}\n}"
+        );
+        let entry = pdb_entry(&json, Format::Bibtex).unwrap();
+        assert_eq!(entry.kind, "misc");
+        assert_eq!(entry.get("howpublished"), Some("Protein Data Bank"));
+    }
+
+    #[test]
+    fn test_trial_entry() {
+        assert_eq!(
+            Registry::parse("https://clinicaltrials.gov/study/nct04280705"),
+            Some((Registry::ClinicalTrials, "NCT04280705".to_owned()))
+        );
+        let json = serde_json::json!({"protocolSection": {
+            "identificationModule": {
+                "nctId": "NCT04280705",
+                "briefTitle": "Adaptive COVID-19 Treatment Trial (ACTT)"
+            },
+            "sponsorCollaboratorsModule": {
+                "leadSponsor": {"name": "National Institute of Allergy and Infectious Diseases (NIAID)"}
+            },
+            "statusModule": {"studyFirstSubmitDate": "2020-02-20"},
+            "referencesModule": {"references": [
+                {"pmid": "32445440", "type": "RESULT"},
+                {"pmid": "12345678", "type": "BACKGROUND"}
+            ]}
+        }});
+        assert_eq!(
+            trial_entry(&json, Format::Biblatex).unwrap().to_string(),
+            "@online{NCT04280705,\n  title={Adaptive COVID-19 Treatment Trial (ACTT)},\n  \
+             organization={National Institute of Allergy and Infectious Diseases (NIAID)},\n  \
+             date={2020-02-20},\n  note={ClinicalTrials.gov identifier NCT04280705},\n  \
+             url={https://clinicaltrials.gov/study/NCT04280705}\n}"
+        );
+        let entry = trial_entry(&json, Format::Bibtex).unwrap();
+        assert_eq!(
+            entry.get("author"),
+            Some("{National Institute of Allergy and Infectious Diseases (NIAID)}")
+        );
+        assert_eq!(entry.get("year"), Some("2020"));
+        assert_eq!(result_pmids(&json), ["32445440"]);
+    }
+
+    #[test]
+    fn test_osf_entry() {
+        for input in [
+            "osf:ABC12",
+            "https://psyarxiv.com/abc12/",
+            "https://osf.io/preprints/socarxiv/abc12_v2",
+            "https://doi.org/10.31234/osf.io/abc12",
+        ] {
+            assert_eq!(
+                Registry::parse(input),
+                Some((Registry::Osf, "abc12".to_owned()))
+            );
+        }
+        let preprint = serde_json::json!({"data": {
+            "id": "abc12",
+            "type": "preprints",
+            "attributes": {"title": "Many labs ", "date_published": "2019-03-01T12:00:00Z", "doi": null},
+            "relationships": {"provider": {"data": {"id": "psyarxiv"}}},
+            "links": {"preprint_doi": "https://doi.org/10.31234/osf.io/abc12"}
+        }});
+        let contributors = serde_json::json!({"data": [
+            {"attributes": {"bibliographic": true}, "embeds": {"users": {"data": {"attributes":
+                {"full_name": "Ana García", "given_name": "Ana", "family_name": "García"}}}}},
+            {"attributes": {"bibliographic": false}, "embeds": {"users": {"data": {"attributes":
+                {"full_name": "Lab Manager"}}}}},
+            {"attributes": {"bibliographic": true}, "embeds": {"users": {"data": {"attributes":
+                {"full_name": "Jeff Shen", "given_name": "", "family_name": ""}}}}}
+        ]});
+        assert_eq!(
+            osf_entry(&preprint, &contributors, Format::Biblatex)
+                .unwrap()
+                .to_string(),
+            "@online{García_2019,\n  title={Many labs},\n  author={García, Ana and Shen, Jeff},\n  \
+             year={2019},\n  eprint={abc12},\n  archivePrefix={PsyArXiv},\n  \
+             doi={10.31234/osf.io/abc12},\n  url={https://osf.io/abc12/}\n}"
+        );
+
+        let project = serde_json::json!({"data": {
+            "id": "xyz98", "type": "nodes",
+            "attributes": {"title": "Data", "date_created": "2017-01-01T00:00:00"}
+        }});
+        let entry = osf_entry(&project, &Value::Null, Format::Bibtex).unwrap();
+        assert_eq!(entry.key, "anonymous_2017");
+        assert_eq!(entry.get("howpublished"), Some("OSF"));
+    }
+
+    #[test]
+    fn test_genbank_record() {
+        let src = "\
+LOCUS       MN908947               29903 bp ss-RNA     linear   VRL 18-MAR-2020
+DEFINITION  Severe acute respiratory syndrome coronavirus 2 isolate Wuhan-Hu-1,
+            complete genome.
+ACCESSION   MN908947
+VERSION     MN908947.3
+REFERENCE   1  (bases 1 to 29903)
+  AUTHORS   Wu,F., Zhao,S., Yu,B. and Zhang,Y.Z.
+  TITLE     A new coronavirus associated with human respiratory disease in
+            China
+  JOURNAL   Nature 579 (7798), 265-269 (2020)
+   PUBMED   32015508
+REFERENCE   2  (bases 1 to 29903)
+  AUTHORS   Zhang,Y.-Z.
+  TITLE     Direct Submission
+  JOURNAL   Submitted (05-JAN-2020) Shanghai Public Health Clinical Center,
+            Shanghai, China
+FEATURES             Location/Qualifiers
+";
+        let record = genbank_record(src).unwrap();
+        assert_eq!(record.pubmed.as_deref(), Some("32015508"));
+        assert_eq!(
+            record.entry(Format::Biblatex).to_string(),
+            "@dataset{GenBank_MN908947,\n  \
+             title={Severe acute respiratory syndrome coronavirus 2 isolate Wuhan-Hu-1, complete genome},\n  \
+             author={Zhang, Y.-Z.},\n  year={2020},\n  publisher={GenBank},\n  \
+             note={GenBank accession MN908947.3},\n  \
+             url={https://www.ncbi.nlm.nih.gov/nuccore/MN908947.3}\n}"
+        );
+    }
+}
@@ -0,0 +1,227 @@
+//! `d2b rekey`: regenerates the keys of a `.bib` file from a template and rewrites the `\cite`s in
+//! the LaTeX sources to match, so that the two can't drift apart. A dry run, printing the changes
+//! as a diff, unless `--apply` is given.
+
+use crate::bibtex::{self, Entry, ILLEGAL_KEY_CHARS};
+use crate::error::{Error, Kind};
+use crate::{atomic, color, diff, i18n, rename, Options};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+pub const DEFAULT_FORMAT: &str = "{author}{year}";
+
+lazy_static! {
+    /// `\cite`, `\citep`, `\textcite`, `\nocite`, `\parencite*`, ... with up to two optional
+    /// arguments, capturing the command and the braced key list.
+    static ref CITE_RE: Regex = Regex::new(
+        r"(\\[A-Za-z]*cite[A-Za-z]*\*?\s*(?:\[[^\]]*\]\s*){0,2}\{)([^}]*)\}"
+    )
+    .unwrap();
+    /// The key at the start of an entry's text.
+    static ref ENTRY_KEY_RE: Regex = Regex::new(r"^(@\s*\w+\s*[{(]\s*)([^,\s]*)").unwrap();
+}
+
+/// The key a template gives an entry, with the placeholders of `d2b rename` and without the
+/// whitespace and characters keys can't have.
+pub fn key_for(format: &str, entry: &Entry) -> String {
+    rename::file_name(format, entry)
+        .chars()
+        .filter(|c| !c.is_whitespace() && !ILLEGAL_KEY_CHARS.contains(c))
+        .collect()
+}
+
+/// The new key for every entry whose key changes. Entries the template gives the same key get
+/// `a`, `b`, ... suffixes, and entries it gives no key at all keep theirs.
+pub fn new_keys(entries: &[Entry], format: &str) -> HashMap<String, String> {
+    let mut rekeyed = entries
+        .iter()
+        .map(|e| {
+            let mut new = e.clone();
+            let key = key_for(format, e);
+            if !key.is_empty() {
+                new.key = key;
+            }
+            new
+        })
+        .collect::<Vec<_>>();
+    bibtex::disambiguate_keys(&mut rekeyed);
+    entries
+        .iter()
+        .zip(rekeyed)
+        .filter(|(old, new)| old.key != new.key)
+        .map(|(old, new)| (old.key.clone(), new.key))
+        .collect()
+}
+
+/// Rewrites the keys in every citation command of a LaTeX source.
+pub fn rewrite_cites(src: &str, keys: &HashMap<String, String>) -> String {
+    CITE_RE
+        .replace_all(src, |c: &Captures| {
+            let list = c[2]
+                .split(',')
+                .map(|k| {
+                    // Keep the spacing around each key.
+                    let trimmed = k.trim();
+                    match keys.get(trimmed) {
+                        Some(new) => k.replacen(trimmed, new, 1),
+                        None => k.to_owned(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("{}{}}}", &c[1], list)
+        })
+        .into_owned()
+}
+
+/// Rewrites the keys of a `.bib` file's entries, and the `crossref` fields that refer to them,
+/// leaving everything else as it was.
+pub fn rewrite_bib(src: &str, keys: &HashMap<String, String>) -> String {
+    let mut out = String::new();
+    for chunk in bibtex::split_file(src) {
+        let entry = match &chunk.entry {
+            Some(entry) => entry,
+            None => {
+                out.push_str(chunk.text);
+                continue;
+            }
+        };
+        let mut text = match keys.get(&entry.key) {
+            Some(new) => ENTRY_KEY_RE
+                .replace(chunk.text, |c: &Captures| format!("{}{}", &c[1], new))
+                .into_owned(),
+            None => chunk.text.to_owned(),
+        };
+        if let Some(new) = entry.get("crossref").and_then(|k| keys.get(k)) {
+            let old = entry.get("crossref").unwrap();
+            let re = Regex::new(&format!(
+                r"(?i)(crossref\s*=\s*[{{\x22]\s*){}",
+                regex::escape(old)
+            ))
+            .unwrap();
+            text = re
+                .replace(&text, |c: &Captures| format!("{}{}", &c[1], new))
+                .into_owned();
+        }
+        out.push_str(&text);
+    }
+    out
+}
+
+/// Runs `d2b rekey`.
+pub fn run(matches: &ArgMatches, opts: Options) {
+    let format = matches.value_of("key-format").unwrap_or(DEFAULT_FORMAT);
+    let apply = matches.is_present("apply");
+    let files = matches.values_of("files").unwrap().collect::<Vec<_>>();
+    // Locked in name order, so that two runs over the same files can't each wait for the other.
+    let mut locked = files.clone();
+    locked.sort();
+    locked.dedup();
+    let _locks = locked
+        .iter()
+        .filter(|_| apply)
+        .map(|file| {
+            atomic::lock(file).unwrap_or_else(|e| {
+                Error::new(
+                    Kind::Io,
+                    i18n::message("file-unwritable", &[("file", file), ("error", &e)]),
+                )
+                .exit(opts.error_format)
+            })
+        })
+        .collect::<Vec<_>>();
+    let read = |file: &str| {
+        std::fs::read_to_string(file).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unreadable", &[("file", &file), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        })
+    };
+    let (bibs, sources): (Vec<_>, Vec<_>) = files
+        .into_iter()
+        .map(|f| (f, read(f)))
+        .partition(|(f, _)| f.to_lowercase().ends_with(".bib"));
+    if bibs.is_empty() {
+        Error::new(Kind::Usage, i18n::text("rekey-no-bib")).exit(opts.error_format);
+    }
+
+    let entries = bibs
+        .iter()
+        .flat_map(|(_, src)| bibtex::split_file(src).into_iter().filter_map(|c| c.entry))
+        .collect::<Vec<_>>();
+    let keys = new_keys(&entries, format);
+
+    let mut changed = 0;
+    for (file, src) in bibs.iter().chain(&sources) {
+        let new = if file.to_lowercase().ends_with(".bib") {
+            rewrite_bib(src, &keys)
+        } else {
+            rewrite_cites(src, &keys)
+        };
+        if new == *src {
+            continue;
+        }
+        changed += 1;
+        if apply {
+            if let Err(e) = atomic::write(file, &new) {
+                Error::new(
+                    Kind::Io,
+                    i18n::message("file-unwritable", &[("file", file), ("error", &e)]),
+                )
+                .exit(opts.error_format)
+            }
+        } else {
+            let diff = diff::unified(file, src, &new);
+            print!("{}", if opts.color { color::diff(&diff) } else { diff });
+        }
+    }
+    eprintln!(
+        "{}",
+        i18n::message(
+            "rekey-summary",
+            &[("keys", &keys.len()), ("files", &changed)]
+        )
+    );
+    if !apply {
+        eprintln!("{}", i18n::text("rekey-dry-run"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rekey() {
+        let bib = "% refs\n@article{lamport78,\n  author={Lamport, Leslie},\n  year={1978}\n}\n\n\
+                   @inproceedings{X, author={Doe, Jane}, year={2020}, crossref={proc}}\n\
+                   @misc{Y, author = {Doe, John}, year = 2020}\n\
+                   @proceedings{proc, title={Proc}}\n";
+        let entries = bibtex::split_file(bib)
+            .into_iter()
+            .filter_map(|c| c.entry)
+            .collect::<Vec<_>>();
+        let keys = new_keys(&entries, DEFAULT_FORMAT);
+        assert_eq!(keys["lamport78"], "Lamport1978");
+        assert_eq!(keys["X"], "Doe2020a");
+        assert_eq!(keys["Y"], "Doe2020b");
+        assert!(!keys.contains_key("proc"));
+
+        let new = rewrite_bib(bib, &keys);
+        assert!(new.starts_with("% refs\n@article{Lamport1978,\n  author={Lamport, Leslie},"));
+        assert!(new.contains(
+            "@inproceedings{Doe2020a, author={Doe, Jane}, year={2020}, crossref={proc}}"
+        ));
+        assert!(new.contains("@misc{Doe2020b, author = {Doe, John}"));
+
+        let tex = "As shown \\citep[see][p.~3]{X, Y} and \\cite{lamport78,other}.\n\\nocite{Y}";
+        assert_eq!(
+            rewrite_cites(tex, &keys),
+            "As shown \\citep[see][p.~3]{Doe2020a, Doe2020b} and \\cite{Lamport1978,other}.\n\\nocite{Doe2020b}"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! `d2b rename`: renames PDFs after the papers they contain, found through the DOI or arXiv ID in
+//! each file.
+
+use crate::bibtex::{family_name, Entry};
+use crate::{feed, i18n, pdf, resolve, Options};
+use clap::{Arg, ArgMatches};
+use std::path::Path;
+
+pub const DEFAULT_TEMPLATE: &str = "{year}-{author}-{shorttitle}.pdf";
+
+/// Words skipped when abbreviating a title for `{shorttitle}`.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "at", "for", "from", "in", "of", "on", "or", "the", "to", "with",
+];
+
+/// Strips BibTeX markup and characters that aren't allowed in file names.
+fn clean(s: &str) -> String {
+    let s = s
+        .chars()
+        .filter(|c| !"{}\\$".contains(*c))
+        .map(|c| if "/:*?\"<>|".contains(c) { ' ' } else { c })
+        .collect::<String>();
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn short_title(title: &str) -> String {
+    clean(title)
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .filter(|w| !w.is_empty() && !STOPWORDS.contains(&w.to_lowercase().as_str()))
+        .take(3)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Fills in a file name template. The placeholders are `{key}`, `{year}`, `{author}` (the first
+/// author's family name), `{title}`, `{shorttitle}` (its first three significant words) and
+/// `{journal}` and `{category}` (an arXiv paper's primary class); any that the entry lacks are
+/// left empty.
+pub fn file_name(template: &str, entry: &Entry) -> String {
+    let author = entry
+        .get("author")
+        .or_else(|| entry.get("editor"))
+        .and_then(|a| a.split(" and ").next())
+        .map(|a| clean(family_name(a)))
+        .unwrap_or_default();
+    let title = entry.get("title").unwrap_or("");
+    let journal = entry
+        .get("journal")
+        .or_else(|| entry.get("booktitle"))
+        .unwrap_or("");
+    template
+        .replace("{key}", &clean(&entry.key))
+        .replace("{year}", &clean(entry.get("year").unwrap_or("")))
+        .replace("{author}", &author)
+        .replace("{shorttitle}", &short_title(title))
+        .replace("{title}", &clean(title))
+        .replace("{journal}", &clean(journal))
+        .replace(
+            "{category}",
+            &clean(feed::categories(entry).first().unwrap_or(&"")),
+        )
+}
+
+/// The `--ocr` option, in builds with the `ocr` feature.
+pub fn ocr_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        #[cfg(feature = "ocr")]
+        Arg::with_name("ocr")
+            .long("ocr")
+            .help(i18n::text("help-ocr")),
+    ]
+}
+
+/// The identifier read off the first page of a scanned PDF, warning if it can't be read.
+#[cfg(feature = "ocr")]
+fn ocr_identifier(file: &str) -> Option<String> {
+    match crate::ocr::extract_identifier(Path::new(file)) {
+        Ok(id) => id,
+        Err(failure) => {
+            let message = match failure {
+                crate::ocr::Failure::Missing(tool) => {
+                    i18n::message("ocr-missing-tool", &[("tool", &tool)])
+                }
+                crate::ocr::Failure::Io(e) => {
+                    i18n::message("ocr-failed", &[("file", &file), ("error", &e)])
+                }
+            };
+            eprintln!("{}: {}", i18n::text("warning"), message);
+            None
+        }
+    }
+}
+
+/// Runs `d2b rename`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let template = matches.value_of("template").unwrap_or(DEFAULT_TEMPLATE);
+    let apply = matches.is_present("apply");
+
+    for file in matches.values_of("files").unwrap() {
+        let path = Path::new(file);
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                eprintln!("{}: {}: {}", i18n::text("warning"), file, e);
+                continue;
+            }
+        };
+        let found = pdf::extract_identifier(&bytes);
+        #[cfg(feature = "ocr")]
+        let found = found.or_else(|| match matches.is_present("ocr") {
+            true => ocr_identifier(file),
+            false => None,
+        });
+        let id = match found {
+            Some(id) => id,
+            None => {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("rename-no-identifier", &[("file", &file)])
+                );
+                continue;
+            }
+        };
+        let entry = match resolve(id, opts.clone()).await {
+            Ok(resolved) => match resolved.into_iter().next() {
+                Some(r) => r.entry,
+                None => continue,
+            },
+            Err(e) => {
+                e.report(opts.error_format);
+                continue;
+            }
+        };
+        let target = path.with_file_name(file_name(template, &entry));
+        if target == path {
+            continue;
+        }
+        println!("{} -> {}", file, target.display());
+        if apply {
+            if target.exists() {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("rename-exists", &[("file", &target.display())])
+                );
+            } else if let Err(e) = std::fs::rename(path, &target) {
+                eprintln!("{}: {}: {}", i18n::text("warning"), file, e);
+            }
+        }
+    }
+    if !apply {
+        eprintln!("{}", i18n::text("rename-dry-run"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex::parse_entry;
+
+    #[test]
+    fn test_file_name() {
+        let entry = parse_entry(
+            "@article{Silver_2018, title={A general reinforcement learning algorithm that masters \
+             chess, shogi, and {Go} through self-play}, author={Silver, David and Hubert, Thomas}, \
+             journal={Science}, year={2018}}",
+        )
+        .unwrap();
+        assert_eq!(
+            file_name(DEFAULT_TEMPLATE, &entry),
+            "2018-Silver-general-reinforcement-learning.pdf"
+        );
+        assert_eq!(
+            file_name("{key} {title}.pdf", &entry),
+            "Silver_2018 A general reinforcement learning algorithm that masters chess, shogi, and \
+             Go through self-play.pdf"
+        );
+        let mut slashed = Entry::new("misc", "x");
+        slashed.set("title", "Input/Output: a {$\\alpha$} study");
+        assert_eq!(file_name("{title}", &slashed), "Input Output a alpha study");
+        slashed.set("categories", "stat.ML, cs.LG");
+        assert_eq!(file_name("{category}-{key}", &slashed), "stat.ML-x");
+    }
+}
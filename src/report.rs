@@ -0,0 +1,199 @@
+//! `--report FILE`: a standalone HTML page of what a batch run did with each input, whether it
+//! resolved, was merged into another input's entry or failed, and why, with the citation, links and
+//! BibTeX of each work, for sharing the results of an import with collaborators who don't use d2b.
+
+use crate::error::Error;
+use crate::style::Style;
+use crate::webhook::escape_html;
+use crate::{i18n, Resolved};
+use std::fmt::Write;
+
+const CSS: &str =
+    "body{font-family:system-ui,sans-serif;margin:2em auto;max-width:70em;padding:0 1em}\
+table{border-collapse:collapse;width:100%}\
+th,td{border-bottom:1px solid #ddd;padding:.5em;text-align:left;vertical-align:top}\
+code{word-break:break-all}pre{white-space:pre-wrap;font-size:.85em}\
+.resolved{color:#1a7f37}.merged{color:#6e7781}.failed{color:#cf222e}.skipped{color:#9a6700}";
+
+/// What became of an input.
+enum Status<'a> {
+    Resolved(&'a Resolved),
+    /// Resolved to the same work as an earlier input, whose entry it was merged into.
+    Merged(&'a Resolved),
+    Failed(&'a Error),
+    /// Resolved, but left out of the output, by `--only-category` say.
+    Skipped,
+}
+
+/// What became of each input, in order; an input with several entries, like a proceedings volume
+/// with `--with-papers`, has a row for each.
+fn statuses<'a>(
+    inputs: &'a [String],
+    resolved: &'a [Resolved],
+    errors: &'a [Error],
+) -> Vec<(&'a str, Status<'a>)> {
+    let mut rows = Vec::new();
+    for input in inputs {
+        let entries = resolved.iter().filter(|r| &r.input == input);
+        let before = rows.len();
+        rows.extend(entries.map(|r| (input.as_str(), Status::Resolved(r))));
+        if rows.len() > before {
+            continue;
+        }
+        let status = if let Some(r) = resolved.iter().find(|r| r.merged.contains(input)) {
+            Status::Merged(r)
+        } else if let Some(e) = errors.iter().find(|e| e.input.as_ref() == Some(input)) {
+            Status::Failed(e)
+        } else {
+            Status::Skipped
+        };
+        rows.push((input.as_str(), status));
+    }
+    rows
+}
+
+/// Links to a work: its DOI, arXiv and web pages.
+fn links(r: &Resolved) -> String {
+    let mut links = Vec::new();
+    if let Some(doi) = r.entry.get("doi") {
+        links.push(("DOI", format!("https://doi.org/{}", doi)));
+    }
+    if let Some(eprint) = r.entry.get("eprint") {
+        links.push(("arXiv", format!("https://arxiv.org/abs/{}", eprint)));
+    }
+    if let Some(url) = r.entry.get("url") {
+        if !links.iter().any(|(_, l)| l == url) {
+            links.push(("URL", url.to_owned()));
+        }
+    }
+    links
+        .iter()
+        .map(|(label, url)| format!("<a href=\"{}\">{}</a>", escape_html(url), label))
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// The report on a run over `inputs`, with citations in `style`.
+pub fn html(
+    inputs: &[String],
+    resolved: &[Resolved],
+    errors: &[Error],
+    style: Style,
+    date: &str,
+) -> String {
+    let rows = statuses(inputs, resolved, errors);
+    let count = |f: fn(&Status) -> bool| rows.iter().filter(|(_, s)| f(s)).count();
+    let title = i18n::text("report-title");
+    let summary = i18n::message(
+        "report-summary",
+        &[
+            ("inputs", &inputs.len()),
+            ("resolved", &count(|s| matches!(s, Status::Resolved(_)))),
+            ("merged", &count(|s| matches!(s, Status::Merged(_)))),
+            ("failed", &count(|s| matches!(s, Status::Failed(_)))),
+            ("date", &date),
+        ],
+    );
+
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n<table>\n\
+         <tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>\n",
+        escape_html(title),
+        CSS,
+        escape_html(title),
+        escape_html(&summary),
+        i18n::text("report-input"),
+        i18n::text("report-status"),
+        i18n::text("report-citation"),
+        i18n::text("report-links"),
+    );
+    for (input, status) in &rows {
+        let (class, label, citation, links) = match status {
+            Status::Resolved(r) => (
+                "resolved",
+                i18n::text("report-resolved").to_owned(),
+                format!(
+                    "{}<details><summary>BibTeX</summary><pre>{}</pre></details>",
+                    escape_html(&style.format(&r.entry)),
+                    escape_html(&r.entry.to_string())
+                ),
+                self::links(r),
+            ),
+            Status::Merged(r) => (
+                "merged",
+                i18n::message("report-merged", &[("input", &r.input)]),
+                escape_html(&style.format(&r.entry)),
+                self::links(r),
+            ),
+            Status::Failed(e) => (
+                "failed",
+                i18n::text("report-failed").to_owned(),
+                escape_html(&e.message),
+                String::new(),
+            ),
+            Status::Skipped => (
+                "skipped",
+                i18n::text("report-skipped").to_owned(),
+                String::new(),
+                String::new(),
+            ),
+        };
+        writeln!(
+            out,
+            "<tr><td><code>{}</code></td><td class=\"{}\">{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(input),
+            class,
+            escape_html(&label),
+            citation,
+            links
+        )
+        .unwrap();
+    }
+    out.push_str("</table>\n</body>\n</html>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex::Entry;
+    use crate::error::Kind;
+    use crate::provenance::Provenance;
+    use crate::IdType;
+
+    #[test]
+    fn test_html() {
+        let mut entry = Entry::new("article", "Hoare_1978");
+        entry.set("author", "Hoare, C. A. R.");
+        entry.set("title", "Communicating Sequential Processes");
+        entry.set("doi", "10.1145/359576.359585");
+        let resolved = [Resolved {
+            input: "10.1145/359576.359585".to_owned(),
+            resolver: IdType::Doi,
+            source: "doi.org".to_owned(),
+            entry,
+            merged: vec!["https://doi.org/10.1145/359576.359585".to_owned()],
+            provenance: Provenance::default(),
+        }];
+        let errors = [Error::new(Kind::NotFound, "no <such> work").with_input("10.9999/x")];
+        let inputs = [
+            "10.1145/359576.359585",
+            "https://doi.org/10.1145/359576.359585",
+            "10.9999/x",
+            "2105.11572",
+        ]
+        .map(str::to_owned);
+        let html = html(&inputs, &resolved, &errors, Style::Iso690, "2024-05-01");
+
+        let rows = html.split("<tr><td>").skip(1).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].contains("class=\"resolved\""));
+        assert!(rows[0].contains("HOARE, C. A. R. Communicating Sequential Processes."));
+        assert!(rows[0].contains("<a href=\"https://doi.org/10.1145/359576.359585\">DOI</a>"));
+        assert!(rows[1].contains("class=\"merged\""));
+        assert!(rows[2].contains("class=\"failed\""));
+        assert!(rows[2].contains("no &lt;such&gt; work"));
+        assert!(rows[3].contains("class=\"skipped\""));
+    }
+}
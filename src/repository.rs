@@ -0,0 +1,284 @@
+//! Dataset entries built from the APIs of figshare and Dryad, which know more about their records
+//! than DataCite does: the exact version, and how many files there are and how large they are.
+//!
+//! figshare collection DOIs (`10.6084/m9.figshare.c.…`) become a dataset entry for the whole
+//! collection, with the number of items it holds.
+
+use crate::bibtex::{self, Entry};
+use crate::http::SendWithRetry;
+use crate::{Format, CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref FIGSHARE_RE: Regex =
+        Regex::new(r"(?i)^10\.6084/m9\.figshare\.(c\.)?(\d+)(?:\.v(\d+))?$").unwrap();
+    static ref DRYAD_RE: Regex = Regex::new(r"(?i)^10\.5061/dryad\.[a-z0-9]+$").unwrap();
+}
+
+/// What a repository says about a dataset.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Record {
+    pub title: String,
+    /// Names as "Family, Given".
+    pub authors: Vec<String>,
+    pub year: Option<String>,
+    pub version: Option<String>,
+    pub publisher: &'static str,
+    pub doi: String,
+    pub url: Option<String>,
+    pub files: Option<u64>,
+    /// The total size of the files, in bytes.
+    pub size: Option<u64>,
+    /// How many items a collection holds.
+    pub items: Option<u64>,
+}
+
+/// A size in bytes as people write it, e.g. `12.3 MB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["kB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit + 1 < UNITS.len() {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+impl Record {
+    /// A `@dataset` for biblatex, or a `@misc` with `howpublished` for BibTeX. The file count and
+    /// size go in `note`, which biblatex has no dedicated fields for.
+    pub fn entry(&self, format: Format) -> Entry {
+        let family = self
+            .authors
+            .first()
+            .map(|a| bibtex::family_name(a).replace(' ', ""));
+        let key = match (family, &self.year) {
+            (Some(family), Some(year)) => format!("{}_{}", family, year),
+            (Some(family), None) => family,
+            (None, _) => self.doi.clone(),
+        };
+        let mut entry = match format {
+            Format::Biblatex => Entry::new("dataset", &key),
+            Format::Bibtex => Entry::new("misc", &key),
+        };
+        entry.set("title", &self.title);
+        if !self.authors.is_empty() {
+            entry.set("author", &self.authors.join(" and "));
+        }
+        if let Some(year) = &self.year {
+            entry.set("year", year);
+        }
+        if let Some(version) = &self.version {
+            entry.set("version", version);
+        }
+        match format {
+            Format::Biblatex => entry.set("publisher", self.publisher),
+            Format::Bibtex => entry.set("howpublished", self.publisher),
+        }
+        let mut note = Vec::new();
+        if let Some(items) = self.items {
+            note.push(format!("{} items", items));
+        }
+        if let Some(files) = self.files {
+            note.push(format!("{} files", files));
+        }
+        if let Some(size) = self.size {
+            note.push(human_size(size));
+        }
+        if !note.is_empty() {
+            entry.set("note", &note.join(", "));
+        }
+        entry.set("doi", &self.doi);
+        if let Some(url) = &self.url {
+            entry.set("url", url);
+        }
+        entry
+    }
+}
+
+fn str_field(v: &Value) -> Option<&str> {
+    v.as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+async fn get_json(url: reqwest::Url) -> Option<Value> {
+    let res = CLIENT.get(url).send_with_retry().await.ok()?;
+    if !res.status().is_success() {
+        return None;
+    }
+    serde_json::from_str(&res.text().await.ok()?).ok()
+}
+
+/// A record from figshare's article or collection JSON.
+pub fn figshare_record(json: &Value) -> Option<Record> {
+    let files = json["files"].as_array();
+    Some(Record {
+        title: str_field(&json["title"])?.to_owned(),
+        authors: json["authors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| str_field(&a["full_name"]))
+            .map(bibtex::name_last_first)
+            .collect(),
+        year: str_field(&json["published_date"]).map(|d| d.chars().take(4).collect()),
+        version: json["version"].as_u64().map(|v| v.to_string()),
+        publisher: "figshare",
+        doi: str_field(&json["doi"])?.to_owned(),
+        url: str_field(&json["url_public_html"]).map(str::to_owned),
+        files: files.map(|f| f.len() as u64),
+        size: files.map(|f| f.iter().filter_map(|f| f["size"].as_u64()).sum()),
+        items: json["articles_count"].as_u64(),
+    })
+}
+
+async fn figshare(doi: &str) -> Option<Record> {
+    let c = FIGSHARE_RE.captures(doi)?;
+    let mut url = reqwest::Url::parse("https://api.figshare.com/v2/").unwrap();
+    {
+        let mut path = url.path_segments_mut().unwrap();
+        path.pop_if_empty();
+        path.push(if c.get(1).is_some() {
+            "collections"
+        } else {
+            "articles"
+        });
+        path.push(&c[2]);
+        if let Some(version) = c.get(3) {
+            path.push("versions").push(version.as_str());
+        }
+    }
+    figshare_record(&get_json(url).await?)
+}
+
+/// A record from Dryad's dataset JSON, with the number of files from its version's file list.
+pub fn dryad_record(json: &Value, files: Option<u64>) -> Option<Record> {
+    Some(Record {
+        title: str_field(&json["title"])?.to_owned(),
+        authors: json["authors"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| {
+                let family = str_field(&a["lastName"])?;
+                Some(match str_field(&a["firstName"]) {
+                    Some(given) => format!("{}, {}", family, given),
+                    None => family.to_owned(),
+                })
+            })
+            .collect(),
+        year: str_field(&json["publicationDate"]).map(|d| d.chars().take(4).collect()),
+        version: json["versionNumber"].as_u64().map(|v| v.to_string()),
+        publisher: "Dryad",
+        doi: str_field(&json["identifier"])?
+            .trim_start_matches("doi:")
+            .to_owned(),
+        url: str_field(&json["sharingLink"]).map(str::to_owned),
+        files,
+        size: json["storageSize"].as_u64(),
+        items: None,
+    })
+}
+
+async fn dryad(doi: &str) -> Option<Record> {
+    if !DRYAD_RE.is_match(doi) {
+        return None;
+    }
+    let mut url = reqwest::Url::parse("https://datadryad.org/api/v2/datasets/").unwrap();
+    url.path_segments_mut()
+        .unwrap()
+        .pop_if_empty()
+        .push(&format!("doi:{}", doi));
+    let json = get_json(url.clone()).await?;
+    let files = match str_field(&json["_links"]["stash:version"]["href"]) {
+        Some(version) => get_json(url.join(&format!("{}/files", version)).ok()?)
+            .await
+            .and_then(|f| f["total"].as_u64()),
+        None => None,
+    };
+    dryad_record(&json, files)
+}
+
+/// The repository a DOI handled by [`fetch`] belongs to.
+pub fn host(doi: &str) -> &'static str {
+    if DRYAD_RE.is_match(doi) {
+        "datadryad.org"
+    } else {
+        "figshare.com"
+    }
+}
+
+/// Builds an entry from figshare or Dryad for a DOI they minted, or returns `None` for any other
+/// DOI or if the repository has no record.
+pub async fn fetch(doi: &str, format: Format) -> Option<Entry> {
+    let record = match figshare(doi).await {
+        Some(record) => record,
+        None => dryad(doi).await?,
+    };
+    Some(record.entry(format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_figshare_record() {
+        let json = serde_json::json!({
+            "id": 12345678,
+            "title": "Light curves of 1,000 quasars",
+            "doi": "10.6084/m9.figshare.12345678.v2",
+            "url_public_html": "https://figshare.com/articles/dataset/x/12345678",
+            "published_date": "2020-05-01T10:00:00Z",
+            "version": 2,
+            "authors": [{"full_name": "Jeff Shen"}, {"full_name": "Jane Doe"}],
+            "files": [{"name": "a.csv", "size": 1200000}, {"name": "b.csv", "size": 34000}]
+        });
+        let entry = figshare_record(&json).unwrap().entry(Format::Biblatex);
+        assert_eq!(
+            entry.to_string(),
+            "@dataset{Shen_2020,\n  title={Light curves of 1,000 quasars},\n  \
+             author={Shen, Jeff and Doe, Jane},\n  year={2020},\n  version={2},\n  \
+             publisher={figshare},\n  note={2 files, 1.2 MB},\n  \
+             doi={10.6084/m9.figshare.12345678.v2},\n  \
+             url={https://figshare.com/articles/dataset/x/12345678}\n}"
+        );
+
+        let collection = serde_json::json!({
+            "title": "Survey", "doi": "10.6084/m9.figshare.c.1234567", "articles_count": 12
+        });
+        let entry = figshare_record(&collection).unwrap().entry(Format::Bibtex);
+        assert_eq!(entry.kind, "misc");
+        assert_eq!(entry.get("note"), Some("12 items"));
+        assert_eq!(entry.get("howpublished"), Some("figshare"));
+        assert_eq!(human_size(999), "999 B");
+        assert_eq!(human_size(3_400_000_000_000_000), "3400.0 TB");
+    }
+
+    #[test]
+    fn test_dryad_record() {
+        let json = serde_json::json!({
+            "identifier": "doi:10.5061/dryad.abc123",
+            "title": "Bird songs",
+            "authors": [{"firstName": "Ana", "lastName": "García"}],
+            "publicationDate": "2019-11-05",
+            "versionNumber": 3,
+            "storageSize": 52000000,
+            "sharingLink": "https://datadryad.org/stash/share/xyz"
+        });
+        let entry = dryad_record(&json, Some(4))
+            .unwrap()
+            .entry(Format::Biblatex);
+        assert_eq!(entry.key, "García_2019");
+        assert_eq!(entry.get("doi"), Some("10.5061/dryad.abc123"));
+        assert_eq!(entry.get("version"), Some("3"));
+        assert_eq!(entry.get("note"), Some("4 files, 52.0 MB"));
+        assert!(DRYAD_RE.is_match("10.5061/DRYAD.abc123"));
+        assert!(FIGSHARE_RE.is_match("10.6084/m9.figshare.c.1234567"));
+    }
+}
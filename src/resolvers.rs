@@ -0,0 +1,651 @@
+//! The backends DOIs and arXiv IDs can be resolved through, tried in the order the configuration
+//! gives until one succeeds:
+//!
+//! ```toml
+//! [resolvers]
+//! doi = ["crossref", "datacite", "doi.org", "europepmc"]
+//! arxiv = ["arxiv", "inspire"]
+//! ```
+//!
+//! Without a `doi` order there, DOIs of publishers whose negotiated BibTeX is known to be poor
+//! (see [`PREFIX_BACKENDS`]) go to the CrossRef API first. `--doi-resolver` overrides both, and
+//! `--race` queries the [`RACE`] backends at once instead, taking the first complete entry.
+//! `--best-of` queries every DOI backend and keeps the entry with the best [`score`], and
+//! `--merge-sources` fills in what that entry lacks from the others.
+//!
+//! Inputs can also be sent to resolvers defined in the configuration, which are tried before the
+//! built-in ones:
+//!
+//! ```toml
+//! [[resolver]]
+//! name = "hal"
+//! pattern = '^(?:hal:)?(hal-\d+)$'
+//! url = "https://api.archives-ouvertes.fr/search/?q=halId_s:{1}&wt=bibtex"
+//! format = "bibtex"
+//! ```
+
+use crate::bibtex::{self, Entry};
+use crate::error::{Error, Kind};
+use crate::http::SendWithRetry;
+use crate::provenance::Provenance;
+use crate::{
+    check_response, crossref, csl, datacite, enrich, europepmc, feed, handle_response, i18n,
+    published_version, request_info, thesis, Format, IdType, Options, CLIENT,
+};
+use async_recursion::async_recursion;
+use futures::{stream::FuturesUnordered, StreamExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+lazy_static! {
+    static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{(\w+)\}").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Content negotiation through doi.org, which asks the registration agency for BibTeX.
+    DoiOrg,
+    /// The CrossRef REST API.
+    Crossref,
+    /// The DataCite REST API.
+    Datacite,
+    /// Europe PMC, for the DOIs of the life sciences, with abstracts and MeSH terms.
+    EuropePmc,
+    /// The arXiv export API.
+    Arxiv,
+    /// INSPIRE-HEP, which has curated records for most of the physics arXiv.
+    Inspire,
+}
+
+/// DOI prefixes whose BibTeX through doi.org is reliably worse than CrossRef's JSON: Elsevier's
+/// comes with HTML entities and abstracts in `note`, and IEEE's with conference papers as articles
+/// and author names in odd formats.
+pub const PREFIX_BACKENDS: &[(&str, &[Backend])] = &[
+    ("10.1006/", &[Backend::Crossref, Backend::DoiOrg]),
+    ("10.1016/", &[Backend::Crossref, Backend::DoiOrg]),
+    ("10.1109/", &[Backend::Crossref, Backend::DoiOrg]),
+];
+
+/// The backends `--race` queries for a DOI.
+pub const RACE: &[Backend] = &[Backend::Crossref, Backend::Datacite, Backend::DoiOrg];
+
+impl Backend {
+    pub const DOI: &'static [(&'static str, Backend)] = &[
+        ("doi.org", Backend::DoiOrg),
+        ("crossref", Backend::Crossref),
+        ("datacite", Backend::Datacite),
+        ("europepmc", Backend::EuropePmc),
+    ];
+    const ARXIV: &'static [(&'static str, Backend)] =
+        &[("arxiv", Backend::Arxiv), ("inspire", Backend::Inspire)];
+
+    pub fn from_doi_name(name: &str) -> Option<Self> {
+        Self::DOI
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, b)| *b)
+    }
+
+    /// The backend whose [`host`](Self::host) an entry's source names.
+    pub fn from_host(host: &str) -> Option<Self> {
+        [
+            Backend::DoiOrg,
+            Backend::Crossref,
+            Backend::Datacite,
+            Backend::EuropePmc,
+            Backend::Arxiv,
+            Backend::Inspire,
+        ]
+        .iter()
+        .copied()
+        .find(|b| b.host() == host)
+    }
+
+    /// The host an entry from this backend was fetched from, for annotations and errors.
+    pub fn host(self) -> &'static str {
+        match self {
+            Backend::DoiOrg => "doi.org",
+            Backend::Crossref => "api.crossref.org",
+            Backend::Datacite => "api.datacite.org",
+            Backend::EuropePmc => "ebi.ac.uk",
+            Backend::Arxiv => "arxiv.org",
+            Backend::Inspire => "inspirehep.net",
+        }
+    }
+}
+
+/// The order backends are tried in for each kind of identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Priorities {
+    pub doi: Vec<Backend>,
+    pub arxiv: Vec<Backend>,
+    /// Whether the DOI order was chosen, in the configuration or on the command line, rather than
+    /// picked by prefix.
+    pub doi_fixed: bool,
+}
+
+impl Default for Priorities {
+    fn default() -> Self {
+        Priorities {
+            // Europe PMC as a fallback for the DOIs doi.org can't negotiate, which are mostly
+            // those of preprints and older biomedical articles.
+            doi: vec![Backend::DoiOrg, Backend::EuropePmc],
+            arxiv: vec![Backend::Arxiv],
+            doi_fixed: false,
+        }
+    }
+}
+
+impl Priorities {
+    /// Reads the `[resolvers]` table of the configuration file.
+    pub fn parse(table: &BTreeMap<String, Vec<String>>) -> Result<Self, String> {
+        let mut priorities = Priorities::default();
+        for (kind, names) in table {
+            let (known, order) = match kind.as_str() {
+                "doi" => {
+                    priorities.doi_fixed = true;
+                    (Backend::DOI, &mut priorities.doi)
+                }
+                "arxiv" => (Backend::ARXIV, &mut priorities.arxiv),
+                _ => return Err(format!("resolvers: unknown identifier type \"{}\"", kind)),
+            };
+            if names.is_empty() {
+                return Err(format!("resolvers.{}: no resolvers given", kind));
+            }
+            *order = names
+                .iter()
+                .map(|name| {
+                    known
+                        .iter()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                        .map(|(_, b)| *b)
+                        .ok_or_else(|| {
+                            let names = known.iter().map(|(n, _)| *n).collect::<Vec<_>>();
+                            format!(
+                                "resolvers.{}: unknown resolver \"{}\" (expected one of {})",
+                                kind,
+                                name,
+                                names.join(", ")
+                            )
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(priorities)
+    }
+
+    /// The backends to try for a DOI, in order.
+    pub fn for_doi(&self, doi: &str) -> &[Backend] {
+        if !self.doi_fixed {
+            let doi = doi.to_lowercase();
+            if let Some((_, backends)) = PREFIX_BACKENDS.iter().find(|(p, _)| doi.starts_with(p)) {
+                return backends;
+            }
+        }
+        &self.doi
+    }
+}
+
+/// An entry as a backend produced it, before it is finished.
+#[derive(Debug, Clone)]
+pub struct Fetched {
+    pub idtype: IdType,
+    pub source: String,
+    pub entry: Entry,
+    /// The sources of the fields that didn't come from `source`.
+    pub provenance: Provenance,
+}
+
+impl Fetched {
+    pub fn new(idtype: IdType, source: impl Into<String>, entry: Entry) -> Self {
+        Fetched {
+            idtype,
+            source: source.into(),
+            entry,
+            provenance: Provenance::default(),
+        }
+    }
+}
+
+/// What a custom resolver's URL answers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResponseFormat {
+    Bibtex,
+    CslJson,
+    Atom,
+}
+
+impl ResponseFormat {
+    fn mime(self) -> &'static str {
+        match self {
+            ResponseFormat::Bibtex => "application/x-bibtex",
+            ResponseFormat::CslJson => "application/vnd.citationstyles.csl+json",
+            ResponseFormat::Atom => "application/atom+xml",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CustomSpec {
+    name: String,
+    pattern: String,
+    url: String,
+    format: ResponseFormat,
+}
+
+/// A resolver from a `[[resolver]]` table of the configuration file. Inputs matching `pattern`
+/// are fetched from `url`, in which `{0}` is replaced by the whole match and `{1}`, `{2}`, ... or
+/// `{name}` by its capture groups.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "CustomSpec")]
+pub struct Custom {
+    pub name: String,
+    pattern: Regex,
+    url: String,
+    format: ResponseFormat,
+}
+
+impl TryFrom<CustomSpec> for Custom {
+    type Error = String;
+
+    fn try_from(spec: CustomSpec) -> Result<Self, String> {
+        let pattern = Regex::new(&spec.pattern)
+            .map_err(|e| format!("resolver \"{}\": bad pattern: {}", spec.name, e))?;
+        Ok(Custom {
+            name: spec.name,
+            pattern,
+            url: spec.url,
+            format: spec.format,
+        })
+    }
+}
+
+impl Custom {
+    pub fn matches(&self, input: &str) -> bool {
+        self.pattern.is_match(input.trim())
+    }
+
+    /// The URL to fetch for an input, if it matches.
+    fn url(&self, input: &str) -> Option<String> {
+        let captures = self.pattern.captures(input.trim())?;
+        let url = PLACEHOLDER_RE.replace_all(&self.url, |c: &regex::Captures| {
+            let group = match c[1].parse::<usize>() {
+                Ok(i) => captures.get(i),
+                Err(_) => captures.name(&c[1]),
+            };
+            group.map_or_else(|| c[0].to_owned(), |m| m.as_str().to_owned())
+        });
+        Some(url.into_owned())
+    }
+
+    pub async fn fetch(&self, input: &str) -> Result<Vec<Fetched>, Error> {
+        let url = self
+            .url(input)
+            .ok_or_else(|| Error::new(Kind::InvalidInput, i18n::text("malformed-identifier")))?;
+        let res = CLIENT
+            .get(&url)
+            .header(ACCEPT, self.format.mime())
+            .send_with_retry()
+            .await;
+        let body = check_response(res, &self.name)?.text().await?;
+        let bad = |e: String| Error::new(Kind::Parse, e);
+        let entry = match self.format {
+            ResponseFormat::Bibtex => bibtex::split_file(&body)
+                .into_iter()
+                .find_map(|chunk| chunk.entry),
+            ResponseFormat::CslJson => {
+                csl::entry(&serde_json::from_str(&body).map_err(|e| bad(e.to_string()))?)
+            }
+            ResponseFormat::Atom => {
+                let feed = feed::parse(&body).map_err(bad)?;
+                feed.items
+                    .first()
+                    .and_then(|item| feed::Source::Repository.entry(&feed, item, Format::Bibtex))
+            }
+        };
+        let entry = entry.ok_or_else(|| {
+            Error::new(
+                Kind::NotFound,
+                i18n::message("no-record", &[("resolver", &self.name)]),
+            )
+        })?;
+        Ok(vec![Fetched::new(IdType::Url, self.name.clone(), entry)])
+    }
+}
+
+fn not_found(backend: Backend) -> Error {
+    Error::new(
+        Kind::NotFound,
+        i18n::message("no-record", &[("resolver", &backend.host())]),
+    )
+}
+
+async fn inspire(id: &str) -> Result<Entry, Error> {
+    let res = CLIENT
+        .get(format!("https://inspirehep.net/api/arxiv/{}", id))
+        .query(&[("format", "bibtex")])
+        .send_with_retry()
+        .await?;
+    if !res.status().is_success() {
+        return Err(not_found(Backend::Inspire));
+    }
+    let body = res.text().await?;
+    bibtex::parse_entry(&body).map_err(|e| Error::new(Kind::Parse, e.0))
+}
+
+async fn fetch(backend: Backend, id: &str, opts: &Options) -> Result<Vec<Fetched>, Error> {
+    match backend {
+        Backend::DoiOrg => {
+            handle_response(request_info(id, IdType::Doi).await, IdType::Doi, opts).await
+        }
+        Backend::Arxiv => {
+            handle_response(request_info(id, IdType::Arxiv).await, IdType::Arxiv, opts).await
+        }
+        Backend::Crossref => {
+            let work = crossref::fetch_work(id)
+                .await
+                .ok_or_else(|| not_found(backend))?;
+            let key = crossref::make_key(&work);
+            let entry = crossref::book_entry(&work, &key)
+                .or_else(|| crossref::paper_entry(&work))
+                .or_else(|| thesis::from_crossref(&work, &key))
+                .or_else(|| crossref::article_entry(&work))
+                .ok_or_else(|| not_found(backend))?;
+            Ok(vec![Fetched::new(IdType::Doi, backend.host(), entry)])
+        }
+        Backend::Datacite => {
+            let attrs = datacite::fetch_doi(id)
+                .await
+                .ok_or_else(|| not_found(backend))?;
+            let entry = datacite::dataset_entry(&attrs, opts.format)
+                .or_else(|| thesis::from_datacite(&attrs))
+                .or_else(|| datacite::misc_entry(&attrs, opts.format))
+                .ok_or_else(|| not_found(backend))?;
+            Ok(vec![Fetched::new(IdType::Doi, backend.host(), entry)])
+        }
+        Backend::EuropePmc => {
+            let entry = europepmc::fetch(europepmc::Id::Doi, id)
+                .await
+                .as_ref()
+                .and_then(europepmc::entry)
+                .ok_or_else(|| not_found(backend))?;
+            Ok(vec![Fetched::new(IdType::Doi, backend.host(), entry)])
+        }
+        Backend::Inspire => Ok(vec![Fetched::new(
+            IdType::Arxiv,
+            backend.host(),
+            inspire(id).await?,
+        )]),
+    }
+}
+
+/// Whether an entry has a title, authors (or editors) and a date, which an entry a backend could
+/// only partly fill in lacks.
+pub fn is_complete(entry: &Entry) -> bool {
+    let has = |field| entry.get(field).is_some_and(|v| !v.trim().is_empty());
+    has("title") && (has("author") || has("editor")) && (has("year") || has("date"))
+}
+
+/// How complete an entry is: points for a title, authors (fewer if the list is cut short with
+/// "and others"), a venue, volume, number, pages, year, publisher and DOI, weighted by how much
+/// their absence hurts a citation.
+pub fn score(entry: &Entry) -> u32 {
+    let has = |field: &str| entry.get(field).is_some_and(|v| !v.trim().is_empty());
+    let mut score = 0;
+    if has("title") {
+        score += 3;
+    }
+    if let Some(authors) = entry.get("author").or_else(|| entry.get("editor")) {
+        score += if authors.trim_end().ends_with("and others") {
+            1
+        } else {
+            3
+        };
+    }
+    let venue = [
+        "journal",
+        "journaltitle",
+        "booktitle",
+        "howpublished",
+        "school",
+        "institution",
+    ];
+    if venue.iter().any(|f| has(f)) {
+        score += 2;
+    }
+    for (field, points) in [
+        ("volume", 2),
+        ("pages", 2),
+        ("number", 1),
+        ("publisher", 1),
+        ("doi", 1),
+    ] {
+        if has(field) {
+            score += points;
+        }
+    }
+    if has("year") || has("date") {
+        score += 1;
+    }
+    score
+}
+
+/// Queries every DOI backend at once and keeps the entry with the best [`score`], ties going to the
+/// backend that comes first in `backends`. With `--merge-sources`, the fields that entry lacks are
+/// filled in from the others, best first, and its source names every backend that contributed.
+async fn best_of(id: &str, backends: &[Backend], opts: &Options) -> Result<Vec<Fetched>, Error> {
+    let order = backends
+        .iter()
+        .copied()
+        .chain(Backend::DOI.iter().map(|(_, b)| *b))
+        .fold(Vec::new(), |mut order, b| {
+            if !order.contains(&b) {
+                order.push(b);
+            }
+            order
+        });
+    let results = futures::future::join_all(order.iter().map(|&b| fetch(b, id, opts))).await;
+    let mut error = None;
+    let mut candidates = Vec::new();
+    for result in results {
+        match result {
+            Ok(mut fetched) if !fetched.is_empty() => candidates.push(fetched.remove(0)),
+            Ok(_) => {}
+            Err(e) => error = Some(e),
+        }
+    }
+    // A stable sort, so that equal scores keep the backends' order.
+    candidates.sort_by_key(|f| std::cmp::Reverse(score(&f.entry)));
+    let mut candidates = candidates.into_iter();
+    let mut best = candidates
+        .next()
+        .ok_or_else(|| error.unwrap_or_else(|| not_found(Backend::DoiOrg)))?;
+    if opts.merge_sources {
+        let mut sources = vec![best.source.clone()];
+        best.provenance.replace(&best.entry, &best.source);
+        for other in candidates {
+            let filled = enrich::fill(&mut best.entry, &other.entry, false);
+            for field in &filled {
+                best.provenance.set(field, &other.source);
+            }
+            if !filled.is_empty() {
+                sources.push(other.source);
+            }
+        }
+        best.source = sources.join(" + ");
+    }
+    Ok(vec![best])
+}
+
+/// Queries the [`RACE`] backends for a DOI concurrently, returning the first complete entry, or
+/// failing that the first incomplete one, or if none of them has the DOI, the last error.
+async fn race(id: &str, opts: &Options) -> Result<Vec<Fetched>, Error> {
+    let mut attempts = RACE
+        .iter()
+        .map(|&backend| fetch(backend, id, opts))
+        .collect::<FuturesUnordered<_>>();
+    let (mut incomplete, mut error) = (None, None);
+    while let Some(result) = attempts.next().await {
+        match result {
+            Ok(fetched) if fetched.first().is_some_and(|f| is_complete(&f.entry)) => {
+                return Ok(fetched)
+            }
+            Ok(fetched) => {
+                incomplete.get_or_insert(fetched);
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+    incomplete.ok_or_else(|| error.unwrap_or_else(|| not_found(Backend::DoiOrg)))
+}
+
+/// Resolves an identifier through the configured backends, returning the first success or, if
+/// every backend fails, the last error.
+#[async_recursion]
+pub async fn resolve(id: &str, idtype: IdType, opts: &Options) -> Result<Vec<Fetched>, Error> {
+    let backends = match idtype {
+        IdType::Doi => opts.resolvers.for_doi(id),
+        IdType::Arxiv => &opts.resolvers.arxiv,
+        IdType::Isbn | IdType::Url => {
+            return handle_response(request_info(id, idtype).await, idtype, opts).await;
+        }
+    };
+    let fetched = match idtype {
+        IdType::Doi if opts.race => race(id, opts).await?,
+        IdType::Doi if opts.best_of => best_of(id, backends, opts).await?,
+        _ => in_order(backends, id, opts).await?,
+    };
+    if let (IdType::Doi, true) = (idtype, opts.follow_published) {
+        if let Some(published) = published_version(&fetched[0].entry).await {
+            // Only one hop, so that works relating to each other can't be followed forever.
+            let opts = Options {
+                follow_published: false,
+                ..opts.clone()
+            };
+            return resolve(&published, IdType::Doi, &opts).await;
+        }
+    }
+    Ok(fetched)
+}
+
+/// Tries backends one after the other until one has a record.
+async fn in_order(backends: &[Backend], id: &str, opts: &Options) -> Result<Vec<Fetched>, Error> {
+    let mut error = None;
+    for &backend in backends {
+        match fetch(backend, id, opts).await {
+            Ok(fetched) => return Ok(fetched),
+            Err(e) => error = Some(e),
+        }
+    }
+    Err(error.unwrap_or_else(|| not_found(Backend::DoiOrg)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priorities() {
+        let table = |kind: &str, names: &[&str]| {
+            let mut table = BTreeMap::new();
+            table.insert(
+                kind.to_owned(),
+                names.iter().map(|n| (*n).to_owned()).collect(),
+            );
+            table
+        };
+        let priorities = Priorities::parse(&table("doi", &["Crossref", "doi.org"])).unwrap();
+        assert_eq!(priorities.doi, [Backend::Crossref, Backend::DoiOrg]);
+        assert_eq!(priorities.arxiv, [Backend::Arxiv]);
+        assert!(Priorities::parse(&table("arxiv", &["crossref"])).is_err());
+        assert!(Priorities::parse(&table("isbn", &["crossref"])).is_err());
+        assert!(Priorities::parse(&table("doi", &[])).is_err());
+
+        let default = Priorities::default();
+        assert_eq!(
+            default.for_doi("10.1016/j.cell.2020.01.001"),
+            [Backend::Crossref, Backend::DoiOrg]
+        );
+        assert_eq!(
+            default.for_doi("10.18637/jss.v076.i01"),
+            [Backend::DoiOrg, Backend::EuropePmc]
+        );
+        assert_eq!(
+            priorities.for_doi("10.1109/CVPR.2016.90"),
+            [Backend::Crossref, Backend::DoiOrg]
+        );
+        let configured = Priorities::parse(&table("doi", &["doi.org"])).unwrap();
+        assert_eq!(
+            configured.for_doi("10.1109/CVPR.2016.90"),
+            [Backend::DoiOrg]
+        );
+    }
+
+    #[test]
+    fn test_is_complete() {
+        let mut entry = Entry::new("article", "Doe_2020");
+        entry.set("title", "A title");
+        entry.set("year", "2020");
+        assert!(!is_complete(&entry));
+        entry.set("editor", "Doe, Jane");
+        assert!(is_complete(&entry));
+        entry.set("title", " ");
+        assert!(!is_complete(&entry));
+    }
+
+    #[test]
+    fn test_score() {
+        let mut negotiated = Entry::new("article", "Doe_2020");
+        negotiated.set("title", "A title");
+        negotiated.set("author", "Doe, Jane and others");
+        negotiated.set("journal", "Nature");
+        negotiated.set("year", "2020");
+        let mut full = negotiated.clone();
+        full.set("author", "Doe, Jane and Roe, John");
+        full.set("volume", "579");
+        full.set("pages", "265--269");
+        assert_eq!(score(&negotiated), 7);
+        assert_eq!(score(&full), 13);
+    }
+
+    #[test]
+    fn test_custom() {
+        let config = crate::config::Config::parse(
+            r#"
+            [[resolver]]
+            name = "hal"
+            pattern = '^(?:hal:)?(?P<id>hal-\d+)(?:v(\d+))?$'
+            url = "https://hal.science/{id}v{2}/bibtex?{0}"
+            format = "bibtex"
+            "#,
+        )
+        .unwrap();
+        let hal = &config.custom[0];
+        assert!(hal.matches(" hal:hal-01234 "));
+        assert!(!hal.matches("10.1000/xyz"));
+        assert_eq!(
+            hal.url("hal-01234v2").as_deref(),
+            Some("https://hal.science/hal-01234v2/bibtex?hal-01234v2")
+        );
+        assert_eq!(
+            hal.url("hal-01234").as_deref(),
+            Some("https://hal.science/hal-01234v{2}/bibtex?hal-01234")
+        );
+
+        let bad = |pattern: &str, format: &str| {
+            crate::config::Config::parse(&format!(
+                "[[resolver]]\nname = 'x'\npattern = '{}'\nurl = 'https://x/{{0}}'\nformat = '{}'",
+                pattern, format
+            ))
+            .is_err()
+        };
+        assert!(bad("(", "bibtex"));
+        assert!(bad("x", "ris"));
+        assert!(!bad("x", "csl-json"));
+    }
+}
@@ -0,0 +1,215 @@
+//! `--screening TOOL`: entries in the import format of a systematic-review screening tool, with
+//! their abstracts, which screening is mostly done from. Covidence is given RIS and Rayyan its CSV
+//! layout.
+//!
+//! Abstracts come from the entry itself, else CrossRef (for DOIs) or arXiv (for eprints).
+
+use crate::bibtex::Entry;
+use crate::http::SendWithRetry;
+use crate::table::Table;
+use crate::{crossref, feed, notes, text, CLIENT};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Write;
+
+lazy_static! {
+    static ref TAG_RE: Regex = Regex::new(r"<[^>]*>").unwrap();
+    static ref ABSTRACT_HEADING_RE: Regex = Regex::new(r"(?i)^abstract[.:]?\s+").unwrap();
+}
+
+/// The columns of Rayyan's CSV import template.
+const RAYYAN_COLUMNS: &str =
+    "key,title,authors,journal,issn,volume,issue,pages,year,publisher,url,abstract,notes,doi";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tool {
+    Covidence,
+    Rayyan,
+}
+
+impl Tool {
+    pub const NAMES: &'static [&'static str] = &["covidence", "rayyan"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "covidence" => Some(Tool::Covidence),
+            "rayyan" => Some(Tool::Rayyan),
+            _ => None,
+        }
+    }
+
+    /// The whole export, for entries paired with their abstracts.
+    pub fn export(self, entries: &[(&Entry, Option<String>)]) -> String {
+        match self {
+            Tool::Covidence => entries
+                .iter()
+                .map(|(entry, abstract_)| ris(entry, abstract_.as_deref()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Tool::Rayyan => {
+                let table = Table::new("csv", RAYYAN_COLUMNS).unwrap();
+                let mut out = table.header();
+                for (entry, abstract_) in entries {
+                    out.push('\n');
+                    out.push_str(&rayyan_row(&table, entry, abstract_.as_deref()));
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Plain text from the JATS markup CrossRef abstracts come in.
+pub fn strip_markup(s: &str) -> String {
+    let s = text::collapse_whitespace(&text::decode_entities(&TAG_RE.replace_all(s, " ")));
+    ABSTRACT_HEADING_RE.replace(&s, "").into_owned()
+}
+
+async fn arxiv_abstract(id: &str) -> Option<String> {
+    let feed = CLIENT
+        .get("http://export.arxiv.org/api/query")
+        .query(&[("id_list", id)])
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let summary = feed::parse(&feed).ok()?.items.first()?.summary.clone()?;
+    Some(text::collapse_whitespace(&summary))
+}
+
+/// The abstract of an entry, looked up if it doesn't have one.
+pub async fn abstract_of(entry: &Entry) -> Option<String> {
+    if let Some(abstract_) = entry.get("abstract") {
+        return Some(notes::plain(abstract_));
+    }
+    if let Some(doi) = entry.get("doi") {
+        let work = crossref::fetch_work(&doi.to_lowercase()).await;
+        if let Some(abstract_) = work.as_ref().and_then(|w| w["abstract"].as_str()) {
+            return Some(strip_markup(abstract_));
+        }
+    }
+    let eprint = entry.get("eprint").filter(|_| {
+        entry
+            .get("archivePrefix")
+            .is_none_or(|p| p.eq_ignore_ascii_case("arxiv"))
+    })?;
+    arxiv_abstract(eprint).await
+}
+
+/// The RIS reference type of an entry.
+fn ris_type(entry: &Entry) -> &'static str {
+    match entry.kind.to_lowercase().as_str() {
+        "article" => "JOUR",
+        "inproceedings" | "conference" | "proceedings" => "CONF",
+        "book" => "BOOK",
+        "incollection" | "inbook" => "CHAP",
+        "phdthesis" | "mastersthesis" | "thesis" => "THES",
+        "techreport" | "report" => "RPRT",
+        "dataset" => "DATA",
+        "software" => "COMP",
+        _ if entry.get("eprint").is_some() => "UNPB",
+        _ => "GEN",
+    }
+}
+
+/// The names in an author or editor field, in `Family, Given` form.
+fn names(field: Option<&str>) -> Vec<String> {
+    field
+        .unwrap_or_default()
+        .split(" and ")
+        .map(notes::plain)
+        .filter(|n| !n.is_empty())
+        .collect()
+}
+
+fn tag(out: &mut String, tag: &str, value: &str) {
+    if !value.is_empty() {
+        writeln!(out, "{}  - {}", tag, value).unwrap();
+    }
+}
+
+/// A RIS record for an entry.
+pub fn ris(entry: &Entry, abstract_: Option<&str>) -> String {
+    let field = |name: &str| entry.get(name).map(notes::plain).unwrap_or_default();
+    let mut out = String::new();
+    tag(&mut out, "TY", ris_type(entry));
+    tag(&mut out, "ID", &entry.key);
+    for author in names(entry.get("author")) {
+        tag(&mut out, "AU", &author);
+    }
+    for editor in names(entry.get("editor")) {
+        tag(&mut out, "ED", &editor);
+    }
+    tag(&mut out, "TI", &field("title"));
+    tag(&mut out, "T2", &notes::venue(entry).unwrap_or_default());
+    tag(&mut out, "PY", &field("year"));
+    tag(&mut out, "VL", &field("volume"));
+    tag(&mut out, "IS", &field("number"));
+    let pages = field("pages");
+    let (start, end) = pages.split_once('-').unwrap_or((&pages, ""));
+    tag(&mut out, "SP", start.trim());
+    tag(&mut out, "EP", end.trim_start_matches('-').trim());
+    tag(&mut out, "PB", &field("publisher"));
+    tag(&mut out, "SN", &field("issn"));
+    tag(&mut out, "DO", entry.get("doi").unwrap_or_default());
+    tag(&mut out, "UR", entry.get("url").unwrap_or_default());
+    tag(&mut out, "AB", abstract_.unwrap_or_default());
+    out.push_str("ER  - \n");
+    out
+}
+
+fn rayyan_row(table: &Table, entry: &Entry, abstract_: Option<&str>) -> String {
+    let field = |name: &str| entry.get(name).map(notes::plain).unwrap_or_default();
+    let values = table.columns.iter().map(|column| match column.as_str() {
+        "key" => entry.key.clone(),
+        "authors" => names(entry.get("author")).join(" and "),
+        "journal" => entry
+            .get("journal")
+            .or_else(|| entry.get("booktitle"))
+            .map(notes::plain)
+            .unwrap_or_default(),
+        "issue" => field("number"),
+        "abstract" => abstract_.unwrap_or_default().to_owned(),
+        "doi" | "url" => entry.get(column).unwrap_or_default().to_owned(),
+        name => field(name),
+    });
+    table.line(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> Entry {
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("author", "Shen, Jeff and {Doe}, Jane");
+        entry.set("title", "{Zebras}, in depth");
+        entry.set("journal", "Nature");
+        entry.set("year", "2021");
+        entry.set("pages", "10--19");
+        entry.set("doi", "10.1000/xyz");
+        entry
+    }
+
+    #[test]
+    fn test_export() {
+        assert_eq!(
+            ris(&entry(), Some("Stripes.")),
+            "TY  - JOUR\nID  - Shen_2021\nAU  - Shen, Jeff\nAU  - Doe, Jane\nTI  - Zebras, in depth\n\
+             T2  - Nature\nPY  - 2021\nSP  - 10\nEP  - 19\nDO  - 10.1000/xyz\nAB  - Stripes.\nER  - \n"
+        );
+        assert_eq!(
+            Tool::Rayyan.export(&[(&entry(), None)]),
+            "key,title,authors,journal,issn,volume,issue,pages,year,publisher,url,abstract,notes,doi\n\
+             Shen_2021,\"Zebras, in depth\",\"Shen, Jeff and Doe, Jane\",Nature,,,,10--19,2021,,,,,10.1000/xyz"
+        );
+        assert_eq!(
+            strip_markup(
+                "<jats:title>Abstract</jats:title><jats:p>Zebras &amp;\n horses.</jats:p>"
+            ),
+            "Zebras & horses."
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Formatter scripts, in [Rhai](https://rhai.rs), for the formatting rules no option covers. A
+//! script is configured per output format under `[formatters]` and defines `format(entry)`, which
+//! returns the text written out for the entry:
+//!
+//! ```rhai
+//! fn format(entry) {
+//!     let out = `@${entry.type}{${entry.key.to_lower()},`;
+//!     for name in entry.order {
+//!         out += `\n  ${name} = {${entry.fields[name]}},`;
+//!     }
+//!     out + "\n}"
+//! }
+//! ```
+//!
+//! `entry` has the `type`, `key`, `input`, the `fields` by name and their names in `order`.
+
+use crate::bibtex::Entry;
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Operations a single call may take, so that a runaway script can't hang a run.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+pub struct Formatter {
+    engine: Engine,
+    ast: AST,
+    path: PathBuf,
+}
+
+impl fmt::Debug for Formatter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Formatter")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+fn to_map(entry: &Entry, input: &str) -> Map {
+    let mut fields = Map::new();
+    let mut order = Array::new();
+    for (name, value) in &entry.fields {
+        fields.insert(name.as_str().into(), value.as_str().into());
+        order.push(name.clone().into());
+    }
+    let mut map = Map::new();
+    map.insert("type".into(), entry.kind.clone().into());
+    map.insert("key".into(), entry.key.clone().into());
+    map.insert("input".into(), input.trim().into());
+    map.insert("fields".into(), fields.into());
+    map.insert("order".into(), order.into());
+    map
+}
+
+impl Formatter {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let src =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::compile(&src, path)
+    }
+
+    fn compile(src: &str, path: &Path) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile(src)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        if !ast
+            .iter_functions()
+            .any(|f| f.name == "format" && f.params.len() == 1)
+        {
+            return Err(format!("{}: no format(entry) function", path.display()));
+        }
+        Ok(Formatter {
+            engine,
+            ast,
+            path: path.to_owned(),
+        })
+    }
+
+    pub fn format(&self, entry: &Entry, input: &str) -> Result<String, String> {
+        let entry = Dynamic::from_map(to_map(entry, input));
+        self.engine
+            .call_fn::<String>(&mut Scope::new(), &self.ast, "format", (entry,))
+            .map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        let script = r#"
+            fn format(entry) {
+                let authors = entry.fields.author.split(" and ");
+                `${entry.key}: ${authors.len()} authors (${entry.order[0]}), from ${entry.input}`
+            }
+        "#;
+        let formatter = Formatter::compile(script, Path::new("lab.rhai")).unwrap();
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("author", "Shen, Jeff and Doe, Jane");
+        assert_eq!(
+            formatter.format(&entry, " 10.1000/xyz ").unwrap(),
+            "Shen_2021: 2 authors (author), from 10.1000/xyz"
+        );
+
+        let looping = Formatter::compile("fn format(e) { loop {} }", Path::new("x")).unwrap();
+        assert!(looping.format(&entry, "").is_err());
+        assert!(Formatter::compile("fn other(e) { 1 }", Path::new("x")).is_err());
+    }
+}
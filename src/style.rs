@@ -0,0 +1,321 @@
+//! `--style`: entries written out as formatted references instead of BibTeX, in the styles that
+//! many European and Russian institutions require and that aren't a CSL file away in LaTeX:
+//!
+//! - `iso690`: ISO 690:2010, with family names in capitals (`LAMPORT, Leslie. Title. Journal.
+//!   1978, vol. 21, no. 7, pp. 558–565.`).
+//! - `gost`: GOST R 7.0.5-2008, with the first author as the heading and the others after a slash
+//!   (`Lamport L. Title // Journal. 1978. Vol. 21, no. 7. P. 558–565.`). References to works in
+//!   Cyrillic get the Russian abbreviations (`Т.`, `№`, `С.`).
+
+use crate::bibtex::Entry;
+use crate::notes::plain;
+use crate::template;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Iso690,
+    Gost,
+}
+
+/// A person of an entry, as family and given names.
+struct Person {
+    family: String,
+    given: String,
+}
+
+impl Person {
+    /// The given names as initials, e.g. `Leslie B.` as `L. B.`, and `Jean-Paul` as `J.-P.`.
+    fn initials(&self) -> String {
+        self.given
+            .split_whitespace()
+            .map(|part| {
+                part.split('-')
+                    .filter_map(|p| p.chars().next())
+                    .map(|c| format!("{}.", c))
+                    .collect::<Vec<_>>()
+                    .join("-")
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// The people of a name list, and whether it ends in `and others`.
+fn people(names: Option<&str>) -> (Vec<Person>, bool) {
+    let mut others = false;
+    let people = template::people(names)
+        .iter()
+        .filter_map(|p| {
+            let family = plain(p["family"].as_str()?);
+            if family == "others" {
+                others = true;
+                return None;
+            }
+            let given = plain(p["given"].as_str().unwrap_or_default());
+            Some(Person { family, given })
+        })
+        .collect();
+    (people, others)
+}
+
+fn year(entry: &Entry) -> Option<String> {
+    entry
+        .get("year")
+        .or_else(|| entry.get("date").and_then(|d| d.get(..4)))
+        .map(plain)
+}
+
+fn field(entry: &Entry, name: &str) -> Option<String> {
+    entry.get(name).map(plain).filter(|v| !v.is_empty())
+}
+
+/// Page ranges with an en dash, as both styles write them.
+fn pages(entry: &Entry) -> Option<String> {
+    field(entry, "pages").map(|p| p.replace("--", "–").replace('-', "–"))
+}
+
+/// Joins parts into sentences, without doubling the full stop of parts that already end in one.
+fn sentence(parts: &[String]) -> String {
+    let mut out = String::new();
+    for part in parts {
+        if !out.is_empty() {
+            if !out.ends_with(['.', '?', '!']) {
+                out.push('.');
+            }
+            out.push(' ');
+        }
+        out.push_str(part);
+    }
+    if !out.ends_with(['.', '?', '!']) {
+        out.push('.');
+    }
+    out
+}
+
+impl Style {
+    pub const NAMES: &'static [&'static str] = &["iso690", "gost"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "iso690" => Some(Style::Iso690),
+            "gost" => Some(Style::Gost),
+            _ => None,
+        }
+    }
+
+    /// Formats an entry as a reference.
+    pub fn format(self, entry: &Entry) -> String {
+        match self {
+            Style::Iso690 => iso690(entry),
+            Style::Gost => gost(entry),
+        }
+    }
+}
+
+/// The statement of where to find a work online: its DOI, or its URL.
+fn available(entry: &Entry, label: &str) -> Option<String> {
+    field(entry, "doi")
+        .map(|doi| format!("{}https://doi.org/{}", label, doi))
+        .or_else(|| field(entry, "url").map(|url| format!("{}{}", label, url)))
+}
+
+fn iso690(entry: &Entry) -> String {
+    let (authors, others) = people(entry.get("author"));
+    let name = |p: &Person| {
+        if p.given.is_empty() {
+            p.family.to_uppercase()
+        } else {
+            format!("{}, {}", p.family.to_uppercase(), p.given)
+        }
+    };
+    let mut parts = Vec::new();
+    // More than three creators are cut down to the first, which ISO 690 allows.
+    if authors.len() > 3 || (others && !authors.is_empty()) {
+        parts.push(format!("{} et al", name(&authors[0])));
+    } else if let Some((last, rest)) = authors.split_last() {
+        let rest = rest.iter().map(name).collect::<Vec<_>>();
+        parts.push(match rest.is_empty() {
+            true => name(last),
+            false => format!("{} and {}", rest.join(", "), name(last)),
+        });
+    }
+    parts.extend(field(entry, "title"));
+
+    let year = year(entry);
+    let kind = entry.kind.to_lowercase();
+    match kind.as_str() {
+        "article" => {
+            parts.extend(field(entry, "journal").or_else(|| field(entry, "journaltitle")));
+            let mut details = year.into_iter().collect::<Vec<_>>();
+            details.extend(field(entry, "volume").map(|v| format!("vol. {}", v)));
+            details.extend(field(entry, "number").map(|n| format!("no. {}", n)));
+            details.extend(pages(entry).map(|p| format!("pp. {}", p)));
+            if !details.is_empty() {
+                parts.push(details.join(", "));
+            }
+        }
+        _ => {
+            if let Some(booktitle) = field(entry, "booktitle") {
+                parts.push(format!("In: {}", booktitle));
+            }
+            parts.extend(field(entry, "edition").map(|e| format!("{} ed", e)));
+            let publisher = field(entry, "publisher")
+                .or_else(|| field(entry, "school"))
+                .or_else(|| field(entry, "institution"))
+                .or_else(|| field(entry, "organization"));
+            let imprint = match (field(entry, "address"), publisher) {
+                (Some(address), Some(publisher)) => Some(format!("{}: {}", address, publisher)),
+                (None, publisher) => publisher,
+                (address, None) => address,
+            };
+            let mut details = imprint.into_iter().chain(year).collect::<Vec<_>>();
+            details.extend(pages(entry).map(|p| format!("pp. {}", p)));
+            if !details.is_empty() {
+                parts.push(details.join(", "));
+            }
+        }
+    }
+    parts.extend(field(entry, "isbn").map(|isbn| format!("ISBN {}", isbn)));
+    parts.extend(available(entry, "Available from: "));
+    sentence(&parts)
+}
+
+fn gost(entry: &Entry) -> String {
+    let (authors, others) = people(entry.get("author"));
+    let title = field(entry, "title").unwrap_or_default();
+    let russian = title.chars().any(|c| ('\u{400}'..='\u{4ff}').contains(&c));
+    let (vol, no, p, et_al) = match russian {
+        true => ("Т.", "№", "С.", "[и др.]"),
+        false => ("Vol.", "no.", "P.", "[et al.]"),
+    };
+    let heading = |p: &Person| format!("{} {}", p.family, p.initials()).trim().to_owned();
+    let statement = |p: &Person| format!("{} {}", p.initials(), p.family).trim().to_owned();
+
+    // Up to three authors, the first heads the reference and all are named after the title; with
+    // four or more there is no heading and only the first three are named.
+    let mut out = String::new();
+    let many = authors.len() > 3 || others;
+    if let (Some(first), false) = (authors.first(), many) {
+        out.push_str(&heading(first));
+        out.push(' ');
+    }
+    out.push_str(&title);
+    if authors.len() > 1 || many {
+        let named = authors.iter().take(3).map(statement).collect::<Vec<_>>();
+        out.push_str(" / ");
+        out.push_str(&named.join(", "));
+        if many {
+            out.push(' ');
+            out.push_str(et_al);
+        }
+    }
+
+    let year = year(entry);
+    let kind = entry.kind.to_lowercase();
+    let mut parts = vec![out];
+    let container = match kind.as_str() {
+        "article" => field(entry, "journal").or_else(|| field(entry, "journaltitle")),
+        _ => field(entry, "booktitle"),
+    };
+    if let Some(container) = container {
+        // The slashes join the container to the reference as one area, not a new sentence.
+        let last = parts.pop().unwrap();
+        parts.push(format!("{} // {}", last, container));
+        if kind == "article" {
+            parts.extend(year);
+            let mut details = Vec::new();
+            details.extend(field(entry, "volume").map(|v| format!("{} {}", vol, v)));
+            details.extend(field(entry, "number").map(|n| format!("{} {}", no, n)));
+            if !details.is_empty() {
+                parts.push(details.join(", "));
+            }
+        } else {
+            parts.extend(imprint(entry, year));
+        }
+        parts.extend(pages(entry).map(|pages| format!("{} {}", p, pages)));
+    } else {
+        parts.extend(imprint(entry, year));
+        if let Some(pages) = pages(entry) {
+            parts.push(format!("{} {}", p, pages));
+        }
+    }
+    parts.extend(field(entry, "isbn").map(|isbn| format!("ISBN {}", isbn)));
+    match field(entry, "doi") {
+        Some(doi) => parts.push(format!("DOI: {}", doi)),
+        None => parts.extend(field(entry, "url").map(|url| format!("URL: {}", url))),
+    }
+    sentence(&parts)
+}
+
+/// GOST's publication area: `Place : Publisher, Year`.
+fn imprint(entry: &Entry, year: Option<String>) -> Option<String> {
+    let publisher = field(entry, "publisher")
+        .or_else(|| field(entry, "school"))
+        .or_else(|| field(entry, "institution"));
+    let place = match (field(entry, "address"), publisher) {
+        (Some(address), Some(publisher)) => Some(format!("{} : {}", address, publisher)),
+        (None, publisher) => publisher,
+        (address, None) => address,
+    };
+    match (place, year) {
+        (Some(place), Some(year)) => Some(format!("{}, {}", place, year)),
+        (place, year) => place.or(year),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bibtex;
+
+    #[test]
+    fn test_format() {
+        let article = bibtex::parse_entry(
+            "@article{Lamport_1978, title={Time, Clocks, and the Ordering of Events in a {Distributed} System}, \
+             author={Lamport, Leslie}, journal={Communications of the ACM}, volume={21}, number={7}, \
+             pages={558--565}, year={1978}, doi={10.1145/359545.359563}}",
+        )
+        .unwrap();
+        assert_eq!(
+            Style::Iso690.format(&article),
+            "LAMPORT, Leslie. Time, Clocks, and the Ordering of Events in a Distributed System. \
+             Communications of the ACM. 1978, vol. 21, no. 7, pp. 558–565. \
+             Available from: https://doi.org/10.1145/359545.359563."
+        );
+        assert_eq!(
+            Style::Gost.format(&article),
+            "Lamport L. Time, Clocks, and the Ordering of Events in a Distributed System // \
+             Communications of the ACM. 1978. Vol. 21, no. 7. P. 558–565. \
+             DOI: 10.1145/359545.359563."
+        );
+
+        let book = bibtex::parse_entry(
+            "@book{a, title={Теория вероятностей}, author={Гнеденко, Борис Владимирович and Колмогоров, Андрей Николаевич}, \
+             publisher={Наука}, address={Москва}, year={1949}}",
+        )
+        .unwrap();
+        assert_eq!(
+            Style::Gost.format(&book),
+            "Гнеденко Б. В. Теория вероятностей / Б. В. Гнеденко, А. Н. Колмогоров. Москва : Наука, 1949."
+        );
+        assert_eq!(
+            Style::Iso690.format(&book),
+            "ГНЕДЕНКО, Борис Владимирович and КОЛМОГОРОВ, Андрей Николаевич. Теория вероятностей. \
+             Москва: Наука, 1949."
+        );
+
+        let many = bibtex::parse_entry(
+            "@inproceedings{b, title={Paxos}, author={A, Ann and B, Bob and C, Cy and D, Di}, \
+             booktitle={PODC}, pages={1--2}, year={2001}}",
+        )
+        .unwrap();
+        assert_eq!(
+            Style::Gost.format(&many),
+            "Paxos / A. A, B. B, C. C [et al.] // PODC. 2001. P. 1–2."
+        );
+        assert_eq!(
+            Style::Iso690.format(&many),
+            "A, Ann et al. Paxos. In: PODC. 2001, pp. 1–2."
+        );
+    }
+}
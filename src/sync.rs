@@ -0,0 +1,468 @@
+//! `d2b sync`: keeps a `.bib` file in step with a manifest of identifiers, only ever touching the
+//! entries that have to change so that diffs stay small.
+
+use crate::bibtex::{self, Chunk, Entry};
+use crate::error::{self, Error, Kind};
+use crate::{
+    annotation, atomic, diff, entry_ids, i18n, input_id, read_file, render, resolve, Options,
+};
+use clap::{Arg, ArgMatches};
+use futures::{stream::FuturesUnordered, StreamExt};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::io::BufRead;
+
+lazy_static! {
+    /// The comment left by `--annotate`, which records the input an entry was resolved from.
+    static ref ANNOTATION_RE: Regex =
+        Regex::new(r"(?m)^% (.+) fetched by d2b from .+ on \d{4}-\d{2}-\d{2}\r?(?:\n|$)").unwrap();
+}
+
+/// What to do when an entry to be appended has the key of a different work already in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Append it with the first free `a`, `b`, ... suffix on its key.
+    Suffix,
+    /// Leave it out.
+    Skip,
+    /// Put it in place of the entry already there.
+    Replace,
+    /// Ask which of the others to do, for each conflict.
+    Ask,
+}
+
+impl OnConflict {
+    pub const NAMES: &'static [&'static str] = &["suffix", "skip", "replace", "ask"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "suffix" => Some(OnConflict::Suffix),
+            "skip" => Some(OnConflict::Skip),
+            "replace" => Some(OnConflict::Replace),
+            "ask" => Some(OnConflict::Ask),
+            _ => None,
+        }
+    }
+
+    /// The policy for a conflict over `key`, asking on the terminal for `ask`. An empty answer
+    /// suffixes the key, and no answer at all skips the entry.
+    fn decide(self, key: &str, existing: &Entry, new: &Entry) -> Self {
+        if self != OnConflict::Ask {
+            return self;
+        }
+        let title = |e: &Entry| e.get("title").unwrap_or_default().to_owned();
+        eprint!(
+            "{} ",
+            i18n::message(
+                "conflict-prompt",
+                &[
+                    ("key", &key),
+                    ("existing", &title(existing)),
+                    ("new", &title(new))
+                ],
+            )
+        );
+        let mut line = String::new();
+        match std::io::stdin().lock().read_line(&mut line) {
+            Ok(0) | Err(_) => OnConflict::Skip,
+            Ok(_) => match line.trim().to_lowercase().as_str() {
+                "k" | "skip" => OnConflict::Skip,
+                "r" | "replace" => OnConflict::Replace,
+                _ => OnConflict::Suffix,
+            },
+        }
+    }
+}
+
+/// The `--on-conflict` option of the subcommands that append to a `.bib` file.
+pub fn conflict_arg() -> Arg<'static, 'static> {
+    Arg::with_name("on-conflict")
+        .long("on-conflict")
+        .takes_value(true)
+        .possible_values(OnConflict::NAMES)
+        .default_value("suffix")
+        .help(i18n::text("help-on-conflict"))
+}
+
+/// The policy given with `--on-conflict`.
+pub fn on_conflict(matches: &ArgMatches) -> OnConflict {
+    OnConflict::from_name(matches.value_of("on-conflict").unwrap()).unwrap()
+}
+
+/// `key` with the first of the suffixes `a`, `b`, ... that no key in `taken` has. Keys are
+/// compared without case, as BibTeX does.
+fn free_key(key: &str, taken: &[String]) -> String {
+    (b'a'..=b'z')
+        .map(|c| format!("{}{}", key, c as char))
+        .chain((2..).map(|n| format!("{}{}", key, n)))
+        .find(|k| !taken.iter().any(|t| t.eq_ignore_ascii_case(k)))
+        .unwrap()
+}
+
+/// `src` with the first entry under `key` replaced by `text`, which drops the annotation just
+/// before the entry too since `text` brings its own.
+fn replace_entry(src: &str, key: &str, text: &str) -> String {
+    let chunks = bibtex::split_file(src);
+    let is_key = |c: &Chunk| {
+        c.entry
+            .as_ref()
+            .is_some_and(|e| e.key.eq_ignore_ascii_case(key))
+    };
+    let Some(at) = chunks.iter().position(is_key) else {
+        return src.to_owned();
+    };
+    let mut out = String::new();
+    for (n, chunk) in chunks.iter().enumerate() {
+        if n == at {
+            out.push_str(text);
+        } else if n + 1 == at {
+            match ANNOTATION_RE.find_iter(chunk.text).last() {
+                Some(m) if m.end() == chunk.text.len() => out.push_str(&chunk.text[..m.start()]),
+                _ => out.push_str(chunk.text),
+            }
+        } else {
+            out.push_str(chunk.text);
+        }
+    }
+    out
+}
+
+/// The identifiers in a manifest, skipping blank lines and `#` comments.
+pub fn manifest_inputs(lines: &[String]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Which of `inputs` each chunk's entry belongs to, by identifier or by the input recorded in the
+/// annotation comment just before it.
+fn matches(chunks: &[Chunk], inputs: &[String]) -> Vec<Option<usize>> {
+    let ids = inputs.iter().map(|i| input_id(i)).collect::<Vec<_>>();
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(n, chunk)| {
+            let entry = chunk.entry.as_ref()?;
+            let annotated = n
+                .checked_sub(1)
+                .and_then(|p| ANNOTATION_RE.captures_iter(chunks[p].text).last())
+                .map(|c| c[1].trim().to_owned());
+            let entry_ids = entry_ids(entry);
+            inputs.iter().enumerate().position(|(i, input)| {
+                annotated
+                    .as_deref()
+                    .is_some_and(|a| a.eq_ignore_ascii_case(input))
+                    || ids[i].as_ref().is_some_and(|id| entry_ids.contains(id))
+            })
+        })
+        .collect()
+}
+
+/// Plans a sync: the existing file with unmatched entries (and their annotations) dropped if
+/// `prune` is set, and the inputs that have no entry yet.
+pub fn plan(src: &str, inputs: &[String], prune: bool) -> (String, Vec<String>) {
+    let chunks = bibtex::split_file(src);
+    let matched = matches(&chunks, inputs);
+
+    let mut kept = String::new();
+    for (n, chunk) in chunks.iter().enumerate() {
+        let pruned = |n: usize| prune && chunks[n].entry.is_some() && matched[n].is_none();
+        if pruned(n) {
+            continue;
+        }
+        if chunk.entry.is_none() && n + 1 < chunks.len() && pruned(n + 1) {
+            // Drop the annotation of a pruned entry along with it, but keep other comments.
+            kept.push_str(&ANNOTATION_RE.replace_all(chunk.text, ""));
+            continue;
+        }
+        kept.push_str(chunk.text);
+    }
+
+    let missing = inputs
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !matched.contains(&Some(*i)))
+        .map(|(_, input)| input.clone())
+        .collect();
+    (kept, missing)
+}
+
+/// Runs `d2b sync`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let manifest = read_file(matches.value_of("manifest").unwrap())
+        .unwrap_or_else(|e| e.exit(opts.error_format));
+    let inputs = manifest_inputs(&manifest);
+    let bib = matches.value_of("bib").unwrap();
+    let prune = matches.is_present("prune");
+    let preview = diff::Preview::from_matches(matches);
+    update(bib, &inputs, prune, on_conflict(matches), preview, opts).await;
+}
+
+/// What [`apply`] did to a `.bib` file.
+#[derive(Debug, Default)]
+pub struct Applied {
+    /// The key of the entry for each input that has one, already or now, in the order of the
+    /// inputs.
+    pub keys: Vec<(String, String)>,
+    pub added: usize,
+    pub removed: usize,
+    /// The inputs that had to be resolved and failed to.
+    pub errors: Vec<Error>,
+    /// How many of the inputs that had to be resolved were.
+    pub resolved: usize,
+    /// Whether the changes were shown and turned down, and so not written.
+    pub declined: bool,
+}
+
+/// Brings a `.bib` file in step with `inputs`, resolving and appending those it has no entry for
+/// yet, and reports what changed. New entries whose key a different work already has are dealt
+/// with by `policy`, and the changes are shown for confirmation first if `preview` asks for it.
+pub async fn update(
+    bib: &str,
+    inputs: &[String],
+    prune: bool,
+    policy: OnConflict,
+    preview: diff::Preview,
+    opts: Options,
+) {
+    let applied = apply(bib, inputs, prune, policy, preview, opts).await;
+    if applied.declined {
+        return;
+    }
+    eprintln!(
+        "{}",
+        i18n::message(
+            "sync-summary",
+            &[
+                ("file", &bib),
+                ("added", &applied.added),
+                ("removed", &applied.removed)
+            ],
+        )
+    );
+    let status = error::status(applied.resolved, &applied.errors);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+/// Does what [`update`] does short of printing the summary and exiting on failure, returning the
+/// outcome instead.
+pub async fn apply(
+    bib: &str,
+    inputs: &[String],
+    prune: bool,
+    policy: OnConflict,
+    preview: diff::Preview,
+    opts: Options,
+) -> Applied {
+    // Held until the file is written, so that another process appending to it meanwhile waits
+    // rather than having its entries overwritten.
+    let _lock = atomic::lock(bib).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unwritable", &[("file", &bib), ("error", &e)]),
+        )
+        .exit(opts.error_format)
+    });
+    let src = std::fs::read_to_string(bib).unwrap_or_default();
+    let (mut out, missing) = plan(&src, inputs, prune);
+    let chunks = bibtex::split_file(&src);
+    let mut keys = matches(&chunks, inputs)
+        .into_iter()
+        .zip(&chunks)
+        .filter_map(|(m, c)| Some((inputs[m?].clone(), c.entry.as_ref()?.key.clone())))
+        .collect::<Vec<_>>();
+    let removed = bibtex::split_file(&src)
+        .iter()
+        .filter(|c| c.entry.is_some())
+        .count()
+        - bibtex::split_file(&out)
+            .iter()
+            .filter(|c| c.entry.is_some())
+            .count();
+
+    let mut futures = missing
+        .iter()
+        .map(|p| resolve(p.clone(), opts.clone()))
+        .collect::<FuturesUnordered<_>>();
+    let mut added = Vec::new();
+    let mut errors = Vec::new();
+    while let Some(resolved) = futures.next().await {
+        match resolved {
+            Ok(resolved) => added.extend(resolved),
+            Err(e) => errors.push(e),
+        }
+    }
+    for e in &errors {
+        e.report(opts.error_format);
+    }
+    // Keep additions in manifest order, so that syncing is deterministic.
+    added.sort_by_key(|r| missing.iter().position(|m| *m == r.input));
+
+    let entries = bibtex::split_file(&out)
+        .into_iter()
+        .filter_map(|c| c.entry)
+        .collect::<Vec<_>>();
+    let mut taken_keys = entries.iter().map(|e| e.key.clone()).collect::<Vec<_>>();
+    let mut count = 0;
+    for r in &added {
+        // An input can resolve to an entry that's already there under another identifier, e.g. an
+        // arXiv ID whose published version was added by DOI.
+        let ids = entry_ids(&r.entry);
+        if let Some(same) = entries
+            .iter()
+            .find(|e| entry_ids(e).iter().any(|id| ids.contains(id)))
+        {
+            keys.push((r.input.clone(), same.key.clone()));
+            continue;
+        }
+        let mut r = r.clone();
+        let key = r.entry.key.clone();
+        let taken = taken_keys.iter().any(|k| k.eq_ignore_ascii_case(&key));
+        let action = match entries.iter().find(|e| e.key.eq_ignore_ascii_case(&key)) {
+            Some(existing) => Some(policy.decide(&key, existing, &r.entry)),
+            // Two new entries can share a key too, and replacing one with the other would lose a
+            // work the manifest asks for.
+            None if taken => Some(OnConflict::Suffix),
+            None => None,
+        };
+        match action {
+            Some(OnConflict::Skip) => {
+                eprintln!(
+                    "{}",
+                    i18n::message("conflict-skipped", &[("key", &key), ("input", &r.input)])
+                );
+                continue;
+            }
+            Some(OnConflict::Suffix) => {
+                r.entry.key = free_key(&key, &taken_keys);
+                eprintln!(
+                    "{}",
+                    i18n::message(
+                        "conflict-suffixed",
+                        &[("key", &key), ("renamed", &r.entry.key)]
+                    )
+                );
+            }
+            _ => (),
+        }
+        if !taken || action == Some(OnConflict::Suffix) {
+            taken_keys.push(r.entry.key.clone());
+        }
+        keys.push((r.input.clone(), r.entry.key.clone()));
+
+        let mut text = String::new();
+        // The annotation is what lets later syncs match the entry to its input.
+        if !opts.annotate {
+            text.push_str(&annotation(&r.input, &r.source));
+            text.push('\n');
+        }
+        text.push_str(&render(&r, &opts));
+        if action == Some(OnConflict::Replace) {
+            out = replace_entry(&out, &key, &text);
+            eprintln!(
+                "{}",
+                i18n::message("conflict-replaced", &[("key", &key), ("input", &r.input)])
+            );
+            count += 1;
+            continue;
+        }
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push_str(if out.ends_with('\n') { "\n" } else { "\n\n" });
+        }
+        out.push_str(&text);
+        out.push('\n');
+        count += 1;
+    }
+
+    keys.sort_by_key(|(input, _)| inputs.iter().position(|i| i == input));
+    let mut applied = Applied {
+        keys,
+        added: count,
+        removed,
+        resolved: missing.len() - errors.len(),
+        errors,
+        declined: false,
+    };
+    if out != src {
+        if !preview.confirm(bib, &src, &out, &opts) {
+            applied.declined = true;
+            return applied;
+        }
+        atomic::write(bib, &out).unwrap_or_else(|e| {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &bib), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        });
+    }
+    applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let src = "% My notes\n\n\
+                   @article{Lamport_1978, title={Time, Clocks}, doi={10.1145/359545.359563}}\n\n\
+                   % https://example.com/paper fetched by d2b from the web on 2024-01-01\n\
+                   @misc{Doe_2020, title={Paper}, url={https://example.org/elsewhere}}\n\n\
+                   @article{Old_1999, title={Gone}}\n";
+        let inputs = manifest_inputs(&[
+            "# comment".to_owned(),
+            "10.1145/359545.359563".to_owned(),
+            "https://example.com/paper".to_owned(),
+            "".to_owned(),
+            "arxiv:1712.01815".to_owned(),
+        ]);
+        assert_eq!(inputs.len(), 3);
+
+        let (kept, missing) = plan(src, &inputs, false);
+        assert_eq!(kept, src);
+        assert_eq!(missing, ["arxiv:1712.01815"]);
+
+        let (pruned, _) = plan(src, &inputs, true);
+        assert!(!pruned.contains("Old_1999"));
+        assert!(pruned.starts_with("% My notes\n\n@article{Lamport_1978"));
+        assert!(pruned.contains("fetched by d2b from the web on 2024-01-01\n@misc{Doe_2020"));
+
+        let annotated = format!(
+            "{}\n% 10.1/gone fetched by d2b from doi.org on 2024-01-01\n@misc{{G, title={{G}}}}\n",
+            src
+        );
+        let (pruned, _) = plan(&annotated, &inputs, true);
+        assert!(!pruned.contains("10.1/gone"));
+
+        let commented = "% Converted with d2b from my old Zotero library\n\
+                         % 10.1/gone fetched by d2b from doi.org on 2024-01-01\n\
+                         @misc{G, title={G}}\n";
+        let (pruned, _) = plan(commented, &inputs, true);
+        assert_eq!(
+            pruned,
+            "% Converted with d2b from my old Zotero library\n\n"
+        );
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let taken = ["Doe_2020".to_owned(), "doe_2020a".to_owned()];
+        assert_eq!(free_key("Doe_2020", &taken), "Doe_2020b");
+        assert_eq!(OnConflict::from_name("replace"), Some(OnConflict::Replace));
+
+        let src = "@misc{Other, title={Other}}\n\n\
+                   % 10.1/old fetched by d2b from doi.org on 2024-01-01\n\
+                   @article{Doe_2020, title={Old}}\n";
+        assert_eq!(
+            replace_entry(src, "doe_2020", "@article{Doe_2020, title={New}}"),
+            "@misc{Other, title={Other}}\n\n@article{Doe_2020, title={New}}\n"
+        );
+        assert_eq!(replace_entry(src, "Missing", "x"), src);
+    }
+}
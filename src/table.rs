@@ -0,0 +1,121 @@
+//! `--format csv` and `--format tsv`: entries as rows of a spreadsheet, one column per `--columns`
+//! name, for keeping track of papers in systematic reviews and the like.
+//!
+//! Besides any field of the entry, a column can be `key`, `type`, `authors` (as "Given Family"
+//! names separated by semicolons), `venue` (the journal, proceedings or publisher) or
+//! `citations`, the number of works citing it according to CrossRef.
+
+use crate::bibtex::Entry;
+use crate::{crossref, notes};
+use std::collections::HashMap;
+
+pub const DEFAULT_COLUMNS: &str = "key,authors,year,title,venue,doi,url";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub separator: char,
+    pub columns: Vec<String>,
+}
+
+impl Table {
+    /// The table for an output format, if it's a tabular one.
+    pub fn new(format: &str, columns: &str) -> Option<Self> {
+        let separator = match format {
+            "csv" => ',',
+            "tsv" => '\t',
+            _ => return None,
+        };
+        let columns = columns
+            .split(',')
+            .map(|c| c.trim().to_lowercase())
+            .filter(|c| !c.is_empty())
+            .collect();
+        Some(Table { separator, columns })
+    }
+
+    /// Whether citation counts have to be looked up.
+    pub fn wants_citations(&self) -> bool {
+        self.columns.iter().any(|c| c == "citations")
+    }
+
+    fn cell(&self, value: &str) -> String {
+        if self.separator == '\t' {
+            // TSV has no quoting, so tabs and line breaks become spaces.
+            return value.replace(['\t', '\r', '\n'], " ");
+        }
+        if value.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_owned()
+        }
+    }
+
+    pub fn line(&self, values: impl Iterator<Item = String>) -> String {
+        values
+            .map(|v| self.cell(&v))
+            .collect::<Vec<_>>()
+            .join(&self.separator.to_string())
+    }
+
+    pub fn header(&self) -> String {
+        self.line(self.columns.iter().cloned())
+    }
+
+    /// The row for an entry, with `citations` as its citation count if that was looked up.
+    pub fn row(&self, entry: &Entry, citations: Option<u64>) -> String {
+        self.line(self.columns.iter().map(|column| match column.as_str() {
+            "key" => entry.key.clone(),
+            "type" => entry.kind.to_lowercase(),
+            "authors" => notes::authors(entry).join("; "),
+            "venue" => notes::venue(entry).unwrap_or_default(),
+            "citations" => citations.map(|c| c.to_string()).unwrap_or_default(),
+            "doi" | "url" => entry.get(column).unwrap_or_default().to_owned(),
+            field => entry.get(field).map(notes::plain).unwrap_or_default(),
+        }))
+    }
+}
+
+/// How many works cite each DOI, by CrossRef's count, for those it knows of.
+pub async fn citations(entries: &[&Entry]) -> HashMap<String, u64> {
+    let dois = entries
+        .iter()
+        .filter_map(|e| e.get("doi"))
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>();
+    let counts = futures::future::join_all(dois.iter().map(|doi| async move {
+        let work = crossref::fetch_work(doi).await?;
+        Some((doi.clone(), work["is-referenced-by-count"].as_u64()?))
+    }))
+    .await;
+    counts.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row() {
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("author", "Shen, Jeff and Doe, Jane");
+        entry.set("title", "{Zebras}, \"stripes\"\nand all");
+        entry.set("journal", "Nature");
+        entry.set("year", "2021");
+        entry.set("doi", "10.1000/xyz");
+
+        let csv = Table::new("csv", "key, authors,year,title,venue,doi,citations,pages").unwrap();
+        assert_eq!(
+            csv.header(),
+            "key,authors,year,title,venue,doi,citations,pages"
+        );
+        assert_eq!(
+            csv.row(&entry, Some(12)),
+            "Shen_2021,Jeff Shen; Jane Doe,2021,\"Zebras, \"\"stripes\"\" and all\",Nature,10.1000/xyz,12,"
+        );
+        assert!(csv.wants_citations());
+
+        let tsv = Table::new("tsv", "title,citations").unwrap();
+        assert_eq!(tsv.row(&entry, None), "Zebras, \"stripes\" and all\t");
+        assert_eq!(Table::new("bibtex", DEFAULT_COLUMNS), None);
+    }
+}
@@ -0,0 +1,117 @@
+//! `--template FILE`: entries written out through a [Handlebars](https://handlebarsjs.com)
+//! template instead of as BibTeX, for front matter, notes, `\bibitem`s and the like.
+//!
+//! The template is rendered once per entry, with `type`, `key`, `input`, `source`, the `fields`
+//! under their lowercased names, `authors` and `editors` as lists of `{name, family, given}`, and
+//! the `bibtex` it would have printed. Nothing is HTML-escaped.
+
+use crate::bibtex::Entry;
+use crate::Resolved;
+use handlebars::Handlebars;
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+#[derive(Debug)]
+pub struct Template {
+    registry: Handlebars<'static>,
+}
+
+/// Splits a BibTeX name list into `{name, family, given}` objects.
+pub fn people(names: Option<&str>) -> Vec<Value> {
+    names
+        .unwrap_or_default()
+        .split(" and ")
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(|name| {
+            let name = name.trim_matches(|c| c == '{' || c == '}');
+            let (family, given) = match name.split_once(',') {
+                Some((family, given)) => (family.trim(), given.trim()),
+                None => match name.rsplit_once(' ') {
+                    Some((given, family)) => (family, given),
+                    None => (name, ""),
+                },
+            };
+            let full = if given.is_empty() {
+                family.to_owned()
+            } else {
+                format!("{} {}", given, family)
+            };
+            json!({ "name": full, "family": family, "given": given })
+        })
+        .collect()
+}
+
+pub fn context(entry: &Entry, input: &str, source: &str) -> Value {
+    let fields = entry
+        .fields
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), Value::from(value.as_str())))
+        .collect::<Map<_, _>>();
+    json!({
+        "type": entry.kind,
+        "key": entry.key,
+        "input": input.trim(),
+        "source": source,
+        "fields": fields,
+        "authors": people(entry.get("author")),
+        "editors": people(entry.get("editor")),
+        "bibtex": entry.to_string(),
+    })
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut registry = Handlebars::new();
+        registry.register_escape_fn(handlebars::no_escape);
+        registry
+            .register_template_string("entry", src)
+            .map_err(|e| e.to_string())?;
+        Ok(Template { registry })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let src =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&src).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn render(&self, resolved: &Resolved) -> Result<String, String> {
+        let context = context(&resolved.entry, &resolved.input, &resolved.source);
+        self.registry
+            .render("entry", &context)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IdType;
+
+    #[test]
+    fn test_render() {
+        let template = Template::parse(
+            "\\bibitem{ {{~key~}} } {{#each authors}}{{given}} {{family}}{{#unless @last}}, {{/unless}}{{/each}}, \\emph{ {{~fields.title~}} } ({{fields.year}}). doi:{{fields.doi}}",
+        )
+        .unwrap();
+        let mut entry = Entry::new("article", "Shen_2021");
+        entry.set("author", "Shen, Jeff and Jane Doe and {NASA}");
+        entry.set("title", "Dwarfs & giants");
+        entry.set("year", "2021");
+        entry.set("DOI", "10.1000/xyz");
+        let resolved = Resolved {
+            input: "10.1000/xyz".to_owned(),
+            resolver: IdType::Doi,
+            source: "doi.org".to_owned(),
+            entry,
+            merged: Vec::new(),
+            provenance: Default::default(),
+        };
+        assert_eq!(
+            template.render(&resolved).unwrap(),
+            "\\bibitem{Shen_2021} Jeff Shen, Jane Doe,  NASA, \\emph{Dwarfs & giants} (2021). doi:10.1000/xyz"
+        );
+        assert!(Template::parse("{{#each authors}}").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Plain-text cleanup for metadata that arrives as HTML or loosely formatted XML.
+
+/// Named entities that show up in arXiv and publisher metadata. Anything else is left as is.
+const ENTITIES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("lt", "<"),
+    ("gt", ">"),
+    ("quot", "\""),
+    ("apos", "'"),
+    ("nbsp", " "),
+    ("ndash", "\u{2013}"),
+    ("mdash", "\u{2014}"),
+    ("lsquo", "\u{2018}"),
+    ("rsquo", "\u{2019}"),
+    ("ldquo", "\u{201C}"),
+    ("rdquo", "\u{201D}"),
+    ("hellip", "\u{2026}"),
+];
+
+/// Decodes named and numeric (`&#233;`, `&#xE9;`) HTML entities.
+pub fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let name = &rest[1..end];
+            let c = if let Some(num) = name.strip_prefix('#') {
+                let code = match num.strip_prefix('x').or_else(|| num.strip_prefix('X')) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => num.parse().ok(),
+                };
+                code.and_then(char::from_u32).map(String::from)
+            } else {
+                ENTITIES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, c)| (*c).to_owned())
+            };
+            c.map(|c| (c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push_str(&c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Collapses every run of whitespace (including newlines) into a single space.
+pub fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Removes `$...$` math delimiters and the TeX markup inside them, keeping a plain-text
+/// approximation (`$\alpha$-decay` becomes `alpha-decay`).
+pub fn strip_math(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut math = false;
+    let mut escaped = false;
+    for c in s.chars() {
+        if escaped {
+            escaped = false;
+            out.push('\\');
+            out.push(c);
+            continue;
+        }
+        match c {
+            '\\' if math => {}
+            '\\' => escaped = true,
+            '$' => math = !math,
+            '{' | '}' | '^' | '_' if math => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decodes `%XX` escapes, leaving malformed escapes untouched.
+pub fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit()
+        {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+            out.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_title_cleanup() {
+        assert_eq!(
+            decode_entities("Q&amp;A &lt;3 &#233;t&#xE9; &bogus; & co"),
+            "Q&A <3 été &bogus; & co"
+        );
+        assert_eq!(
+            collapse_whitespace("Deep\n  Learning   for\tCats"),
+            "Deep Learning for Cats"
+        );
+        assert_eq!(
+            strip_math("$\\alpha$-decay in $z \\sim 2$ galaxies costs \\$5"),
+            "alpha-decay in z sim 2 galaxies costs \\$5"
+        );
+    }
+}
@@ -0,0 +1,213 @@
+//! `@phdthesis`/`@mastersthesis` entries from CrossRef and DataCite dissertation records, and from
+//! the `citation_*` meta tags of institutional repository pages (DSpace, EPrints, and the
+//! repositories DART-Europe links to).
+
+use crate::bibtex::{family_name, name_last_first, Entry};
+use crate::language::language_name;
+use crate::{crossref, html};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref MASTERS_RE: Regex =
+        Regex::new(r"(?i)\bmaster|\bm\.?\s?(?:sc|a|s|phil|eng|res)\b\.?|\bmagister|\bdiplom")
+            .unwrap();
+    static ref THESIS_RE: Regex =
+        Regex::new(r"(?i)thesis|dissertation|proefschrift|doctora").unwrap();
+    static ref YEAR_RE: Regex = Regex::new(r"\b(1[5-9]|20)\d{2}\b").unwrap();
+}
+
+/// Whether a negotiated entry of this type might be a thesis with a richer registry record.
+pub fn is_thesis_like(kind: &str) -> bool {
+    matches!(
+        kind.to_ascii_lowercase().as_str(),
+        "phdthesis" | "mastersthesis" | "thesis" | "misc"
+    )
+}
+
+/// `mastersthesis` if the degree description names a master's degree, `phdthesis` otherwise.
+fn thesis_kind(degree: Option<&str>) -> &'static str {
+    match degree {
+        Some(d) if MASTERS_RE.is_match(d) => "mastersthesis",
+        _ => "phdthesis",
+    }
+}
+
+fn make_key(first_author: Option<&str>, year: Option<&str>) -> String {
+    let family = first_author
+        .map(family_name)
+        .unwrap_or("thesis")
+        .replace(' ', "");
+    match year {
+        Some(year) => format!("{}_{}", family, year),
+        None => family,
+    }
+}
+
+/// Builds a thesis entry from a CrossRef `dissertation` work.
+pub fn from_crossref(work: &Value, key: &str) -> Option<Entry> {
+    if work["type"].as_str()? != "dissertation" {
+        return None;
+    }
+    let degree = work["degree"][0].as_str();
+    let mut entry = Entry::new(thesis_kind(degree), key);
+    entry.set("title", work["title"][0].as_str()?);
+    if let Some(authors) = crossref::names(work, "author") {
+        entry.set("author", &authors);
+    }
+    let institution = &work["institution"][0];
+    if let Some(school) = institution["name"].as_str() {
+        entry.set("school", school);
+    }
+    if let Some(place) = institution["place"][0].as_str() {
+        entry.set("address", place);
+    }
+    if let Some(degree) = degree {
+        entry.set("type", degree);
+    }
+    let year = work["approved"]["date-parts"][0][0]
+        .as_i64()
+        .or_else(|| crossref::year(work));
+    if let Some(year) = year {
+        entry.set("year", &year.to_string());
+    }
+    if let Some(language) = work["language"].as_str() {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = work["DOI"].as_str() {
+        entry.set("doi", doi);
+        entry.set("url", &format!("https://doi.org/{}", doi));
+    }
+    Some(entry)
+}
+
+/// Builds a thesis entry from a DataCite record whose resource type is a dissertation or thesis.
+pub fn from_datacite(attrs: &Value) -> Option<Entry> {
+    let general = attrs["types"]["resourceTypeGeneral"].as_str()?;
+    let specific = attrs["types"]["resourceType"].as_str();
+    if general != "Dissertation" && !specific.is_some_and(|t| THESIS_RE.is_match(t)) {
+        return None;
+    }
+
+    let authors = attrs["creators"]
+        .as_array()?
+        .iter()
+        .filter_map(|c| c["name"].as_str().map(name_last_first))
+        .collect::<Vec<_>>();
+    let year = attrs["publicationYear"]
+        .as_i64()
+        .map(|y| y.to_string())
+        .or_else(|| attrs["publicationYear"].as_str().map(str::to_owned));
+
+    let mut entry = Entry::new(
+        thesis_kind(specific),
+        &make_key(authors.first().map(String::as_str), year.as_deref()),
+    );
+    entry.set("title", attrs["titles"][0]["title"].as_str()?);
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    let school = attrs["publisher"]
+        .as_str()
+        .or_else(|| attrs["publisher"]["name"].as_str());
+    if let Some(school) = school {
+        entry.set("school", school);
+    }
+    if let Some(specific) = specific {
+        entry.set("type", specific);
+    }
+    if let Some(year) = &year {
+        entry.set("year", year);
+    }
+    if let Some(language) = attrs["language"].as_str() {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = attrs["doi"].as_str() {
+        entry.set("doi", doi);
+    }
+    if let Some(url) = attrs["url"].as_str() {
+        entry.set("url", url);
+    }
+    Some(entry)
+}
+
+/// Builds a thesis entry from the meta tags of a repository landing page at `url`.
+pub fn from_meta(tags: &[(String, String)], url: &str) -> Option<Entry> {
+    let degree = html::first(tags, "citation_dissertation_name")
+        .or_else(|| html::first(tags, "dc.type").filter(|t| THESIS_RE.is_match(t)));
+    let school = html::first(tags, "citation_dissertation_institution")
+        .or_else(|| html::first(tags, "citation_publisher"))
+        .or_else(|| html::first(tags, "dc.publisher"));
+    if html::first(tags, "citation_dissertation_institution").is_none() && degree.is_none() {
+        return None;
+    }
+
+    let authors = html::all(tags, "citation_author")
+        .into_iter()
+        .chain(html::all(tags, "dc.creator"))
+        .map(name_last_first)
+        .collect::<Vec<_>>();
+    let year = html::first(tags, "citation_publication_date")
+        .or_else(|| html::first(tags, "citation_date"))
+        .or_else(|| html::first(tags, "dc.date"))
+        .and_then(|d| YEAR_RE.find(d))
+        .map(|m| m.as_str());
+
+    let mut entry = Entry::new(
+        thesis_kind(degree),
+        &make_key(authors.first().map(String::as_str), year),
+    );
+    entry.set(
+        "title",
+        html::first(tags, "citation_title").or_else(|| html::first(tags, "dc.title"))?,
+    );
+    if !authors.is_empty() {
+        entry.set("author", &authors.join(" and "));
+    }
+    if let Some(school) = school {
+        entry.set("school", school);
+    }
+    if let Some(degree) = degree {
+        entry.set("type", degree);
+    }
+    if let Some(year) = year {
+        entry.set("year", year);
+    }
+    let language =
+        html::first(tags, "citation_language").or_else(|| html::first(tags, "dc.language"));
+    if let Some(language) = language {
+        entry.set("language", &language_name(language));
+    }
+    if let Some(doi) = html::first(tags, "citation_doi") {
+        entry.set("doi", doi);
+    }
+    entry.set("url", url);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thesis_from_meta() {
+        let tags = html::meta_tags(
+            r#"<meta name="citation_title" content="Galaxy Dynamics at High Redshift">
+               <meta name="citation_author" content="Jane Doe">
+               <meta name="citation_publication_date" content="2019-06-30">
+               <meta name="citation_dissertation_institution" content="University of Toronto">
+               <meta name="DC.type" content="Master's Thesis">"#,
+        );
+        let entry = from_meta(&tags, "https://hdl.handle.net/1807/1234").unwrap();
+        assert_eq!(entry.kind, "mastersthesis");
+        assert_eq!(entry.key, "Doe_2019");
+        assert_eq!(entry.get("author"), Some("Doe, Jane"));
+        assert_eq!(entry.get("school"), Some("University of Toronto"));
+        assert_eq!(entry.get("type"), Some("Master's Thesis"));
+        assert_eq!(entry.get("year"), Some("2019"));
+
+        let article = html::meta_tags(r#"<meta name="citation_title" content="A paper">"#);
+        assert!(from_meta(&article, "https://example.com").is_none());
+    }
+}
@@ -0,0 +1,166 @@
+//! Backups of the files d2b rewrites, and `d2b undo` to put them back. Before a file is replaced,
+//! its old contents are copied to `.d2b/undo/<run>/` in its directory, where `<run>` is when the
+//! command started. A run that rewrites several files, like `d2b rekey --apply`, is undone as one,
+//! and only the last few runs are kept.
+
+use crate::atomic;
+use crate::error::{Error, Kind};
+use crate::{i18n, Options};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How many runs are kept per directory.
+const KEEP: usize = 20;
+
+/// The format of run names, which sorts them by when they started.
+const RUN_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    /// The name of this run's backups.
+    static ref RUN: String = format!(
+        "{}-{}",
+        chrono::Local::now().format(RUN_FORMAT),
+        std::process::id()
+    );
+}
+
+/// Turns backups off, for `--no-backup`.
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+}
+
+/// The journal of a directory.
+fn journal(dir: &Path) -> PathBuf {
+    dir.join(".d2b").join("undo")
+}
+
+/// The runs in a journal, oldest first.
+fn runs(journal: &Path) -> Vec<PathBuf> {
+    let mut runs = std::fs::read_dir(journal)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| Some(e.ok()?.path()))
+        .filter(|p| p.is_dir())
+        .collect::<Vec<_>>();
+    runs.sort();
+    runs
+}
+
+/// Copies a file about to be replaced into this run's backups, unless it has one already, which
+/// keeps the state from before the run rather than from between two of its writes.
+pub fn backup(path: &Path) -> io::Result<()> {
+    if DISABLED.load(Ordering::Relaxed) || !path.is_file() {
+        return Ok(());
+    }
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Ok(());
+    };
+    let journal = journal(dir);
+    let run = journal.join(&*RUN);
+    let copy = run.join(name);
+    if copy.exists() {
+        return Ok(());
+    }
+    let new = !run.exists();
+    std::fs::create_dir_all(&run)?;
+    std::fs::copy(path, copy)?;
+    if new {
+        let runs = runs(&journal);
+        for old in &runs[..runs.len().saturating_sub(KEEP)] {
+            std::fs::remove_dir_all(old)?;
+        }
+    }
+    Ok(())
+}
+
+/// When a run started, for people.
+fn started(run: &Path) -> String {
+    let name = run.file_name().unwrap_or_default().to_string_lossy();
+    let stamp = name.split('-').next().unwrap_or_default();
+    chrono::NaiveDateTime::parse_from_str(stamp, RUN_FORMAT)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| name.into_owned())
+}
+
+/// The files backed up in a run, by name.
+fn files(run: &Path) -> Vec<String> {
+    let mut files = std::fs::read_dir(run)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Puts back the files of the last run in a directory's journal, and drops the run so that undoing
+/// again goes back one run further. Returns when the run started and the files restored.
+pub fn restore(dir: &Path) -> io::Result<Option<(String, Vec<String>)>> {
+    let Some(run) = runs(&journal(dir)).pop() else {
+        return Ok(None);
+    };
+    let files = files(&run);
+    for name in &files {
+        let path = dir.join(name);
+        let _lock = atomic::lock(&path)?;
+        atomic::replace(&path, std::fs::read(run.join(name))?)?;
+    }
+    std::fs::remove_dir_all(&run)?;
+    Ok(Some((started(&run), files)))
+}
+
+/// Runs `d2b undo`.
+pub fn run(matches: &ArgMatches, opts: Options) {
+    let dir = Path::new(matches.value_of("dir").unwrap_or("."));
+    if matches.is_present("list") {
+        for run in runs(&journal(dir)).iter().rev() {
+            println!("{}\t{}", started(run), files(run).join(", "));
+        }
+        return;
+    }
+    match restore(dir) {
+        Ok(Some((started, files))) => {
+            for file in files {
+                eprintln!(
+                    "{}",
+                    i18n::message("undo-restored", &[("file", &file), ("time", &started)])
+                );
+            }
+        }
+        Ok(None) => Error::new(
+            Kind::Usage,
+            i18n::message("undo-nothing", &[("dir", &dir.display())]),
+        )
+        .exit(opts.error_format),
+        Err(e) => Error::new(Kind::Io, i18n::message("undo-failed", &[("error", &e)]))
+            .exit(opts.error_format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo() {
+        let dir = std::env::temp_dir().join(format!("d2b-undo-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("refs.bib");
+        std::fs::write(&path, "first").unwrap();
+
+        atomic::write(&path, "second").unwrap();
+        atomic::write(&path, "third").unwrap();
+        assert_eq!(files(&runs(&journal(&dir))[0]), ["refs.bib"]);
+
+        let (_, restored) = restore(&dir).unwrap().unwrap();
+        assert_eq!(restored, ["refs.bib"]);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "first");
+        assert!(restore(&dir).unwrap().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,335 @@
+//! `d2b verify`: checks a `.bib` file for malformed entries, missing DOIs, identifiers that no
+//! longer resolve and, for entries written with `--hash`, metadata that changed at its source, for
+//! use in CI and pre-commit hooks.
+
+use crate::bibtex::{self, Entry};
+use crate::error::{self, Error, Kind};
+use crate::http::SendWithRetry;
+use crate::resolvers::Backend;
+use crate::{digest, enrich, i18n, resolve, Options, CLIENT};
+use clap::ArgMatches;
+use futures::{stream::FuturesUnordered, StreamExt};
+use serde_json::json;
+
+/// Something wrong with an entry, and the line it starts on.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    pub line: usize,
+    pub key: Option<String>,
+    /// `malformed`, `missing-doi`, `unresolvable`, `drifted`, or `unreachable` when the server an
+    /// entry is checked against couldn't be reached.
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// The problems that can be found without going online, and the entries that were parsed along
+/// with their line numbers.
+pub fn check(src: &str, require_doi: bool) -> (Vec<Problem>, Vec<(usize, Entry)>) {
+    let mut problems = Vec::new();
+    let mut entries: Vec<(usize, Entry)> = Vec::new();
+    let mut line = 1;
+    for chunk in bibtex::split_file(src) {
+        // Entries and other `@` chunks start right at the `@`.
+        let start = line;
+        line += chunk.text.matches('\n').count();
+
+        let mut entry = match chunk.entry {
+            Some(entry) => entry,
+            None => {
+                if chunk.text.starts_with('@') {
+                    if let Err(e) = bibtex::parse_entry(chunk.text) {
+                        problems.push(Problem {
+                            line: start,
+                            key: None,
+                            kind: "malformed",
+                            message: e.0,
+                        });
+                    }
+                }
+                continue;
+            }
+        };
+        let key = Some(entry.key.clone());
+        if entries
+            .iter()
+            .any(|(_, e)| e.key.eq_ignore_ascii_case(&entry.key))
+        {
+            problems.push(Problem {
+                line: start,
+                key: key.clone(),
+                kind: "malformed",
+                message: format!("duplicate key \"{}\"", entry.key),
+            });
+        }
+        for message in bibtex::validate(&mut entry, false) {
+            problems.push(Problem {
+                line: start,
+                key: key.clone(),
+                kind: "malformed",
+                message,
+            });
+        }
+        if require_doi && entry.get("doi").is_none() {
+            problems.push(Problem {
+                line: start,
+                key,
+                kind: "missing-doi",
+                message: "entry has no DOI".to_owned(),
+            });
+        }
+        entries.push((start, entry));
+    }
+    (problems, entries)
+}
+
+/// Whether the DOI, arXiv ID or URL of an entry still resolves, or the kind of problem and a
+/// message. Entries without any are skipped.
+async fn resolves(entry: &Entry) -> Option<Result<(), (&'static str, String)>> {
+    let url = if let Some(doi) = entry.get("doi") {
+        // The handle API answers 404 for DOIs that were never registered, without following the
+        // redirect to the publisher, which is often slow or blocks bots.
+        let mut url = reqwest::Url::parse("https://doi.org/api/handles/").unwrap();
+        url.path_segments_mut()
+            .unwrap()
+            .pop_if_empty()
+            .extend(doi.split('/'));
+        url.to_string()
+    } else if let Some(eprint) = entry.get("eprint") {
+        format!("https://export.arxiv.org/abs/{}", eprint)
+    } else {
+        entry.get("url")?.to_owned()
+    };
+    Some(match CLIENT.get(&url).send_with_retry().await {
+        Ok(res) if res.status().is_success() => Ok(()),
+        Ok(res) => Err(("unresolvable", format!("{} returned {}", url, res.status()))),
+        Err(e) => Err(("unreachable", format!("could not reach {}: {}", url, e))),
+    })
+}
+
+/// The options an entry stamped with `stamp` is fetched again with: those of the run, but through
+/// the backends and in the format it was built with.
+fn refetch_options(stamp: &digest::Stamp, opts: &Options) -> Options {
+    let mut opts = Options {
+        hash: true,
+        format: stamp.format.unwrap_or(opts.format),
+        follow_published: false,
+        race: false,
+        best_of: false,
+        merge_sources: false,
+        ..opts.clone()
+    };
+    let backends = stamp
+        .source
+        .split(" + ")
+        .map(Backend::from_host)
+        .collect::<Option<Vec<_>>>();
+    if let Some(backends) = backends {
+        if matches!(backends[0], Backend::Arxiv | Backend::Inspire) {
+            opts.resolvers.arxiv = backends;
+        } else {
+            opts.best_of = backends.len() > 1;
+            opts.merge_sources = backends.len() > 1;
+            opts.resolvers.doi = backends;
+            opts.resolvers.doi_fixed = true;
+        }
+    }
+    opts
+}
+
+/// Whether an entry's `sourcehash` still matches the metadata its DOI or arXiv ID resolves to,
+/// or the kind of problem and a message. Entries without a hash or an identifier are skipped.
+async fn unchanged(entry: &Entry, opts: &Options) -> Option<Result<(), (&'static str, String)>> {
+    let stored = digest::Stamp::parse(entry.get(digest::FIELD)?)?;
+    let id = enrich::find_id(entry)?;
+    let opts = refetch_options(&stored, opts);
+    Some(match resolve(id.clone(), opts).await {
+        Ok(resolved) => match resolved
+            .first()
+            .and_then(|r| digest::Stamp::parse(r.entry.get(digest::FIELD)?))
+        {
+            Some(stamp) if stamp.hash == stored.hash => Ok(()),
+            _ => Err((
+                "drifted",
+                format!("the metadata of {} changed since it was fetched", id),
+            )),
+        },
+        // Not being able to ask says nothing about whether the source changed.
+        Err(e) if matches!(e.kind, Kind::Network | Kind::Service) => Err((
+            "unreachable",
+            format!("could not resolve {}: {}", id, e.message),
+        )),
+        Err(e) => Err((
+            "drifted",
+            format!("could not resolve {}: {}", id, e.message),
+        )),
+    })
+}
+
+/// The exit status for `problems`: [`error::NETWORK`] if the only ones are servers that couldn't
+/// be reached, so that CI can tell a flaky network from a bad `.bib`, and [`error::PARTIAL`] for
+/// anything else.
+fn status(problems: &[Problem]) -> i32 {
+    if problems.is_empty() {
+        error::OK
+    } else if problems.iter().all(|p| p.kind == "unreachable") {
+        error::NETWORK
+    } else {
+        error::PARTIAL
+    }
+}
+
+/// Runs `d2b verify`, exiting with a nonzero [`status`] if anything is wrong.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let format = opts.error_format;
+    let path = matches.value_of("bib").unwrap();
+    let src = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        Error::new(
+            Kind::Io,
+            i18n::message("file-unreadable", &[("file", &path), ("error", &e)]),
+        )
+        .exit(format)
+    });
+    let (mut problems, entries) = check(&src, matches.is_present("require-doi"));
+
+    if matches.is_present("require-resolvable") {
+        let mut checks = entries
+            .iter()
+            .map(|(line, entry)| async move { (*line, &entry.key, resolves(entry).await) })
+            .collect::<FuturesUnordered<_>>();
+        while let Some((line, key, result)) = checks.next().await {
+            if let Some(Err((kind, message))) = result {
+                problems.push(Problem {
+                    line,
+                    key: Some(key.clone()),
+                    kind,
+                    message,
+                });
+            }
+        }
+        problems.sort_by_key(|p| p.line);
+    }
+
+    if matches.is_present("check-hashes") {
+        let mut checks = entries
+            .iter()
+            .map(|(line, entry)| {
+                let opts = &opts;
+                async move { (*line, &entry.key, unchanged(entry, opts).await) }
+            })
+            .collect::<FuturesUnordered<_>>();
+        while let Some((line, key, result)) = checks.next().await {
+            if let Some(Err((kind, message))) = result {
+                problems.push(Problem {
+                    line,
+                    key: Some(key.clone()),
+                    kind,
+                    message,
+                });
+            }
+        }
+        problems.sort_by_key(|p| p.line);
+    }
+
+    if matches.is_present("json") {
+        let report = json!({
+            "file": path,
+            "entries": entries.len(),
+            "ok": problems.is_empty(),
+            "problems": problems
+                .iter()
+                .map(|p| json!({
+                    "line": p.line,
+                    "key": p.key,
+                    "kind": p.kind,
+                    "message": p.message,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        println!("{}", serde_json::to_string_pretty(&report).unwrap());
+    } else {
+        for p in &problems {
+            let key = p
+                .key
+                .as_deref()
+                .map_or(String::new(), |k| format!(" {}:", k));
+            println!("{}:{}:{} {} ({})", path, p.line, key, p.message, p.kind);
+        }
+        eprintln!(
+            "{}",
+            i18n::message(
+                "verify-summary",
+                &[
+                    ("file", &path),
+                    ("entries", &entries.len()),
+                    ("problems", &problems.len()),
+                ],
+            )
+        );
+    }
+    let status = status(&problems);
+    if status != error::OK {
+        std::process::exit(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let src = "% refs\n\n\
+                   @article{Lamport_1978,\n  title={Time, Clocks},\n  author={Lamport, Leslie},\n  journal={CACM},\n  year={1978},\n  doi={10.1145/359545.359563}\n}\n\n\
+                   @misc{No Doi, title={Paper}}\n\
+                   @misc{lamport_1978, title={Again}, doi={10.1/x}}\n";
+        let (problems, entries) = check(src, true);
+        assert_eq!(entries.len(), 3);
+        let found = problems
+            .iter()
+            .map(|p| (p.line, p.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [(11, "malformed"), (11, "missing-doi"), (12, "malformed")]
+        );
+        assert!(problems[2].message.contains("duplicate key"));
+        assert!(check(src, false).0.iter().all(|p| p.kind != "missing-doi"));
+        assert_eq!(status(&problems), error::PARTIAL);
+        assert_eq!(status(&[]), error::OK);
+
+        let offline = |line| Problem {
+            line,
+            key: None,
+            kind: "unreachable",
+            message: String::new(),
+        };
+        assert_eq!(status(&[offline(1), offline(5)]), error::NETWORK);
+    }
+
+    #[test]
+    fn test_refetch_options() {
+        let mut opts = Options::from_matches(&ArgMatches::default(), &crate::Config::default());
+        opts.race = true;
+        let stamp = |field: &str| digest::Stamp::parse(field).unwrap();
+
+        let refetch = refetch_options(&stamp("sha256:0; api.crossref.org; biblatex"), &opts);
+        assert_eq!(refetch.format, crate::Format::Biblatex);
+        assert_eq!(refetch.resolvers.doi, [Backend::Crossref]);
+        assert!(refetch.resolvers.doi_fixed && !refetch.race && !refetch.best_of);
+
+        let merged = stamp("sha256:0; api.crossref.org + api.datacite.org; bibtex");
+        let refetch = refetch_options(&merged, &opts);
+        assert!(refetch.best_of && refetch.merge_sources);
+        assert_eq!(
+            refetch.resolvers.doi,
+            [Backend::Crossref, Backend::Datacite]
+        );
+
+        let refetch = refetch_options(&stamp("sha256:0; inspirehep.net; bibtex"), &opts);
+        assert_eq!(refetch.resolvers.arxiv, [Backend::Inspire]);
+
+        let refetch = refetch_options(&stamp("sha256:0"), &opts);
+        assert_eq!(refetch.resolvers, opts.resolvers);
+        assert_eq!(refetch.format, opts.format);
+    }
+}
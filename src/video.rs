@@ -0,0 +1,126 @@
+//! Recorded talks and lectures on YouTube and Vimeo, from the oEmbed record of the video and, for
+//! YouTube, whose oEmbed has neither, the upload date and duration in the `itemprop` tags of its
+//! page. Videos are `@online`s (or `@misc`es for BibTeX) by their uploader, with the platform and
+//! the running time.
+
+use crate::bibtex::Entry;
+use crate::{html, news, Format};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde_json::Value;
+
+lazy_static! {
+    static ref DATE_RE: Regex = Regex::new(r"^(\d{4})-(\d{2})-(\d{2})").unwrap();
+    static ref ISO_DURATION_RE: Regex =
+        Regex::new(r"^PT(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?$").unwrap();
+}
+
+/// The length of an ISO 8601 duration such as `PT1H2M3S`, in seconds.
+fn parse_duration(iso: &str) -> Option<u64> {
+    let c = ISO_DURATION_RE.captures(iso.trim())?;
+    let part = |i| c.get(i).map_or(Ok(0), |m| m.as_str().parse::<u64>());
+    Some(part(1).ok()? * 3600 + part(2).ok()? * 60 + part(3).ok()?)
+}
+
+/// A running time as `H:MM:SS`, or `M:SS` for videos shorter than an hour.
+pub fn format_duration(seconds: u64) -> String {
+    let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+/// Builds an entry for the video at `url` from its oEmbed record and its page, which may be empty.
+pub fn entry(oembed: &Value, page: &str, url: &str, format: Format) -> Option<Entry> {
+    let tags = html::meta_tags(page);
+    let title = oembed["title"]
+        .as_str()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())?;
+    let uploader = oembed["author_name"]
+        .as_str()
+        .map(str::trim)
+        .filter(|a| !a.is_empty());
+    let platform = oembed["provider_name"].as_str().unwrap_or("YouTube");
+    let date = oembed["upload_date"]
+        .as_str()
+        .or_else(|| html::first(&tags, "uploaddate"))
+        .or_else(|| html::first(&tags, "datepublished"))
+        .and_then(|d| DATE_RE.captures(d));
+    let duration = oembed["duration"]
+        .as_u64()
+        .or_else(|| html::first(&tags, "duration").and_then(parse_duration));
+
+    let family = uploader
+        .unwrap_or(platform)
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>();
+    let key = match &date {
+        Some(date) => format!("{}_{}", family, &date[1]),
+        None => family,
+    };
+    let mut entry = match format {
+        Format::Biblatex => Entry::new("online", &key),
+        Format::Bibtex => Entry::new("misc", &key),
+    };
+    entry.set("title", title);
+    // Uploaders are as often channels as people, so their names are kept as they are.
+    if let Some(uploader) = uploader {
+        entry.set("author", &format!("{{{}}}", uploader));
+    }
+    match format {
+        Format::Biblatex => entry.set("organization", platform),
+        Format::Bibtex => entry.set("howpublished", platform),
+    }
+    if let Some(date) = &date {
+        news::set_date(&mut entry, &date[0], format);
+    }
+    entry.set(
+        "note",
+        &match duration {
+            Some(duration) => format!("Video, {}", format_duration(duration)),
+            None => "Video".to_owned(),
+        },
+    );
+    entry.set("url", url);
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry() {
+        let oembed = serde_json::json!({
+            "title": "Growing a Language", "author_name": "ACM SIGPLAN",
+            "provider_name": "YouTube"
+        });
+        let page = r#"<meta itemprop="duration" content="PT53M44S">
+            <meta itemprop="uploadDate" content="2012-01-17T03:15:00-08:00">"#;
+        let url = "https://www.youtube.com/watch?v=_ahvzDzKdB0";
+        assert_eq!(
+            entry(&oembed, page, url, Format::Biblatex).unwrap().to_string(),
+            "@online{ACMSIGPLAN_2012,\n  title={Growing a Language},\n  author={{ACM SIGPLAN}},\n  \
+             organization={YouTube},\n  date={2012-01-17},\n  note={Video, 53:44},\n  \
+             url={https://www.youtube.com/watch?v=_ahvzDzKdB0}\n}"
+        );
+
+        let oembed = serde_json::json!({
+            "title": "Keynote", "author_name": "Jane Doe", "provider_name": "Vimeo",
+            "upload_date": "2013-09-13 10:52:07", "duration": 3725
+        });
+        assert_eq!(
+            entry(&oembed, "", "https://vimeo.com/1", Format::Bibtex)
+                .unwrap()
+                .to_string(),
+            "@misc{JaneDoe_2013,\n  title={Keynote},\n  author={{Jane Doe}},\n  \
+             howpublished={Vimeo},\n  year={2013},\n  month=sep,\n  note={Video, 1:02:05},\n  \
+             url={https://vimeo.com/1}\n}"
+        );
+        assert_eq!(parse_duration("PT2H"), Some(7200));
+    }
+}
@@ -0,0 +1,279 @@
+//! `d2b watch-ids`: re-checks a list of identifiers and reports what changed since the last check:
+//! new arXiv versions, journal DOIs assigned to preprints, and retractions.
+//!
+//! What was last seen is kept in a state file, `IDS.watch.json` next to the list unless `--state`
+//! says otherwise, so that a single check can run from cron. With `--interval`, d2b keeps checking
+//! instead. The first check of an identifier only records it, except for retractions. Changes can
+//! also be posted to a webhook with `--notify-url`.
+
+use crate::error::{Error, Kind};
+use crate::http::SendWithRetry;
+use crate::sync::manifest_inputs;
+use crate::{crossref, feed, i18n, input_id, read_file, webhook, Options, CLIENT};
+use clap::ArgMatches;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+lazy_static! {
+    static ref VERSION_RE: Regex = Regex::new(r"v(\d+)$").unwrap();
+    static ref INTERVAL_RE: Regex = Regex::new(r"^(\d+)\s*([smhdw]?)$").unwrap();
+}
+
+/// What a check found out about an identifier.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Status {
+    /// The latest version of an arXiv paper, e.g. `v3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The DOI of the published version of a preprint.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+    #[serde(default)]
+    pub retracted: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    NewVersion(String),
+    Published(String),
+    Retracted,
+}
+
+impl Change {
+    pub fn message(&self, input: &str) -> String {
+        match self {
+            Change::NewVersion(version) => i18n::message(
+                "watch-new-version",
+                &[("input", &input), ("version", version)],
+            ),
+            Change::Published(doi) => {
+                i18n::message("watch-published", &[("input", &input), ("doi", doi)])
+            }
+            Change::Retracted => i18n::message("watch-retracted", &[("input", &input)]),
+        }
+    }
+}
+
+/// What changed between two checks. Without an earlier check there is nothing to compare with,
+/// but a retraction is still worth knowing about.
+pub fn changes(old: Option<&Status>, new: &Status) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let old = match old {
+        Some(old) => old,
+        None => {
+            if new.retracted {
+                changes.push(Change::Retracted);
+            }
+            return changes;
+        }
+    };
+    if let Some(version) = &new.version {
+        if old.version.as_ref() != Some(version) {
+            changes.push(Change::NewVersion(version.clone()));
+        }
+    }
+    if let Some(doi) = &new.published {
+        if old.published.as_ref() != Some(doi) {
+            changes.push(Change::Published(doi.clone()));
+        }
+    }
+    if new.retracted && !old.retracted {
+        changes.push(Change::Retracted);
+    }
+    changes
+}
+
+/// Parses an interval such as `90s`, `30m`, `12h`, `1d` or `1w`. A bare number is in seconds.
+pub fn parse_interval(s: &str) -> Option<Duration> {
+    let c = INTERVAL_RE.captures(s.trim())?;
+    let n = c[1].parse::<u64>().ok()?;
+    let unit = match &c[2] {
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => 1,
+    };
+    Some(Duration::from_secs(n.checked_mul(unit)?)).filter(|d| !d.is_zero())
+}
+
+async fn arxiv_status(id: &str) -> Option<Status> {
+    let body = CLIENT
+        .get(format!("http://export.arxiv.org/api/query?id_list={}", id))
+        .send_with_retry()
+        .await
+        .ok()?
+        .text()
+        .await
+        .ok()?;
+    let feed = feed::parse(&body).ok()?;
+    let item = feed.items.first()?;
+    let published = item.extension("arxiv:doi").map(str::to_lowercase);
+    Some(Status {
+        version: VERSION_RE.find(&item.id).map(|v| v.as_str().to_owned()),
+        published,
+        retracted: false,
+    })
+}
+
+async fn doi_status(doi: &str) -> Option<Status> {
+    let work = crossref::fetch_work(doi).await?;
+    // CrossRef lists the notices that retract or withdraw a work under `updated-by`.
+    let retracted = work["updated-by"].as_array().is_some_and(|updates| {
+        updates.iter().any(|u| {
+            matches!(
+                u["type"].as_str(),
+                Some("retraction") | Some("withdrawal") | Some("removal")
+            )
+        })
+    });
+    Some(Status {
+        version: None,
+        published: crossref::published_version(&work).map(|d| d.to_lowercase()),
+        retracted,
+    })
+}
+
+/// Checks a DOI or arXiv ID in the form [`input_id`] gives, or `None` if that failed.
+async fn status(id: &str) -> Option<Status> {
+    match id.split_once(':')? {
+        ("doi", doi) => doi_status(doi).await,
+        ("arxiv", arxiv) => {
+            let mut status = arxiv_status(arxiv).await?;
+            if let Some(published) = &status.published {
+                status.retracted = doi_status(published).await.is_some_and(|s| s.retracted);
+            }
+            Some(status)
+        }
+        _ => None,
+    }
+}
+
+/// Checks every input once, printing what changed, and returns the new state. Inputs that
+/// couldn't be checked keep their old state.
+async fn check(
+    inputs: &[String],
+    state: &BTreeMap<String, Status>,
+    notify_url: Option<&str>,
+) -> BTreeMap<String, Status> {
+    let statuses = futures::future::join_all(inputs.iter().map(|input| async move {
+        let id = input_id(input).filter(|id| id.starts_with("doi:") || id.starts_with("arxiv:"))?;
+        Some(status(&id).await)
+    }))
+    .await;
+
+    let mut next = state.clone();
+    for (input, status) in inputs.iter().zip(statuses) {
+        let status = match status {
+            Some(Some(status)) => status,
+            Some(None) => {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("watch-unchecked", &[("input", input)])
+                );
+                continue;
+            }
+            None => {
+                eprintln!(
+                    "{}: {}",
+                    i18n::text("warning"),
+                    i18n::message("watch-unsupported", &[("input", input)])
+                );
+                continue;
+            }
+        };
+        for change in changes(state.get(input), &status) {
+            let message = change.message(input);
+            println!("{}", message);
+            if let Some(url) = notify_url {
+                webhook::post(url, &message, None).await;
+            }
+        }
+        next.insert(input.clone(), status);
+    }
+    next
+}
+
+/// Runs `d2b watch-ids`.
+pub async fn run(matches: &ArgMatches<'_>, opts: Options) {
+    let list = matches.value_of("ids").unwrap();
+    let state_path = matches
+        .value_of("state")
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("{}.watch.json", list.trim_end_matches(".txt")));
+    let interval = matches.value_of("interval").map(|i| {
+        parse_interval(i).unwrap_or_else(|| {
+            Error::new(
+                Kind::Usage,
+                i18n::message("invalid-duration", &[("arg", &i)]),
+            )
+            .exit(opts.error_format)
+        })
+    });
+
+    let mut state = std::fs::read_to_string(&state_path)
+        .ok()
+        .and_then(|src| serde_json::from_str(&src).ok())
+        .unwrap_or_default();
+    loop {
+        // The list is read again for every check, so that it can be edited while watching.
+        let inputs =
+            manifest_inputs(&read_file(list).unwrap_or_else(|e| e.exit(opts.error_format)));
+        state = check(&inputs, &state, matches.value_of("notify-url")).await;
+        let json = serde_json::to_string_pretty(&state).unwrap();
+        if let Err(e) = std::fs::write(&state_path, json + "\n") {
+            Error::new(
+                Kind::Io,
+                i18n::message("file-unwritable", &[("file", &state_path), ("error", &e)]),
+            )
+            .exit(opts.error_format)
+        }
+        match interval {
+            Some(interval) => tokio::time::sleep(interval).await,
+            None => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes() {
+        let old = Status {
+            version: Some("v1".to_owned()),
+            published: None,
+            retracted: false,
+        };
+        let new = Status {
+            version: Some("v2".to_owned()),
+            published: Some("10.1000/xyz".to_owned()),
+            retracted: true,
+        };
+        assert_eq!(
+            changes(Some(&old), &new),
+            [
+                Change::NewVersion("v2".to_owned()),
+                Change::Published("10.1000/xyz".to_owned()),
+                Change::Retracted
+            ]
+        );
+        assert_eq!(changes(Some(&new), &new), []);
+        assert_eq!(changes(None, &old), []);
+        assert_eq!(changes(None, &new), [Change::Retracted]);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(parse_interval("30m"), Some(Duration::from_secs(1800)));
+        assert_eq!(parse_interval("45"), Some(Duration::from_secs(45)));
+        assert_eq!(parse_interval("0h"), None);
+        assert_eq!(parse_interval("daily"), None);
+    }
+}
@@ -0,0 +1,135 @@
+//! `--notify-url URL`: posts what the watching modes (`clipwatch`, `watch-ids`) find to a chat
+//! webhook, so that a lab channel hears about every paper filed.
+//!
+//! The payload is picked from the URL: Discord's for Discord webhooks, matrix-hookshot's for Matrix
+//! ones, and Slack's `{"text": ...}`, which Mattermost and others accept as well, for the rest.
+
+use crate::http::SendWithRetry;
+use crate::{i18n, CLIENT};
+use clap::Arg;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+
+/// The longest message Discord accepts.
+const DISCORD_MAX_CHARS: usize = 2000;
+
+pub fn arg() -> Arg<'static, 'static> {
+    Arg::with_name("notify-url")
+        .long("notify-url")
+        .takes_value(true)
+        .help(i18n::text("help-notify-url"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Slack,
+    Discord,
+    Matrix,
+}
+
+impl Flavor {
+    pub fn from_url(url: &str) -> Self {
+        let url = reqwest::Url::parse(url).ok();
+        let host = url.as_ref().and_then(|u| u.host_str()).unwrap_or("");
+        let path = url.as_ref().map(|u| u.path()).unwrap_or("");
+        if host.ends_with("discord.com") || host.ends_with("discordapp.com") {
+            Flavor::Discord
+        } else if path.contains("/_matrix/") || path.contains("/webhook/") {
+            Flavor::Matrix
+        } else {
+            Flavor::Slack
+        }
+    }
+
+    /// The JSON body for a message, with the BibTeX it is about, if any, in a code block.
+    pub fn payload(self, text: &str, bibtex: Option<&str>) -> Value {
+        let markdown = match bibtex {
+            Some(bibtex) => format!("{}\n```\n{}\n```", text, bibtex),
+            None => text.to_owned(),
+        };
+        match self {
+            Flavor::Slack => json!({ "text": markdown }),
+            Flavor::Discord => {
+                // Cut long entries short rather than have the whole message rejected.
+                let content = if markdown.chars().count() > DISCORD_MAX_CHARS {
+                    let cut = markdown
+                        .chars()
+                        .take(DISCORD_MAX_CHARS - 5)
+                        .collect::<String>();
+                    format!("{}…\n```", cut)
+                } else {
+                    markdown
+                };
+                json!({ "content": content })
+            }
+            Flavor::Matrix => {
+                let html = match bibtex {
+                    Some(bibtex) => format!(
+                        "<p>{}</p><pre><code>{}</code></pre>",
+                        escape_html(text),
+                        escape_html(bibtex)
+                    ),
+                    None => format!("<p>{}</p>", escape_html(text)),
+                };
+                json!({ "text": markdown, "html": html })
+            }
+        }
+    }
+}
+
+/// Text as HTML, which may also go in a quoted attribute.
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Posts a message to a webhook, warning if that fails.
+pub async fn post(url: &str, text: &str, bibtex: Option<&str>) {
+    let body = Flavor::from_url(url).payload(text, bibtex).to_string();
+    let res = CLIENT
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .send_with_retry()
+        .await;
+    let error = match res {
+        Ok(res) if res.status().is_success() => return,
+        Ok(res) => res.status().to_string(),
+        Err(e) => e.to_string(),
+    };
+    eprintln!(
+        "{}: {}",
+        i18n::text("warning"),
+        i18n::message("webhook-failed", &[("url", &url), ("error", &error)])
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payload() {
+        let slack = Flavor::from_url("https://hooks.slack.com/services/T0/B0/x");
+        assert_eq!(slack, Flavor::Slack);
+        assert_eq!(
+            slack.payload("Filed 10.1/x", Some("@misc{X}")),
+            json!({ "text": "Filed 10.1/x\n```\n@misc{X}\n```" })
+        );
+
+        let discord = Flavor::from_url("https://discord.com/api/webhooks/1/abc");
+        assert_eq!(discord, Flavor::Discord);
+        assert_eq!(discord.payload("hi", None), json!({ "content": "hi" }));
+        let long = discord.payload("x", Some(&"y".repeat(3000)));
+        assert_eq!(long["content"].as_str().unwrap().chars().count(), 2000);
+
+        let matrix = Flavor::from_url("https://hookshot.example.org/webhook/abc");
+        assert_eq!(matrix, Flavor::Matrix);
+        assert_eq!(
+            matrix.payload("a <b>", Some("@misc{X, title={A & B}}"))["html"],
+            "<p>a &lt;b&gt;</p><pre><code>@misc{X, title={A &amp; B}}</code></pre>"
+        );
+    }
+}
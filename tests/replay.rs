@@ -0,0 +1,33 @@
+//! Runs d2b against the responses recorded in `tests/fixtures`, so that these tests don't need
+//! the network. New fixtures are made with `d2b --record tests/fixtures ...`.
+
+use std::process::{Command, Output};
+
+fn d2b(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_d2b"))
+        .arg("--replay")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .args(args)
+        .env("D2B_CONFIG", "/nonexistent/config.toml")
+        .env("D2B_LANG", "en")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_doi() {
+    let out = d2b(&["--annotate", "doi:10.18637/jss.v076.i01"]);
+    assert!(out.status.success());
+    let stdout = String::from_utf8(out.stdout).unwrap();
+    assert!(stdout.starts_with("% doi:10.18637/jss.v076.i01 fetched by d2b from doi.org on "));
+    assert!(stdout.contains("@article{Carpenter_2017,"));
+    assert!(stdout.contains("  journal={Journal of Statistical Software},\n"));
+}
+
+#[test]
+fn test_missing_fixture() {
+    let out = d2b(&["10.18637/jss.v076.i01", "10.1000/not-recorded"]);
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8(out.stderr).unwrap();
+    assert!(stderr.contains("No recorded response for https://doi.org/10.1000/not-recorded"));
+}